@@ -0,0 +1,85 @@
+// Barkod okutma gecikmesi: Store::find_product_by_barcode, uygulamanın kullandığı yolun aynısı
+// (göçleri uygulanmış geçici DB, okuma havuzu, önbellekli ifadeler, alias çözümleme).
+//
+//   cargo bench --no-default-features --bench barcode_scan
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use app_lib::db::Store;
+
+const PRODUCTS: usize = 5_000;
+/// Her N üründen birine ek (alias) barkod
+const ALIAS_EVERY: usize = 10;
+
+struct Seeded {
+  _dir: tempfile::TempDir,
+  store: Store,
+  barcodes: Vec<String>,
+  aliases: Vec<String>,
+}
+
+fn seed() -> Seeded {
+  let dir = tempfile::tempdir().unwrap();
+  let store = Store::open(dir.path().join("bench.sqlite")).unwrap();
+
+  let mut barcodes = Vec::with_capacity(PRODUCTS);
+  let mut aliases = Vec::new();
+  for i in 0..PRODUCTS {
+    let bc = store
+      .add_product(
+        None,
+        // otomatik ürün kodu kategori başına 999 ile sınırlı
+        Some(format!("BENCH{}", i)),
+        Some("ELBISE".into()),
+        format!("Ürün {}", i),
+        None,
+        None,
+        Some(250.0),
+        499.90,
+        Some(5),
+        Some(2),
+      )
+      .unwrap()
+      .barcode;
+    if i % ALIAS_EVERY == 0 {
+      let alias = format!("869{:010}", i);
+      store.add_product_barcode(&bc, &alias, None).unwrap();
+      aliases.push(alias);
+    }
+    barcodes.push(bc);
+  }
+  Seeded { _dir: dir, store, barcodes, aliases }
+}
+
+fn bench_scan(c: &mut Criterion) {
+  let seeded = seed();
+  let codes: Vec<&String> = seeded.barcodes.iter().step_by(97).collect();
+
+  let mut group = c.benchmark_group("barcode_scan");
+
+  group.bench_function("find_product_by_barcode", |b| {
+    let mut i = 0;
+    b.iter(|| {
+      i = (i + 1) % codes.len();
+      black_box(seeded.store.find_product_by_barcode(codes[i]).unwrap())
+    })
+  });
+
+  // önce ana barkod aranır, bulunamazsa product_barcodes
+  group.bench_function("find_product_by_alias", |b| {
+    let mut i = 0;
+    b.iter(|| {
+      i = (i + 1) % seeded.aliases.len();
+      black_box(seeded.store.find_product_by_barcode(&seeded.aliases[i]).unwrap())
+    })
+  });
+
+  group.bench_function("unknown_barcode", |b| {
+    b.iter(|| black_box(seeded.store.find_product_by_barcode("0000000000000").unwrap()))
+  });
+
+  group.finish();
+}
+
+criterion_group!(benches, bench_scan);
+criterion_main!(benches);
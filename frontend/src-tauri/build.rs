@@ -1,3 +1,5 @@
 fn main() {
+  // --no-default-features (başsız CLI) derlemesinde Tauri kaynakları üretilmez
+  #[cfg(feature = "desktop")]
   tauri_build::build()
 }
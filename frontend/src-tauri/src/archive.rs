@@ -0,0 +1,208 @@
+// -------------------- YEDEK ARŞİVİ (.cpbak) --------------------
+//
+// Düzen: MAGIC(6) | sürüm(1) | bayrak(1) | başlık uzunluğu (u32 LE) | başlık JSON | yük
+//   yük = gzip(sqlite)                         (şifresiz)
+//   yük = AES-256-GCM(gzip(sqlite))            (şifreli, anahtar = Argon2id(parola, salt))
+// Başlık JSON, GCM'de ek doğrulanmış veri (AAD): başlık değiştirilirse çözme başarısız olur.
+// sha256 sıkıştırılmamış SQLite dosyasının özetidir; arşiv her açılışta doğrulanır.
+
+use std::io::{Read, Write};
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+
+use crate::error::PosError;
+use crate::i18n::tr;
+
+pub const ARCHIVE_EXT: &str = "cpbak";
+
+const MAGIC: &[u8; 6] = b"CPBAK\0";
+const FORMAT_VERSION: u8 = 1;
+const FLAG_ENCRYPTED: u8 = 1;
+const PREFIX_LEN: usize = MAGIC.len() + 2 + 4;
+/// deflate en fazla ~1032:1 sıkıştırır; başlıktaki boyut bundan büyükse arşiv bozuk / sahte
+const MAX_DEFLATE_RATIO: u64 = 1032;
+/// Açarken ön ayırma başlığa değil yük boyutuna göre sınırlanır
+const PREALLOC_RATIO: usize = 8;
+
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ArchiveHeader {
+  // unix saniye
+  pub created_at: i64,
+  // sıkıştırılmamış SQLite boyutu
+  pub size: u64,
+  pub sha256: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub salt: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub nonce: Option<String>,
+}
+
+struct Parsed<'a> {
+  encrypted: bool,
+  header: ArchiveHeader,
+  header_bytes: &'a [u8],
+  payload: &'a [u8],
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Rastgele n bayt, hex (API anahtarı vb.)
+pub(crate) fn random_hex(n: usize) -> String {
+  let mut bytes = vec![0u8; n];
+  OsRng.fill_bytes(&mut bytes);
+  to_hex(&bytes)
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>, PosError> {
+  if s.len() % 2 != 0 {
+    return Err(PosError::Validation(tr!("Arşiv başlığı bozuk (hex)")));
+  }
+  (0..s.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| PosError::Validation(tr!("Arşiv başlığı bozuk (hex)"))))
+    .collect()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+  to_hex(&Sha256::digest(bytes))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], PosError> {
+  let mut key = [0u8; 32];
+  argon2::Argon2::default()
+    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+    .map_err(|e| PosError::Validation(tr!("Anahtar türetilemedi: {}", e)))?;
+  Ok(key)
+}
+
+/// Dosya içeriği .cpbak arşivi mi (uzantıya değil imzaya bakar)
+pub fn is_archive(bytes: &[u8]) -> bool {
+  bytes.starts_with(MAGIC)
+}
+
+fn parse(bytes: &[u8]) -> Result<Parsed<'_>, PosError> {
+  if !is_archive(bytes) || bytes.len() < PREFIX_LEN {
+    return Err(PosError::Validation(tr!("Yedek arşivi değil")));
+  }
+  let version = bytes[MAGIC.len()];
+  if version != FORMAT_VERSION {
+    return Err(PosError::Validation(tr!("Desteklenmeyen arşiv sürümü: {}", version)));
+  }
+  let flags = bytes[MAGIC.len() + 1];
+
+  let mut len = [0u8; 4];
+  len.copy_from_slice(&bytes[MAGIC.len() + 2..PREFIX_LEN]);
+  let header_end = PREFIX_LEN + u32::from_le_bytes(len) as usize;
+  if bytes.len() < header_end {
+    return Err(PosError::Validation(tr!("Arşiv eksik (başlık)")));
+  }
+
+  let header_bytes = &bytes[PREFIX_LEN..header_end];
+  let header: ArchiveHeader =
+    serde_json::from_slice(header_bytes).map_err(|e| PosError::Validation(tr!("Arşiv başlığı okunamadı: {}", e)))?;
+
+  Ok(Parsed {
+    encrypted: flags & FLAG_ENCRYPTED != 0,
+    header,
+    header_bytes,
+    payload: &bytes[header_end..],
+  })
+}
+
+/// Arşivin şifreli olup olmadığı
+pub fn is_encrypted(bytes: &[u8]) -> Result<bool, PosError> {
+  Ok(parse(bytes)?.encrypted)
+}
+
+/// SQLite dosya içeriğini arşivler. Parola verilirse şifreler.
+pub fn write_archive(sqlite: &[u8], created_at: i64, passphrase: Option<&str>) -> Result<Vec<u8>, PosError> {
+  let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+  gz.write_all(sqlite)?;
+  let compressed = gz.finish()?;
+
+  let mut header = ArchiveHeader {
+    created_at,
+    size: sqlite.len() as u64,
+    sha256: sha256_hex(sqlite),
+    salt: None,
+    nonce: None,
+  };
+
+  let mut salt = [0u8; 16];
+  let mut nonce = [0u8; 12];
+  if passphrase.is_some() {
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    header.salt = Some(to_hex(&salt));
+    header.nonce = Some(to_hex(&nonce));
+  }
+  let header_bytes = serde_json::to_vec(&header).map_err(|e| PosError::Io(e.to_string()))?;
+
+  let (flags, payload) = match passphrase {
+    Some(p) => {
+      let key = derive_key(p, &salt)?;
+      let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+      let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: &compressed, aad: &header_bytes })
+        .map_err(|_| PosError::Io(tr!("Şifreleme başarısız")))?;
+      (FLAG_ENCRYPTED, sealed)
+    }
+    None => (0, compressed),
+  };
+
+  let mut out = Vec::with_capacity(PREFIX_LEN + header_bytes.len() + payload.len());
+  out.extend_from_slice(MAGIC);
+  out.push(FORMAT_VERSION);
+  out.push(flags);
+  out.extend_from_slice(&(header_bytes.len() as u32).to_le_bytes());
+  out.extend_from_slice(&header_bytes);
+  out.extend_from_slice(&payload);
+  Ok(out)
+}
+
+/// Arşivi açar (gerekirse şifre çözer) ve sağlamayı doğrular; SQLite içeriğini döner.
+pub fn open_archive(bytes: &[u8], passphrase: Option<&str>) -> Result<Vec<u8>, PosError> {
+  let parsed = parse(bytes)?;
+  // başlık dosyadan geliyor: yükle tutarsız boyut bozuk / sahte arşivdir (şifre çözmeden önce)
+  if parsed.header.size > (parsed.payload.len() as u64).saturating_mul(MAX_DEFLATE_RATIO) {
+    return Err(PosError::Validation(tr!("Arşiv başlığı bozuk (boyut)")));
+  }
+
+  let compressed = if parsed.encrypted {
+    let passphrase = passphrase.ok_or_else(|| PosError::Validation(tr!("Yedek şifreli; parola gerekli")))?;
+    let salt = from_hex(parsed.header.salt.as_deref().unwrap_or_default())?;
+    let nonce = from_hex(parsed.header.nonce.as_deref().unwrap_or_default())?;
+    if nonce.len() != 12 || salt.is_empty() {
+      return Err(PosError::Validation(tr!("Arşiv başlığı bozuk (salt/nonce)")));
+    }
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    cipher
+      .decrypt(Nonce::from_slice(&nonce), Payload { msg: parsed.payload, aad: parsed.header_bytes })
+      .map_err(|_| PosError::Validation(tr!("Parola yanlış ya da arşiv bozuk")))?
+  } else {
+    parsed.payload.to_vec()
+  };
+
+  // başlıktaki boyuttan fazlası okunmaz (boyut farkı aşağıda sağlama hatası olur)
+  let mut sqlite = Vec::with_capacity((parsed.header.size as usize).min(compressed.len().saturating_mul(PREALLOC_RATIO)));
+  GzDecoder::new(compressed.as_slice())
+    .take(parsed.header.size.saturating_add(1))
+    .read_to_end(&mut sqlite)
+    .map_err(|e| PosError::Io(tr!("Arşiv açılamadı: {}", e)))?;
+
+  if sqlite.len() as u64 != parsed.header.size || sha256_hex(&sqlite) != parsed.header.sha256 {
+    return Err(PosError::Validation(tr!("Sağlama (SHA-256) tutmuyor; yedek bozuk")));
+  }
+  Ok(sqlite)
+}
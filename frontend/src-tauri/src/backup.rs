@@ -1,9 +1,16 @@
-use std::{fs, path::PathBuf};
-use rusqlite::Connection;
-use tauri::{AppHandle, Manager};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::fs;
+use rusqlite::{Connection, OpenFlags};
+
+use crate::archive::{self, ARCHIVE_EXT};
+use crate::db::{ListQuery, Page, Store};
+use crate::error::PosError;
+use crate::paths::AppPaths;
+use crate::i18n::tr;
 
 fn now_stamp() -> String {
-  use std::time::{SystemTime, UNIX_EPOCH};
   let secs = SystemTime::now()
     .duration_since(UNIX_EPOCH)
     .unwrap_or_default()
@@ -11,52 +18,487 @@ fn now_stamp() -> String {
   format!("{}", secs)
 }
 
-pub fn backup_dir(app: &AppHandle) -> Result<PathBuf, String> {
-  let app_dir = app
-    .path()
-    .app_data_dir()
-    .map_err(|e| e.to_string())?;
+// -------------------- YEDEK TÜRLERİ --------------------
+//
+// Dosya adı türü taşır: ciel_pos_<TÜR>_<unix saniye>.cpbak (sıkıştırılmış arşiv, archive.rs)
+// Eski sürümlerin düz SQLite kopyaları (ciel_pos_<TÜR>_<saniye>.sqlite ve
+// ciel_pos_<saniye>.sqlite) da listelenir ve geri yüklenebilir; türsüzler otomatik sayılır.
+
+const BACKUP_PREFIX: &str = "ciel_pos_";
+const LEGACY_EXT: &str = "sqlite";
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackupKind {
+  Manual,
+  Auto,
+  BeforeRestore,
+  BeforeMigration,
+  BeforeRepair,
+}
+
+impl BackupKind {
+  const ALL: [BackupKind; 5] = [
+    BackupKind::Manual,
+    BackupKind::Auto,
+    BackupKind::BeforeRestore,
+    BackupKind::BeforeMigration,
+    BackupKind::BeforeRepair,
+  ];
+
+  fn tag(self) -> &'static str {
+    match self {
+      BackupKind::Manual => "MANUAL",
+      BackupKind::Auto => "AUTO",
+      BackupKind::BeforeRestore => "BEFORE_RESTORE",
+      BackupKind::BeforeMigration => "BEFORE_MIGRATION",
+      BackupKind::BeforeRepair => "BEFORE_REPAIR",
+    }
+  }
+
+  fn file_name(self, stamp: &str) -> String {
+    format!("{}{}_{}.{}", BACKUP_PREFIX, self.tag(), stamp, ARCHIVE_EXT)
+  }
+}
+
+/// Dosya adından (tür, unix saniye). Yedek dosyası değilse None.
+fn parse_backup_name(name: &str) -> Option<(BackupKind, i64)> {
+  let rest = name.strip_prefix(BACKUP_PREFIX)?;
+  let rest = rest
+    .strip_suffix(ARCHIVE_EXT)
+    .or_else(|| rest.strip_suffix(LEGACY_EXT))?
+    .strip_suffix('.')?;
+
+  // legacy: ciel_pos_<saniye>.sqlite
+  if let Ok(secs) = rest.parse::<i64>() {
+    return Some((BackupKind::Auto, secs));
+  }
+
+  let (tag, stamp) = rest.rsplit_once('_')?;
+  let kind = BackupKind::ALL.into_iter().find(|k| k.tag() == tag)?;
+  Some((kind, stamp.parse().ok()?))
+}
+
+/// Yedek alır (ek hedefler dahil), yerel dosyanın yolunu döner.
+pub fn backup_sqlite_db(store: &Store, paths: &AppPaths, kind: BackupKind) -> Result<String, PosError> {
+  create_backup(store, paths, kind).map(|o| o.path)
+}
 
-  let dir = app_dir.join("backups");
-  fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-  Ok(dir)
+#[derive(serde::Serialize)]
+pub struct MirrorResult {
+  pub target_id: i64,
+  pub target_path: String,
+  pub ok: bool,
+  // başarılıysa hedefteki dosya
+  pub file: Option<String>,
+  pub error: Option<String>,
 }
 
-pub fn db_path(app: &AppHandle) -> Result<PathBuf, String> {
-  let app_dir = app
-    .path()
-    .app_data_dir()
-    .map_err(|e| e.to_string())?;
+#[derive(serde::Serialize)]
+pub struct BackupOutcome {
+  pub path: String,
+  pub mirrors: Vec<MirrorResult>,
+}
 
-  Ok(app_dir.join("ciel_pos.sqlite"))
+/// Yerel yedek + ek hedeflere kopya. Ek hedef hataları yedeği başarısız saymaz,
+/// `mirrors` içinde ve backup_targets tablosunda raporlanır.
+pub fn create_backup(store: &Store, paths: &AppPaths, kind: BackupKind) -> Result<BackupOutcome, PosError> {
+  let path = write_local_backup(store, paths, kind)?;
+  let mirrors = mirror_backup(store, Path::new(&path));
+  Ok(BackupOutcome { path, mirrors })
 }
 
-pub fn backup_sqlite_db(app: &AppHandle) -> Result<String, String> {
-  let src_path = db_path(app)?;
+fn write_local_backup(store: &Store, paths: &AppPaths, kind: BackupKind) -> Result<String, PosError> {
+  let passphrase = store.backup_passphrase()?;
+  write_local_backup_with(paths, kind, passphrase.as_deref())
+}
+
+// DB bağlantısı almaz; özel DB erişimi (Store::with_exclusive_conn) içinden de çağrılabilir
+fn write_local_backup_with(paths: &AppPaths, kind: BackupKind, passphrase: Option<&str>) -> Result<String, PosError> {
+  let src_path = paths.db_path();
   if !src_path.exists() {
-    return Err(format!("DB bulunamadı: {}", src_path.display()));
+    return Err(PosError::NotFound(tr!("DB bulunamadı: {}", src_path.display())));
   }
 
-  let backups = backup_dir(app)?;
-  let file_name = format!("ciel_pos_{}.sqlite", now_stamp());
-  let dst_path = backups.join(file_name);
+  let backups = paths.backup_dir()?;
+  let stamp = now_stamp();
+  let dst_path = backups.join(kind.file_name(&stamp));
+
+  // önce tutarlı bir SQLite kopyası (backup API, WAL dahil), sonra arşiv
+  let snapshot = dst_path.with_extension("snapshot.tmp");
+  let result = (|| {
+    let src = Connection::open(&src_path)?;
+    let mut dst = Connection::open(&snapshot)?;
 
-  let src = Connection::open(&src_path).map_err(|e| e.to_string())?;
-  let mut dst = Connection::open(&dst_path).map_err(|e| e.to_string())?;
+    let bk = rusqlite::backup::Backup::new(&src, &mut dst)?;
+    bk.step(-1)?;
+    drop(bk);
+    drop(dst);
 
-  let bk = rusqlite::backup::Backup::new(&src, &mut dst)
-    .map_err(|e: rusqlite::Error| e.to_string())?;
+    let sqlite = fs::read(&snapshot)?;
+    let bytes = archive::write_archive(&sqlite, stamp.parse().unwrap_or(0), passphrase)?;
+
+    let part = dst_path.with_extension("part");
+    fs::write(&part, bytes).map_err(|e| PosError::Io(tr!("Yedek yazılamadı: {}", e)))?;
+    fs::rename(&part, &dst_path).map_err(|e| PosError::Io(tr!("Yedek yazılamadı: {}", e)))
+  })();
+  let _ = fs::remove_file(&snapshot);
+  result?;
 
-  bk.step(-1).map_err(|e: rusqlite::Error| e.to_string())?;
   Ok(dst_path.to_string_lossy().to_string())
 }
 
+/// Tek dosyayı hedefe kopyalar (.part + rename: yarım dosya yedek sayılmaz)
+fn copy_to_target(file: &Path, target: &Path) -> Result<String, PosError> {
+  if !target.is_dir() {
+    return Err(PosError::Validation(tr!("Klasör erişilemiyor: {}", target.display())));
+  }
+  let name = file.file_name().ok_or_else(|| PosError::Validation(tr!("Geçersiz yedek dosyası")))?;
+  let dst = target.join(name);
+  let part = dst.with_extension("part");
+
+  let result = fs::copy(file, &part)
+    .and_then(|_| fs::rename(&part, &dst))
+    .map_err(|e| PosError::Validation(tr!("Kopyalanamadı: {}", e)));
+  if result.is_err() {
+    let _ = fs::remove_file(&part);
+  }
+  result.map(|_| dst.to_string_lossy().to_string())
+}
+
+/// Yedeği aktif tüm ek hedeflere kopyalar, her hedefin durumunu kaydeder.
+fn mirror_backup(store: &Store, file: &Path) -> Vec<MirrorResult> {
+  let targets = match store.list_backup_targets(&ListQuery::all()) {
+    Ok(t) => t.items,
+    Err(e) => {
+      eprintln!("[backup] yedek hedefleri okunamadı: {}", e);
+      return Vec::new();
+    }
+  };
+
+  targets
+    .into_iter()
+    .filter(|t| t.is_active == 1)
+    .map(|t| {
+      let result = copy_to_target(file, Path::new(&t.path));
+      if let Err(e) = store.record_backup_target_status(t.id, &result) {
+        eprintln!("[backup] hedef durumu kaydedilemedi: {}", e);
+      }
+      let (file, error) = match result {
+        Ok(f) => (Some(f), None),
+        Err(e) => (None, Some(e.to_string())),
+      };
+      MirrorResult {
+        target_id: t.id,
+        target_path: t.path,
+        ok: error.is_none(),
+        file,
+        error,
+      }
+    })
+    .collect()
+}
+
+/// Yedek dosyasını okuyup SQLite içeriğini döner. Arşivse sağlama doğrulanır,
+/// şifreliyse verilen (yoksa kayıtlı) parola ile çözülür. Düz .sqlite olduğu gibi döner.
+pub fn read_backup(store: &Store, path: &Path, passphrase: Option<&str>) -> Result<Vec<u8>, PosError> {
+  let bytes = fs::read(path).map_err(|e| PosError::Io(tr!("Yedek okunamadı: {}", e)))?;
+  if !archive::is_archive(&bytes) {
+    return Ok(bytes);
+  }
+
+  let stored = match passphrase {
+    Some(_) => None,
+    None => store.backup_passphrase().ok().flatten(),
+  };
+  archive::open_archive(&bytes, passphrase.or(stored.as_deref()))
+}
+
+// -------------------- YEDEK LİSTESİ --------------------
+
+#[derive(serde::Serialize)]
+pub struct BackupInfo {
+  pub file_name: String,
+  pub path: String,
+  pub kind: BackupKind,
+  pub size_bytes: u64,
+  // .cpbak arşivi mi (değilse eski düz SQLite kopyası)
+  pub compressed: bool,
+  pub encrypted: bool,
+  pub timestamp: i64,
+  // yerel saat, "YYYY-MM-DD HH:MM:SS"
+  pub created_at: String,
+}
+
+/// verify_backup sonucu (listeleme dosyayı açmaz; doğrulama ayrı ve açıkça istenir)
+#[derive(serde::Serialize)]
+pub struct BackupVerification {
+  pub file_name: String,
+  pub valid: bool,
+  // geçersizse nedeni
+  pub error: Option<String>,
+}
+
+struct BackupFile {
+  path: PathBuf,
+  kind: BackupKind,
+  timestamp: i64,
+}
+
+fn scan_backups(dir: &Path) -> Result<Vec<BackupFile>, PosError> {
+  let mut out = Vec::new();
+  for entry in fs::read_dir(dir)? {
+    let entry = entry?;
+    let name = entry.file_name().to_string_lossy().to_string();
+    if let Some((kind, timestamp)) = parse_backup_name(&name) {
+      out.push(BackupFile { path: entry.path(), kind, timestamp });
+    }
+  }
+  // en yeni başta
+  out.sort_by_key(|b| std::cmp::Reverse(b.timestamp));
+  Ok(out)
+}
+
+fn local_time(secs: i64) -> Option<chrono::DateTime<chrono::Local>> {
+  chrono::DateTime::from_timestamp(secs, 0).map(|t| t.with_timezone(&chrono::Local))
+}
+
+/// Salt okunur açıp quick_check + products tablosu kontrolü.
+fn verify_sqlite_file(path: &Path) -> Result<(), PosError> {
+  let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+  let check: String = conn
+    .query_row("PRAGMA quick_check", [], |r| r.get(0))?;
+  if check != "ok" {
+    return Err(PosError::Validation(check));
+  }
+
+  let has_products: i64 = conn
+    .query_row(
+      "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='products'",
+      [],
+      |r| r.get(0),
+    )?;
+  if has_products == 0 {
+    return Err(PosError::Validation(tr!("products tablosu yok")));
+  }
+
+  Ok(())
+}
+
+/// Yedeği açıp (arşivse sağlama + şifre çözme) geçici dosyaya yazar, SQLite kontrolünden
+/// sonra salt-okunur bağlantıyla `f`'i çalıştırır. Geçici dosyalar her durumda silinir.
+fn with_backup_conn<T>(
+  store: &Store,
+  path: &Path,
+  passphrase: Option<&str>,
+  f: impl FnOnce(&Connection) -> Result<T, PosError>,
+) -> Result<T, PosError> {
+  let sqlite = read_backup(store, path, passphrase)?;
+  // eşzamanlı doğrulamalar çakışmasın diye her çağrı kendi klasöründe; yedek WAL modundaysa
+  // okurken oluşan -wal/-shm de klasörle birlikte silinir
+  let tmp_dir = tempfile::Builder::new().prefix("ciel_pos_verify_").tempdir()?;
+  let tmp = tmp_dir.path().join("backup.sqlite");
+  fs::write(&tmp, sqlite)?;
+  verify_sqlite_file(&tmp).and_then(|_| {
+    let conn = Connection::open_with_flags(&tmp, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    f(&conn)
+  })
+}
+
+/// Dosyanın başından arşiv / şifre bilgisi
+fn archive_flags(path: &Path) -> (bool, bool) {
+  let mut head = vec![0u8; 4096];
+  let n = fs::File::open(path)
+    .and_then(|mut f| std::io::Read::read(&mut f, &mut head))
+    .unwrap_or(0);
+  head.truncate(n);
+  if !archive::is_archive(&head) {
+    return (false, false);
+  }
+  (true, archive::is_encrypted(&head).unwrap_or(false))
+}
+
+/// Yalnız dosya adı, boyut ve arşiv başlığı okunur (şifre çözme / SQLite kontrolü yok).
+/// En yeni başta; q dosya adında arar. Boyut ve başlık yalnız istenen sayfadaki dosyalar için okunur.
+pub fn list_backups(paths: &AppPaths, query: &ListQuery) -> Result<Page<BackupInfo>, PosError> {
+  let dir = paths.backup_dir()?;
+  let q = query.q.as_deref().map(|q| q.trim().to_lowercase()).filter(|q| !q.is_empty());
+
+  let found: Vec<BackupFile> = scan_backups(&dir)?
+    .into_iter()
+    .filter(|b| match &q {
+      Some(q) => file_name(&b.path).to_lowercase().contains(q),
+      None => true,
+    })
+    .collect();
+
+  Ok(query.paginate(found).map(|b| {
+    let size_bytes = fs::metadata(&b.path).map(|m| m.len()).unwrap_or(0);
+    let (compressed, encrypted) = archive_flags(&b.path);
+    BackupInfo {
+      file_name: file_name(&b.path),
+      path: b.path.to_string_lossy().to_string(),
+      kind: b.kind,
+      size_bytes,
+      compressed,
+      encrypted,
+      timestamp: b.timestamp,
+      created_at: local_time(b.timestamp)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default(),
+    }
+  }))
+}
+
+fn file_name(path: &Path) -> String {
+  path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Yedeği açıp (sağlama + şifre çözme) SQLite bütünlüğünü kontrol eder. Yanlış parola / bozuk
+/// arşiv / bozuk DB hata değil, `valid: false` ve nedeni olarak döner.
+pub fn verify_backup(store: &Store, paths: &AppPaths, payload: InspectBackupPayload) -> Result<BackupVerification, PosError> {
+  let canon_backup = resolve_backup_path(paths, &payload.backup_path)?;
+  let passphrase = payload.passphrase.as_deref().map(str::trim).filter(|p| !p.is_empty());
+  let check = with_backup_conn(store, &canon_backup, passphrase, |_| Ok(()));
+  Ok(BackupVerification {
+    file_name: file_name(&canon_backup),
+    valid: check.is_ok(),
+    error: check.err().map(|e| e.to_string()),
+  })
+}
+
+// -------------------- SAKLAMA (ROTASYON) --------------------
+//
+// Sadece otomatik yedekler silinir. Manuel ve güvenlik (geri yükleme / göç / onarım
+// öncesi) yedeklerine dokunulmaz. Her gün / hafta / ay için o dönemin en yeni yedeği
+// tutulur; en yeni otomatik yedek her zaman kalır. Aynı politika ek hedeflere de uygulanır.
+
+/// Silinen dosyaların yollarını döner. Erişilemeyen ek hedefler atlanır.
+pub fn prune_backups(store: &Store, paths: &AppPaths, policy: &crate::db::BackupPolicy) -> Result<Vec<String>, PosError> {
+  let mut removed = prune_dir(&paths.backup_dir()?, policy)?;
+
+  for t in store.list_backup_targets(&ListQuery::all())?.items {
+    let dir = PathBuf::from(&t.path);
+    if t.is_active != 1 || !dir.is_dir() {
+      continue;
+    }
+    match prune_dir(&dir, policy) {
+      Ok(r) => removed.extend(r),
+      Err(e) => eprintln!("[backup] {} temizlenemedi: {}", t.path, e),
+    }
+  }
+  Ok(removed)
+}
+
+fn prune_dir(dir: &Path, policy: &crate::db::BackupPolicy) -> Result<Vec<String>, PosError> {
+  let autos: Vec<BackupFile> = scan_backups(dir)?
+    .into_iter()
+    .filter(|b| b.kind == BackupKind::Auto)
+    .collect();
+
+  let mut keep: HashSet<&Path> = HashSet::new();
+  if let Some(newest) = autos.first() {
+    keep.insert(&newest.path);
+  }
+
+  for (n, fmt) in [
+    (policy.keep_daily, "%Y-%m-%d"),
+    (policy.keep_weekly, "%G-W%V"),
+    (policy.keep_monthly, "%Y-%m"),
+  ] {
+    let mut periods: Vec<String> = Vec::new();
+    for b in &autos {
+      let Some(t) = local_time(b.timestamp) else { continue };
+      let key = t.format(fmt).to_string();
+      if periods.contains(&key) {
+        continue;
+      }
+      if periods.len() as i64 >= n {
+        break;
+      }
+      periods.push(key);
+      keep.insert(&b.path);
+    }
+  }
+
+  let mut removed = Vec::new();
+  for b in &autos {
+    if keep.contains(b.path.as_path()) {
+      continue;
+    }
+    fs::remove_file(&b.path).map_err(|e| PosError::Io(tr!("Eski yedek silinemedi: {}", e)))?;
+    removed.push(b.path.to_string_lossy().to_string());
+  }
+  Ok(removed)
+}
+
+/// Otomatik yedek + saklama politikası.
+pub fn auto_backup(store: &Store, paths: &AppPaths) -> Result<String, PosError> {
+  let path = backup_sqlite_db(store, paths, BackupKind::Auto)?;
+  let policy = store.get_backup_policy()?;
+  prune_backups(store, paths, &policy)?;
+  Ok(path)
+}
+
+// DB (veya WAL) son otomatik yedekten sonra değişti mi
+fn changed_since_last_auto(paths: &AppPaths) -> Result<bool, PosError> {
+  let dir = paths.backup_dir()?;
+  let Some(last) = scan_backups(&dir)?.into_iter().find(|b| b.kind == BackupKind::Auto) else {
+    return Ok(true);
+  };
+
+  let db = paths.db_path();
+  let modified = [db.clone(), db.with_extension("sqlite-wal")]
+    .iter()
+    .filter_map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+    .max();
+
+  Ok(match modified {
+    Some(m) => {
+      let secs = m.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+      secs >= last.timestamp
+    }
+    None => false,
+  })
+}
+
+/// Uygulama açıkken arka planda periyodik otomatik yedek.
+/// Aralık her dakika ayarlardan okunur; DB değişmediyse yedek alınmaz.
+pub fn start_backup_scheduler(store: Store, paths: AppPaths) {
+  std::thread::spawn(move || {
+    let mut last_run = Instant::now();
+    loop {
+      std::thread::sleep(Duration::from_secs(60));
+
+      let interval = match store.get_backup_policy() {
+        Ok(p) => p.interval_minutes,
+        Err(_) => continue,
+      };
+      if interval <= 0 || last_run.elapsed() < Duration::from_secs(interval as u64 * 60) {
+        continue;
+      }
+      last_run = Instant::now();
+
+      if !changed_since_last_auto(&paths).unwrap_or(true) {
+        continue;
+      }
+      if let Err(e) = auto_backup(&store, &paths) {
+        eprintln!("[backup] periyodik yedek alınamadı: {}", e);
+      }
+    }
+  });
+}
+
 // -------------------- RESTORE (GERİ YÜKLEME) --------------------
 
 #[derive(serde::Deserialize)]
 pub struct RestoreFromBackupPayload {
 
   pub backup_path: String,
+  // şifreli arşiv için; verilmezse kayıtlı yedek parolası denenir
+  #[serde(default)]
+  pub passphrase: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -64,18 +506,20 @@ pub struct RestoreFromBackupResult {
   pub restored_db_path: String,
   pub used_backup_path: String,
   pub safety_backup_path: String,
+  // yedeğin şema sürümü; daha eskiyse geri yüklemeden sonra göçler uygulanır
+  pub backup_schema_version: i64,
+  pub schema_version: i64,
 }
 
+/// Kullanıcının verdiği yedek yolunu çözer; backups klasörü dışına çıkılamaz.
+fn resolve_backup_path(paths: &AppPaths, raw: &str) -> Result<PathBuf, PosError> {
+  let backups_dir = paths.backup_dir()?;
 
-pub fn restore_from_backup(app: &AppHandle, payload: RestoreFromBackupPayload) -> Result<RestoreFromBackupResult, String> {
-  let db = db_path(app)?;
-  let backups_dir = backup_dir(app)?;
-
-  if payload.backup_path.trim().is_empty() {
-    return Err("backup_path boş olamaz".into());
+  if raw.trim().is_empty() {
+    return Err(PosError::Validation(tr!("backup_path boş olamaz")));
   }
 
-  let candidate = PathBuf::from(payload.backup_path.trim());
+  let candidate = PathBuf::from(raw.trim());
 
   let backup_file: PathBuf = if candidate.is_absolute() {
     candidate
@@ -84,51 +528,272 @@ pub fn restore_from_backup(app: &AppHandle, payload: RestoreFromBackupPayload) -
   };
 
   if !backup_file.exists() {
-    return Err(format!("Backup dosyası bulunamadı: {}", backup_file.display()));
+    return Err(PosError::NotFound(tr!("Backup dosyası bulunamadı: {}", backup_file.display())));
   }
 
-  let canon_backup = fs::canonicalize(&backup_file).map_err(|e| e.to_string())?;
-  let canon_backups_dir = fs::canonicalize(&backups_dir).map_err(|e| e.to_string())?;
+  let canon_backup = fs::canonicalize(&backup_file)?;
+  let canon_backups_dir = fs::canonicalize(&backups_dir)?;
   if !canon_backup.starts_with(&canon_backups_dir) {
-    return Err("Güvenlik: Backup dosyası backups klasörünün dışında olamaz.".into());
+    return Err(PosError::Validation(tr!("Güvenlik: Backup dosyası backups klasörünün dışında olamaz.")));
   }
+  Ok(canon_backup)
+}
 
-  let safety_name = format!("ciel_pos_BEFORE_RESTORE_{}.sqlite", now_stamp());
-  let safety_path = backups_dir.join(safety_name);
+// Yedeği canlı DB'ye backup API ile (ters yönde) kopyalar. Dosya silinmez/taşınmaz;
+// açık bağlantılar yeni içeriği bir sonraki sorguda görür, uygulamayı yeniden başlatmak gerekmez.
+// Cihaza özel ayarlar (yedek parolası / politikası / hedefleri, yerel API) yedekten gelmez, korunur.
+pub fn restore_from_backup(store: &Store, paths: &AppPaths, payload: RestoreFromBackupPayload) -> Result<RestoreFromBackupResult, PosError> {
+  let db = paths.db_path();
+  let canon_backup = resolve_backup_path(paths, &payload.backup_path)?;
 
-  if db.exists() {
+  // arşivi aç + sağlamayı doğrula + şifreyi çöz; mevcut DB'ye dokunmadan önce
+  let passphrase = payload.passphrase.as_deref().map(str::trim).filter(|p| !p.is_empty());
+  let sqlite = read_backup(store, &canon_backup, passphrase)?;
 
-    fs::copy(&db, &safety_path).map_err(|e| {
-      format!(
-        "Mevcut DB safety backup alınamadı (DB kilitli olabilir). Uygulamayı kapatıp tekrar deneyin. Hata: {}",
-        e
-      )
-    })?;
+  let tmp_path = db.with_extension("sqlite.tmp_restore");
+  fs::write(&tmp_path, sqlite).map_err(|e| PosError::Io(tr!("Restore kopyalama hatası: {}", e)))?;
+  let result = restore_verified(store, paths, &db, &tmp_path);
+  // yedek WAL modundaysa okurken -wal/-shm oluşur
+  for ext in ["sqlite.tmp_restore", "sqlite.tmp_restore-wal", "sqlite.tmp_restore-shm"] {
+    let _ = fs::remove_file(db.with_extension(ext));
   }
+  let (safety_path, backup_version) = result?;
 
-  let tmp_path = db.with_extension("sqlite.tmp_restore");
+  // ek hedeflere kopyalama kilit dışında (yavaş olabilir)
+  if !safety_path.is_empty() {
+    mirror_backup(store, Path::new(&safety_path));
+  }
 
-  fs::copy(&canon_backup, &tmp_path).map_err(|e| format!("Restore kopyalama hatası: {}", e))?;
+  Ok(RestoreFromBackupResult {
+    restored_db_path: db.to_string_lossy().to_string(),
+    used_backup_path: canon_backup.to_string_lossy().to_string(),
+    safety_backup_path: safety_path,
+    backup_schema_version: backup_version,
+    schema_version: crate::db::schema_version(),
+  })
+}
 
+fn restore_verified(store: &Store, paths: &AppPaths, db: &Path, tmp_path: &Path) -> Result<(String, i64), PosError> {
+  if let Err(e) = verify_sqlite_file(tmp_path) {
+    return Err(PosError::Validation(tr!("Yedek geçerli bir veritabanı değil: {}", e)));
+  }
 
-  if db.exists() {
-    fs::remove_file(&db).map_err(|e| {
-      format!(
-        "Mevcut DB silinemedi (DB kilitli olabilir). Uygulamayı kapatıp tekrar deneyin. Hata: {}",
-        e
-      )
-    })?;
+  let src = Connection::open(tmp_path)?;
+  let backup_version = crate::db::stored_schema_version(&src)?;
+  if backup_version > crate::db::schema_version() {
+    return Err(PosError::Conflict(tr!(
+      "Yedek daha yeni bir sürümle alınmış (şema {} > {}). Önce uygulamayı güncelleyin.",
+      backup_version,
+      crate::db::schema_version()
+    )));
   }
 
-  fs::rename(&tmp_path, &db).map_err(|e| format!("Restore finalize hatası: {}", e))?;
+  // göç geçici kopyada: başarısız olursa canlı DB'ye hiç dokunulmamış olur
+  crate::db::migrate_restored(&src)
+    .map_err(|e| PosError::Db(tr!("Yedek güncel şemaya getirilemedi: {}", e)))?;
+  if let Err(e) = verify_sqlite_file(tmp_path) {
+    return Err(PosError::Validation(tr!("Güncellenen yedek doğrulanamadı: {}", e)));
+  }
 
-  // WAL ve SHM dosyalarını temizle (eski DB'den kalan)
-  let _ = fs::remove_file(db.with_extension("sqlite-wal"));
-  let _ = fs::remove_file(db.with_extension("sqlite-shm"));
+  // kilit içinde read() çağrılamaz; parola önceden okunur
+  let stored_passphrase = store.backup_passphrase()?;
 
-  Ok(RestoreFromBackupResult {
-    restored_db_path: db.to_string_lossy().to_string(),
-    used_backup_path: canon_backup.to_string_lossy().to_string(),
-    safety_backup_path: safety_path.to_string_lossy().to_string(),
+  store.with_exclusive_conn(|live| {
+    let safety_path = if db.exists() {
+      write_local_backup_with(paths, BackupKind::BeforeRestore, stored_passphrase.as_deref())
+        .map_err(|e| PosError::Db(tr!("Mevcut DB güvenlik yedeği alınamadı: {}", e)))?
+    } else {
+      String::new()
+    };
+
+    crate::db::copy_device_settings(live, &src)?;
+    {
+      let bk = rusqlite::backup::Backup::new(&src, live)?;
+      bk.run_to_completion(256, Duration::from_millis(20), None)
+        .map_err(|e| PosError::Db(tr!("Geri yükleme başarısız (güvenlik yedeği: {}): {}", safety_path, e)))?;
+    }
+
+    crate::db::load_language(live)?;
+    Ok((safety_path, backup_version))
+  })
+}
+
+// -------------------- YEDEK ÖNİZLEME --------------------
+//
+// Geri yüklemeden önce yedeğin içeriği ve mevcut DB'ye göre farkı. Hiçbir şey yazılmaz.
+
+#[derive(serde::Deserialize)]
+pub struct InspectBackupPayload {
+  pub backup_path: String,
+  #[serde(default)]
+  pub passphrase: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct DbSummary {
+  pub schema_version: i64,
+  pub product_count: i64,
+  pub sale_count: i64,
+  pub last_sale_at: Option<String>,
+  // iptal edilmemiş satışlar
+  pub sales_total: f64,
+  pub expense_count: i64,
+  pub expenses_total: f64,
+}
+
+#[derive(serde::Serialize)]
+pub struct BackupDiff {
+  // mevcut DB - yedek
+  pub products: i64,
+  pub sales: i64,
+  pub expenses: i64,
+  pub sales_total: f64,
+  pub expenses_total: f64,
+  // yedekten sonra eklenen (geri yüklenirse kaybolacak) satışlar
+  pub sales_missing: i64,
+  pub sales_missing_since: Option<String>,
+  pub expenses_missing: i64,
+  pub expenses_missing_since: Option<String>,
+  // okunabilir özet satırları
+  pub summary: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct BackupInspection {
+  pub path: String,
+  pub file_name: String,
+  pub kind: Option<BackupKind>,
+  pub compressed: bool,
+  pub encrypted: bool,
+  pub backup: DbSummary,
+  pub current: DbSummary,
+  pub diff: BackupDiff,
+  // geri yüklenebilir mi (şema sürümü uygulamadan yeni değil)
+  pub restorable: bool,
+}
+
+fn db_summary(conn: &Connection) -> Result<DbSummary, PosError> {
+  let one = |sql: &str| -> Result<(i64, f64), PosError> {
+    conn
+      .query_row(sql, [], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, f64>(1)?)))
+      .map_err(PosError::from)
+  };
+
+  let (product_count, _) = one("SELECT COUNT(*), 0.0 FROM products")?;
+  let (sale_count, sales_total) =
+    one("SELECT COUNT(*), COALESCE(SUM(CASE WHEN COALESCE(voided,0)=0 THEN total ELSE 0 END),0.0) FROM sales")?;
+  let (expense_count, expenses_total) = one("SELECT COUNT(*), COALESCE(SUM(amount),0.0) FROM expenses")?;
+  let last_sale_at: Option<String> = conn
+    .query_row("SELECT MAX(sold_at) FROM sales", [], |r| r.get(0))?;
+
+  Ok(DbSummary {
+    schema_version: crate::db::stored_schema_version(conn)?,
+    product_count,
+    sale_count,
+    last_sale_at,
+    sales_total,
+    expense_count,
+    expenses_total,
+  })
+}
+
+// Yedekteki en büyük id'den sonra mevcut DB'ye eklenen satırlar: (adet, en eski tarih)
+fn rows_after(current: &Connection, backup: &Connection, table: &str, at_col: &str) -> Result<(i64, Option<String>), PosError> {
+  let max_id: i64 = backup
+    .query_row(&format!("SELECT COALESCE(MAX(id),0) FROM {}", table), [], |r| r.get(0))?;
+  current
+    .query_row(
+      &format!("SELECT COUNT(*), MIN({}) FROM {} WHERE id > ?1", at_col, table),
+      [max_id],
+      |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .map_err(PosError::from)
+}
+
+fn diff_summary(backup: &DbSummary, current: &DbSummary, diff: &BackupDiff) -> Vec<String> {
+  let mut out = Vec::new();
+  if diff.sales_missing > 0 {
+    out.push(tr!(
+      "Yedekte {} satış yok ({} tarihinden beri)",
+      diff.sales_missing,
+      diff.sales_missing_since.as_deref().unwrap_or("?")
+    ));
+  }
+  if diff.expenses_missing > 0 {
+    out.push(tr!(
+      "Yedekte {} gider yok ({} tarihinden beri)",
+      diff.expenses_missing,
+      diff.expenses_missing_since.as_deref().unwrap_or("?")
+    ));
+  }
+  if diff.products > 0 {
+    out.push(tr!("Mevcut DB'de {} ürün fazla", diff.products));
+  } else if diff.products < 0 {
+    out.push(tr!("Yedekte {} ürün fazla", -diff.products));
+  }
+  if diff.sales < 0 {
+    out.push(tr!("Yedekte mevcut DB'de olmayan {} satış var", -diff.sales));
+  }
+  if backup.schema_version > current.schema_version {
+    out.push(tr!(
+      "Yedek daha yeni bir sürümle alınmış (şema {} > {}); geri yüklenemez",
+      backup.schema_version, current.schema_version
+    ));
+  } else if backup.schema_version < current.schema_version {
+    out.push(tr!(
+      "Yedek eski şemada (v{}); geri yüklenirse v{}'e güncellenir",
+      backup.schema_version, current.schema_version
+    ));
+  }
+  if out.is_empty() {
+    out.push(tr!("Yedek mevcut DB ile aynı görünüyor"));
+  }
+  out
+}
+
+pub fn inspect_backup(store: &Store, paths: &AppPaths, payload: InspectBackupPayload) -> Result<BackupInspection, PosError> {
+  let canon_backup = resolve_backup_path(paths, &payload.backup_path)?;
+  let passphrase = payload.passphrase.as_deref().map(str::trim).filter(|p| !p.is_empty());
+
+  let current_conn = store.read()?;
+  let current = db_summary(&current_conn)?;
+
+  let (backup, diff) = with_backup_conn(store, &canon_backup, passphrase, |conn| {
+    let backup = db_summary(conn)?;
+    let (sales_missing, sales_missing_since) = rows_after(&current_conn, conn, "sales", "sold_at")?;
+    let (expenses_missing, expenses_missing_since) = rows_after(&current_conn, conn, "expenses", "spent_at")?;
+
+    let mut diff = BackupDiff {
+      products: current.product_count - backup.product_count,
+      sales: current.sale_count - backup.sale_count,
+      expenses: current.expense_count - backup.expense_count,
+      sales_total: current.sales_total - backup.sales_total,
+      expenses_total: current.expenses_total - backup.expenses_total,
+      sales_missing,
+      sales_missing_since,
+      expenses_missing,
+      expenses_missing_since,
+      summary: Vec::new(),
+    };
+    diff.summary = diff_summary(&backup, &current, &diff);
+    Ok((backup, diff))
+  })?;
+
+  let file_name = canon_backup
+    .file_name()
+    .map(|s| s.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let (compressed, encrypted) = archive_flags(&canon_backup);
+
+  Ok(BackupInspection {
+    path: canon_backup.to_string_lossy().to_string(),
+    kind: parse_backup_name(&file_name).map(|(kind, _)| kind),
+    file_name,
+    compressed,
+    encrypted,
+    restorable: backup.schema_version <= current.schema_version,
+    backup,
+    current,
+    diff,
   })
 }
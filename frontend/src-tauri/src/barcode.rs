@@ -0,0 +1,172 @@
+// -------------------- BARKOD SEMBOLOJİLERİ (EAN-13 / Code 128) --------------------
+//
+// Çıktı: modül dizisi (true = siyah çubuk). Etiket çizimi labels.rs'te.
+
+use crate::error::PosError;
+
+/// EAN-13 kontrol hanesi (ilk 12 hane için).
+/// Sağdan sola: tek pozisyonlar x3, çift pozisyonlar x1.
+pub fn ean13_check_digit(first12: &str) -> Result<u32, PosError> {
+  if first12.len() != 12 || !first12.chars().all(|c| c.is_ascii_digit()) {
+    return Err(PosError::Validation(format!("EAN-13 için 12 rakam gerekli: {}", first12)));
+  }
+
+  let sum: u32 = first12
+    .chars()
+    .rev()
+    .enumerate()
+    .map(|(i, c)| {
+      let d = c.to_digit(10).unwrap_or(0);
+      if i % 2 == 0 { d * 3 } else { d }
+    })
+    .sum();
+
+  Ok((10 - sum % 10) % 10)
+}
+
+pub fn is_valid_ean13(code: &str) -> bool {
+  if code.len() != 13 || !code.chars().all(|c| c.is_ascii_digit()) {
+    return false;
+  }
+  match ean13_check_digit(&code[..12]) {
+    Ok(d) => code[12..].parse::<u32>().ok() == Some(d),
+    Err(_) => false,
+  }
+}
+
+/// 12 haneye kontrol hanesini ekler.
+pub fn ean13_with_check_digit(first12: &str) -> Result<String, PosError> {
+  let d = ean13_check_digit(first12)?;
+  Ok(format!("{}{}", first12, d))
+}
+
+const EAN_L: [&str; 10] = [
+  "0001101", "0011001", "0010011", "0111101", "0100011",
+  "0110001", "0101111", "0111011", "0110111", "0001011",
+];
+
+const EAN_G: [&str; 10] = [
+  "0100111", "0110011", "0011011", "0100001", "0011101",
+  "0111001", "0000101", "0010001", "0001001", "0010111",
+];
+
+const EAN_R: [&str; 10] = [
+  "1110010", "1100110", "1101100", "1000010", "1011100",
+  "1001110", "1010000", "1000100", "1001000", "1110100",
+];
+
+// ilk hane, soldaki 6 hanenin L/G paritesini belirler
+const EAN_PARITY: [&str; 10] = [
+  "LLLLLL", "LLGLGG", "LLGGLG", "LLGGGL", "LGLLGG",
+  "LGGLLG", "LGGGLL", "LGLGLG", "LGLGGL", "LGGLGL",
+];
+
+fn push_bits(out: &mut Vec<bool>, bits: &str) {
+  out.extend(bits.chars().map(|c| c == '1'));
+}
+
+/// 95 modüllük EAN-13 deseni (sessiz bölge hariç).
+pub fn encode_ean13(code: &str) -> Result<Vec<bool>, PosError> {
+  if !is_valid_ean13(code) {
+    return Err(PosError::Validation(format!("Geçersiz EAN-13: {}", code)));
+  }
+
+  let digits: Vec<usize> = code
+    .chars()
+    .map(|c| c.to_digit(10).unwrap_or(0) as usize)
+    .collect();
+  let parity = EAN_PARITY[digits[0]].as_bytes();
+
+  let mut out = Vec::with_capacity(95);
+  push_bits(&mut out, "101");
+  for i in 0..6 {
+    let d = digits[i + 1];
+    let pattern = if parity[i] == b'L' { EAN_L[d] } else { EAN_G[d] };
+    push_bits(&mut out, pattern);
+  }
+  push_bits(&mut out, "01010");
+  for &d in &digits[7..13] {
+    push_bits(&mut out, EAN_R[d]);
+  }
+  push_bits(&mut out, "101");
+
+  Ok(out)
+}
+
+// Code 128 çubuk/boşluk genişlikleri (değer 0..=105), her biri 11 modül.
+const CODE128_WIDTHS: [&str; 106] = [
+  "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312",
+  "132212", "221213", "221312", "231212", "112232", "122132", "122231", "113222",
+  "123122", "123221", "223211", "221132", "221231", "213212", "223112", "312131",
+  "311222", "321122", "321221", "312212", "322112", "322211", "212123", "212321",
+  "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+  "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121",
+  "313121", "211331", "231131", "213113", "213311", "213131", "311123", "311321",
+  "331121", "312113", "312311", "332111", "314111", "221411", "431111", "111224",
+  "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+  "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+  "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112",
+  "421211", "212141", "214121", "412121", "111143", "111341", "131141", "114113",
+  "114311", "411113", "411311", "113141", "114131", "311141", "411131", "211412",
+  "211214", "211232",
+];
+
+const CODE128_START_B: usize = 104;
+const CODE128_STOP: &str = "2331112";
+
+fn push_widths(out: &mut Vec<bool>, widths: &str) {
+  for (i, w) in widths.chars().enumerate() {
+    let n = w.to_digit(10).unwrap_or(0);
+    // çift indeksler çubuk, tek indeksler boşluk
+    for _ in 0..n {
+      out.push(i % 2 == 0);
+    }
+  }
+}
+
+/// Code 128 (B kümesi) deseni. EAN-13 olmayan eski sıralı barkodlar için.
+pub fn encode_code128(text: &str) -> Result<Vec<bool>, PosError> {
+  if text.is_empty() {
+    return Err(PosError::Validation("Barkod boş olamaz".to_string()));
+  }
+
+  let mut values: Vec<usize> = Vec::with_capacity(text.len() + 2);
+  values.push(CODE128_START_B);
+  for ch in text.chars() {
+    let c = ch as u32;
+    if !(32..=126).contains(&c) {
+      return Err(PosError::Validation(format!("Code 128 için desteklenmeyen karakter: {}", ch)));
+    }
+    values.push((c - 32) as usize);
+  }
+
+  let checksum = values
+    .iter()
+    .enumerate()
+    .map(|(i, v)| if i == 0 { *v } else { v * i })
+    .sum::<usize>()
+    % 103;
+
+  let mut out = Vec::new();
+  for v in values {
+    push_widths(&mut out, CODE128_WIDTHS[v]);
+  }
+  push_widths(&mut out, CODE128_WIDTHS[checksum]);
+  push_widths(&mut out, CODE128_STOP);
+
+  Ok(out)
+}
+
+pub enum Symbology {
+  Ean13,
+  Code128,
+}
+
+/// Geçerli EAN-13 ise EAN-13, değilse Code 128.
+pub fn encode_auto(code: &str) -> Result<(Symbology, Vec<bool>), PosError> {
+  if is_valid_ean13(code) {
+    Ok((Symbology::Ean13, encode_ean13(code)?))
+  } else {
+    Ok((Symbology::Code128, encode_code128(code)?))
+  }
+}
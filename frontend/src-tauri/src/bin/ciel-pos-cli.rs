@@ -0,0 +1,265 @@
+// -------------------- KOMUT SATIRI (ciel-pos-cli) --------------------
+//
+// Arayüz açmadan arka ofis işleri: yedek / geri yükleme, bütünlük kontrolü,
+// ürün CSV içe/dışa aktarma, raporlar (JSON / CSV / XLSX), stok düzeltme.
+// Masaüstü uygulamasıyla aynı veri klasörünü açar; çıktı stdout'a JSON,
+// hata stderr'e ve çıkış kodu 1.
+//
+//   ciel-pos-cli backup
+//   ciel-pos-cli restore pos_backup_MANUAL_....sqlite
+//   ciel-pos-cli check --repair
+//   ciel-pos-cli report low-stock --max-stock 2
+//   ciel-pos-cli report cash-report --from 2025-01-01 --format csv --out kasa.csv
+//   ciel-pos-cli import-products urunler.csv
+//   ciel-pos-cli stock adjust 1000001 --depo -3
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use app_lib::backup::{self, BackupKind};
+use app_lib::db::{self, Store};
+use app_lib::error::PosError;
+use app_lib::export::{self, ExportFormat, ExportReport};
+use app_lib::i18n;
+use app_lib::import;
+use app_lib::paths::AppPaths;
+
+#[derive(Parser)]
+#[command(name = "ciel-pos-cli", version, about = "Ciel POS arka ofis komutları")]
+struct Cli {
+  /// Veri klasörü (varsayılan: masaüstü uygulamasının klasörü)
+  #[arg(long, global = true, env = "CIEL_POS_DATA_DIR")]
+  data_dir: Option<PathBuf>,
+
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Elle yedek al
+  Backup,
+  /// Yedekleri listele, en yeni başta (dosyalar açılmaz; doğrulamak için verify-backup)
+  ListBackups(PageArgs),
+  /// Yedeği aç ve bütünlüğünü kontrol et
+  VerifyBackup {
+    /// backups klasöründeki dosya adı ya da yolu
+    backup: String,
+    #[arg(long)]
+    passphrase: Option<String>,
+  },
+  /// Yedekten geri yükle (önce güvenlik yedeği alınır)
+  Restore {
+    /// backups klasöründeki dosya adı ya da yolu
+    backup: String,
+    #[arg(long)]
+    passphrase: Option<String>,
+  },
+  /// Bütünlük kontrolü
+  Check {
+    /// Onarılabilir sorunları düzelt (önce yedek alınır)
+    #[arg(long)]
+    repair: bool,
+  },
+  /// Rapor üret
+  Report(ReportArgs),
+  /// Ürün listesini CSV olarak yaz
+  ExportProducts {
+    #[arg(long)]
+    out: PathBuf,
+  },
+  /// Ürün CSV'sini içe aktar (barkod varsa günceller, yoksa ekler)
+  ImportProducts { file: PathBuf },
+  /// Stok düzeltme
+  #[command(subcommand)]
+  Stock(StockCommand),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportName {
+  VelocityReport,
+  DeadStock,
+  CategoryMargin,
+  BasketPairs,
+  LowStock,
+  CashReport,
+  Expenses,
+  SaleGroups,
+  Products,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+  Json,
+  Csv,
+  Xlsx,
+}
+
+#[derive(Args)]
+struct ReportArgs {
+  name: ReportName,
+  #[arg(long)]
+  from: Option<String>,
+  #[arg(long)]
+  to: Option<String>,
+  #[arg(long)]
+  q: Option<String>,
+  #[arg(long, default_value_t = 90)]
+  min_days: i64,
+  #[arg(long, default_value_t = 2)]
+  max_stock: i64,
+  #[arg(long, default_value_t = 20)]
+  limit: i64,
+  #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+  format: OutputFormat,
+  /// CSV / XLSX için dosya (verilmezse geçerli klasöre varsayılan adla)
+  #[arg(long)]
+  out: Option<PathBuf>,
+}
+
+/// Liste komutlarının sayfası (db::ListQuery)
+#[derive(Args)]
+struct PageArgs {
+  #[arg(long, default_value_t = 1)]
+  page: i64,
+  #[arg(long, default_value_t = db::DEFAULT_PAGE_SIZE)]
+  page_size: i64,
+  #[arg(long)]
+  q: Option<String>,
+}
+
+impl PageArgs {
+  fn query(self) -> db::ListQuery {
+    db::ListQuery {
+      page: Some(self.page),
+      page_size: Some(self.page_size),
+      q: self.q,
+      ..Default::default()
+    }
+  }
+}
+
+#[derive(Subcommand)]
+enum StockCommand {
+  /// Stoğu verilen değere ayarla
+  Set {
+    barcode: String,
+    #[arg(long)]
+    magaza: Option<i64>,
+    #[arg(long)]
+    depo: Option<i64>,
+  },
+  /// Stoğu artır / azalt (ör. --depo -3)
+  Adjust {
+    barcode: String,
+    #[arg(long, allow_hyphen_values = true)]
+    magaza: Option<i64>,
+    #[arg(long, allow_hyphen_values = true)]
+    depo: Option<i64>,
+  },
+}
+
+fn main() -> ExitCode {
+  let cli = Cli::parse();
+  match run(cli) {
+    Ok(out) => {
+      // `| head` gibi kapanan boru panik yaratmasın
+      let _ = writeln!(std::io::stdout(), "{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+      ExitCode::SUCCESS
+    }
+    Err(e) => {
+      eprintln!("{}", serde_json::to_string(&e).unwrap_or_else(|_| e.to_string()));
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn run(cli: Cli) -> Result<serde_json::Value, PosError> {
+  let data_dir = match cli.data_dir {
+    Some(d) => d,
+    None => AppPaths::default_data_dir()?,
+  };
+  let paths = AppPaths::new(data_dir);
+  let store = db::init(&paths)?;
+
+  match cli.command {
+    Command::Backup => json(backup::create_backup(&store, &paths, BackupKind::Manual)?),
+    Command::ListBackups(page) => json(backup::list_backups(&paths, &page.query())?),
+    Command::VerifyBackup { backup, passphrase } => json(backup::verify_backup(
+      &store,
+      &paths,
+      backup::InspectBackupPayload { backup_path: backup, passphrase },
+    )?),
+    Command::Restore { backup, passphrase } => json(backup::restore_from_backup(
+      &store,
+      &paths,
+      backup::RestoreFromBackupPayload { backup_path: backup, passphrase },
+    )?),
+    Command::Check { repair } => {
+      if repair {
+        backup::backup_sqlite_db(&store, &paths, BackupKind::BeforeRepair)?;
+      }
+      json(store.check_integrity(repair)?)
+    }
+    Command::Report(args) => report(&store, args),
+    Command::ExportProducts { out } => {
+      let table = export::build_table(&store, &ExportReport::Products)?;
+      export::write_table(&out, &table, ExportFormat::Csv)?;
+      Ok(serde_json::json!({ "path": out.to_string_lossy(), "rows": table.rows.len() }))
+    }
+    Command::ImportProducts { file } => json(import::import_products_csv(&store, &file)?),
+    Command::Stock(cmd) => stock(&store, cmd),
+  }
+}
+
+fn json<T: serde::Serialize>(value: T) -> Result<serde_json::Value, PosError> {
+  serde_json::to_value(value).map_err(|e| PosError::Io(e.to_string()))
+}
+
+fn report(store: &Store, args: ReportArgs) -> Result<serde_json::Value, PosError> {
+  let report = match args.name {
+    ReportName::VelocityReport => ExportReport::VelocityReport { from: args.from, to: args.to },
+    ReportName::DeadStock => ExportReport::DeadStock { min_days: args.min_days },
+    ReportName::CategoryMargin => ExportReport::CategoryMargin { from: args.from, to: args.to },
+    ReportName::BasketPairs => ExportReport::BasketPairs { limit: args.limit, from: args.from, to: args.to },
+    ReportName::LowStock => ExportReport::LowStock { max_stock: args.max_stock },
+    ReportName::CashReport => ExportReport::CashReport { from: args.from, to: args.to },
+    ReportName::Expenses => ExportReport::Expenses { from: args.from, to: args.to },
+    ReportName::SaleGroups => ExportReport::SaleGroups { from: args.from, to: args.to, q: args.q },
+    ReportName::Products => ExportReport::Products,
+  };
+  let table = export::build_table(store, &report)?;
+
+  let format = match args.format {
+    OutputFormat::Json => return Ok(export::table_json(&table)),
+    OutputFormat::Csv => ExportFormat::Csv,
+    OutputFormat::Xlsx => ExportFormat::Xlsx,
+  };
+  let out = args
+    .out
+    .unwrap_or_else(|| PathBuf::from(export::default_file_name(&report, format)));
+  export::write_table(&out, &table, format)?;
+  Ok(serde_json::json!({ "path": out.to_string_lossy(), "rows": table.rows.len() }))
+}
+
+fn stock(store: &Store, cmd: StockCommand) -> Result<serde_json::Value, PosError> {
+  let (barcode, magaza_stok, depo_stok) = match cmd {
+    // Göreli düzeltme: oku-hesapla-yaz yerine tek transaction'da `stok + delta`
+    StockCommand::Adjust { barcode, magaza, depo } => {
+      let (m, d) = store.adjust_stock(&barcode, magaza.unwrap_or(0), depo.unwrap_or(0))?;
+      (barcode.trim().to_string(), m, d)
+    }
+    StockCommand::Set { barcode, magaza, depo } => {
+      let p = store.find_product_by_barcode(&barcode)?
+        .ok_or_else(|| PosError::NotFound(i18n::fill(i18n::text("Ürün bulunamadı: {}"), &[&barcode])))?;
+      let magaza_stok = magaza.unwrap_or(p.magaza_stok);
+      let depo_stok = depo.unwrap_or(p.depo_stok);
+      store.update_stock(db::UpdateStockPayload { barcode: p.barcode.clone(), magaza_stok, depo_stok })?;
+      (p.barcode, magaza_stok, depo_stok)
+    }
+  };
+  Ok(serde_json::json!({ "barcode": barcode, "magaza_stok": magaza_stok, "depo_stok": depo_stok }))
+}
@@ -1,62 +1,291 @@
-use rusqlite::{params, Connection, OptionalExtension};
-use std::path::PathBuf;
-use std::sync::OnceLock;
-use tauri::{AppHandle, Manager};
-
-static DB_PATH: OnceLock<PathBuf> = OnceLock::new();
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+
+use crate::error::PosError;
+use crate::i18n::{self, tr};
+use crate::paths::AppPaths;
+
+// -------------------- STORE (bağlantılar) --------------------
+//
+// Tüm db fonksiyonları Store'un metodları. Masaüstünde Tauri state'inde, CLI'da main'de,
+// testlerde geçici bir DB üzerinde tutulur. Clone ucuz (Arc); arka plan thread'lerine verilebilir.
+//
+// Okumalar havuzdaki bağlantılardan; tüm yazmalar tek bağlantıdan, sırayla ve
+// BEGIN IMMEDIATE ile. Böylece eşzamanlı iki satış stok kontrolü/düşümünde yarışamaz.
+
+#[derive(Clone)]
+pub struct Store {
+  inner: Arc<StoreInner>,
+}
 
-pub(crate) fn get_conn() -> Result<Connection, String> {
-  let path = DB_PATH
-    .get()
-    .ok_or_else(|| "DB_PATH not initialized. Did you call db::init(app_handle)?".to_string())?;
+struct StoreInner {
+  // None: hazır bağlantıdan kurulan Store (from_connection); okumalar da yazma bağlantısını kullanır
+  readers: Option<r2d2::Pool<SqliteConnectionManager>>,
+  writer: Mutex<Connection>,
+  // Geri yükleme gibi DB'nin tamamını değiştiren işlemler sırasında havuzdan bağlantı alınmasın.
+  // Zaten alınmış bağlantıları SQLite'ın kendi kilidi (busy timeout) bekletir.
+  gate: RwLock<()>,
+  path: Option<PathBuf>,
+  // commit sonrası olaylar (set_event_listener)
+  listener: RwLock<Option<EventListener>>,
+}
 
-  let conn = Connection::open(path).map_err(|e| e.to_string())?;
+/// Okuma bağlantısı: havuzdan ya da (havuzsuz Store'da) yazma bağlantısının kilidi
+pub(crate) enum ReadConn<'a> {
+  Pooled(r2d2::PooledConnection<SqliteConnectionManager>),
+  Shared(MutexGuard<'a, Connection>),
+}
 
-  conn
-    .execute_batch("PRAGMA foreign_keys = ON;")
-    .map_err(|e| e.to_string())?;
+impl Deref for ReadConn<'_> {
+  type Target = Connection;
 
-  Ok(conn)
+  fn deref(&self) -> &Connection {
+    match self {
+      ReadConn::Pooled(c) => c,
+      ReadConn::Shared(c) => c,
+    }
+  }
 }
 
-pub fn init(app: &AppHandle) -> Result<(), String> {
-  // DB dosya yolu
-  let mut path = app
-    .path()
-    .app_data_dir()
-    .map_err(|e| e.to_string())?;
-
-  std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
-  path.push("ciel_pos.sqlite");
+impl DerefMut for ReadConn<'_> {
+  fn deref_mut(&mut self) -> &mut Connection {
+    match self {
+      ReadConn::Pooled(c) => c,
+      ReadConn::Shared(c) => c,
+    }
+  }
+}
 
-  let _ = DB_PATH.set(path);
+pub(crate) type WriteConn<'a> = MutexGuard<'a, Connection>;
 
-  let conn = get_conn()?;
+const READ_POOL_SIZE: u32 = 4;
+// bağlantı başına hazır ifade önbelleği (prepare_cached)
+const STATEMENT_CACHE_CAPACITY: usize = 64;
 
-  // WAL MODU AKTİF
+fn configure_conn(conn: &Connection) -> rusqlite::Result<()> {
   conn.execute_batch(
     r#"
-    PRAGMA journal_mode = WAL;
-    PRAGMA synchronous = NORMAL;
     PRAGMA foreign_keys = ON;
+    PRAGMA busy_timeout = 5000;
     "#,
-  )
-  .map_err(|e| e.to_string())?;
+  )?;
+  conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+  Ok(())
+}
+
+fn open_conn(path: &Path) -> Result<Connection, PosError> {
+  let conn = Connection::open(path)?;
+  configure_conn(&conn)?;
+  Ok(conn)
+}
 
-  migrate(&conn)?;
-  seed_option_tables(&conn)?;
-  seed_default_dictionaries(&conn)?;
+/// Göçler + varsayılan kayıtlar + kayıtlı dil.
+/// `before_migrate`: veri olan bir DB göç edilecekse ilk göçten önce bir kez çağrılır.
+fn prepare(conn: &Connection, before_migrate: impl FnOnce() -> Result<(), PosError>) -> Result<(), PosError> {
+  migrate(conn, |_from, _to| before_migrate())?;
+  seed_option_tables(conn)?;
+  seed_default_dictionaries(conn)?;
+  load_language(conn)
+}
 
-  Ok(())
+/// Uygulamanın DB'sini açar (masaüstü: setup, CLI: açılış). Bekleyen şema göçü varsa
+/// önce mevcut DB yedeklenir.
+pub fn init(paths: &AppPaths) -> Result<Store, PosError> {
+  std::fs::create_dir_all(paths.data_dir())?;
+  let (store, conn) = Store::connect(&paths.db_path())?;
+
+  // göçler ayrı bağlantıda: göç öncesi yedek, yazma bağlantısını kullanabilir
+  prepare(&conn, || {
+    crate::backup::backup_sqlite_db(&store, paths, crate::backup::BackupKind::BeforeMigration).map(|_| ())
+  })?;
+
+  Ok(store)
 }
 
-pub fn ping() -> Result<String, String> {
-  let conn = get_conn()?;
-  conn
-    .query_row("SELECT 1;", [], |_row| Ok::<(), rusqlite::Error>(()))
-    .map_err(|e| e.to_string())?;
-  Ok("OK (sqlite)".to_string())
+impl Store {
+  /// DB dosyasını açar (yoksa oluşturur) ve göçleri uygular. Göç öncesi yedek almaz.
+  pub fn open(path: impl AsRef<Path>) -> Result<Store, PosError> {
+    let (store, conn) = Store::connect(path.as_ref())?;
+    prepare(&conn, || Ok(()))?;
+    Ok(store)
+  }
+
+  /// Hazır bir bağlantı üzerinde (ör. `Connection::open_in_memory()`) havuzsuz Store; göçler uygulanır.
+  pub fn from_connection(mut conn: Connection) -> Result<Store, PosError> {
+    configure_conn(&conn)?;
+    prepare(&conn, || Ok(()))?;
+    conn.set_transaction_behavior(TransactionBehavior::Immediate);
+
+    Ok(Store {
+      inner: Arc::new(StoreInner {
+        readers: None,
+        writer: Mutex::new(conn),
+        gate: RwLock::new(()),
+        path: None,
+        listener: RwLock::new(None),
+      }),
+    })
+  }
+
+  /// Havuzu ve yazma bağlantısını kurar; göçler için ayrı bir bağlantı da döner.
+  fn connect(path: &Path) -> Result<(Store, Connection), PosError> {
+    let conn = open_conn(path)?;
+
+    // WAL MODU AKTİF (kalıcı; havuz bağlantıları da WAL'da açılır)
+    conn.execute_batch(
+      r#"
+      PRAGMA journal_mode = WAL;
+      PRAGMA synchronous = NORMAL;
+      "#,
+    )?;
+
+    let readers = r2d2::Pool::builder()
+      .max_size(READ_POOL_SIZE)
+      .min_idle(Some(1))
+      .build(SqliteConnectionManager::file(path).with_init(|c| configure_conn(c)))?;
+    let mut writer = open_conn(path)?;
+    writer.execute_batch("PRAGMA synchronous = NORMAL;")?;
+    writer.set_transaction_behavior(TransactionBehavior::Immediate);
+
+    let store = Store {
+      inner: Arc::new(StoreInner {
+        readers: Some(readers),
+        writer: Mutex::new(writer),
+        gate: RwLock::new(()),
+        path: Some(path.to_path_buf()),
+        listener: RwLock::new(None),
+      }),
+    };
+    Ok((store, conn))
+  }
+
+  /// DB dosyası (havuzsuz Store'da None)
+  pub fn path(&self) -> Option<&Path> {
+    self.inner.path.as_deref()
+  }
+
+  /// Okuma bağlantısı. Yazan metodlar write() kullanır.
+  pub(crate) fn read(&self) -> Result<ReadConn<'_>, PosError> {
+    drop(self.inner.gate.read().unwrap_or_else(|e| e.into_inner()));
+    match &self.inner.readers {
+      Some(pool) => Ok(ReadConn::Pooled(pool.get()?)),
+      None => Ok(ReadConn::Shared(self.lock_writer())),
+    }
+  }
+
+  /// Tek yazma bağlantısı; kilit dönen değer düşene kadar tutulur.
+  /// İçeride tekrar write() (havuzsuz Store'da read() de) çağrılmamalı (kilitlenir).
+  pub(crate) fn write(&self) -> Result<WriteConn<'_>, PosError> {
+    drop(self.inner.gate.read().unwrap_or_else(|e| e.into_inner()));
+    Ok(self.lock_writer())
+  }
+
+  fn lock_writer(&self) -> WriteConn<'_> {
+    self.inner.writer.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  pub fn ping(&self) -> Result<String, PosError> {
+    let conn = self.read()?;
+    conn
+      .query_row("SELECT 1;", [], |_row| Ok::<(), rusqlite::Error>(()))?;
+    Ok("OK (sqlite)".to_string())
+  }
+}
+
+// -------------------- CANLI OLAYLAR (commit sonrası) --------------------
+//
+// Stok / satış değiştiren metodlar başarılı commit'ten ve yazma kilidi bırakıldıktan sonra
+// dinleyiciyi çağırır. Masaüstünde main.rs bunları Tauri olayı olarak tüm pencerelere yayar;
+// CLI ve testlerde dinleyici yoksa bir şey yapılmaz.
+//   sale_created       -> outbox'taki sale.created verisiyle aynı
+//   stock_changed      -> StockChange {barcode, location, delta, new_qty}
+//   low_stock_reached  -> LowStockAlert (toplam stok eşiğin üstündeyken eşiğe / altına indi)
+//   cart_updated       -> PricedCart (satış ekranındaki açık sepet; yazma yapmaz, bkz. price_cart)
+
+pub const EVENT_SALE_CREATED: &str = "sale_created";
+pub const EVENT_STOCK_CHANGED: &str = "stock_changed";
+pub const EVENT_LOW_STOCK_REACHED: &str = "low_stock_reached";
+pub const EVENT_CART_UPDATED: &str = "cart_updated";
+
+#[derive(Clone)]
+pub struct StoreEvent {
+  pub name: &'static str,
+  pub payload: serde_json::Value,
+}
+
+impl StoreEvent {
+  fn new(name: &'static str, payload: impl serde::Serialize) -> StoreEvent {
+    StoreEvent { name, payload: serde_json::to_value(payload).unwrap_or_default() }
+  }
+}
+
+pub type EventListener = Arc<dyn Fn(&StoreEvent) + Send + Sync>;
+
+#[derive(serde::Serialize, Clone)]
+pub struct LowStockAlert {
+  pub barcode: String,
+  pub name: String,
+  pub magaza_stok: i64,
+  pub depo_stok: i64,
+  pub total_stock: i64,
+  /// Ayarlardaki low_stock_threshold
+  pub threshold: i64,
+}
+
+impl Store {
+  /// Tek dinleyici; yenisi eskisinin yerine geçer
+  pub fn set_event_listener(&self, f: impl Fn(&StoreEvent) + Send + Sync + 'static) {
+    *self.inner.listener.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(f));
+  }
+
+  fn emit(&self, events: Vec<StoreEvent>) {
+    let listener = self.inner.listener.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(f) = listener {
+      for e in &events {
+        f(e);
+      }
+    }
+  }
+}
+
+/// Stok hareketlerinin olayları (transaction içinde, commit'ten önce hesaplanır)
+fn stock_events(tx: &Connection, changes: &[StockChange]) -> Result<Vec<StoreEvent>, PosError> {
+  let mut events: Vec<StoreEvent> = changes.iter().map(|c| StoreEvent::new(EVENT_STOCK_CHANGED, c)).collect();
+
+  // barkod başına net değişim; yalnız azalan ürünler eşiği geçebilir
+  let mut net: Vec<(&str, i64)> = Vec::new();
+  for c in changes {
+    match net.iter_mut().find(|(bc, _)| *bc == c.barcode) {
+      Some(n) => n.1 += c.delta,
+      None => net.push((&c.barcode, c.delta)),
+    }
+  }
+
+  let threshold = read_settings(tx)?.low_stock_threshold;
+  for (bc, delta) in net.into_iter().filter(|(_, d)| *d < 0) {
+    let row = tx
+      .query_row(
+        "SELECT name, COALESCE(magaza_stok,0), COALESCE(depo_stok,0)
+         FROM products WHERE barcode = ?1 AND COALESCE(is_active,1) = 1",
+        params![bc],
+        |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?, r.get::<_, i64>(2)?)),
+      )
+      .optional()?;
+    let Some((name, magaza_stok, depo_stok)) = row else { continue };
+    let total_stock = magaza_stok + depo_stok;
+    if total_stock <= threshold && total_stock - delta > threshold {
+      events.push(StoreEvent::new(
+        EVENT_LOW_STOCK_REACHED,
+        LowStockAlert { barcode: bc.to_string(), name, magaza_stok, depo_stok, total_stock, threshold },
+      ));
+    }
+  }
+  Ok(events)
 }
+
 // -------------------- INPUT NORMALIZATION (DB layer) --------------------
 
 fn norm_opt(s: Option<String>) -> Option<String> {
@@ -66,9 +295,9 @@ fn norm_opt(s: Option<String>) -> Option<String> {
   })
 }
 
-fn norm_req(field: &str, s: &str) -> Result<String, String> {
+fn norm_req(field: &'static str, s: &str) -> Result<String, PosError> {
   let t = s.trim();
-  if t.is_empty() { Err(format!("{} zorunlu", field)) } else { Ok(t.to_string()) }
+  if t.is_empty() { Err(PosError::Validation(tr!("{} zorunlu", i18n::text(field)))) } else { Ok(t.to_string()) }
 }
 
 fn normalize_prefix_from_category(cat: Option<&str>) -> String {
@@ -110,7 +339,7 @@ fn normalize_prefix_from_category(cat: Option<&str>) -> String {
   if out == "XXX" { "PRD".to_string() } else { out }
 }
 
-fn next_product_code_for_prefix(conn: &Connection, prefix: &str) -> Result<String, String> {
+fn next_product_code_for_prefix(conn: &Connection, prefix: &str) -> Result<String, PosError> {
   // product_code format: PREFIX + 3 digits (e.g. ETK001)
   let like = format!("{}%", prefix);
   let start_pos: i64 = (prefix.len() as i64) + 1; // SUBSTR is 1-based
@@ -127,445 +356,2765 @@ fn next_product_code_for_prefix(conn: &Connection, prefix: &str) -> Result<Strin
       "#,
       params![like, start_pos],
       |r| r.get::<_, Option<i64>>(0),
-    )
-    .map_err(|e| e.to_string())?;
+    )?;
 
+  let limit = read_settings(conn)?.product_code_limit;
   let next = max_n.unwrap_or(0) + 1;
-  if next > 999 {
-    return Err(format!("{} için product_code limiti doldu (999)", prefix));
+  if next > limit {
+    return Err(PosError::Validation(tr!("{} için product_code limiti doldu ({})", prefix, limit)));
   }
 
   Ok(format!("{}{:03}", prefix, next))
 }
 
 
-/// DB’den otomatik barkod üretir
-fn next_barcode(conn: &Connection) -> Result<String, String> {
-  let max_opt: Option<i64> = conn
-    .query_row(
-      r#"
-      SELECT MAX(CAST(barcode AS INTEGER))
-      FROM products
-      WHERE TRIM(barcode) <> ''
-        AND TRIM(barcode) GLOB '[0-9]*'
-      "#,
-      [],
-      |row| row.get::<_, Option<i64>>(0),
-    )
-    .map_err(|e| e.to_string())?;
-
-  let start: i64 = 1_000_001;
-  let next = match max_opt {
-    Some(m) if m >= start => m + 1,
-    Some(_) => start,
-    None => start,
-  };
+// -------------------- _meta --------------------
+//
+// Yalnız şema kayıtları (schema_version, migration.*) ve göç raporları. Ayarlar tipli
+// tablolarda: settings (mağaza) ve device_settings (bu makine).
 
-  Ok(next.to_string())
+fn meta_get(conn: &Connection, key: &str) -> Result<Option<String>, PosError> {
+  conn
+    .query_row("SELECT value FROM _meta WHERE key = ?1", params![key], |r| r.get(0))
+    .optional()
+    .map_err(PosError::from)
+    .map(|v: Option<Option<String>>| v.flatten())
 }
 
-#[derive(serde::Serialize)]
-pub struct Product {
-  pub barcode: String,
-  pub product_code: Option<String>,
-  pub category: Option<String>,
-  pub name: String,
-  pub color: Option<String>,
-  pub size: Option<String>,
-  pub buy_price: f64,
-  pub sell_price: f64,
-  pub created_at: Option<String>, 
+fn meta_set(conn: &Connection, key: &str, value: &str) -> Result<(), PosError> {
+  conn
+    .execute(
+      "INSERT INTO _meta(key, value) VALUES (?1, ?2)
+       ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+      params![key, value],
+    )?;
+  Ok(())
+}
 
+/// Tek satırlık ayar tablosunda (id = 1) satır yoksa varsayılanlarla açar; UPDATE'ler bu satıra yazar
+fn ensure_settings_row(conn: &Connection, table: &str) -> Result<(), PosError> {
+  conn.execute(&format!("INSERT OR IGNORE INTO {} (id) VALUES (1)", table), [])?;
+  Ok(())
+}
 
-  pub stock: i64,
+// -------------------- BARKOD AYARLARI (settings) --------------------
 
-  pub magaza_baslangic: i64,
-  pub depo_baslangic: i64,
-  pub magaza_stok: i64,
-  pub depo_stok: i64,
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct BarcodeSettings {
+  pub mode: String, // 'SEQUENTIAL' | 'EAN13'
+  pub ean13_prefix: String,
 }
 
-#[derive(Debug, Clone)]
-pub struct CreatedProduct {
-  pub barcode: String,
-  pub product_code: Option<String>,
+fn read_barcode_settings(conn: &Connection) -> Result<BarcodeSettings, PosError> {
+  let row = conn
+    .prepare_cached("SELECT barcode_mode, ean13_prefix FROM settings WHERE id = 1")?
+    .query_row([], |r| {
+      Ok(BarcodeSettings {
+        mode: r.get(0)?,
+        ean13_prefix: r.get(1)?,
+      })
+    })
+    .optional()?;
+  Ok(row.unwrap_or_else(|| BarcodeSettings {
+    mode: "SEQUENTIAL".to_string(),
+    ean13_prefix: "200".to_string(),
+  }))
 }
 
-#[derive(serde::Serialize)]
-pub struct SaleLine {
-  pub sold_at: String,
-  pub qty: i64,
-  pub unit_price: f64,
-  pub total: f64,
-  pub sold_from: String,
-  pub refunded_qty: i64,
-}
+impl Store {
+  pub fn get_barcode_settings(&self) -> Result<BarcodeSettings, PosError> {
+    let conn = self.read()?;
+    read_barcode_settings(&conn)
+  }
 
-#[derive(serde::Deserialize)]
-pub struct CreateReturnPayload {
-  pub barcode: String,
-  pub qty: i64,
-  pub return_to: String,          
-  pub sold_at: Option<String>,
-  pub sold_from: Option<String>,
-  pub unit_price: f64,
-}
+  pub fn update_barcode_settings(&self, payload: BarcodeSettings) -> Result<BarcodeSettings, PosError> {
+    let mode = payload.mode.trim().to_uppercase();
+    if mode != "SEQUENTIAL" && mode != "EAN13" {
+      return Err(PosError::Validation(tr!("Barkod modu SEQUENTIAL veya EAN13 olmalı")));
+    }
 
-#[derive(serde::Serialize)]
-pub struct CreateReturnResult {
-  pub return_group_id: String,
-  pub lines: i64,
-  pub returned_total: f64,
-}
+    // Mağaza içi EAN-13: 2 ile başlayan 2-5 haneli ön ek (örn: 200, 210, 29)
+    let prefix = payload.ean13_prefix.trim().to_string();
+    if prefix.len() < 2
+      || prefix.len() > 5
+      || !prefix.starts_with('2')
+      || !prefix.chars().all(|c| c.is_ascii_digit())
+    {
+      return Err(PosError::Validation(tr!("EAN-13 ön eki 2 ile başlayan 2-5 haneli bir sayı olmalı (örn: 200)")));
+    }
 
-#[derive(serde::Deserialize)]
-pub struct CreateExchangeReturnedPayload {
-  pub barcode: String,
-  pub qty: i64,
-  pub return_to: String,
-  pub sold_at: Option<String>,
-  pub sold_from: Option<String>,
-  pub unit_price: f64,
+    let conn = self.write()?;
+    ensure_settings_row(&conn, "settings")?;
+    conn.execute(
+      "UPDATE settings SET barcode_mode = ?1, ean13_prefix = ?2, updated_at = datetime('now','localtime')
+       WHERE id = 1",
+      params![mode, prefix],
+    )?;
+    read_barcode_settings(&conn)
+  }
 }
 
-#[derive(serde::Deserialize)]
-pub struct CreateExchangeGivenItemPayload {
-  pub barcode: String,
-  pub qty: i64,
-  pub sold_from: String,          
-  pub unit_price: f64,
+// -------------------- DİL (settings) --------------------
+
+/// Kayıtlı dili mesaj kataloğuna yükler (açılışta ve geri yüklemeden sonra)
+pub(crate) fn load_language(conn: &Connection) -> Result<(), PosError> {
+  let lang = conn
+    .query_row("SELECT language FROM settings WHERE id = 1", [], |r| r.get::<_, String>(0))
+    .optional()?
+    .and_then(|v| i18n::Lang::parse(&v))
+    .unwrap_or(i18n::DEFAULT_LANG);
+  i18n::set_lang(lang);
+  Ok(())
 }
 
-#[derive(serde::Deserialize)]
-pub struct CreateExchangeSummaryPayload {
-  pub diff_payment_method: Option<String>, 
+pub fn get_language() -> Result<String, PosError> {
+  Ok(i18n::lang().code().to_string())
 }
 
-#[derive(serde::Deserialize)]
-pub struct CreateExchangePayload {
-  pub returned: CreateExchangeReturnedPayload,
-  pub given: Vec<CreateExchangeGivenItemPayload>,
-  pub summary: CreateExchangeSummaryPayload,
+impl Store {
+  pub fn set_language(&self, lang: String) -> Result<String, PosError> {
+    let lang = i18n::Lang::parse(&lang).ok_or_else(|| PosError::Validation(tr!("Dil tr veya en olmalı")))?;
+    let conn = self.write()?;
+    ensure_settings_row(&conn, "settings")?;
+    conn.execute(
+      "UPDATE settings SET language = ?1, updated_at = datetime('now','localtime') WHERE id = 1",
+      params![lang.code()],
+    )?;
+    i18n::set_lang(lang);
+    Ok(lang.code().to_string())
+  }
 }
 
+// -------------------- MAĞAZA AYARLARI (settings) --------------------
+//
+// Tek satırlık, sütunları tipli tablo (id = 1). Satır yoksa varsayılanlar kullanılır.
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct ShopSettings {
+  /// Satış geri alma penceresi (dakika)
+  pub undo_window_minutes: i64,
+  /// Sıralı barkod serisinin başlangıcı
+  pub barcode_start: i64,
+  /// Ön ek başına en büyük product_code numarası (ETK001 .. ETK999)
+  pub product_code_limit: i64,
+  /// Satırda lokasyon yoksa satışın düşeceği yer: 'MAGAZA' | 'DEPO'
+  pub default_location: String,
+  /// Ödeme yöntemi boş / tanınmıyorsa: 'CARD' | 'CASH' | 'TRANSFER'
+  pub default_payment_method: String,
+  /// Dashboard grafik serilerinin üst sınırı (en yeni günler / aylar tutulur)
+  pub dashboard_max_daily: i64,
+  pub dashboard_max_months: i64,
+  /// Toplam stok bu değere inince low_stock_reached olayı
+  pub low_stock_threshold: i64,
+}
 
-#[derive(serde::Serialize)]
-pub struct CreateExchangeResult {
-  pub exchange_group_id: String,
-  pub lines: i64,
-  pub returned_total: f64,
-  pub given_total: f64,
-  pub diff: f64,
+impl Default for ShopSettings {
+  fn default() -> Self {
+    ShopSettings {
+      undo_window_minutes: 30,
+      barcode_start: 1_000_001,
+      product_code_limit: 999,
+      default_location: "MAGAZA".to_string(),
+      default_payment_method: "CARD".to_string(),
+      dashboard_max_daily: 366,
+      dashboard_max_months: 36,
+      low_stock_threshold: 2,
+    }
+  }
 }
 
-pub fn list_products() -> Result<Vec<Product>, String> {
-  let conn = get_conn()?;
+impl ShopSettings {
+  fn validated(mut self) -> Result<ShopSettings, PosError> {
+    self.default_location = self.default_location.trim().to_uppercase();
+    self.default_payment_method = self.default_payment_method.trim().to_uppercase();
 
-  let mut stmt = conn
-    .prepare(
-      r#"
-      SELECT
-        barcode,
-        product_code,
-        category,
-        name,
-        color,
-        size,
-        COALESCE(buy_price, 0),
-        sell_price,
-        created_at,
-        COALESCE(stock, 0),
-        COALESCE(magaza_baslangic, 0),
-        COALESCE(depo_baslangic, 0),
-        COALESCE(magaza_stok, 0),
-        COALESCE(depo_stok, 0)
-      FROM products WHERE COALESCE(is_active, 1) = 1
-      ORDER BY CAST(barcode AS INTEGER) ASC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
+    if !(1..=1440).contains(&self.undo_window_minutes) {
+      return Err(PosError::Validation(tr!("Geri alma süresi 1-1440 dakika olmalı")));
+    }
+    // 13 hane EAN-13'e ayrılmış; sıralı seri daha kısa kalmalı
+    if !(1..=999_999_999_999).contains(&self.barcode_start) {
+      return Err(PosError::Validation(tr!("Barkod başlangıcı 1 ile 999999999999 arasında olmalı")));
+    }
+    if !(1..=99_999).contains(&self.product_code_limit) {
+      return Err(PosError::Validation(tr!("Ürün kodu limiti 1-99999 arasında olmalı")));
+    }
+    if self.default_location != "MAGAZA" && self.default_location != "DEPO" {
+      return Err(PosError::Validation(tr!("Lokasyon MAGAZA veya DEPO olmalı")));
+    }
+    if !matches!(self.default_payment_method.as_str(), "CARD" | "CASH" | "TRANSFER") {
+      return Err(PosError::Validation(tr!("Ödeme yöntemi CARD, CASH veya TRANSFER olmalı")));
+    }
+    if !(1..=3660).contains(&self.dashboard_max_daily) {
+      return Err(PosError::Validation(tr!("Günlük seri sınırı 1-3660 olmalı")));
+    }
+    if !(1..=120).contains(&self.dashboard_max_months) {
+      return Err(PosError::Validation(tr!("Aylık seri sınırı 1-120 olmalı")));
+    }
+    if !(0..=10_000).contains(&self.low_stock_threshold) {
+      return Err(PosError::Validation(tr!("Düşük stok eşiği 0-10000 olmalı")));
+    }
+    Ok(self)
+  }
+}
 
-  let rows = stmt
-    .query_map([], |row| {
-      Ok(Product {
-        barcode: row.get(0)?,
-        product_code: row.get(1)?,
-        category: row.get(2)?,
-        name: row.get(3)?,
-        color: row.get(4)?,
-        size: row.get(5)?,
-        buy_price: row.get(6)?,
-        sell_price: row.get(7)?,
-        created_at: row.get(8)?, 
-
-        stock: row.get(9)?,
-        magaza_baslangic: row.get(10)?,
-        depo_baslangic: row.get(11)?,
-        magaza_stok: row.get(12)?,
-        depo_stok: row.get(13)?,
+fn read_settings(conn: &Connection) -> Result<ShopSettings, PosError> {
+  let row = conn
+    .prepare_cached(
+      "SELECT undo_window_minutes, barcode_start, product_code_limit, default_location,
+              default_payment_method, dashboard_max_daily, dashboard_max_months, low_stock_threshold
+       FROM settings WHERE id = 1",
+    )?
+    .query_row([], |r| {
+      Ok(ShopSettings {
+        undo_window_minutes: r.get(0)?,
+        barcode_start: r.get(1)?,
+        product_code_limit: r.get(2)?,
+        default_location: r.get(3)?,
+        default_payment_method: r.get(4)?,
+        dashboard_max_daily: r.get(5)?,
+        dashboard_max_months: r.get(6)?,
+        low_stock_threshold: r.get(7)?,
       })
     })
-    .map_err(|e| e.to_string())?;
+    .optional()?;
+  Ok(row.unwrap_or_default())
+}
+
+impl Store {
+  pub fn get_settings(&self) -> Result<ShopSettings, PosError> {
+    let conn = self.read()?;
+    read_settings(&conn)
+  }
 
-  let mut out = Vec::new();
-  for r in rows {
-    out.push(r.map_err(|e| e.to_string())?);
+  pub fn update_settings(&self, payload: ShopSettings) -> Result<ShopSettings, PosError> {
+    let s = payload.validated()?;
+    let conn = self.write()?;
+    conn.execute(
+      "INSERT INTO settings (id, undo_window_minutes, barcode_start, product_code_limit, default_location,
+                             default_payment_method, dashboard_max_daily, dashboard_max_months,
+                             low_stock_threshold, updated_at)
+       VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, datetime('now','localtime'))
+       ON CONFLICT(id) DO UPDATE SET
+         undo_window_minutes    = excluded.undo_window_minutes,
+         barcode_start          = excluded.barcode_start,
+         product_code_limit     = excluded.product_code_limit,
+         default_location       = excluded.default_location,
+         default_payment_method = excluded.default_payment_method,
+         dashboard_max_daily    = excluded.dashboard_max_daily,
+         dashboard_max_months   = excluded.dashboard_max_months,
+         low_stock_threshold    = excluded.low_stock_threshold,
+         updated_at             = excluded.updated_at",
+      params![
+        s.undo_window_minutes,
+        s.barcode_start,
+        s.product_code_limit,
+        s.default_location,
+        s.default_payment_method,
+        s.dashboard_max_daily,
+        s.dashboard_max_months,
+        s.low_stock_threshold
+      ],
+    )?;
+    read_settings(&conn)
   }
-  Ok(out)
 }
 
+// -------------------- YEDEK AYARLARI (device_settings) --------------------
+
+/// Otomatik yedek saklama politikası: son N gün / hafta / ay için birer yedek tutulur.
+/// interval_minutes = 0 ise uygulama açıkken periyodik yedek alınmaz.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct BackupPolicy {
+  pub keep_daily: i64,
+  pub keep_weekly: i64,
+  pub keep_monthly: i64,
+  pub interval_minutes: i64,
+  // salt okunur: yedek parolası kayıtlıysa arşivler şifrelenir (set_backup_passphrase)
+  #[serde(default)]
+  pub encrypted: bool,
+}
 
-pub fn find_product_by_barcode(barcode: &str) -> Result<Option<Product>, String> {
-  let conn = get_conn()?;
+impl Default for BackupPolicy {
+  fn default() -> Self {
+    BackupPolicy {
+      keep_daily: 7,
+      keep_weekly: 4,
+      keep_monthly: 12,
+      interval_minutes: 60,
+      encrypted: false,
+    }
+  }
+}
 
-  let mut stmt = conn
-    .prepare(
-      r#"
-      SELECT
-        barcode,
-        product_code,
-        category,
-        name,
-        color,
-        size,
-        COALESCE(buy_price, 0),
-        sell_price,
-        created_at,
-        COALESCE(stock, 0),
-        COALESCE(magaza_baslangic, 0),
-        COALESCE(depo_baslangic, 0),
-        COALESCE(magaza_stok, 0),
-        COALESCE(depo_stok, 0)
-      FROM products
-      WHERE barcode = ?1
-      LIMIT 1
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
-
-  let row = stmt
-    .query_row(params![barcode], |row| {
-      Ok(Product {
-        barcode: row.get(0)?,
-        product_code: row.get(1)?,
-        category: row.get(2)?,
-        name: row.get(3)?,
-        color: row.get(4)?,
-        size: row.get(5)?,
-        buy_price: row.get(6)?,
-        sell_price: row.get(7)?,
-        created_at: row.get(8)?,
-        stock: row.get(9)?,
-        magaza_baslangic: row.get(10)?,
-        depo_baslangic: row.get(11)?,
-        magaza_stok: row.get(12)?,
-        depo_stok: row.get(13)?,
+fn read_backup_policy(conn: &Connection) -> Result<BackupPolicy, PosError> {
+  let row = conn
+    .prepare_cached(
+      "SELECT backup_keep_daily, backup_keep_weekly, backup_keep_monthly, backup_interval_minutes,
+              COALESCE(backup_passphrase, '') <> ''
+       FROM device_settings WHERE id = 1",
+    )?
+    .query_row([], |r| {
+      Ok(BackupPolicy {
+        keep_daily: r.get(0)?,
+        keep_weekly: r.get(1)?,
+        keep_monthly: r.get(2)?,
+        interval_minutes: r.get(3)?,
+        encrypted: r.get(4)?,
       })
     })
-    .optional()
-    .map_err(|e| e.to_string())?;
-
-  Ok(row)
-}
-
-/*
-pub fn add_product(
-  barcode: Option<String>,
-  product_code: Option<String>,
-  category: Option<String>,
-  name: String,
-  color: Option<String>,
-  size: Option<String>,
-  buy_price: Option<f64>,
-  sell_price: f64,
-  stock: Option<i64>,
-  magaza_baslangic: Option<i64>,
-  depo_baslangic: Option<i64>,
-) -> Result<String, String> {
-  let conn = get_conn()?;
-
-  let name = norm_req("Ürün adı", &name)?;
-  if !sell_price.is_finite() {
-    return Err("Satış fiyatı sayı olmalı".to_string());
-  }
-
-  let final_barcode = match norm_opt(barcode) {
-    Some(b) => b,
-    None => next_barcode(&conn)?,
-  };
+    .optional()?;
+  Ok(row.unwrap_or_default())
+}
+
+impl Store {
+  pub fn get_backup_policy(&self) -> Result<BackupPolicy, PosError> {
+    let conn = self.read()?;
+    read_backup_policy(&conn)
+  }
 
-  let category = norm_opt(category);
-  let color = norm_opt(color);
-  let size = norm_opt(size);
+  /// Yedek arşivlerini şifrelemek için kayıtlı parola. Otomatik yedekler kullanıcıya
+  /// sormadan alındığı için yerel DB'de tutulur; amaç USB'ye kopyalanan arşivleri korumak.
+  pub(crate) fn backup_passphrase(&self) -> Result<Option<String>, PosError> {
+    let conn = self.read()?;
+    // v10 göçünden önce (göç öncesi yedek) parola _meta'da; versiyonsuz DB'de _meta da yok
+    let p = if table_exists(&conn, "device_settings")? {
+      conn
+        .query_row("SELECT backup_passphrase FROM device_settings WHERE id = 1", [], |r| {
+          r.get::<_, Option<String>>(0)
+        })
+        .optional()?
+        .flatten()
+    } else if table_exists(&conn, "_meta")? {
+      meta_get(&conn, "backup.passphrase")?
+    } else {
+      None
+    };
+    Ok(p.filter(|p| !p.is_empty()))
+  }
 
-  // product_code: ürün ailesi kodu (varyantlar aynı kodu paylaşabilir)
-  // Kullanıcı bir kod verdiyse ASLA otomatik değiştir/bump yapma.
-  // Kod yoksa: kategori prefix + 3 haneli artan (ETK001) üret.
-  let product_code = match norm_opt(product_code) {
-    Some(pc) => Some(pc.trim().to_uppercase().replace('-', "")),
-    None => {
-      let prefix = normalize_prefix_from_category(category.as_deref());
-      let pc = next_product_code_for_prefix(&conn, &prefix)?;
-      Some(pc)
+  /// None / boş = şifrelemeyi kapat. Mevcut arşivler değişmez.
+  pub fn set_backup_passphrase(&self, passphrase: Option<String>) -> Result<BackupPolicy, PosError> {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    if let Some(p) = &passphrase {
+      if p.chars().count() < crate::archive::MIN_PASSPHRASE_LEN {
+        return Err(PosError::Validation(tr!(
+          "Yedek parolası en az {} karakter olmalı",
+          crate::archive::MIN_PASSPHRASE_LEN
+        )));
+      }
     }
-  };
-
-  let bp = buy_price.unwrap_or(0.0);
-  let mb = magaza_baslangic.unwrap_or(0);
-  let db = depo_baslangic.unwrap_or(0);
 
-  let st = stock.unwrap_or(mb + db);
+    let conn = self.write()?;
+    ensure_settings_row(&conn, "device_settings")?;
+    conn.execute(
+      "UPDATE device_settings SET backup_passphrase = ?1, updated_at = datetime('now','localtime')
+       WHERE id = 1",
+      params![passphrase],
+    )?;
+    read_backup_policy(&conn)
+  }
 
-  let ms = mb;
-  let ds = db;
+  pub fn update_backup_policy(&self, payload: BackupPolicy) -> Result<BackupPolicy, PosError> {
+    for (label, v) in [
+      ("Günlük", payload.keep_daily),
+      ("Haftalık", payload.keep_weekly),
+      ("Aylık", payload.keep_monthly),
+    ] {
+      if !(0..=365).contains(&v) {
+        return Err(PosError::Validation(tr!("{} saklama sayısı 0-365 arasında olmalı", i18n::text(label))));
+      }
+    }
+    if payload.keep_daily + payload.keep_weekly + payload.keep_monthly == 0 {
+      return Err(PosError::Validation(tr!("En az bir otomatik yedek saklanmalı")));
+    }
+    if payload.interval_minutes != 0 && !(5..=1440).contains(&payload.interval_minutes) {
+      return Err(PosError::Validation(tr!("Yedek aralığı 0 (kapalı) veya 5-1440 dakika olmalı")));
+    }
 
-  conn.execute(
-    r#"
-    INSERT INTO products
-      (barcode, product_code, category, name, color, size, buy_price, sell_price, stock,
-      magaza_baslangic, depo_baslangic, magaza_stok, depo_stok)
-    VALUES
-      (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
-    "#,
-    params![
-      final_barcode,
-      product_code,
-      category,
-      name,
-      color,
-      size,
-      bp,
-      sell_price,
-      st,
-      mb,
-      db,
-      ms,
-      ds
-    ],
+    let conn = self.write()?;
+    ensure_settings_row(&conn, "device_settings")?;
+    conn.execute(
+      "UPDATE device_settings SET
+         backup_keep_daily       = ?1,
+         backup_keep_weekly      = ?2,
+         backup_keep_monthly     = ?3,
+         backup_interval_minutes = ?4,
+         updated_at              = datetime('now','localtime')
+       WHERE id = 1",
+      params![
+        payload.keep_daily,
+        payload.keep_weekly,
+        payload.keep_monthly,
+        payload.interval_minutes
+      ],
+    )?;
+    read_backup_policy(&conn)
+  }
+}
+
+// -------------------- YEREL API AYARLARI (device_settings) --------------------
+
+/// Gömülü HTTP/JSON API (http_api.rs). lan = false ise yalnız 127.0.0.1'den erişilir.
+/// Anahtar ilk okumada üretilir; istemciler `Authorization: Bearer <token>` gönderir.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct ApiSettings {
+  pub enabled: bool,
+  pub lan: bool,
+  pub port: i64,
+  // salt okunur: değiştirmek için regenerate_api_token
+  #[serde(default)]
+  pub token: String,
+}
+
+pub const DEFAULT_API_PORT: i64 = 8787;
+
+fn read_api_settings(conn: &Connection) -> Result<ApiSettings, PosError> {
+  let row = conn
+    .prepare_cached("SELECT api_enabled, api_lan, api_port, api_token FROM device_settings WHERE id = 1")?
+    .query_row([], |r| {
+      Ok(ApiSettings {
+        enabled: r.get(0)?,
+        lan: r.get(1)?,
+        port: r.get(2)?,
+        token: r.get::<_, Option<String>>(3)?.unwrap_or_default(),
+      })
+    })
+    .optional()?;
+  Ok(row.unwrap_or(ApiSettings {
+    enabled: false,
+    lan: false,
+    port: DEFAULT_API_PORT,
+    token: String::new(),
+  }))
+}
+
+impl Store {
+  pub fn get_api_settings(&self) -> Result<ApiSettings, PosError> {
+    let s = {
+      let conn = self.read()?;
+      read_api_settings(&conn)?
+    };
+    if !s.token.is_empty() {
+      return Ok(s);
+    }
+    self.regenerate_api_token()
+  }
+
+  pub fn update_api_settings(&self, payload: ApiSettings) -> Result<ApiSettings, PosError> {
+    if !(1024..=65535).contains(&payload.port) {
+      return Err(PosError::Validation(tr!("API portu 1024-65535 arasında olmalı")));
+    }
+    {
+      let conn = self.write()?;
+      ensure_settings_row(&conn, "device_settings")?;
+      conn.execute(
+        "UPDATE device_settings SET api_enabled = ?1, api_lan = ?2, api_port = ?3,
+                                    updated_at = datetime('now','localtime')
+         WHERE id = 1",
+        params![payload.enabled, payload.lan, payload.port],
+      )?;
+    }
+    self.get_api_settings()
+  }
+
+  /// Eski anahtarı kullanan istemciler 401 almaya başlar
+  pub fn regenerate_api_token(&self) -> Result<ApiSettings, PosError> {
+    let conn = self.write()?;
+    ensure_settings_row(&conn, "device_settings")?;
+    conn.execute(
+      "UPDATE device_settings SET api_token = ?1, updated_at = datetime('now','localtime') WHERE id = 1",
+      params![crate::archive::random_hex(24)],
+    )?;
+    read_api_settings(&conn)
+  }
+}
+
+// -------------------- CİHAZA ÖZEL AYARLAR (geri yüklemede korunur) --------------------
+//
+// Yedek politikası / parolası, ek yedek hedefleri ve yerel API bu makineye ait. Eski ya da
+// başka kurulumdan bir yedeğin geri yüklenmesi bunları sıfırlamamalı: aksi halde parolasız
+// bir yedek sonraki tüm otomatik yedekleri sessizce şifresiz yazdırır.
+
+const DEVICE_SETTINGS_COLS: &str = "id, backup_keep_daily, backup_keep_weekly, backup_keep_monthly, \
+  backup_interval_minutes, backup_passphrase, api_enabled, api_lan, api_port, api_token, updated_at";
+
+/// `from`daki (canlı DB) cihaz ayarlarını, canlıya kopyalanacak olan `to`ya yazar.
+/// İkisi de göç edilmiş olmalı (device_settings v10'da açılır).
+pub(crate) fn copy_device_settings(from: &Connection, to: &Connection) -> Result<(), PosError> {
+  let n = DEVICE_SETTINGS_COLS.split(',').count();
+  let device = from
+    .query_row(&format!("SELECT {} FROM device_settings", DEVICE_SETTINGS_COLS), [], |r| {
+      (0..n).map(|i| r.get::<_, rusqlite::types::Value>(i)).collect::<Result<Vec<_>, _>>()
+    })
+    .optional()?;
+
+  let tx = to.unchecked_transaction()?;
+  tx.execute("DELETE FROM device_settings", [])?;
+  if let Some(values) = device {
+    let marks = (1..=n).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ");
+    tx.execute(
+      &format!("INSERT INTO device_settings ({}) VALUES ({})", DEVICE_SETTINGS_COLS, marks),
+      rusqlite::params_from_iter(values),
+    )?;
+  }
+
+  if table_exists(from, "backup_targets")? {
+    let mut stmt = from.prepare(
+      "SELECT id, path, label, is_active, last_status, last_error, last_backup_at, last_file, created_at
+       FROM backup_targets",
+    )?;
+    let mut rows = stmt.query([])?;
+    tx.execute("DELETE FROM backup_targets", [])?;
+    while let Some(r) = rows.next()? {
+      tx.execute(
+        "INSERT INTO backup_targets
+           (id, path, label, is_active, last_status, last_error, last_backup_at, last_file, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+          r.get::<_, i64>(0)?,
+          r.get::<_, String>(1)?,
+          r.get::<_, Option<String>>(2)?,
+          r.get::<_, i64>(3)?,
+          r.get::<_, Option<String>>(4)?,
+          r.get::<_, Option<String>>(5)?,
+          r.get::<_, Option<String>>(6)?,
+          r.get::<_, Option<String>>(7)?,
+          r.get::<_, Option<String>>(8)?,
+        ],
+      )?;
+    }
+  }
+  tx.commit()?;
+  Ok(())
+}
+
+// -------------------- EK YEDEK HEDEFLERİ --------------------
+
+#[derive(serde::Serialize)]
+pub struct BackupTarget {
+  pub id: i64,
+  pub path: String,
+  pub label: Option<String>,
+  pub is_active: i64,
+  pub last_status: Option<String>,
+  pub last_error: Option<String>,
+  pub last_backup_at: Option<String>,
+  pub last_file: Option<String>,
+}
+
+impl Store {
+  /// Pasif hedefler de listelenir (ayarlar ekranı açıp kapatır); q yol / etikette arar.
+  pub fn list_backup_targets(&self, query: &ListQuery) -> Result<Page<BackupTarget>, PosError> {
+    let conn = self.read()?;
+    // v4 göçünden önce (ör. göç öncesi yedek) tablo henüz yok
+    if !table_exists(&conn, "backup_targets")? {
+      return Ok(query.page_of(Vec::new(), 0));
+    }
+    let q_like = query.q_like();
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(&[("id", "id"), ("label", "COALESCE(label,'')"), ("path", "path")], false)?;
+
+    let filter = "(?1 IS NULL OR path LIKE ?1 OR COALESCE(label,'') LIKE ?1)";
+    let total: i64 = conn.query_row(
+      &format!("SELECT COUNT(*) FROM backup_targets WHERE {}", filter),
+      params![q_like],
+      |r| r.get(0),
+    )?;
+
+    let mut stmt = conn
+      .prepare(&format!(
+        "SELECT id, path, label, is_active, last_status, last_error, last_backup_at, last_file
+         FROM backup_targets
+         WHERE {}
+         ORDER BY {}, id ASC
+         LIMIT ?2 OFFSET ?3",
+        filter, order
+      ))?;
+
+    let items = stmt
+      .query_map(params![q_like, limit, offset], |r| {
+        Ok(BackupTarget {
+          id: r.get(0)?,
+          path: r.get(1)?,
+          label: r.get(2)?,
+          is_active: r.get(3)?,
+          last_status: r.get(4)?,
+          last_error: r.get(5)?,
+          last_backup_at: r.get(6)?,
+          last_file: r.get(7)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(query.page_of(items, total))
+  }
+
+  /// Hedef eklenirken klasör erişilebilir olmalı; sonradan çıkarılan USB sadece
+  /// o yedekte ERROR olarak raporlanır.
+  pub fn add_backup_target(&self, path: String, label: Option<String>) -> Result<i64, PosError> {
+    let p = norm_req("Hedef klasör", &path)?;
+    let dir = PathBuf::from(&p);
+    if !dir.is_absolute() {
+      return Err(PosError::Validation(tr!("Hedef klasör tam yol olmalı")));
+    }
+    if !dir.is_dir() {
+      return Err(PosError::NotFound(tr!("Klasör bulunamadı: {}", p)));
+    }
+
+    let conn = self.write()?;
+    conn
+      .execute(
+        "INSERT INTO backup_targets(path, label) VALUES (?1, ?2)",
+        params![p, norm_opt(label)],
+      )
+      .map_err(|e| {
+        if crate::error::is_unique_violation(&e) {
+          PosError::Conflict(tr!("Bu klasör zaten ekli"))
+        } else {
+          e.into()
+        }
+      })?;
+    Ok(conn.last_insert_rowid())
+  }
+
+  pub fn update_backup_target(&self, id: i64, label: Option<String>, is_active: Option<i64>) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let changed = conn
+      .execute(
+        "UPDATE backup_targets
+         SET label     = COALESCE(?2, label),
+             is_active = COALESCE(?3, is_active)
+         WHERE id = ?1",
+        params![id, norm_opt(label), is_active.map(|a| if a == 0 { 0 } else { 1 })],
+      )?;
+    if changed == 0 {
+      return Err(PosError::NotFound(tr!("Yedek hedefi bulunamadı")));
+    }
+    Ok(changed as i64)
+  }
+
+  /// Sadece kaydı siler; hedefteki yedek dosyalarına dokunmaz.
+  pub fn delete_backup_target(&self, id: i64) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let changed = conn
+      .execute("DELETE FROM backup_targets WHERE id = ?1", params![id])?;
+    Ok(changed as i64)
+  }
+
+  pub(crate) fn record_backup_target_status(&self, id: i64, result: &Result<String, PosError>) -> Result<(), PosError> {
+    let conn = self.write()?;
+    let (status, error, file) = match result {
+      Ok(f) => ("OK", None, Some(f.as_str())),
+      Err(e) => ("ERROR", Some(e.to_string()), None),
+    };
+    conn
+      .execute(
+        "UPDATE backup_targets
+         SET last_status    = ?2,
+             last_error     = ?3,
+             last_file      = COALESCE(?4, last_file),
+             last_backup_at = datetime('now','localtime')
+         WHERE id = ?1",
+        params![id, status, error, file],
+      )?;
+    Ok(())
+  }
+}
+
+// -------------------- OLAY KUYRUĞU (outbox) / WEBHOOK --------------------
+//
+// Satış / iade / değişim / transfer, bunların geri alınması ve elle stok düzeltme kendi
+// transaction'ı içinde event_outbox'a bir olay ve her aktif webhook için bir teslimat satırı
+// yazar; commit olmazsa olay da yoktur.
+// Gönderim webhooks.rs'deki arka plan thread'inde. Başarısız teslimat artan aralıklarla
+// (30 sn, 1 dk, 2 dk ... en fazla 1 saat) yeniden denenir; WEBHOOK_MAX_ATTEMPTS'ten sonra FAILED.
+// Ulaşılamayan adresin diğer teslimatları da aynı süre ertelenir (defer_webhook_deliveries).
+
+pub const WEBHOOK_MAX_ATTEMPTS: i64 = 10;
+const WEBHOOK_BACKOFF_BASE_SECS: i64 = 30;
+const WEBHOOK_BACKOFF_MAX_SECS: i64 = 3600;
+
+/// Olaydaki stok hareketi; new_qty commit anındaki lokasyon stoğu
+#[derive(serde::Serialize, Clone)]
+pub struct StockChange {
+  pub barcode: String,
+  pub location: String, // 'MAGAZA' | 'DEPO'
+  pub delta: i64,
+  pub new_qty: i64,
+}
+
+fn stock_change(tx: &Connection, barcode: &str, loc: &str, delta: i64) -> Result<StockChange, PosError> {
+  let col = col_for_loc(loc);
+  // satır başına çalışır; iki lokasyon için iki ifade, bağlantı önbelleğinden
+  let new_qty: i64 = tx
+    .prepare_cached(&format!("SELECT COALESCE({}, 0) FROM products WHERE barcode = ?1", col))?
+    .query_row(params![barcode], |r| r.get(0))?;
+  Ok(StockChange {
+    barcode: barcode.to_string(),
+    location: if col == "depo_stok" { "DEPO" } else { "MAGAZA" }.to_string(),
+    delta,
+    new_qty,
+  })
+}
+
+/// Çağıranın transaction'ı içinde
+fn enqueue_event(tx: &Connection, event_type: &str, data: serde_json::Value) -> Result<(), PosError> {
+  tx.execute(
+    "INSERT INTO event_outbox(event_type, payload) VALUES (?1, ?2)",
+    params![event_type, data.to_string()],
+  )?;
+  let event_id = tx.last_insert_rowid();
+  tx.execute(
+    "INSERT INTO webhook_deliveries(event_id, webhook_id) SELECT ?1, id FROM webhooks WHERE is_active = 1",
+    params![event_id],
+  )?;
+  Ok(())
+}
+
+/// Elle stok düzeltme (sayım, CSV içe aktarma, CLI); değişmeyen stok olay yazmaz
+fn enqueue_stock_updated(tx: &Connection, barcode: &str, changes: &[StockChange]) -> Result<(), PosError> {
+  if changes.is_empty() {
+    return Ok(());
+  }
+  enqueue_event(
+    tx,
+    "stock.updated",
+    serde_json::json!({ "barcode": barcode, "stock_changes": changes }),
+  )
+}
+
+#[derive(serde::Serialize)]
+pub struct Webhook {
+  pub id: i64,
+  pub url: String,
+  pub label: Option<String>,
+  pub is_active: i64,
+  pub pending: i64,
+  pub failed: i64,
+  pub last_delivered_at: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct WebhookDeliveryRow {
+  pub id: i64,
+  pub event_id: i64,
+  pub event_type: String,
+  pub url: String,
+  pub status: String, // 'PENDING' | 'DELIVERED' | 'FAILED'
+  pub attempts: i64,
+  pub next_attempt_at: String,
+  pub last_error: Option<String>,
+  pub delivered_at: Option<String>,
+  pub created_at: String,
+}
+
+/// Gönderilecek teslimat; body olay zarfı: {id, type, created_at, data}
+pub struct DueDelivery {
+  pub id: i64,
+  pub webhook_id: i64,
+  pub url: String,
+  pub event_type: String,
+  pub body: String,
+}
+
+impl Store {
+  /// Pasif adresler de listelenir; q adres / etikette arar.
+  pub fn list_webhooks(&self, query: &ListQuery) -> Result<Page<Webhook>, PosError> {
+    let conn = self.read()?;
+    let q_like = query.q_like();
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(&[("id", "w.id"), ("label", "COALESCE(w.label,'')"), ("url", "w.url")], false)?;
+
+    let filter = "(?1 IS NULL OR w.url LIKE ?1 OR COALESCE(w.label,'') LIKE ?1)";
+    let total: i64 = conn.query_row(
+      &format!("SELECT COUNT(*) FROM webhooks w WHERE {}", filter),
+      params![q_like],
+      |r| r.get(0),
+    )?;
+
+    let mut stmt = conn.prepare(&format!(
+      "SELECT w.id, w.url, w.label, w.is_active,
+              (SELECT COUNT(*) FROM webhook_deliveries d WHERE d.webhook_id = w.id AND d.status = 'PENDING'),
+              (SELECT COUNT(*) FROM webhook_deliveries d WHERE d.webhook_id = w.id AND d.status = 'FAILED'),
+              (SELECT MAX(delivered_at) FROM webhook_deliveries d WHERE d.webhook_id = w.id)
+       FROM webhooks w
+       WHERE {}
+       ORDER BY {}, w.id ASC
+       LIMIT ?2 OFFSET ?3",
+      filter, order
+    ))?;
+    let items = stmt
+      .query_map(params![q_like, limit, offset], |r| {
+        Ok(Webhook {
+          id: r.get(0)?,
+          url: r.get(1)?,
+          label: r.get(2)?,
+          is_active: r.get(3)?,
+          pending: r.get(4)?,
+          failed: r.get(5)?,
+          last_delivered_at: r.get(6)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(query.page_of(items, total))
+  }
+
+  /// Yalnız eklendikten sonraki olaylar gönderilir
+  pub fn add_webhook(&self, url: String, label: Option<String>) -> Result<i64, PosError> {
+    let url = norm_req("Adres", &url)?;
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+      return Err(PosError::Validation(tr!("Adres http:// veya https:// ile başlamalı")));
+    }
+
+    let conn = self.write()?;
+    conn
+      .execute("INSERT INTO webhooks(url, label) VALUES (?1, ?2)", params![url, norm_opt(label)])
+      .map_err(|e| {
+        if crate::error::is_unique_violation(&e) {
+          PosError::Conflict(tr!("Bu adres zaten ekli"))
+        } else {
+          e.into()
+        }
+      })?;
+    Ok(conn.last_insert_rowid())
+  }
+
+  /// Pasife alınan adresin bekleyen teslimatları da gönderilmez (tekrar açılınca devam eder)
+  pub fn update_webhook(&self, id: i64, label: Option<String>, is_active: Option<i64>) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let changed = conn.execute(
+      "UPDATE webhooks
+       SET label     = COALESCE(?2, label),
+           is_active = COALESCE(?3, is_active)
+       WHERE id = ?1",
+      params![id, norm_opt(label), is_active.map(|a| if a == 0 { 0 } else { 1 })],
+    )?;
+    if changed == 0 {
+      return Err(PosError::NotFound(tr!("Webhook bulunamadı")));
+    }
+    Ok(changed as i64)
+  }
+
+  /// Teslimat kayıtları da silinir (ON DELETE CASCADE)
+  pub fn delete_webhook(&self, id: i64) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let changed = conn.execute("DELETE FROM webhooks WHERE id = ?1", params![id])?;
+    Ok(changed as i64)
+  }
+
+  /// En yeni teslimatlar (izleme ekranı için)
+  pub fn list_webhook_deliveries(&self, limit: i64) -> Result<Vec<WebhookDeliveryRow>, PosError> {
+    let conn = self.read()?;
+    let mut stmt = conn.prepare(
+      "SELECT d.id, d.event_id, e.event_type, w.url, d.status, d.attempts, d.next_attempt_at,
+              d.last_error, d.delivered_at, e.created_at
+       FROM webhook_deliveries d
+       JOIN event_outbox e ON e.id = d.event_id
+       JOIN webhooks w ON w.id = d.webhook_id
+       ORDER BY d.id DESC
+       LIMIT ?1",
+    )?;
+    let rows = stmt.query_map(params![limit.clamp(1, MAX_PAGE_SIZE)], |r| {
+      Ok(WebhookDeliveryRow {
+        id: r.get(0)?,
+        event_id: r.get(1)?,
+        event_type: r.get(2)?,
+        url: r.get(3)?,
+        status: r.get(4)?,
+        attempts: r.get(5)?,
+        next_attempt_at: r.get(6)?,
+        last_error: r.get(7)?,
+        delivered_at: r.get(8)?,
+        created_at: r.get(9)?,
+      })
+    })?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(PosError::from)
+  }
+
+  /// Zamanı gelmiş teslimatlar, eskiden yeniye
+  pub fn due_webhook_deliveries(&self, limit: i64) -> Result<Vec<DueDelivery>, PosError> {
+    let conn = self.read()?;
+    let mut stmt = conn.prepare(
+      "SELECT d.id, w.url, e.id, e.event_type, e.payload, e.created_at, w.id
+       FROM webhook_deliveries d
+       JOIN event_outbox e ON e.id = d.event_id
+       JOIN webhooks w ON w.id = d.webhook_id
+       WHERE d.status = 'PENDING'
+         AND w.is_active = 1
+         AND d.next_attempt_at <= datetime('now','localtime')
+       ORDER BY d.id ASC
+       LIMIT ?1",
+    )?;
+    let rows = stmt.query_map(params![limit], |r| {
+      let event_id: i64 = r.get(2)?;
+      let event_type: String = r.get(3)?;
+      let payload: String = r.get(4)?;
+      let created_at: String = r.get(5)?;
+      let webhook_id: i64 = r.get(6)?;
+      Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, event_id, event_type, payload, created_at, webhook_id))
+    })?;
+
+    rows
+      .map(|row| {
+        let (id, url, event_id, event_type, payload, created_at, webhook_id) = row?;
+        let data: serde_json::Value = serde_json::from_str(&payload).unwrap_or(serde_json::Value::Null);
+        let body = serde_json::json!({
+          "id": event_id,
+          "type": event_type,
+          "created_at": created_at,
+          "data": data,
+        })
+        .to_string();
+        Ok(DueDelivery { id, webhook_id, url, event_type, body })
+      })
+      .collect()
+  }
+
+  pub fn mark_webhook_delivered(&self, id: i64) -> Result<(), PosError> {
+    let conn = self.write()?;
+    conn.execute(
+      "UPDATE webhook_deliveries
+       SET status = 'DELIVERED', attempts = attempts + 1, last_error = NULL,
+           delivered_at = datetime('now','localtime')
+       WHERE id = ?1",
+      params![id],
+    )?;
+    Ok(())
+  }
+
+  /// Deneme sayısını artırır; sonraki deneme üstel aralıkla. Dönen değer yeni durum.
+  pub fn mark_webhook_failed(&self, id: i64, error: &str) -> Result<String, PosError> {
+    let conn = self.write()?;
+    let attempts: i64 = conn
+      .query_row("SELECT attempts FROM webhook_deliveries WHERE id = ?1", params![id], |r| r.get::<_, i64>(0))
+      .optional()?
+      .ok_or_else(|| PosError::NotFound(tr!("Teslimat bulunamadı")))?
+      + 1;
+    let status = if attempts >= WEBHOOK_MAX_ATTEMPTS { "FAILED" } else { "PENDING" };
+    let delay = (WEBHOOK_BACKOFF_BASE_SECS << (attempts - 1).min(16)).min(WEBHOOK_BACKOFF_MAX_SECS);
+    conn.execute(
+      "UPDATE webhook_deliveries
+       SET status = ?2, attempts = ?3, last_error = ?4,
+           next_attempt_at = datetime('now','localtime', ?5)
+       WHERE id = ?1",
+      params![id, status, attempts, error, format!("+{} seconds", delay)],
+    )?;
+    Ok(status.to_string())
+  }
+
+  /// Adrese ulaşılamadı (bağlantı hatası / zaman aşımı): aynı adresin diğer bekleyen teslimatları
+  /// bu teslimatın sonraki deneme zamanına kadar ertelenir (deneme sayılmaz). Ölü bir adres
+  /// her turda tüm kuyruğu zaman aşımıyla bekletmez. Dönen değer ertelenen teslimat sayısı.
+  pub fn defer_webhook_deliveries(&self, failed_delivery_id: i64) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let changed = conn.execute(
+      "UPDATE webhook_deliveries
+       SET next_attempt_at = f.next_attempt_at
+       FROM (SELECT webhook_id, next_attempt_at FROM webhook_deliveries WHERE id = ?1) AS f
+       WHERE webhook_deliveries.webhook_id = f.webhook_id
+         AND webhook_deliveries.id != ?1
+         AND webhook_deliveries.status = 'PENDING'
+         AND webhook_deliveries.next_attempt_at < f.next_attempt_at",
+      params![failed_delivery_id],
+    )?;
+    Ok(changed as i64)
+  }
+
+  /// Bekleyen ve FAILED teslimatları hemen (deneme sayacı sıfırdan) yeniden kuyruğa alır
+  pub fn retry_webhook_deliveries(&self) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let changed = conn.execute(
+      "UPDATE webhook_deliveries
+       SET status = 'PENDING',
+           attempts = CASE WHEN status = 'FAILED' THEN 0 ELSE attempts END,
+           next_attempt_at = datetime('now','localtime')
+       WHERE status IN ('PENDING','FAILED')",
+      [],
+    )?;
+    Ok(changed as i64)
+  }
+
+  /// `days` günden eski, bekleyen teslimatı kalmamış olayları siler
+  pub fn prune_event_outbox(&self, days: i64) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let changed = conn.execute(
+      "DELETE FROM event_outbox
+       WHERE created_at < datetime('now','localtime', ?1)
+         AND NOT EXISTS (
+           SELECT 1 FROM webhook_deliveries d WHERE d.event_id = event_outbox.id AND d.status = 'PENDING'
+         )",
+      params![format!("-{} days", days.max(1))],
+    )?;
+    Ok(changed as i64)
+  }
+}
+
+/// Ön ek + sıra no + kontrol hanesi. Sıra no ön ekle aynı başlayan
+/// mevcut 13 haneli barkodların en büyüğünden devam eder; ek barkod olarak kayıtlı
+/// numaralar atlanır.
+fn next_ean13_barcode(conn: &Connection, prefix: &str) -> Result<String, PosError> {
+  let seq_len = 12 - prefix.len();
+  let like = format!("{}%", prefix);
+  let start_pos: i64 = (prefix.len() as i64) + 1; // SUBSTR is 1-based
+
+  let max_seq: Option<i64> = conn
+    .query_row(
+      r#"
+      SELECT MAX(CAST(SUBSTR(barcode, ?2, ?3) AS INTEGER))
+      FROM products
+      WHERE LENGTH(barcode) = 13
+        AND barcode LIKE ?1
+        AND barcode NOT GLOB '*[^0-9]*'
+      "#,
+      params![like, start_pos, seq_len as i64],
+      |row| row.get::<_, Option<i64>>(0),
+    )?;
+
+  let mut next = max_seq.unwrap_or(0) + 1;
+  loop {
+    if next >= 10_i64.pow(seq_len as u32) {
+      return Err(PosError::Validation(tr!("{} ön eki için EAN-13 numara aralığı doldu", prefix)));
+    }
+    let code = crate::barcode::ean13_with_check_digit(&format!("{}{:0width$}", prefix, next, width = seq_len))?;
+    if !is_alias_barcode(conn, &code)? {
+      return Ok(code);
+    }
+    next += 1;
+  }
+}
+
+/// DB’den otomatik barkod üretir
+/// EAN13 modunda mağaza içi EAN-13, aksi halde 1.000.001'den başlayan sıralı sayı.
+fn next_barcode(conn: &Connection) -> Result<String, PosError> {
+  let settings = read_barcode_settings(conn)?;
+  if settings.mode == "EAN13" {
+    return next_ean13_barcode(conn, &settings.ean13_prefix);
+  }
+
+  let max_opt: Option<i64> = conn
+    .query_row(
+      r#"
+      SELECT MAX(CAST(barcode AS INTEGER))
+      FROM products
+      WHERE TRIM(barcode) <> ''
+        AND TRIM(barcode) GLOB '[0-9]*'
+        AND LENGTH(TRIM(barcode)) < 13 -- EAN-13 kodları sıralı seriyi bozmasın
+      "#,
+      [],
+      |row| row.get::<_, Option<i64>>(0),
+    )?;
+
+  let start = read_settings(conn)?.barcode_start;
+  let mut next = match max_opt {
+    Some(m) if m >= start => m + 1,
+    Some(_) => start,
+    None => start,
+  };
+
+  // ek barkod olarak kayıtlı numara atlanır (MAX'a katılmaz: 8 haneli tedarikçi
+  // kodları seriyi ileri sıçratmasın)
+  while is_alias_barcode(conn, &next.to_string())? {
+    next += 1;
+  }
+  Ok(next.to_string())
+}
+
+#[derive(serde::Serialize)]
+pub struct Product {
+  pub barcode: String,
+  pub product_code: Option<String>,
+  pub category: Option<String>,
+  pub name: String,
+  pub color: Option<String>,
+  pub size: Option<String>,
+  pub buy_price: f64,
+  pub sell_price: f64,
+  pub created_at: Option<String>, 
+
+
+  pub stock: i64,
+
+  pub magaza_baslangic: i64,
+  pub depo_baslangic: i64,
+  pub magaza_stok: i64,
+  pub depo_stok: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreatedProduct {
+  pub barcode: String,
+  pub product_code: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SaleLine {
+  pub sold_at: String,
+  pub qty: i64,
+  pub unit_price: f64,
+  pub total: f64,
+  pub sold_from: String,
+  pub refunded_qty: i64,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateReturnPayload {
+  pub barcode: String,
+  pub qty: i64,
+  pub return_to: String,          
+  pub sold_at: Option<String>,
+  pub sold_from: Option<String>,
+  pub unit_price: f64,
+}
+
+#[derive(serde::Serialize)]
+pub struct CreateReturnResult {
+  pub return_group_id: String,
+  pub lines: i64,
+  pub returned_total: f64,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateExchangeReturnedPayload {
+  pub barcode: String,
+  pub qty: i64,
+  pub return_to: String,
+  pub sold_at: Option<String>,
+  pub sold_from: Option<String>,
+  pub unit_price: f64,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateExchangeGivenItemPayload {
+  pub barcode: String,
+  pub qty: i64,
+  pub sold_from: String,          
+  pub unit_price: f64,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateExchangeSummaryPayload {
+  pub diff_payment_method: Option<String>, 
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateExchangePayload {
+  pub returned: CreateExchangeReturnedPayload,
+  pub given: Vec<CreateExchangeGivenItemPayload>,
+  pub summary: CreateExchangeSummaryPayload,
+}
+
+
+#[derive(serde::Serialize)]
+pub struct CreateExchangeResult {
+  pub exchange_group_id: String,
+  pub lines: i64,
+  pub returned_total: f64,
+  pub given_total: f64,
+  pub diff: f64,
+}
+
+// -------------------- LİSTE SORGULARI (sayfa / sıralama / filtre) --------------------
+
+pub const MAX_PAGE_SIZE: i64 = 500;
+/// page_size verilmezse
+pub const DEFAULT_PAGE_SIZE: i64 = 100;
+
+/// Tüm list_* komutlarının ortak parametreleri. Hepsi opsiyonel:
+/// page_size verilmezse DEFAULT_PAGE_SIZE, en fazla MAX_PAGE_SIZE kayıt döner.
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ListQuery {
+  pub page: Option<i64>, // 1'den başlar
+  pub page_size: Option<i64>,
+  pub sort: Option<String>,
+  pub desc: Option<bool>,
+  pub q: Option<String>,
+  // YYYY-MM-DD, iki uç dahil
+  pub from: Option<String>,
+  pub to: Option<String>,
+  pub include_inactive: bool,
+  // sayfalamadan tüm kayıtlar; yalnız Rust içinden (dışa aktarma, yedek hedefleri),
+  // IPC / HTTP isteğiyle istenemez
+  #[serde(skip)]
+  pub all: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct Page<T> {
+  pub items: Vec<T>,
+  pub total: i64,
+  pub page: i64,
+  pub page_size: i64,
+}
+
+impl<T> Page<T> {
+  pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+    Page {
+      items: self.items.into_iter().map(f).collect(),
+      total: self.total,
+      page: self.page,
+      page_size: self.page_size,
+    }
+  }
+}
+
+/// Tarih aralığı (YYYY-MM-DD). Boş uç = sınırsız.
+#[derive(Clone, Default)]
+pub struct DateRange {
+  pub from: Option<String>,
+  pub to: Option<String>,
+}
+
+impl DateRange {
+  pub fn parse(from: Option<String>, to: Option<String>) -> Result<DateRange, PosError> {
+    let parse_one = |label: &'static str, v: Option<String>| -> Result<Option<chrono::NaiveDate>, PosError> {
+      match norm_opt(v) {
+        Some(s) => chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+          .map(Some)
+          .map_err(|_| PosError::Validation(tr!("{} tarihi YYYY-AA-GG olmalı: {}", i18n::text(label), s))),
+        None => Ok(None),
+      }
+    };
+
+    let from = parse_one("Başlangıç", from)?;
+    let to = parse_one("Bitiş", to)?;
+
+    if let (Some(f), Some(t)) = (from, to) {
+      if f > t {
+        return Err(PosError::Validation(tr!("Başlangıç tarihi bitişten sonra olamaz")));
+      }
+    }
+
+    Ok(DateRange {
+      from: from.map(|d| d.format("%Y-%m-%d").to_string()),
+      to: to.map(|d| d.format("%Y-%m-%d").to_string()),
+    })
+  }
+}
+
+impl ListQuery {
+  pub fn dates(&self) -> Result<DateRange, PosError> {
+    DateRange::parse(self.from.clone(), self.to.clone())
+  }
+
+  /// LIKE deseni: "%q%"
+  fn q_like(&self) -> Option<String> {
+    norm_opt(self.q.clone()).map(|t| format!("%{}%", t))
+  }
+
+  /// Sayfalamadan tüm kayıtlar (dışa aktarma gibi iç kullanımlar için)
+  pub fn all() -> ListQuery {
+    ListQuery { all: true, ..Default::default() }
+  }
+
+  /// (limit, offset). `all` ise limit -1 (SQLite: sınırsız).
+  fn limit_offset(&self) -> (i64, i64) {
+    if self.all {
+      return (-1, 0);
+    }
+    let size = self.page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let page = self.page.unwrap_or(1).max(1);
+    (size, (page - 1) * size)
+  }
+
+  /// allowed: (sort anahtarı, SQL ifadesi). İlk eleman varsayılan.
+  /// Kullanıcı girdisi SQL'e hiç girmez, sadece listeden seçilir.
+  fn order_by(&self, allowed: &[(&str, &str)], default_desc: bool) -> Result<String, PosError> {
+    let (expr, desc) = match norm_opt(self.sort.clone()) {
+      Some(s) => {
+        let (_, expr) = allowed
+          .iter()
+          .find(|(k, _)| k.eq_ignore_ascii_case(&s))
+          .ok_or_else(|| PosError::Validation(tr!("Geçersiz sıralama alanı: {}", s)))?;
+        (*expr, self.desc.unwrap_or(false))
+      }
+      None => (allowed[0].1, self.desc.unwrap_or(default_desc)),
+    };
+
+    Ok(format!("{} {}", expr, if desc { "DESC" } else { "ASC" }))
+  }
+
+  fn page_of<T>(&self, items: Vec<T>, total: i64) -> Page<T> {
+    let (limit, _) = self.limit_offset();
+    Page {
+      items,
+      total,
+      page: if limit < 0 { 1 } else { self.page.unwrap_or(1).max(1) },
+      page_size: if limit < 0 { total } else { limit },
+    }
+  }
+
+  /// SQL dışındaki listeler için (yedek klasörü); tablolar SQL'de LIMIT / OFFSET ile sayfalanır.
+  pub(crate) fn paginate<T>(&self, rows: Vec<T>) -> Page<T> {
+    let total = rows.len() as i64;
+    let (limit, offset) = self.limit_offset();
+    let items = if limit < 0 {
+      rows
+    } else {
+      rows
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect()
+    };
+    self.page_of(items, total)
+  }
+}
+
+// -------------------- RAPOR ARALIĞI / DÖNEM KARŞILAŞTIRMA --------------------
+
+/// from/to verilmezse: bugün dahil son 30 gün
+pub const DEFAULT_REPORT_DAYS: i64 = 30;
+
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ReportRange {
+  // YYYY-MM-DD, iki uç dahil
+  pub from: Option<String>,
+  pub to: Option<String>,
+  // true: aynı uzunluktaki bir önceki dönem de hesaplanır
+  pub compare: bool,
+}
+
+/// Çözümlenmiş rapor dönemi (iki uç dahil).
+#[derive(Clone, Copy)]
+pub struct Period {
+  pub from: chrono::NaiveDate,
+  pub to: chrono::NaiveDate,
+}
+
+impl Period {
+  pub fn days(&self) -> i64 {
+    (self.to - self.from).num_days() + 1
+  }
+
+  /// Hemen önceki, aynı uzunluktaki dönem. Örn: 1-31 Mart -> 30 Oca - 28 Şub
+  pub fn previous(&self) -> Period {
+    let len = chrono::Duration::days(self.days());
+    Period {
+      from: self.from - len,
+      to: self.from - chrono::Duration::days(1),
+    }
+  }
+
+  pub fn first_day(&self) -> String {
+    self.from.format("%Y-%m-%d").to_string()
+  }
+
+  pub fn last_day(&self) -> String {
+    self.to.format("%Y-%m-%d").to_string()
+  }
+
+  /// Dönemdeki günler, eskiden yeniye
+  fn each_day(&self) -> Vec<String> {
+    (0..self.days())
+      .map(|i| (self.from + chrono::Duration::days(i)).format("%Y-%m-%d").to_string())
+      .collect()
+  }
+
+  /// Dönemin dokunduğu aylar (YYYY-MM), eskiden yeniye
+  fn each_month(&self) -> Vec<String> {
+    use chrono::Datelike;
+    let mut out = Vec::new();
+    let (mut y, mut m) = (self.from.year(), self.from.month());
+    while (y, m) <= (self.to.year(), self.to.month()) {
+      out.push(format!("{:04}-{:02}", y, m));
+      if m == 12 {
+        y += 1;
+        m = 1;
+      } else {
+        m += 1;
+      }
+    }
+    out
+  }
+}
+
+impl ReportRange {
+  pub fn period(&self) -> Result<Period, PosError> {
+    let range = DateRange::parse(self.from.clone(), self.to.clone())?;
+    let parse = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| PosError::Validation(e.to_string()));
+
+    let to = match &range.to {
+      Some(t) => parse(t)?,
+      None => chrono::Local::now().date_naive(),
+    };
+    let from = match &range.from {
+      Some(f) => parse(f)?,
+      None => to - chrono::Duration::days(DEFAULT_REPORT_DAYS - 1),
+    };
+
+    if from > to {
+      return Err(PosError::Validation(tr!("Başlangıç tarihi bitişten sonra olamaz")));
+    }
+    Ok(Period { from, to })
+  }
+}
+
+/// Karşılaştırılabilir rapor: dönem toplamları (metrik adı, değer).
+pub trait ReportTotals {
+  fn totals(&self) -> Vec<(&'static str, f64)>;
+}
+
+#[derive(serde::Serialize)]
+pub struct MetricDelta {
+  pub metric: &'static str,
+  pub current: f64,
+  pub previous: f64,
+  pub change: f64,
+  // önceki 0 ise None
+  pub change_pct: Option<f64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ReportComparison<T> {
+  pub from: String,
+  pub to: String,
+  pub data: T,
+  pub deltas: Vec<MetricDelta>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ReportResult<T> {
+  pub from: String,
+  pub to: String,
+  pub data: T,
+  pub comparison: Option<ReportComparison<T>>,
+}
+
+fn metric_deltas(current: &[(&'static str, f64)], previous: &[(&'static str, f64)]) -> Vec<MetricDelta> {
+  current
+    .iter()
+    .map(|(metric, cur)| {
+      let prev = previous
+        .iter()
+        .find(|(m, _)| m == metric)
+        .map(|(_, v)| *v)
+        .unwrap_or(0.0);
+      // boş listenin f64 toplamı -0.0; JSON'da "-0" görünmesin
+      let (cur, prev) = (cur + 0.0, prev + 0.0);
+      let change = cur - prev;
+      let change_pct = if prev.abs() > 0.0001 {
+        Some((change / prev.abs() * 100.0 * 10.0).round() / 10.0)
+      } else {
+        None
+      };
+      MetricDelta {
+        metric,
+        current: cur,
+        previous: prev,
+        change,
+        change_pct,
+      }
+    })
+    .collect()
+}
+
+/// Raporu verilen aralık için çalıştırır; compare ise önceki dönem + farklar da eklenir.
+pub fn run_report<T: ReportTotals>(
+  range: &ReportRange,
+  f: impl Fn(&Period) -> Result<T, PosError>,
+) -> Result<ReportResult<T>, PosError> {
+  let period = range.period()?;
+  let data = f(&period)?;
+
+  let comparison = if range.compare {
+    let prev = period.previous();
+    let prev_data = f(&prev)?;
+    let deltas = metric_deltas(&data.totals(), &prev_data.totals());
+    Some(ReportComparison {
+      from: prev.first_day(),
+      to: prev.last_day(),
+      data: prev_data,
+      deltas,
+    })
+  } else {
+    None
+  };
+
+  Ok(ReportResult {
+    from: period.first_day(),
+    to: period.last_day(),
+    data,
+    comparison,
+  })
+}
+
+impl Store {
+  pub fn list_products(&self, query: &ListQuery) -> Result<Page<Product>, PosError> {
+    let conn = self.read()?;
+    let q_like = query.q_like();
+    let include = if query.include_inactive { 1 } else { 0 };
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(
+      &[
+        ("barcode", "CAST(p.barcode AS INTEGER)"),
+        ("name", "p.name"),
+        ("category", "COALESCE(p.category,'')"),
+        ("sell_price", "p.sell_price"),
+        ("stock", "COALESCE(p.magaza_stok,0) + COALESCE(p.depo_stok,0)"),
+        ("created_at", "p.created_at"),
+      ],
+      false,
+    )?;
+
+    // ?1 pasifler dahil mi, ?2 barkod / ad / ürün kodu araması
+    let filter = r#"
+      (?1 = 1 OR COALESCE(p.is_active, 1) = 1)
+      AND (?2 IS NULL
+        OR p.barcode LIKE ?2
+        OR p.name LIKE ?2
+        OR COALESCE(p.product_code,'') LIKE ?2)
+    "#;
+
+    let total: i64 = conn
+      .query_row(
+        &format!("SELECT COUNT(*) FROM products p WHERE {}", filter),
+        params![include, q_like],
+        |r| r.get(0),
+      )?;
+
+    let mut stmt = conn
+      .prepare(&format!(
+        r#"
+        SELECT {cols}
+        FROM products p
+        WHERE {filter}
+        ORDER BY {order}, p.barcode ASC
+        LIMIT ?3 OFFSET ?4
+        "#,
+        cols = PRODUCT_SELECT_P,
+        filter = filter,
+        order = order
+      ))?;
+
+    let items = stmt
+      .query_map(params![include, q_like, limit, offset], product_from_row)?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(query.page_of(items, total))
+  }
+}
+
+
+// -------------------- ÜRÜN ARAMA (FTS5) --------------------
+//
+// products_fts: ad, product_code, kategori, renk, beden, barkod ve ek barkodlar üzerinde FTS5
+// indeksi. Tetikleyicilerle products / product_barcodes tablolarıyla senkron tutulur. unicode61 + remove_diacritics
+// ç/ş/ğ/ö/ü harflerini katlar; Türkçe ı/İ ise SQL tarafında 'i'ye çevrilir.
+
+const FTS_FOLD_OPEN: &str = "replace(replace(COALESCE(";
+const FTS_FOLD_CLOSE: &str = ",''),'ı','i'),'İ','i')";
+
+fn fts_fold(col: &str) -> String {
+  format!("{}{}{}", FTS_FOLD_OPEN, col, FTS_FOLD_CLOSE)
+}
+
+fn fts_cols(prefix: &str) -> String {
+  ["barcode", "name", "product_code", "category", "color", "size"]
+    .iter()
+    .map(|c| fts_fold(&format!("{}{}", prefix, c)))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// products satırının ek barkodları, boşlukla ayrılmış (v9 aliases kolonu)
+const FTS_ALIASES_SQL: &str =
+  "COALESCE((SELECT group_concat(pb.barcode, ' ') FROM product_barcodes pb WHERE pb.product_barcode = products.barcode), '')";
+
+/// v2 şeması (aliases kolonu v9'da eklenir)
+fn ensure_products_fts(conn: &Connection) -> Result<(), PosError> {
+
+  conn
+    .execute_batch(&format!(
+      r#"
+      CREATE VIRTUAL TABLE IF NOT EXISTS products_fts USING fts5(
+        barcode, name, product_code, category, color, size,
+        tokenize = "unicode61 remove_diacritics 2"
+      );
+
+      DROP TRIGGER IF EXISTS trg_products_fts_insert;
+      DROP TRIGGER IF EXISTS trg_products_fts_update;
+      DROP TRIGGER IF EXISTS trg_products_fts_delete;
+
+      CREATE TRIGGER trg_products_fts_insert
+      AFTER INSERT ON products
+      BEGIN
+        INSERT INTO products_fts(rowid, barcode, name, product_code, category, color, size)
+        VALUES (NEW.rowid, {new_cols});
+      END;
+
+      CREATE TRIGGER trg_products_fts_update
+      AFTER UPDATE ON products
+      BEGIN
+        DELETE FROM products_fts WHERE rowid = OLD.rowid;
+        INSERT INTO products_fts(rowid, barcode, name, product_code, category, color, size)
+        VALUES (NEW.rowid, {new_cols});
+      END;
+
+      CREATE TRIGGER trg_products_fts_delete
+      AFTER DELETE ON products
+      BEGIN
+        DELETE FROM products_fts WHERE rowid = OLD.rowid;
+      END;
+      "#,
+      new_cols = fts_cols("NEW.")
+    ))?;
+
+  // İlk kurulum / tetikleyici öncesi eklenmiş ürünler: sayılar tutmuyorsa yeniden doldur
+  let products: i64 = conn
+    .query_row("SELECT COUNT(*) FROM products", [], |r| r.get(0))?;
+  let indexed: i64 = conn
+    .query_row("SELECT COUNT(*) FROM products_fts", [], |r| r.get(0))?;
+
+  if products != indexed {
+    conn
+      .execute_batch(&format!(
+        r#"
+        DELETE FROM products_fts;
+        INSERT INTO products_fts(rowid, barcode, name, product_code, category, color, size)
+        SELECT rowid, {cols} FROM products;
+        "#,
+        cols = fts_cols("")
+      ))?;
+  }
+
+  Ok(())
+}
+
+/// Türkçe büyük/küçük harf + aksan katlama: "GÖMLEK", "gömlek", "Gomlek" aynı.
+/// İ/I/ı/i hepsi 'i' olur (klavyede ı yazmayan kullanıcılar için).
+fn fold_tr(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for ch in s.chars() {
+    match ch {
+      'İ' | 'I' | 'ı' | 'i' => out.push('i'),
+      'Ş' | 'ş' => out.push('s'),
+      'Ğ' | 'ğ' => out.push('g'),
+      'Ç' | 'ç' => out.push('c'),
+      'Ö' | 'ö' => out.push('o'),
+      'Ü' | 'ü' => out.push('u'),
+      _ => out.extend(ch.to_lowercase()),
+    }
+  }
+  out
+}
+
+fn search_tokens(query: &str) -> Vec<String> {
+  fold_tr(query)
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|t| !t.is_empty())
+    .map(|t| t.to_string())
+    .collect()
+}
+
+/// Her kelime önek araması, hepsi AND: "gom siy" -> "gom"* "siy"*
+fn fts_match_expr(tokens: &[String]) -> String {
+  tokens
+    .iter()
+    .map(|t| format!("\"{}\"*", t.replace('"', "\"\"")))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ActiveFilter {
+  #[default]
+  Active,
+  Inactive,
+  All,
+}
+
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+pub struct ProductSearchFilters {
+  pub active: ActiveFilter,
+  // 'MAGAZA' | 'DEPO': sadece o lokasyonda stoğu olanlar
+  pub location: Option<String>,
+  pub category: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(default)]
+pub struct PageRequest {
+  pub page: i64, // 1'den başlar
+  pub page_size: i64,
+}
+
+impl Default for PageRequest {
+  fn default() -> Self {
+    PageRequest { page: 1, page_size: 50 }
+  }
+}
+
+impl PageRequest {
+  fn normalized(&self) -> (i64, i64) {
+    let size = self.page_size.clamp(1, 200);
+    let page = self.page.max(1);
+    (page, size)
+  }
+
+  fn offset(&self) -> i64 {
+    let (page, size) = self.normalized();
+    (page - 1) * size
+  }
+}
+
+#[derive(serde::Serialize)]
+pub struct ProductSearchResult {
+  pub items: Vec<Product>,
+  pub total: i64,
+  pub page: i64,
+  pub page_size: i64,
+  // FTS sonuç vermediyse yazım hatası toleranslı arama kullanıldı
+  pub fuzzy: bool,
+}
+
+const PRODUCT_SELECT_P: &str = r#"
+  p.barcode,
+  p.product_code,
+  p.category,
+  p.name,
+  p.color,
+  p.size,
+  COALESCE(p.buy_price, 0),
+  p.sell_price,
+  p.created_at,
+  COALESCE(p.magaza_stok, 0) + COALESCE(p.depo_stok, 0),
+  COALESCE(p.magaza_baslangic, 0),
+  COALESCE(p.depo_baslangic, 0),
+  COALESCE(p.magaza_stok, 0),
+  COALESCE(p.depo_stok, 0)
+"#;
+
+fn product_from_row(row: &rusqlite::Row) -> rusqlite::Result<Product> {
+  Ok(Product {
+    barcode: row.get(0)?,
+    product_code: row.get(1)?,
+    category: row.get(2)?,
+    name: row.get(3)?,
+    color: row.get(4)?,
+    size: row.get(5)?,
+    buy_price: row.get(6)?,
+    sell_price: row.get(7)?,
+    created_at: row.get(8)?,
+    stock: row.get(9)?,
+    magaza_baslangic: row.get(10)?,
+    depo_baslangic: row.get(11)?,
+    magaza_stok: row.get(12)?,
+    depo_stok: row.get(13)?,
+  })
+}
+
+// ?2 aktiflik ('ACTIVE'|'INACTIVE'|'ALL'), ?3 lokasyon, ?4 kategori
+const SEARCH_FILTER_SQL: &str = r#"
+  (?2 = 'ALL' OR COALESCE(p.is_active, 1) = CASE ?2 WHEN 'ACTIVE' THEN 1 ELSE 0 END)
+  AND (
+    ?3 IS NULL
+    OR (?3 = 'MAGAZA' AND COALESCE(p.magaza_stok, 0) > 0)
+    OR (?3 = 'DEPO' AND COALESCE(p.depo_stok, 0) > 0)
   )
-  .map_err(|e| e.to_string())?;
-
-  Ok(final_barcode)
-}*/
-pub fn add_product(
-  barcode: Option<String>,
-  product_code: Option<String>,
-  category: Option<String>,
-  name: String,
-  color: Option<String>,
-  size: Option<String>,
-  buy_price: Option<f64>,
-  sell_price: f64,
-  stock: Option<i64>,
-  magaza_baslangic: Option<i64>,
-  depo_baslangic: Option<i64>,
-) -> Result<CreatedProduct, String> {
-  let conn = get_conn()?;
-
-  let name = norm_req("Ürün adı", &name)?;
-  if !sell_price.is_finite() {
-    return Err("Satış fiyatı sayı olmalı".to_string());
-  }
-
-  let category = norm_opt(category);
-  let color = norm_opt(color);
-  let size = norm_opt(size);
-
-  let product_code_norm: Option<String> = norm_opt(product_code)
-    .map(|pc| pc.trim().to_uppercase().replace('-', ""));
-
-  let mb = magaza_baslangic.unwrap_or(0);
-  let db_val = depo_baslangic.unwrap_or(0);
-
-  // ── Aynı aile (product_code) + renk + beden zaten var mı? ──
-  // Varsa yeni ürün açmak yerine stoğunu artır.
-  if let Some(ref pc) = product_code_norm {
-    if !pc.is_empty() {
-      let existing: Option<(String, Option<String>)> = conn
+  AND (?4 IS NULL OR p.category_id IN (SELECT id FROM categories WHERE name = ?4))
+"#;
+
+impl Store {
+  pub fn search_products(
+    &self,
+    query: &str,
+    filters: ProductSearchFilters,
+    page: PageRequest,
+  ) -> Result<ProductSearchResult, PosError> {
+    let conn = self.read()?;
+    let (page_no, page_size) = page.normalized();
+    let offset = page.offset();
+
+    let active = match filters.active {
+      ActiveFilter::Active => "ACTIVE",
+      ActiveFilter::Inactive => "INACTIVE",
+      ActiveFilter::All => "ALL",
+    };
+    let location = norm_opt(filters.location).map(|l| l.to_uppercase());
+    if let Some(loc) = &location {
+      if loc != "MAGAZA" && loc != "DEPO" {
+        return Err(PosError::Validation(tr!("Lokasyon MAGAZA veya DEPO olmalı")));
+      }
+    }
+    let category = norm_opt(filters.category);
+
+    let tokens = search_tokens(query);
+
+    // Boş arama: filtreye uyan ürünler, ada göre
+    if tokens.is_empty() {
+      let total: i64 = conn
         .query_row(
-          "SELECT barcode, product_code FROM products
-           WHERE product_code = ?1
-             AND COALESCE(color,'') = COALESCE(?2,'')
-             AND COALESCE(size,'')  = COALESCE(?3,'')
-             AND COALESCE(is_active,1) = 1
-           LIMIT 1",
-          params![pc, color, size],
-          |r| Ok((r.get::<_,String>(0)?, r.get::<_,Option<String>>(1)?)),
-        )
-        .optional()
-        .map_err(|e| e.to_string())?;
+          &format!(
+            "SELECT COUNT(*) FROM products p WHERE {}",
+            SEARCH_FILTER_SQL
+          ),
+          params![Option::<String>::None, active, location, category],
+          |r| r.get(0),
+        )?;
+
+      let mut stmt = conn
+        .prepare(&format!(
+          r#"
+          SELECT {cols} FROM products p
+          WHERE {cond}
+          ORDER BY p.name ASC, p.barcode ASC
+          LIMIT ?5 OFFSET ?6
+          "#,
+          cols = PRODUCT_SELECT_P,
+          cond = SEARCH_FILTER_SQL
+        ))?;
+
+      let items = stmt
+        .query_map(
+          params![Option::<String>::None, active, location, category, page_size, offset],
+          product_from_row,
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+      return Ok(ProductSearchResult {
+        items,
+        total,
+        page: page_no,
+        page_size,
+        fuzzy: false,
+      });
+    }
+
+    let match_expr = fts_match_expr(&tokens);
+
+    let total: i64 = conn
+      .query_row(
+        &format!(
+          r#"
+          SELECT COUNT(*)
+          FROM products_fts f
+          JOIN products p ON p.rowid = f.rowid
+          WHERE products_fts MATCH ?1 AND {}
+          "#,
+          SEARCH_FILTER_SQL
+        ),
+        params![&match_expr, active, location, category],
+        |r| r.get(0),
+      )?;
+
+    if total == 0 {
+      // Yazım hatası toleransı: kelimelere yakın indeks terimleriyle FTS'ten sınırlı sayıda
+      // aday çekilir, adaylar mesafeye göre sıralanır. Katalog belleğe yüklenmez.
+      let Some(fuzzy_expr) = fuzzy_match_expr(&conn, &tokens)? else {
+        return Ok(ProductSearchResult {
+          items: Vec::new(),
+          total: 0,
+          page: page_no,
+          page_size,
+          fuzzy: true,
+        });
+      };
+
+      let mut stmt = conn
+        .prepare(&format!(
+          r#"
+          SELECT {cols}, {text}
+          FROM products_fts f
+          JOIN products p ON p.rowid = f.rowid
+          WHERE products_fts MATCH ?1 AND {cond}
+          ORDER BY bm25(products_fts, 8.0, 10.0, 6.0, 2.0, 3.0, 3.0, 8.0) ASC
+          LIMIT ?5
+          "#,
+          cols = PRODUCT_SELECT_P,
+          text = FTS_TEXT_SQL,
+          cond = SEARCH_FILTER_SQL
+        ))?;
+
+      let candidates = stmt
+        .query_map(
+          params![&fuzzy_expr, active, location, category, FUZZY_CANDIDATES],
+          |r| Ok((product_from_row(r)?, r.get::<_, String>(14)?)),
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+      let matched = fuzzy_match(candidates, &tokens);
+      let total = matched.len() as i64;
+      let items = matched
+        .into_iter()
+        .skip(offset as usize)
+        .take(page_size as usize)
+        .collect();
+
+      return Ok(ProductSearchResult {
+        items,
+        total,
+        page: page_no,
+        page_size,
+        fuzzy: true,
+      });
+    }
+
+    // bm25 ağırlıkları: barcode, name, product_code, category, color, size, aliases
+    let mut stmt = conn
+      .prepare(&format!(
+        r#"
+        SELECT {cols}
+        FROM products_fts f
+        JOIN products p ON p.rowid = f.rowid
+        WHERE products_fts MATCH ?1 AND {cond}
+        ORDER BY bm25(products_fts, 8.0, 10.0, 6.0, 2.0, 3.0, 3.0, 8.0) ASC, p.name ASC
+        LIMIT ?5 OFFSET ?6
+        "#,
+        cols = PRODUCT_SELECT_P,
+        cond = SEARCH_FILTER_SQL
+      ))?;
+
+    let items = stmt
+      .query_map(
+        params![&match_expr, active, location, category, page_size, offset],
+        product_from_row,
+      )?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ProductSearchResult {
+      items,
+      total,
+      page: page_no,
+      page_size,
+      fuzzy: false,
+    })
+  }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+  let a: Vec<char> = a.chars().collect();
+  let b: Vec<char> = b.chars().collect();
+  let mut prev: Vec<usize> = (0..=b.len()).collect();
+  let mut cur = vec![0; b.len() + 1];
+
+  for i in 1..=a.len() {
+    cur[0] = i;
+    for j in 1..=b.len() {
+      let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+      cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+    }
+    std::mem::swap(&mut prev, &mut cur);
+  }
+  prev[b.len()]
+}
+
+/// Kelime başına izin verilen hata: kısa kelimede 0, 4-6 harfte 1, daha uzunda 2.
+fn token_distance(token: &str, words: &[String]) -> Option<usize> {
+  let max_d = match token.chars().count() {
+    0..=3 => 0,
+    4..=6 => 1,
+    _ => 2,
+  };
+
+  words
+    .iter()
+    .filter_map(|w| {
+      if w.starts_with(token) {
+        return Some(0);
+      }
+      // önek + yazım hatası: kelimenin token uzunluğundaki başı ile de karşılaştır
+      let head: String = w.chars().take(token.chars().count()).collect();
+      let d = levenshtein(token, w).min(levenshtein(token, &head));
+      if d <= max_d { Some(d) } else { None }
+    })
+    .min()
+}
+
+/// Yazım hatalı aramada FTS'ten çekilen en fazla aday ürün
+const FUZZY_CANDIDATES: i64 = 500;
+/// Kelime başına MATCH ifadesine eklenen en fazla yakın terim
+const FUZZY_TERMS_PER_TOKEN: usize = 32;
+
+/// products_fts satırının katlanmış metni (fuzzy_match kelimeleri)
+const FTS_TEXT_SQL: &str =
+  "f.barcode || ' ' || f.name || ' ' || f.product_code || ' ' || f.category || ' ' || f.color || ' ' || f.size || ' ' || f.aliases";
+
+/// Her kelime için önek + indeksteki yakın terimler (products_fts_vocab), kelimeler AND:
+/// "gomlk siyah" -> ("gomlk"* OR "gomlek") AND ("siyah"* OR ...). Yakın terimi olmayan
+/// kelime varsa None (eşleşme olamaz).
+fn fuzzy_match_expr(conn: &Connection, tokens: &[String]) -> Result<Option<String>, PosError> {
+  let quote = |t: &str| format!("\"{}\"", t.replace('"', "\"\""));
+  let mut stmt = conn.prepare_cached("SELECT term FROM products_fts_vocab WHERE length(term) >= ?1")?;
+
+  let mut groups = Vec::with_capacity(tokens.len());
+  for t in tokens {
+    // token_distance kelimenin başını da karşılaştırır: token'dan 2 harf kısa terimler de aday
+    let min_len = t.chars().count().saturating_sub(2) as i64;
+    let mut has_prefix = false;
+    let mut near: Vec<(usize, String)> = Vec::new();
+    for term in stmt.query_map(params![min_len], |r| r.get::<_, String>(0))? {
+      let term = term?;
+      if term.starts_with(t.as_str()) {
+        // önek zaten "t"* ile eşleşir
+        has_prefix = true;
+      } else if let Some(d) = token_distance(t, std::slice::from_ref(&term)) {
+        near.push((d, term));
+      }
+    }
+    if !has_prefix && near.is_empty() {
+      return Ok(None);
+    }
+    near.sort();
+    near.truncate(FUZZY_TERMS_PER_TOKEN);
+
+    let mut parts = vec![format!("{}*", quote(t))];
+    parts.extend(near.iter().map(|(_, term)| quote(term)));
+    groups.push(format!("({})", parts.join(" OR ")));
+  }
+
+  Ok(Some(groups.join(" AND ")))
+}
+
+/// FTS sonuç vermediğinde (yazım hatası): her kelime bir alanın başıyla
+/// önek ya da küçük Levenshtein mesafesiyle eşleşmeli. Mesafe toplamına göre sıralı.
+/// Adaylar (ürün, products_fts metni) olarak gelir; metin ek barkodları da içerir.
+fn fuzzy_match(candidates: Vec<(Product, String)>, tokens: &[String]) -> Vec<Product> {
+  let mut scored: Vec<(usize, Product)> = Vec::new();
+
+  for (p, text) in candidates {
+    let words: Vec<String> = fold_tr(&text)
+      .split(|c: char| !c.is_alphanumeric())
+      .filter(|w| !w.is_empty())
+      .map(|w| w.to_string())
+      .collect();
+
+    let mut score = 0;
+    let mut all = true;
+    for t in tokens {
+      match token_distance(t, &words) {
+        Some(d) => score += d,
+        None => {
+          all = false;
+          break;
+        }
+      }
+    }
+
+    if all {
+      scored.push((score, p));
+    }
+  }
+
+  scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.name.cmp(&b.1.name)));
+  scored.into_iter().map(|(_, p)| p).collect()
+}
+
+
+impl Store {
+  pub fn find_product_by_barcode(&self, barcode: &str) -> Result<Option<Product>, PosError> {
+    let conn = self.read()?;
+
+    // alias okutulduysa ana ürünü döndür
+    let barcode = match resolve_barcode(&conn, barcode)? {
+      Some(bc) => bc,
+      None => return Ok(None),
+    };
+
+    let mut stmt = conn
+      .prepare_cached(
+        r#"
+        SELECT
+          barcode,
+          product_code,
+          category,
+          name,
+          color,
+          size,
+          COALESCE(buy_price, 0),
+          sell_price,
+          created_at,
+          COALESCE(magaza_stok, 0) + COALESCE(depo_stok, 0),
+          COALESCE(magaza_baslangic, 0),
+          COALESCE(depo_baslangic, 0),
+          COALESCE(magaza_stok, 0),
+          COALESCE(depo_stok, 0)
+        FROM products
+        WHERE barcode = ?1
+        LIMIT 1
+        "#,
+      )?;
+
+    let row = stmt
+      .query_row(params![barcode], |row| {
+        Ok(Product {
+          barcode: row.get(0)?,
+          product_code: row.get(1)?,
+          category: row.get(2)?,
+          name: row.get(3)?,
+          color: row.get(4)?,
+          size: row.get(5)?,
+          buy_price: row.get(6)?,
+          sell_price: row.get(7)?,
+          created_at: row.get(8)?,
+          stock: row.get(9)?,
+          magaza_baslangic: row.get(10)?,
+          depo_baslangic: row.get(11)?,
+          magaza_stok: row.get(12)?,
+          depo_stok: row.get(13)?,
+        })
+      })
+      .optional()?;
+
+    Ok(row)
+  }
+}
+
+
+// -------------------- ALIAS BARKODLAR --------------------
+//
+// Tedarikçi barkodu / eski etiket gibi ek kodlar product_barcodes tablosunda.
+// Satış, iade ve raporlar her zaman ana (products.barcode) barkod ile çalışır.
+
+/// Barkod veya alias -> ana ürün barkodu. Eşleşme yoksa None.
+fn resolve_barcode(conn: &Connection, code: &str) -> Result<Option<String>, PosError> {
+  let code = code.trim();
+  if code.is_empty() {
+    return Ok(None);
+  }
+
+  // okutma başına çalışır; ifadeler bağlantı önbelleğinden
+  let direct: Option<String> = conn
+    .prepare_cached("SELECT barcode FROM products WHERE barcode = ?1")
+    .and_then(|mut stmt| stmt.query_row(params![code], |r| r.get(0)))
+    .optional()?;
+
+  if direct.is_some() {
+    return Ok(direct);
+  }
+
+  conn
+    .prepare_cached("SELECT product_barcode FROM product_barcodes WHERE barcode = ?1")
+    .and_then(|mut stmt| stmt.query_row(params![code], |r| r.get(0)))
+    .optional()
+    .map_err(PosError::from)
+}
+
+fn is_alias_barcode(conn: &Connection, code: &str) -> Result<bool, PosError> {
+  let n: i64 = conn
+    .query_row(
+      "SELECT COUNT(*) FROM product_barcodes WHERE barcode = ?1",
+      params![code.trim()],
+      |r| r.get(0),
+    )?;
+  Ok(n > 0)
+}
+
+#[derive(serde::Serialize)]
+pub struct ProductBarcodeRow {
+  pub barcode: String,
+  pub product_barcode: String,
+  pub note: Option<String>,
+  pub created_at: Option<String>,
+}
+
+impl Store {
+  pub fn list_product_barcodes(
+    &self,
+    product_barcode: &str,
+    query: &ListQuery,
+  ) -> Result<Page<ProductBarcodeRow>, PosError> {
+    let conn = self.read()?;
+    let canonical = resolve_barcode(&conn, product_barcode)?
+      .ok_or_else(|| PosError::NotFound(tr!("Ürün bulunamadı: {}", product_barcode)))?;
+
+    let q_like = query.q_like();
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(&[("created_at", "created_at"), ("barcode", "barcode")], false)?;
+
+    // ?1 ana barkod, ?2 ek barkod / not araması
+    let filter = "product_barcode = ?1 AND (?2 IS NULL OR barcode LIKE ?2 OR COALESCE(note,'') LIKE ?2)";
+
+    let total: i64 = conn.query_row(
+      &format!("SELECT COUNT(*) FROM product_barcodes WHERE {}", filter),
+      params![&canonical, q_like],
+      |r| r.get(0),
+    )?;
+
+    let mut stmt = conn
+      .prepare(&format!(
+        r#"
+        SELECT barcode, product_barcode, note, created_at
+        FROM product_barcodes
+        WHERE {}
+        ORDER BY {}, barcode ASC
+        LIMIT ?3 OFFSET ?4
+        "#,
+        filter, order
+      ))?;
+
+    let items = stmt
+      .query_map(params![&canonical, q_like, limit, offset], |r| {
+        Ok(ProductBarcodeRow {
+          barcode: r.get(0)?,
+          product_barcode: r.get(1)?,
+          note: r.get(2)?,
+          created_at: r.get(3)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(query.page_of(items, total))
+  }
+
+  pub fn add_product_barcode(&self, product_barcode: &str, alias: &str, note: Option<String>) -> Result<i64, PosError> {
+    let alias = norm_req("Ek barkod", alias)?;
+    let note = norm_opt(note);
+
+    let conn = self.write()?;
+
+    // alias da verilebilir; her zaman ana ürüne bağla
+    let canonical = resolve_barcode(&conn, product_barcode)?
+      .ok_or_else(|| PosError::NotFound(tr!("Ürün bulunamadı: {}", product_barcode)))?;
+
+    if resolve_barcode(&conn, &alias)?.is_some() {
+      return Err(PosError::Conflict(tr!("Bu barkod zaten kullanılıyor: {}", alias)));
+    }
+
+    conn
+      .execute(
+        "INSERT INTO product_barcodes (barcode, product_barcode, note) VALUES (?1, ?2, ?3)",
+        params![&alias, &canonical, note],
+      )?;
+
+    Ok(1)
+  }
 
-      if let Some((existing_barcode, existing_pc)) = existing {
-        // Var olan ürünün stokunu artır
-        conn.execute(
-          "UPDATE products SET
-             magaza_stok      = COALESCE(magaza_stok,0) + ?1,
-             depo_stok        = COALESCE(depo_stok,0)   + ?2,
-             stock            = COALESCE(stock,0)        + ?3,
-             magaza_baslangic = COALESCE(magaza_baslangic,0) + ?1,
-             depo_baslangic   = COALESCE(depo_baslangic,0)   + ?2,
-             updated_at       = datetime('now','localtime')
-           WHERE barcode = ?4",
-          params![mb, db_val, mb + db_val, &existing_barcode],
-        )
-        .map_err(|e| e.to_string())?;
+  pub fn delete_product_barcode(&self, alias: &str) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let changed = conn
+      .execute(
+        "DELETE FROM product_barcodes WHERE barcode = ?1",
+        params![alias.trim()],
+      )?;
+    Ok(changed as i64)
+  }
+}
 
-        return Ok(CreatedProduct {
-          barcode: existing_barcode,
-          product_code: existing_pc,
-        });
+impl Store {
+  pub fn add_product(
+    &self,
+    barcode: Option<String>,
+    product_code: Option<String>,
+    category: Option<String>,
+    name: String,
+    color: Option<String>,
+    size: Option<String>,
+    buy_price: Option<f64>,
+    sell_price: f64,
+    magaza_baslangic: Option<i64>,
+    depo_baslangic: Option<i64>,
+  ) -> Result<CreatedProduct, PosError> {
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
+
+    let name = norm_req("Ürün adı", &name)?;
+    if !sell_price.is_finite() {
+      return Err(PosError::Validation(tr!("Satış fiyatı sayı olmalı")));
+    }
+
+    let category = norm_opt(category);
+    let category_id = resolve_dict_id(&tx, "categories", "Kategori", category.as_deref())?;
+    let color_id = resolve_dict_id(&tx, "colors", "Renk", norm_opt(color).as_deref())?;
+    let size_id = resolve_dict_id(&tx, "sizes", "Beden", norm_opt(size).as_deref())?;
+
+    let product_code_norm: Option<String> = norm_opt(product_code)
+      .map(|pc| pc.trim().to_uppercase().replace('-', ""));
+
+    let mb = magaza_baslangic.unwrap_or(0);
+    let db_val = depo_baslangic.unwrap_or(0);
+
+    // ── Aynı aile (product_code) + renk + beden zaten var mı? ──
+    // Varsa yeni ürün açmak yerine stoğunu artır.
+    if let Some(ref pc) = product_code_norm {
+      if !pc.is_empty() {
+        let existing: Option<(String, Option<String>)> = tx
+          .query_row(
+            "SELECT barcode, product_code FROM products
+             WHERE product_code = ?1
+               AND color_id IS ?2
+               AND size_id  IS ?3
+               AND COALESCE(is_active,1) = 1
+             LIMIT 1",
+            params![pc, color_id, size_id],
+            |r| Ok((r.get::<_,String>(0)?, r.get::<_,Option<String>>(1)?)),
+          )
+          .optional()?;
+
+        if let Some((existing_barcode, existing_pc)) = existing {
+          // Var olan ürünün stokunu artır; stok hareketi olarak kaydedilir (webhook stock.updated)
+          tx.execute(
+            "UPDATE products SET
+               magaza_stok      = COALESCE(magaza_stok,0) + ?1,
+               depo_stok        = COALESCE(depo_stok,0)   + ?2,
+               magaza_baslangic = COALESCE(magaza_baslangic,0) + ?1,
+               depo_baslangic   = COALESCE(depo_baslangic,0)   + ?2,
+               updated_at       = datetime('now','localtime')
+             WHERE barcode = ?3",
+            params![mb, db_val, &existing_barcode],
+          )?;
+
+          let mut changes = Vec::new();
+          for (loc, delta) in [("MAGAZA", mb), ("DEPO", db_val)] {
+            if delta != 0 {
+              changes.push(stock_change(&tx, &existing_barcode, loc, delta)?);
+            }
+          }
+          enqueue_stock_updated(&tx, &existing_barcode, &changes)?;
+          let events = stock_events(&tx, &changes)?;
+          tx.commit()?;
+          drop(conn);
+          self.emit(events);
+
+          return Ok(CreatedProduct {
+            barcode: existing_barcode,
+            product_code: existing_pc,
+          });
+        }
       }
     }
+
+    // ── Yeni ürün oluştur ──
+    let final_barcode = match norm_opt(barcode) {
+      Some(b) => b,
+      None => next_barcode(&tx)?,
+    };
+
+    if is_alias_barcode(&tx, &final_barcode)? {
+      return Err(PosError::Conflict(tr!("Bu barkod başka bir ürünün ek barkodu: {}", final_barcode)));
+    }
+
+    let product_code_final: Option<String> = match product_code_norm {
+      Some(pc) => Some(pc),
+      None => {
+        let prefix = normalize_prefix_from_category(category.as_deref());
+        let pc = next_product_code_for_prefix(&tx, &prefix)?;
+        Some(pc)
+      }
+    };
+
+    let bp = buy_price.unwrap_or(0.0);
+
+    // kategori/renk/beden adları tetikleyiciyle id'lerden doldurulur
+    tx.execute(
+      r#"
+      INSERT INTO products
+        (barcode, product_code, category_id, name, color_id, size_id, buy_price, sell_price,
+         magaza_baslangic, depo_baslangic, magaza_stok, depo_stok)
+      VALUES
+        (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+      "#,
+      params![
+        final_barcode,
+        product_code_final,
+        category_id,
+        name,
+        color_id,
+        size_id,
+        bp,
+        sell_price,
+        mb,
+        db_val,
+        mb,
+        db_val
+      ],
+    )?;
+    tx.commit()?;
+
+    Ok(CreatedProduct {
+      barcode: final_barcode,
+      product_code: product_code_final,
+    })
   }
+}
 
-  // ── Yeni ürün oluştur ──
-  let final_barcode = match norm_opt(barcode) {
-    Some(b) => b,
-    None => next_barcode(&conn)?,
-  };
+// -------------------- ÜRÜN AİLESİ (renk × beden varyant matrisi) --------------------
+
+#[derive(serde::Deserialize)]
+pub struct CreateProductFamilyPayload {
+  pub product_code: Option<String>,
+  pub category: Option<String>,
+  pub name: String,
+  pub buy_price: Option<f64>,
+  pub sell_price: f64,
+  pub colors: Vec<String>,
+  pub sizes: Vec<String>,
+  // her varyant için başlangıç stoğu (opsiyonel)
+  pub magaza_baslangic: Option<i64>,
+  pub depo_baslangic: Option<i64>,
+  // renk / beden bazında başlangıç stoğu; listede olmayan varyant yukarıdakileri alır
+  #[serde(default)]
+  pub stocks: Vec<FamilyVariantStock>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct FamilyVariantStock {
+  pub color: String,
+  pub size: String,
+  pub magaza: i64,
+  pub depo: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct FamilyVariant {
+  pub barcode: String,
+  pub color: String,
+  pub size: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct CreateProductFamilyResult {
+  pub product_code: String,
+  pub created: Vec<FamilyVariant>,
+  // aynı aile + renk + beden zaten varsa atlanır
+  pub skipped: Vec<FamilyVariant>,
+}
 
-  let product_code_final: Option<String> = match product_code_norm {
-    Some(pc) => Some(pc),
-    None => {
-      let prefix = normalize_prefix_from_category(category.as_deref());
-      let pc = next_product_code_for_prefix(&conn, &prefix)?;
-      Some(pc)
+const MAX_FAMILY_VARIANTS: usize = 200;
+
+fn dedup_names(values: Vec<String>) -> Vec<String> {
+  let mut out: Vec<String> = Vec::new();
+  for v in values {
+    let t = v.trim().to_string();
+    if !t.is_empty() && !out.iter().any(|x| x == &t) {
+      out.push(t);
     }
+  }
+  out
+}
+
+/// Ürün yazımı için ad -> id. Pasif kayıtlar da bulunur (pasif kategorideki ürün
+/// düzenlenebilsin), aktif olan önceliklidir. Boş ad = None, bilinmeyen ad = hata.
+fn resolve_dict_id(
+  conn: &Connection,
+  table: &str,
+  label: &'static str,
+  name: Option<&str>,
+) -> Result<Option<i64>, PosError> {
+  let Some(n) = name else {
+    return Ok(None);
   };
+  conn
+    .query_row(
+      &format!(
+        "SELECT id FROM {} WHERE name = ?1 ORDER BY COALESCE(is_active,1) DESC LIMIT 1",
+        table
+      ),
+      params![n],
+      |r| r.get::<_, i64>(0),
+    )
+    .optional()?
+    .map(Some)
+    .ok_or_else(|| PosError::NotFound(tr!("{} bulunamadı: {}", i18n::text(label), n)))
+}
+
+fn dict_id_by_name(conn: &Connection, table: &str, name: &str) -> Result<Option<i64>, PosError> {
+  conn
+    .query_row(
+      &format!(
+        "SELECT id FROM {} WHERE name = ?1 AND COALESCE(is_active,1)=1 LIMIT 1",
+        table
+      ),
+      params![name],
+      |r| r.get(0),
+    )
+    .optional()
+    .map_err(PosError::from)
+}
 
-  let bp = buy_price.unwrap_or(0.0);
-  let st = stock.unwrap_or(mb + db_val);
+impl Store {
+  /// Tek transaction içinde renk × beden matrisindeki tüm varyantları oluşturur.
+  /// Her varyant kendi barkodunu alır, hepsi aynı product_code altında toplanır.
+  pub fn create_product_family(&self, payload: CreateProductFamilyPayload) -> Result<CreateProductFamilyResult, PosError> {
+    let name = norm_req("Ürün adı", &payload.name)?;
+    if !payload.sell_price.is_finite() || payload.sell_price < 0.0 {
+      return Err(PosError::Validation(tr!("Satış fiyatı sayı olmalı")));
+    }
 
-  conn.execute(
-    r#"
-    INSERT INTO products
-      (barcode, product_code, category, name, color, size, buy_price, sell_price, stock,
-       magaza_baslangic, depo_baslangic, magaza_stok, depo_stok)
-    VALUES
-      (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
-    "#,
-    params![
-      final_barcode,
-      product_code_final,
-      category,
-      name,
-      color,
-      size,
-      bp,
-      sell_price,
-      st,
-      mb,
-      db_val,
-      mb,
-      db_val
-    ],
-  )
-  .map_err(|e| e.to_string())?;
+    let colors = dedup_names(payload.colors);
+    let sizes = dedup_names(payload.sizes);
+    if colors.is_empty() {
+      return Err(PosError::Validation(tr!("En az bir renk seçilmeli")));
+    }
+    if sizes.is_empty() {
+      return Err(PosError::Validation(tr!("En az bir beden seçilmeli")));
+    }
+    if colors.len() * sizes.len() > MAX_FAMILY_VARIANTS {
+      return Err(PosError::Validation(tr!("Tek seferde en fazla {} varyant oluşturulabilir", MAX_FAMILY_VARIANTS)));
+    }
 
-  Ok(CreatedProduct {
-    barcode: final_barcode,
-    product_code: product_code_final,
-  })
+    let category = norm_opt(payload.category);
+    let bp = payload.buy_price.unwrap_or(0.0);
+    let mb = payload.magaza_baslangic.unwrap_or(0);
+    let db_val = payload.depo_baslangic.unwrap_or(0);
+    if mb < 0 || db_val < 0 || payload.stocks.iter().any(|v| v.magaza < 0 || v.depo < 0) {
+      return Err(PosError::Validation(tr!("Stok negatif olamaz")));
+    }
+    let start_stock = |color: &str, size: &str| {
+      payload
+        .stocks
+        .iter()
+        .find(|v| v.color.trim() == color && v.size.trim() == size)
+        .map_or((mb, db_val), |v| (v.magaza, v.depo))
+    };
+
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
+
+    // renk / beden sözlükte aktif olmalı
+    let category_id = match category.as_deref() {
+      Some(c) => Some(
+        dict_id_by_name(&tx, "categories", c)?
+          .ok_or_else(|| PosError::NotFound(tr!("Kategori bulunamadı: {}", c)))?,
+      ),
+      None => None,
+    };
+
+    let mut color_ids: Vec<i64> = Vec::with_capacity(colors.len());
+    for c in &colors {
+      color_ids.push(
+        dict_id_by_name(&tx, "colors", c)?.ok_or_else(|| PosError::NotFound(tr!("Renk bulunamadı: {}", c)))?,
+      );
+    }
+
+    let mut size_ids: Vec<i64> = Vec::with_capacity(sizes.len());
+    for s in &sizes {
+      size_ids.push(
+        dict_id_by_name(&tx, "sizes", s)?.ok_or_else(|| PosError::NotFound(tr!("Beden bulunamadı: {}", s)))?,
+      );
+    }
+
+    let product_code = match norm_opt(payload.product_code) {
+      Some(pc) => pc.to_uppercase().replace('-', ""),
+      None => {
+        let prefix = normalize_prefix_from_category(category.as_deref());
+        next_product_code_for_prefix(&tx, &prefix)?
+      }
+    };
+
+    let mut created: Vec<FamilyVariant> = Vec::new();
+    let mut skipped: Vec<FamilyVariant> = Vec::new();
+
+    for (color, color_id) in colors.iter().zip(&color_ids) {
+      for (size, size_id) in sizes.iter().zip(&size_ids) {
+        let existing: Option<String> = tx
+          .query_row(
+            "SELECT barcode FROM products
+             WHERE product_code = ?1
+               AND color_id = ?2
+               AND size_id  = ?3
+               AND COALESCE(is_active,1) = 1
+             LIMIT 1",
+            params![&product_code, color_id, size_id],
+            |r| r.get(0),
+          )
+          .optional()?;
+
+        if let Some(barcode) = existing {
+          skipped.push(FamilyVariant {
+            barcode,
+            color: color.clone(),
+            size: size.clone(),
+          });
+          continue;
+        }
+
+        // next_barcode aynı transaction içinde eklenenleri de görür
+        let barcode = next_barcode(&tx)?;
+        if is_alias_barcode(&tx, &barcode)? {
+          return Err(PosError::Conflict(tr!("Bu barkod başka bir ürünün ek barkodu: {}", barcode)));
+        }
+        let (magaza, depo) = start_stock(color, size);
+
+        tx.execute(
+          r#"
+          INSERT INTO products
+            (barcode, product_code, name, buy_price, sell_price,
+             magaza_baslangic, depo_baslangic, magaza_stok, depo_stok,
+             category_id, color_id, size_id)
+          VALUES
+            (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+          "#,
+          params![
+            &barcode,
+            &product_code,
+            name,
+            bp,
+            payload.sell_price,
+            magaza,
+            depo,
+            magaza,
+            depo,
+            category_id,
+            color_id,
+            size_id
+          ],
+        )?;
+
+        created.push(FamilyVariant {
+          barcode,
+          color: color.clone(),
+          size: size.clone(),
+        });
+      }
+    }
+
+    tx.commit()?;
+
+    Ok(CreateProductFamilyResult {
+      product_code,
+      created,
+      skipped,
+    })
+  }
+}
+
+#[derive(serde::Serialize)]
+pub struct FamilyGridCell {
+  pub barcode: String,
+  pub magaza_stok: i64,
+  pub depo_stok: i64,
+  pub stock: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct FamilyGridRow {
+  pub color: String,
+  // `sizes` sırasıyla hizalı; o renk/beden yoksa None
+  pub cells: Vec<Option<FamilyGridCell>>,
+  pub total_stock: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct ProductFamilyGrid {
+  pub product_code: String,
+  pub name: String,
+  pub category: Option<String>,
+  pub sizes: Vec<String>,
+  pub rows: Vec<FamilyGridRow>,
+  pub size_totals: Vec<i64>,
+  pub total_stock: i64,
+}
+
+impl Store {
+  /// Aile (product_code) için renk × beden stok tablosu.
+  /// Bedenler sözlükteki sort_order'a göre, renkler ada göre sıralanır.
+  pub fn get_product_family(&self, product_code: &str) -> Result<ProductFamilyGrid, PosError> {
+    let conn = self.read()?;
+    let pc = product_code.trim().to_uppercase().replace('-', "");
+    if pc.is_empty() {
+      return Err(PosError::Validation(tr!("product_code zorunlu")));
+    }
+
+    let mut stmt = conn
+      .prepare(
+        r#"
+        SELECT
+          p.barcode,
+          p.name,
+          p.category,
+          COALESCE(p.color, '') AS color,
+          COALESCE(p.size, '')  AS size,
+          COALESCE(p.magaza_stok, 0),
+          COALESCE(p.depo_stok, 0),
+          COALESCE(sz.sort_order, 999999) AS size_order
+        FROM products p
+        LEFT JOIN sizes sz ON sz.id = p.size_id
+        WHERE p.product_code = ?1
+          AND COALESCE(p.is_active, 1) = 1
+        ORDER BY size_order ASC, size ASC, color ASC
+        "#,
+      )?;
+
+    let rows = stmt
+      .query_map(params![&pc], |r| {
+        Ok((
+          r.get::<_, String>(0)?,
+          r.get::<_, String>(1)?,
+          r.get::<_, Option<String>>(2)?,
+          r.get::<_, String>(3)?,
+          r.get::<_, String>(4)?,
+          r.get::<_, i64>(5)?,
+          r.get::<_, i64>(6)?,
+        ))
+      })?;
+
+    let mut variants = Vec::new();
+    for r in rows {
+      variants.push(r?);
+    }
+
+    let (name, category) = match variants.first() {
+      Some(v) => (v.1.clone(), v.2.clone()),
+      None => return Err(PosError::NotFound(tr!("Ürün ailesi bulunamadı: {}", pc))),
+    };
+
+    // bedenler sorgu sırasıyla (sort_order), renkler alfabetik
+    let mut sizes: Vec<String> = Vec::new();
+    let mut colors: Vec<String> = Vec::new();
+    for v in &variants {
+      if !sizes.contains(&v.4) {
+        sizes.push(v.4.clone());
+      }
+      if !colors.contains(&v.3) {
+        colors.push(v.3.clone());
+      }
+    }
+    colors.sort();
+
+    let mut grid_rows: Vec<FamilyGridRow> = colors
+      .iter()
+      .map(|c| FamilyGridRow {
+        color: c.clone(),
+        cells: sizes.iter().map(|_| None).collect(),
+        total_stock: 0,
+      })
+      .collect();
+    let mut size_totals: Vec<i64> = vec![0; sizes.len()];
+
+    for (barcode, _, _, color, size, magaza_stok, depo_stok) in variants {
+      let ci = colors.iter().position(|c| *c == color).unwrap_or(0);
+      let si = sizes.iter().position(|s| *s == size).unwrap_or(0);
+      let stock = magaza_stok + depo_stok;
+
+      let row = &mut grid_rows[ci];
+      row.total_stock += stock;
+      size_totals[si] += stock;
+
+      // aynı renk/beden için birden fazla barkod varsa stokları topla, ilk barkodu göster
+      match row.cells[si].as_mut() {
+        Some(cell) => {
+          cell.magaza_stok += magaza_stok;
+          cell.depo_stok += depo_stok;
+          cell.stock += stock;
+        }
+        None => {
+          row.cells[si] = Some(FamilyGridCell {
+            barcode,
+            magaza_stok,
+            depo_stok,
+            stock,
+          });
+        }
+      }
+    }
+
+    let total_stock = size_totals.iter().sum();
+
+    Ok(ProductFamilyGrid {
+      product_code: pc,
+      name,
+      category,
+      sizes,
+      rows: grid_rows,
+      size_totals,
+      total_stock,
+    })
+  }
 }
 
 // dashboard
@@ -594,77 +3143,89 @@ pub struct UpdateProductPayload {
   pub sell_price: f64,
 }
 
-pub fn update_product(payload: UpdateProductPayload) -> Result<i64, String> {
-  let conn = get_conn()?;
+impl Store {
+  pub fn update_product(&self, payload: UpdateProductPayload) -> Result<i64, PosError> {
+    let conn = self.write()?;
 
-  let bc = payload.barcode.trim();
-  if bc.is_empty() {
-    return Err("Barkod zorunlu".to_string());
-  }
-  let name = norm_req("Ürün adı", &payload.name)?;
-
-  // product_code: varyant ailesi kodu. Unique değil.
-  // UI boş gönderirse mevcut değer korunmalı.
-  let product_code = norm_opt(payload.product_code)
-    .map(|pc| pc.trim().to_uppercase().replace('-', ""));
-  let category = norm_opt(payload.category);
-  let color = norm_opt(payload.color);
-  let size = norm_opt(payload.size);
-
-  if !payload.sell_price.is_finite() {
-    return Err("Satış fiyatı sayı olmalı".to_string());
-  }
-
-  let bp = payload.buy_price.unwrap_or(0.0);
+    let bc = payload.barcode.trim();
+    if bc.is_empty() {
+      return Err(PosError::Validation(tr!("Barkod zorunlu")));
+    }
+    let name = norm_req("Ürün adı", &payload.name)?;
+
+    // product_code: varyant ailesi kodu. Unique değil.
+    // UI boş gönderirse mevcut değer korunmalı.
+    let product_code = norm_opt(payload.product_code)
+      .map(|pc| pc.trim().to_uppercase().replace('-', ""));
+    let category = norm_opt(payload.category);
+    let category_id = resolve_dict_id(&conn, "categories", "Kategori", category.as_deref())?;
+    let color_id = resolve_dict_id(&conn, "colors", "Renk", norm_opt(payload.color).as_deref())?;
+    let size_id = resolve_dict_id(&conn, "sizes", "Beden", norm_opt(payload.size).as_deref())?;
+
+    if !payload.sell_price.is_finite() {
+      return Err(PosError::Validation(tr!("Satış fiyatı sayı olmalı")));
+    }
 
-  let changed = conn
-    .execute(
-      r#"
-      UPDATE products
-      SET
-        product_code = COALESCE(?2, product_code),
-        category     = ?3,
-        name         = ?4,
-        color        = ?5,
-        size         = ?6,
-        buy_price    = ?7,
-        sell_price   = ?8,
-        updated_at   = datetime('now','localtime')
-      WHERE barcode = ?1
-        AND COALESCE(is_active,1)=1
-      "#,
-      params![bc, product_code, category, name, color, size, bp, payload.sell_price],
-    )
-    .map_err(|e| e.to_string())?;
+    let bp = payload.buy_price.unwrap_or(0.0);
 
-  // Fiyatları tüm aile üyelerine yay (aynı product_code, bu barkod hariç)
-  let effective_pc: Option<String> = product_code.clone().or_else(|| {
-    conn.query_row(
-      "SELECT product_code FROM products WHERE barcode = ?1",
-      params![bc],
-      |r| r.get(0),
-    ).ok().flatten()
-  });
+    // Aile fiyatları yalnız fiyat gerçekten değiştiyse yayılır; aksi halde aynı
+    // değerlerle kaydetmek (ör. CSV içe aktarma) kardeşlerin farklı fiyatlarını ezerdi.
+    let old_prices: Option<(f64, f64)> = conn
+      .query_row(
+        "SELECT COALESCE(buy_price,0), COALESCE(sell_price,0) FROM products WHERE barcode = ?1",
+        params![bc],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+      )
+      .optional()?;
+    let price_changed = old_prices.map_or(true, |(b, s)| b != bp || s != payload.sell_price);
 
-  if let Some(pc) = effective_pc {
-    if !pc.is_empty() {
-      conn.execute(
+    let changed = conn
+      .execute(
         r#"
         UPDATE products
-        SET buy_price  = ?1,
-            sell_price = ?2,
-            updated_at = datetime('now','localtime')
-        WHERE product_code = ?3
-          AND barcode != ?4
-          AND COALESCE(is_active,1) = 1
+        SET
+          product_code = COALESCE(?2, product_code),
+          category_id  = ?3,
+          name         = ?4,
+          color_id     = ?5,
+          size_id      = ?6,
+          buy_price    = ?7,
+          sell_price   = ?8,
+          updated_at   = datetime('now','localtime')
+        WHERE barcode = ?1
+          AND COALESCE(is_active,1)=1
         "#,
-        params![bp, payload.sell_price, pc, bc],
-      )
-      .map_err(|e| e.to_string())?;
+        params![bc, product_code, category_id, name, color_id, size_id, bp, payload.sell_price],
+      )?;
+
+    // Fiyatları tüm aile üyelerine yay (aynı product_code, bu barkod hariç)
+    let effective_pc: Option<String> = product_code.clone().or_else(|| {
+      conn.query_row(
+        "SELECT product_code FROM products WHERE barcode = ?1",
+        params![bc],
+        |r| r.get(0),
+      ).ok().flatten()
+    });
+
+    if let Some(pc) = effective_pc.filter(|_| price_changed) {
+      if !pc.is_empty() {
+        conn.execute(
+          r#"
+          UPDATE products
+          SET buy_price  = ?1,
+              sell_price = ?2,
+              updated_at = datetime('now','localtime')
+          WHERE product_code = ?3
+            AND barcode != ?4
+            AND COALESCE(is_active,1) = 1
+          "#,
+          params![bp, payload.sell_price, pc, bc],
+        )?;
+      }
     }
-  }
 
-  Ok(changed as i64)
+    Ok(changed as i64)
+  }
 }
 #[derive(serde::Deserialize)]
 pub struct UpdateStockPayload {
@@ -673,35 +3234,123 @@ pub struct UpdateStockPayload {
   pub depo_stok: i64,
 }
 
-pub fn update_stock(payload: UpdateStockPayload) -> Result<i64, String> {
-  let bc = payload.barcode.trim();
-  if bc.is_empty() {
-    return Err("Barkod zorunlu".to_string());
-  }
-  if payload.magaza_stok < 0 || payload.depo_stok < 0 {
-    return Err("Stok negatif olamaz".to_string());
+impl Store {
+  pub fn update_stock(&self, payload: UpdateStockPayload) -> Result<i64, PosError> {
+    let bc = payload.barcode.trim();
+    if bc.is_empty() {
+      return Err(PosError::Validation(tr!("Barkod zorunlu")));
+    }
+    if payload.magaza_stok < 0 || payload.depo_stok < 0 {
+      return Err(PosError::Validation(tr!("Stok negatif olamaz")));
+    }
+
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
+
+    let before: Option<(i64, i64)> = tx
+      .query_row(
+        "SELECT COALESCE(magaza_stok,0), COALESCE(depo_stok,0) FROM products WHERE barcode = ?1 AND COALESCE(is_active,1)=1",
+        params![bc],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+      )
+      .optional()?;
+
+    let changed = tx
+      .execute(
+        r#"
+        UPDATE products
+        SET
+          magaza_stok = ?2,
+          depo_stok   = ?3,
+          updated_at  = datetime('now','localtime')
+        WHERE barcode = ?1
+          AND COALESCE(is_active,1)=1
+        "#,
+        params![bc, payload.magaza_stok, payload.depo_stok],
+      )?;
+
+    let mut changes = Vec::new();
+    if let Some((magaza, depo)) = before {
+      for (loc, old, new) in [("MAGAZA", magaza, payload.magaza_stok), ("DEPO", depo, payload.depo_stok)] {
+        if old != new {
+          changes.push(stock_change(&tx, bc, loc, new - old)?);
+        }
+      }
+    }
+    enqueue_stock_updated(&tx, bc, &changes)?;
+    let events = stock_events(&tx, &changes)?;
+
+    tx.commit()?;
+    drop(conn);
+    self.emit(events);
+    Ok(changed as i64)
   }
 
-  let conn = get_conn()?;
-  let total = payload.magaza_stok + payload.depo_stok;
+  /// Göreli stok düzeltmesi (CLI `stock adjust`): okuma + yazma tek write transaction'ında,
+  /// `col = col + delta` ile; sonuç negatife düşecekse hiçbir konum değişmez.
+  /// Dönen değer: (magaza_stok, depo_stok) yeni değerleri.
+  pub fn adjust_stock(&self, barcode: &str, magaza_delta: i64, depo_delta: i64) -> Result<(i64, i64), PosError> {
+    let bc = barcode.trim();
+    if bc.is_empty() {
+      return Err(PosError::Validation(tr!("Barkod zorunlu")));
+    }
 
-  let changed = conn
-    .execute(
-      r#"
-      UPDATE products
-      SET
-        magaza_stok = ?2,
-        depo_stok   = ?3,
-        stock       = ?4,
-        updated_at  = datetime('now','localtime')
-      WHERE barcode = ?1
-        AND COALESCE(is_active,1)=1
-      "#,
-      params![bc, payload.magaza_stok, payload.depo_stok, total],
-    )
-    .map_err(|e| e.to_string())?;
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
+
+    let exists: bool = tx.query_row(
+      "SELECT EXISTS(SELECT 1 FROM products WHERE barcode = ?1 AND COALESCE(is_active,1)=1)",
+      params![bc],
+      |r| r.get(0),
+    )?;
+    if !exists {
+      return Err(PosError::NotFound(tr!("Ürün bulunamadı: {}", bc)));
+    }
+
+    let mut changes = Vec::new();
+    for (loc, delta) in [("MAGAZA", magaza_delta), ("DEPO", depo_delta)] {
+      if delta == 0 {
+        continue;
+      }
+      let col = col_for_loc(loc);
+      let changed = tx.execute(
+        &format!(
+          "UPDATE products
+           SET {col} = COALESCE({col},0) + ?2,
+               updated_at = datetime('now','localtime')
+           WHERE barcode = ?1 AND COALESCE(is_active,1)=1 AND COALESCE({col},0) + ?2 >= 0"
+        ),
+        params![bc, delta],
+      )?;
+      if changed == 0 {
+        let available: i64 = tx.query_row(
+          &format!("SELECT COALESCE({col},0) FROM products WHERE barcode = ?1"),
+          params![bc],
+          |r| r.get(0),
+        )?;
+        return Err(PosError::InsufficientStock {
+          barcode: bc.to_string(),
+          location: loc.to_string(),
+          available,
+          requested: -delta,
+        });
+      }
+      changes.push(stock_change(&tx, bc, loc, delta)?);
+    }
 
-  Ok(changed as i64)
+    let levels: (i64, i64) = tx.query_row(
+      "SELECT COALESCE(magaza_stok,0), COALESCE(depo_stok,0) FROM products WHERE barcode = ?1",
+      params![bc],
+      |r| Ok((r.get(0)?, r.get(1)?)),
+    )?;
+    enqueue_stock_updated(&tx, bc, &changes)?;
+    let events = stock_events(&tx, &changes)?;
+
+    tx.commit()?;
+    drop(conn);
+    self.emit(events);
+    Ok(levels)
+  }
 }
 
 #[derive(serde::Serialize)]
@@ -711,369 +3360,343 @@ pub struct CategoryRow {
   pub is_active: i64,
   pub created_at: Option<String>,
 }
-pub fn list_categories_full(include_inactive: bool) -> Result<Vec<CategoryRow>, String> {
-  let conn = get_conn()?;
-  let include = if include_inactive { 1 } else { 0 };
+impl Store {
+  pub fn list_categories_full(&self, query: &ListQuery) -> Result<Page<CategoryRow>, PosError> {
+    let conn = self.read()?;
+    let include = if query.include_inactive { 1 } else { 0 };
+    let q_like = query.q_like();
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(&[("name", "name"), ("created_at", "created_at"), ("id", "id")], false)?;
+
+    // ?1 pasifler dahil mi, ?2 ad araması
+    let filter = "((?1 = 1) OR COALESCE(is_active,1)=1) AND (?2 IS NULL OR name LIKE ?2)";
+
+    let total: i64 = conn.query_row(
+      &format!("SELECT COUNT(*) FROM categories WHERE {}", filter),
+      params![include, q_like],
+      |r| r.get(0),
+    )?;
 
-  let mut st = conn
-    .prepare(
-      r#"
-      SELECT id, name, COALESCE(is_active,1) AS is_active, created_at
-      FROM categories
-      WHERE (?1 = 1) OR COALESCE(is_active,1)=1
-      ORDER BY name ASC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
+    let mut st = conn
+      .prepare(&format!(
+        r#"
+        SELECT id, name, COALESCE(is_active,1) AS is_active, created_at
+        FROM categories
+        WHERE {}
+        ORDER BY {}, name ASC, id ASC
+        LIMIT ?3 OFFSET ?4
+        "#,
+        filter, order
+      ))?;
 
-  let rows = st
-    .query_map(params![include], |r| {
-      Ok(CategoryRow {
-        id: r.get(0)?,
-        name: r.get(1)?,
-        is_active: r.get(2)?,
-        created_at: r.get(3)?,
-      })
-    })
-    .map_err(|e| e.to_string())?;
+    let items = st
+      .query_map(params![include, q_like, limit, offset], |r| {
+        Ok(CategoryRow {
+          id: r.get(0)?,
+          name: r.get(1)?,
+          is_active: r.get(2)?,
+          created_at: r.get(3)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
 
-  let mut out = Vec::new();
-  for x in rows {
-    out.push(x.map_err(|e| e.to_string())?);
+    Ok(query.page_of(items, total))
   }
-  Ok(out)
-}
-
-
-
-#[derive(serde::Serialize)]
-pub struct ColorRow {
-  pub id: i64,
-  pub name: String,
-  pub is_active: i64,
-  pub created_at: Option<String>,
 }
 
-pub fn list_colors_full(include_inactive: bool) -> Result<Vec<ColorRow>, String> {
-  let conn = get_conn()?;
-  let include = if include_inactive { 1 } else { 0 };
 
-  let mut st = conn
-    .prepare(
-      r#"
-      SELECT id, name, COALESCE(is_active,1) AS is_active, created_at
-      FROM colors
-      WHERE (?1 = 1) OR COALESCE(is_active,1)=1
-      ORDER BY name ASC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
-
-  let rows = st
-    .query_map(params![include], |r| {
-      Ok(ColorRow {
-        id: r.get(0)?,
-        name: r.get(1)?,
-        is_active: r.get(2)?,
-        created_at: r.get(3)?,
-      })
-    })
-    .map_err(|e| e.to_string())?;
 
-  let mut out = Vec::new();
-  for x in rows {
-    out.push(x.map_err(|e| e.to_string())?);
-  }
-  Ok(out)
+#[derive(serde::Serialize)]
+pub struct ColorRow {
+  pub id: i64,
+  pub name: String,
+  pub is_active: i64,
+  pub created_at: Option<String>,
 }
 
+impl Store {
+  pub fn list_colors_full(&self, query: &ListQuery) -> Result<Page<ColorRow>, PosError> {
+    let conn = self.read()?;
+    let include = if query.include_inactive { 1 } else { 0 };
+    let q_like = query.q_like();
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(&[("name", "name"), ("created_at", "created_at"), ("id", "id")], false)?;
 
-// ------- Helper functions to count products using a category/color/size -------
-fn count_products_using_category(tx: &rusqlite::Connection, id: i64, name: &str) -> i64 {
-  let used_by_id: i64 = tx
-    .query_row(
-      "SELECT COALESCE(COUNT(*),0) FROM products WHERE COALESCE(category_id,0)=?1",
-      params![id],
-      |r| r.get(0),
-    )
-    .unwrap_or(0);
+    // ?1 pasifler dahil mi, ?2 ad araması
+    let filter = "((?1 = 1) OR COALESCE(is_active,1)=1) AND (?2 IS NULL OR name LIKE ?2)";
 
-  let used_by_text: i64 = tx
-    .query_row(
-      "SELECT COALESCE(COUNT(*),0) FROM products WHERE TRIM(COALESCE(category,'')) = TRIM(?1)",
-      params![name],
+    let total: i64 = conn.query_row(
+      &format!("SELECT COUNT(*) FROM colors WHERE {}", filter),
+      params![include, q_like],
       |r| r.get(0),
-    )
-    .unwrap_or(0);
-
-  used_by_id + used_by_text
-}
+    )?;
 
-fn count_products_using_color(tx: &rusqlite::Connection, id: i64, name: &str) -> i64 {
-  let used_by_id: i64 = tx
-    .query_row(
-      "SELECT COALESCE(COUNT(*),0) FROM products WHERE COALESCE(color_id,0)=?1",
-      params![id],
-      |r| r.get(0),
-    )
-    .unwrap_or(0);
+    let mut st = conn
+      .prepare(&format!(
+        r#"
+        SELECT id, name, COALESCE(is_active,1) AS is_active, created_at
+        FROM colors
+        WHERE {}
+        ORDER BY {}, name ASC, id ASC
+        LIMIT ?3 OFFSET ?4
+        "#,
+        filter, order
+      ))?;
 
-  let used_by_text: i64 = tx
-    .query_row(
-      "SELECT COALESCE(COUNT(*),0) FROM products WHERE TRIM(COALESCE(color,'')) = TRIM(?1)",
-      params![name],
-      |r| r.get(0),
-    )
-    .unwrap_or(0);
+    let items = st
+      .query_map(params![include, q_like, limit, offset], |r| {
+        Ok(ColorRow {
+          id: r.get(0)?,
+          name: r.get(1)?,
+          is_active: r.get(2)?,
+          created_at: r.get(3)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
 
-  used_by_id + used_by_text
+    Ok(query.page_of(items, total))
+  }
 }
 
-fn count_products_using_size(tx: &rusqlite::Connection, id: i64, name: &str) -> i64 {
-  let used_by_id: i64 = tx
-    .query_row(
-      "SELECT COALESCE(COUNT(*),0) FROM products WHERE COALESCE(size_id,0)=?1",
-      params![id],
-      |r| r.get(0),
-    )
-    .unwrap_or(0);
 
-  let used_by_text: i64 = tx
-    .query_row(
-      "SELECT COALESCE(COUNT(*),0) FROM products WHERE TRIM(COALESCE(size,'')) = TRIM(?1)",
-      params![name],
-      |r| r.get(0),
-    )
-    .unwrap_or(0);
+// ------- Helper functions to count products using a category/color/size -------
+// Ürün ↔ sözlük bağı yalnızca *_id kolonlarıyla (v3 göçü); ad kolonları türetilmiş kopya.
+fn count_products_using(tx: &rusqlite::Connection, col: &str, id: i64) -> i64 {
+  tx.query_row(
+    &format!("SELECT COALESCE(COUNT(*),0) FROM products WHERE {} = ?1", col),
+    params![id],
+    |r| r.get(0),
+  )
+  .unwrap_or(0)
+}
 
-  used_by_id + used_by_text
+fn count_products_using_category(tx: &rusqlite::Connection, id: i64) -> i64 {
+  count_products_using(tx, "category_id", id)
 }
-pub fn update_color(id: i64, name: Option<String>, is_active: Option<i64>) -> Result<i64, String> {
-  if id <= 0 {
-    return Err("id geçersiz".to_string());
-  }
 
-  let conn = get_conn()?;
+fn count_products_using_color(tx: &rusqlite::Connection, id: i64) -> i64 {
+  count_products_using(tx, "color_id", id)
+}
 
-  let exists: Option<i64> = conn
-    .query_row("SELECT id FROM colors WHERE id=?1", params![id], |r| r.get(0))
-    .optional()
-    .map_err(|e| e.to_string())?;
+fn count_products_using_size(tx: &rusqlite::Connection, id: i64) -> i64 {
+  count_products_using(tx, "size_id", id)
+}
+impl Store {
+  pub fn update_color(&self, id: i64, name: Option<String>, is_active: Option<i64>) -> Result<i64, PosError> {
+    if id <= 0 {
+      return Err(PosError::Validation(tr!("id geçersiz")));
+    }
 
-  if exists.is_none() {
-    return Err("Renk bulunamadı".to_string());
-  }
+    let conn = self.write()?;
 
-  let mut changed: i64 = 0;
+    let exists: Option<i64> = conn
+      .query_row("SELECT id FROM colors WHERE id=?1", params![id], |r| r.get(0))
+      .optional()?;
 
-  if let Some(n) = name {
-    let t = n.trim();
-    if t.is_empty() {
-      return Err("Renk adı boş olamaz".to_string());
+    if exists.is_none() {
+      return Err(PosError::NotFound(tr!("Renk bulunamadı")));
     }
 
-    let c = conn
-      .execute("UPDATE colors SET name=?2 WHERE id=?1", params![id, t])
-      .map_err(|e| e.to_string())?;
-    changed += c as i64;
-  }
-
-  if let Some(a) = is_active {
-    let v = if a == 0 { 0 } else { 1 };
+    let mut changed: i64 = 0;
 
-    // Eğer pasife alınacaksa ve ürünlerde kullanılıyorsa engelle
-    if v == 0 {
-      let color_name: String = conn
-        .query_row("SELECT name FROM colors WHERE id=?1", params![id], |r| r.get(0))
-        .map_err(|e| e.to_string())?;
-      let used = count_products_using_color(&conn, id, &color_name);
-      if used > 0 {
-        return Err("Bu renk ürünlerde kullanılıyor; pasife alınamaz".to_string());
+    if let Some(n) = name {
+      let t = n.trim();
+      if t.is_empty() {
+        return Err(PosError::Validation(tr!("Renk adı boş olamaz")));
       }
-    }
 
-    let c = conn
-      .execute("UPDATE colors SET is_active=?2 WHERE id=?1", params![id, v])
-      .map_err(|e| e.to_string())?;
-    changed += c as i64;
-  }
+      let c = conn
+        .execute("UPDATE colors SET name=?2 WHERE id=?1", params![id, t])?;
+      changed += c as i64;
+    }
 
-  Ok(changed)
-}
+    if let Some(a) = is_active {
+      let v = if a == 0 { 0 } else { 1 };
 
-pub fn delete_color(id: i64) -> Result<i64, String> {
-  if id <= 0 {
-    return Err("id geçersiz".to_string());
-  }
+      // Eğer pasife alınacaksa ve ürünlerde kullanılıyorsa engelle
+      if v == 0 {
+        let used = count_products_using_color(&conn, id);
+        if used > 0 {
+          return Err(PosError::Conflict(tr!("Bu renk ürünlerde kullanılıyor; pasife alınamaz")));
+        }
+      }
 
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
+      let c = conn
+        .execute("UPDATE colors SET is_active=?2 WHERE id=?1", params![id, v])?;
+      changed += c as i64;
+    }
 
-  let color_name: Option<String> = tx
-    .query_row("SELECT name FROM colors WHERE id=?1", params![id], |r| r.get(0))
-    .optional()
-    .map_err(|e| e.to_string())?;
+    Ok(changed)
+  }
 
-  let color_name = color_name.ok_or_else(|| "Renk bulunamadı".to_string())?;
+  pub fn delete_color(&self, id: i64) -> Result<i64, PosError> {
+    if id <= 0 {
+      return Err(PosError::Validation(tr!("id geçersiz")));
+    }
 
-  let used = count_products_using_color(&tx, id, &color_name);
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
 
-  if used > 0 {
-    return Err("Bu renk ürünlerde kullanılıyor; silinemez / pasife alınamaz".to_string());
-  }
+    let exists: Option<i64> = tx
+      .query_row("SELECT id FROM colors WHERE id=?1", params![id], |r| r.get(0))
+      .optional()?;
 
-  // hiç kullanılmamışsa hard delete
-  let c = tx
-    .execute("DELETE FROM colors WHERE id=?1", params![id])
-    .map_err(|e| e.to_string())?;
+    if exists.is_none() {
+      return Err(PosError::NotFound(tr!("Renk bulunamadı")));
+    }
 
-  tx.commit().map_err(|e| e.to_string())?;
-  Ok(c as i64)
-}
-pub fn add_category(name: String) -> Result<i64, String> {
-  let conn = get_conn()?;
-  let n = name.trim();
-  if n.is_empty() {
-    return Err("Kategori adı boş olamaz".to_string());
-  }
+    let used = count_products_using_color(&tx, id);
 
-  conn.execute(
-    "INSERT OR IGNORE INTO categories(name, is_active) VALUES (?1, 1)",
-    params![n],
-  ).map_err(|e| e.to_string())?;
+    if used > 0 {
+      return Err(PosError::Conflict(tr!("Bu renk ürünlerde kullanılıyor; silinemez / pasife alınamaz")));
+    }
 
-  Ok(1)
-}
+    // hiç kullanılmamışsa hard delete
+    let c = tx
+      .execute("DELETE FROM colors WHERE id=?1", params![id])?;
 
-pub fn create_category(name: String) -> Result<i64, String> {
-  add_category(name)
-}
-pub fn update_category(id: i64, name: Option<String>, is_active: Option<i64>) -> Result<i64, String> {
-  if id <= 0 {
-    return Err("id geçersiz".to_string());
+    tx.commit()?;
+    Ok(c as i64)
   }
+  pub fn add_category(&self, name: String) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let n = name.trim();
+    if n.is_empty() {
+      return Err(PosError::Validation(tr!("Kategori adı boş olamaz")));
+    }
 
-  let conn = get_conn()?;
+    conn.execute(
+      "INSERT OR IGNORE INTO categories(name, is_active) VALUES (?1, 1)",
+      params![n],
+    )?;
 
-  let exists: Option<i64> = conn
-    .query_row("SELECT id FROM categories WHERE id=?1", params![id], |r| r.get(0))
-    .optional()
-    .map_err(|e| e.to_string())?;
+    Ok(1)
+  }
 
-  if exists.is_none() {
-    return Err("Kategori bulunamadı".to_string());
+  pub fn create_category(&self, name: String) -> Result<i64, PosError> {
+    self.add_category(name)
   }
+  pub fn update_category(&self, id: i64, name: Option<String>, is_active: Option<i64>) -> Result<i64, PosError> {
+    if id <= 0 {
+      return Err(PosError::Validation(tr!("id geçersiz")));
+    }
 
-  let mut changed: i64 = 0;
+    let conn = self.write()?;
 
-  if let Some(n) = name {
-    let t = n.trim();
-    if t.is_empty() {
-      return Err("Kategori adı boş olamaz".to_string());
-    }
+    let exists: Option<i64> = conn
+      .query_row("SELECT id FROM categories WHERE id=?1", params![id], |r| r.get(0))
+      .optional()?;
 
-    let c = conn
-      .execute("UPDATE categories SET name=?2 WHERE id=?1", params![id, t])
-      .map_err(|e| e.to_string())?;
-    changed += c as i64;
-  }
+    if exists.is_none() {
+      return Err(PosError::NotFound(tr!("Kategori bulunamadı")));
+    }
 
-  if let Some(a) = is_active {
-    let v = if a == 0 { 0 } else { 1 };
+    let mut changed: i64 = 0;
 
-    if v == 0 {
-      let cat_name: String = conn
-        .query_row("SELECT name FROM categories WHERE id=?1", params![id], |r| r.get(0))
-        .map_err(|e| e.to_string())?;
-      let used = count_products_using_category(&conn, id, &cat_name);
-      if used > 0 {
-        return Err("Bu kategori ürünlerde kullanılıyor; pasife alınamaz".to_string());
+    if let Some(n) = name {
+      let t = n.trim();
+      if t.is_empty() {
+        return Err(PosError::Validation(tr!("Kategori adı boş olamaz")));
       }
+
+      let c = conn
+        .execute("UPDATE categories SET name=?2 WHERE id=?1", params![id, t])?;
+      changed += c as i64;
     }
 
-    let c = conn
-      .execute("UPDATE categories SET is_active=?2 WHERE id=?1", params![id, v])
-      .map_err(|e| e.to_string())?;
-    changed += c as i64;
-  }
+    if let Some(a) = is_active {
+      let v = if a == 0 { 0 } else { 1 };
 
-  Ok(changed)
-}
+      if v == 0 {
+        let used = count_products_using_category(&conn, id);
+        if used > 0 {
+          return Err(PosError::Conflict(tr!("Bu kategori ürünlerde kullanılıyor; pasife alınamaz")));
+        }
+      }
+
+      let c = conn
+        .execute("UPDATE categories SET is_active=?2 WHERE id=?1", params![id, v])?;
+      changed += c as i64;
+    }
 
-pub fn delete_category(id: i64) -> Result<i64, String> {
-  if id <= 0 {
-    return Err("id geçersiz".to_string());
+    Ok(changed)
   }
 
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
+  pub fn delete_category(&self, id: i64) -> Result<i64, PosError> {
+    if id <= 0 {
+      return Err(PosError::Validation(tr!("id geçersiz")));
+    }
 
-  let cat_name: Option<String> = tx
-    .query_row("SELECT name FROM categories WHERE id=?1", params![id], |r| r.get(0))
-    .optional()
-    .map_err(|e| e.to_string())?;
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
 
-  let cat_name = cat_name.ok_or_else(|| "Kategori bulunamadı".to_string())?;
+    let exists: Option<i64> = tx
+      .query_row("SELECT id FROM categories WHERE id=?1", params![id], |r| r.get(0))
+      .optional()?;
 
-  let used = count_products_using_category(&tx, id, &cat_name);
+    if exists.is_none() {
+      return Err(PosError::NotFound(tr!("Kategori bulunamadı")));
+    }
 
-  if used > 0 {
-    return Err("Bu kategori ürünlerde kullanılıyor; silinemez / pasife alınamaz".to_string());
-  }
+    let used = count_products_using_category(&tx, id);
 
-  let c = tx
-    .execute("DELETE FROM categories WHERE id=?1", params![id])
-    .map_err(|e| e.to_string())?;
+    if used > 0 {
+      return Err(PosError::Conflict(tr!("Bu kategori ürünlerde kullanılıyor; silinemez / pasife alınamaz")));
+    }
 
-  tx.commit().map_err(|e| e.to_string())?;
-  Ok(c as i64)
-}
+    let c = tx
+      .execute("DELETE FROM categories WHERE id=?1", params![id])?;
 
-pub fn add_color(name: String) -> Result<i64, String> {
-  let conn = get_conn()?;
-  let n = name.trim();
-  if n.is_empty() {
-    return Err("Renk adı boş olamaz".to_string());
+    tx.commit()?;
+    Ok(c as i64)
   }
 
-  conn.execute(
-    "INSERT OR IGNORE INTO colors(name, is_active) VALUES (?1, 1)",
-    params![n],
-  ).map_err(|e| e.to_string())?;
+  pub fn add_color(&self, name: String) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let n = name.trim();
+    if n.is_empty() {
+      return Err(PosError::Validation(tr!("Renk adı boş olamaz")));
+    }
 
-  Ok(1)
-}
+    conn.execute(
+      "INSERT OR IGNORE INTO colors(name, is_active) VALUES (?1, 1)",
+      params![n],
+    )?;
 
-pub fn create_color(name: String) -> Result<i64, String> {
-  add_color(name)
-}
+    Ok(1)
+  }
 
-pub fn add_size(name: String, order_no: Option<i64>) -> Result<i64, String> {
-  let conn = get_conn()?;
-  let n = name.trim();
-  if n.is_empty() {
-    return Err("Beden adı boş olamaz".to_string());
+  pub fn create_color(&self, name: String) -> Result<i64, PosError> {
+    self.add_color(name)
   }
 
-  let so = order_no.unwrap_or(0);
+  pub fn add_size(&self, name: String, order_no: Option<i64>) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let n = name.trim();
+    if n.is_empty() {
+      return Err(PosError::Validation(tr!("Beden adı boş olamaz")));
+    }
 
-  // new column
-  conn.execute(
-    "INSERT OR IGNORE INTO sizes(name, sort_order, is_active) VALUES (?1, ?2, 1)",
-    params![n, so],
-  ).map_err(|e| e.to_string())?;
+    let so = order_no.unwrap_or(0);
 
-  // legacy column (ignore if not exists)
-  let _ = conn.execute(
-    "UPDATE sizes SET order_no = COALESCE(order_no, ?2) WHERE name = ?1",
-    params![n, so],
-  );
+    // new column
+    conn.execute(
+      "INSERT OR IGNORE INTO sizes(name, sort_order, is_active) VALUES (?1, ?2, 1)",
+      params![n, so],
+    )?;
 
-  Ok(1)
-}
+    // legacy column (ignore if not exists)
+    let _ = conn.execute(
+      "UPDATE sizes SET order_no = COALESCE(order_no, ?2) WHERE name = ?1",
+      params![n, so],
+    );
+
+    Ok(1)
+  }
 
-pub fn create_size(name: String, sort_order: Option<i64>) -> Result<i64, String> {
-  add_size(name, sort_order)
+  pub fn create_size(&self, name: String, sort_order: Option<i64>) -> Result<i64, PosError> {
+    self.add_size(name, sort_order)
+  }
 }
 
 
@@ -1086,150 +3709,169 @@ pub struct SizeRow {
   pub created_at: Option<String>,
 }
 
-pub fn list_sizes_full(include_inactive: bool) -> Result<Vec<SizeRow>, String> {
-  let conn = get_conn()?;
-  let include = if include_inactive { 1 } else { 0 };
+impl Store {
+  pub fn list_sizes_full(&self, query: &ListQuery) -> Result<Page<SizeRow>, PosError> {
+    let conn = self.read()?;
+    let include = if query.include_inactive { 1 } else { 0 };
+    let q_like = query.q_like();
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(
+      &[
+        ("sort_order", "COALESCE(sort_order, 0)"),
+        ("name", "name"),
+        ("created_at", "created_at"),
+        ("id", "id"),
+      ],
+      false,
+    )?;
 
-  let mut st = conn
-    .prepare(
-      r#"
-      SELECT id, name,
-        COALESCE(sort_order, 0) AS sort_order,
-        COALESCE(is_active,1) AS is_active,
-        created_at
-      FROM sizes
-      WHERE (?1 = 1) OR COALESCE(is_active,1)=1
-      ORDER BY COALESCE(sort_order, 0) ASC, name ASC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
+    // ?1 pasifler dahil mi, ?2 ad araması
+    let filter = "((?1 = 1) OR COALESCE(is_active,1)=1) AND (?2 IS NULL OR name LIKE ?2)";
 
-  let rows = st
-    .query_map(params![include], |r| {
-      Ok(SizeRow {
-        id: r.get(0)?,
-        name: r.get(1)?,
-        sort_order: r.get(2)?,
-        is_active: r.get(3)?,
-        created_at: r.get(4)?,
-      })
-    })
-    .map_err(|e| e.to_string())?;
+    let total: i64 = conn.query_row(
+      &format!("SELECT COUNT(*) FROM sizes WHERE {}", filter),
+      params![include, q_like],
+      |r| r.get(0),
+    )?;
 
-  let mut out = Vec::new();
-  for x in rows {
-    out.push(x.map_err(|e| e.to_string())?);
-  }
-  Ok(out)
-}
+    let mut st = conn
+      .prepare(&format!(
+        r#"
+        SELECT id, name,
+          COALESCE(sort_order, 0) AS sort_order,
+          COALESCE(is_active,1) AS is_active,
+          created_at
+        FROM sizes
+        WHERE {}
+        ORDER BY {}, name ASC, id ASC
+        LIMIT ?3 OFFSET ?4
+        "#,
+        filter, order
+      ))?;
 
+    let items = st
+      .query_map(params![include, q_like, limit, offset], |r| {
+        Ok(SizeRow {
+          id: r.get(0)?,
+          name: r.get(1)?,
+          sort_order: r.get(2)?,
+          is_active: r.get(3)?,
+          created_at: r.get(4)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
 
-pub fn update_size(
-  id: i64,
-  name: Option<String>,
-  sort_order: Option<i64>,
-  is_active: Option<i64>,
-) -> Result<i64, String> {
-  if id <= 0 {
-    return Err("id geçersiz".to_string());
+    Ok(query.page_of(items, total))
   }
 
-  let conn = get_conn()?;
 
-  let exists: Option<i64> = conn
-    .query_row("SELECT id FROM sizes WHERE id=?1", params![id], |r| r.get(0))
-    .optional()
-    .map_err(|e| e.to_string())?;
+  pub fn update_size(
+    &self,
+    id: i64,
+    name: Option<String>,
+    sort_order: Option<i64>,
+    is_active: Option<i64>,
+  ) -> Result<i64, PosError> {
+    if id <= 0 {
+      return Err(PosError::Validation(tr!("id geçersiz")));
+    }
 
-  if exists.is_none() {
-    return Err("Beden bulunamadı".to_string());
-  }
+    let conn = self.write()?;
 
-  let mut changed: i64 = 0;
+    let exists: Option<i64> = conn
+      .query_row("SELECT id FROM sizes WHERE id=?1", params![id], |r| r.get(0))
+      .optional()?;
 
-  if let Some(n) = name {
-    let t = n.trim();
-    if t.is_empty() {
-      return Err("Beden adı boş olamaz".to_string());
+    if exists.is_none() {
+      return Err(PosError::NotFound(tr!("Beden bulunamadı")));
     }
 
-    let c = conn
-      .execute("UPDATE sizes SET name=?2 WHERE id=?1", params![id, t])
-      .map_err(|e| e.to_string())?;
-    changed += c as i64;
-  }
+    let mut changed: i64 = 0;
 
-  if let Some(so) = sort_order {
-    let c = conn
-      .execute("UPDATE sizes SET sort_order=?2 WHERE id=?1", params![id, so])
-      .map_err(|e| e.to_string())?;
-    changed += c as i64;
+    if let Some(n) = name {
+      let t = n.trim();
+      if t.is_empty() {
+        return Err(PosError::Validation(tr!("Beden adı boş olamaz")));
+      }
 
-    // legacy column support (ignore if not exists)
-    let _ = conn.execute(
-      "UPDATE sizes SET order_no = COALESCE(order_no, ?2) WHERE id=?1",
-      params![id, so],
-    );
-  }
+      let c = conn
+        .execute("UPDATE sizes SET name=?2 WHERE id=?1", params![id, t])?;
+      changed += c as i64;
+    }
 
-  if let Some(a) = is_active {
-    let v = if a == 0 { 0 } else { 1 };
+    if let Some(so) = sort_order {
+      let c = conn
+        .execute("UPDATE sizes SET sort_order=?2 WHERE id=?1", params![id, so])?;
+      changed += c as i64;
 
-    if v == 0 {
-      let size_name: String = conn
-        .query_row("SELECT name FROM sizes WHERE id=?1", params![id], |r| r.get(0))
-        .map_err(|e| e.to_string())?;
-      let used = count_products_using_size(&conn, id, &size_name);
-      if used > 0 {
-        return Err("Bu beden ürünlerde kullanılıyor; pasife alınamaz".to_string());
-      }
+      // legacy column support (ignore if not exists)
+      let _ = conn.execute(
+        "UPDATE sizes SET order_no = COALESCE(order_no, ?2) WHERE id=?1",
+        params![id, so],
+      );
     }
 
-    let c = conn
-      .execute("UPDATE sizes SET is_active=?2 WHERE id=?1", params![id, v])
-      .map_err(|e| e.to_string())?;
-    changed += c as i64;
-  }
+    if let Some(a) = is_active {
+      let v = if a == 0 { 0 } else { 1 };
 
-  Ok(changed)
-}
+      if v == 0 {
+        let used = count_products_using_size(&conn, id);
+        if used > 0 {
+          return Err(PosError::Conflict(tr!("Bu beden ürünlerde kullanılıyor; pasife alınamaz")));
+        }
+      }
 
+      let c = conn
+        .execute("UPDATE sizes SET is_active=?2 WHERE id=?1", params![id, v])?;
+      changed += c as i64;
+    }
 
-pub fn delete_size(id: i64) -> Result<i64, String> {
-  if id <= 0 {
-    return Err("id geçersiz".to_string());
+    Ok(changed)
   }
 
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-  let size_name: Option<String> = tx
-    .query_row("SELECT name FROM sizes WHERE id=?1", params![id], |r| r.get(0))
-    .optional()
-    .map_err(|e| e.to_string())?;
+  pub fn delete_size(&self, id: i64) -> Result<i64, PosError> {
+    if id <= 0 {
+      return Err(PosError::Validation(tr!("id geçersiz")));
+    }
 
-  let size_name = size_name.ok_or_else(|| "Beden bulunamadı".to_string())?;
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
 
-  let used = count_products_using_size(&tx, id, &size_name);
+    let exists: Option<i64> = tx
+      .query_row("SELECT id FROM sizes WHERE id=?1", params![id], |r| r.get(0))
+      .optional()?;
 
-  if used > 0 {
-    return Err("Bu beden ürünlerde kullanılıyor; silinemez / pasife alınamaz".to_string());
-  }
+    if exists.is_none() {
+      return Err(PosError::NotFound(tr!("Beden bulunamadı")));
+    }
 
-  let c = tx
-    .execute("DELETE FROM sizes WHERE id=?1", params![id])
-    .map_err(|e| e.to_string())?;
+    let used = count_products_using_size(&tx, id);
 
-  tx.commit().map_err(|e| e.to_string())?;
-  Ok(c as i64)
-}
+    if used > 0 {
+      return Err(PosError::Conflict(tr!("Bu beden ürünlerde kullanılıyor; silinemez / pasife alınamaz")));
+    }
 
-pub fn get_dashboard_summary(days: i64, months: i64) -> Result<DashboardSummary, String> {
-  let conn = get_conn()?;
+    let c = tx
+      .execute("DELETE FROM sizes WHERE id=?1", params![id])?;
 
-  let days = days.clamp(1, 90);
-  let months = months.clamp(1, 24);
+    tx.commit()?;
+    Ok(c as i64)
+  }
+}
+
+#[derive(serde::Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct DashboardQuery {
+  #[serde(flatten)]
+  pub range: ReportRange,
+  // aylık seri için ayrı aralık; yoksa ana aralığın ayları
+  pub monthly_from: Option<String>,
+  pub monthly_to: Option<String>,
+}
 
+/// Bir tarih aralığının (YYYY-MM-DD, dahil) net adet / ciro / kâr / gider toplamları.
+fn period_totals(conn: &Connection, from: &str, to: &str) -> Result<PeriodTotals, PosError> {
   let sales_for_revenue = r#"(
     COALESCE(voided,0)=0
     OR EXISTS (
@@ -1244,76 +3886,58 @@ pub fn get_dashboard_summary(days: i64, months: i64) -> Result<DashboardSummary,
   let sales_active = "COALESCE(voided,0)=0";
   let sales_active_s = "COALESCE(s.voided,0)=0";
 
-  // TODAY
-  let today_sales_qty_active = scalar_i64(
-    &conn,
+  // net adet: aktif satış + exchange verilen - refund
+  let sales_qty_active = scalar_i64(
+    conn,
     &format!(
-      "SELECT COALESCE(SUM(qty),0) FROM sales WHERE {} AND date(sold_at)=date('now','localtime')",
+      "SELECT COALESCE(SUM(qty),0) FROM sales WHERE {} AND date(sold_at) BETWEEN ?1 AND ?2",
       sales_active
     ),
-    &[],
+    &[&from, &to],
   );
 
-  let today_exchange_qty = scalar_i64(
-    &conn,
+  let exchange_qty = scalar_i64(
+    conn,
     r#"SELECT COALESCE(SUM(ei.qty),0)
        FROM exchange_items ei
        JOIN returns r ON r.return_group_id = ei.exchange_group_id
        WHERE r.mode='EXCHANGE'
-         AND date(r.created_at)=date('now','localtime')"#,
-    &[],
+         AND date(r.created_at) BETWEEN ?1 AND ?2"#,
+    &[&from, &to],
   );
 
-  let today_refund_qty = scalar_i64(
-    &conn,
+  let refund_qty = scalar_i64(
+    conn,
     r#"SELECT COALESCE(SUM(ri.qty),0)
        FROM return_items ri
        JOIN returns r ON r.return_group_id = ri.return_group_id
        WHERE r.mode='REFUND'
-         AND date(r.created_at)=date('now','localtime')"#,
-    &[],
-  );
-
-  let today_qty = (today_sales_qty_active + today_exchange_qty - today_refund_qty).max(0);
-
-  // net ciro için: satış toplam (revenue şartlı) + return_diff (refund negatif, exchange diff)
-  let today_sales_total = scalar_f64(
-    &conn,
-    &format!(
-      "SELECT COALESCE(SUM(total),0) FROM sales WHERE {} AND date(sold_at)=date('now','localtime')",
-      sales_for_revenue
-    ),
-    &[],
-  );
-
-  let today_return_diff = scalar_f64(
-    &conn,
-    "SELECT COALESCE(SUM(diff),0) FROM returns WHERE date(created_at)=date('now','localtime')",
-    &[],
+         AND date(r.created_at) BETWEEN ?1 AND ?2"#,
+    &[&from, &to],
   );
 
-  let today_net_revenue = today_sales_total + today_return_diff;
+  let net_qty = (sales_qty_active + exchange_qty - refund_qty).max(0);
 
-  // THIS MONTH 
-  let month_sales_total = scalar_f64(
-    &conn,
+  // net ciro: revenue şartlı satış toplam + returns.diff (refund negatif, exchange diff)
+  let sales_total = scalar_f64(
+    conn,
     &format!(
-      "SELECT COALESCE(SUM(total),0) FROM sales WHERE {} AND strftime('%Y-%m',sold_at)=strftime('%Y-%m','now','localtime')",
+      "SELECT COALESCE(SUM(total),0) FROM sales WHERE {} AND date(sold_at) BETWEEN ?1 AND ?2",
       sales_for_revenue
     ),
-    &[],
+    &[&from, &to],
   );
 
-  let month_return_diff = scalar_f64(
-    &conn,
-    "SELECT COALESCE(SUM(diff),0) FROM returns WHERE strftime('%Y-%m',created_at)=strftime('%Y-%m','now','localtime')",
-    &[],
+  let return_diff = scalar_f64(
+    conn,
+    "SELECT COALESCE(SUM(diff),0) FROM returns WHERE date(created_at) BETWEEN ?1 AND ?2",
+    &[&from, &to],
   );
 
-  let month_net_revenue = month_sales_total + month_return_diff;
+  let net_revenue = sales_total + return_diff;
 
-  // Brüt kâr: aktif satış kârı + exchange verilen ürün kârı - refund iade edilen ürün kârı
-  let month_gross_profit_sales: f64 = conn
+  // brüt kâr: aktif satış kârı + exchange verilen ürün kârı - refund iade edilen ürün kârı
+  let gross_profit_sales: f64 = conn
     .query_row(
       &format!(
         r#"
@@ -1321,16 +3945,16 @@ pub fn get_dashboard_summary(days: i64, months: i64) -> Result<DashboardSummary,
         FROM sales s
         LEFT JOIN products p ON p.barcode = s.product_barcode
         WHERE {cond}
-          AND strftime('%Y-%m', s.sold_at) = strftime('%Y-%m','now','localtime')
+          AND date(s.sold_at) BETWEEN ?1 AND ?2
         "#,
         cond = sales_active_s
       ),
-      [],
+      params![from, to],
       |row| row.get::<_, f64>(0),
     )
-    .map_err(|e| format!("month_gross_profit_sales query error: {}", e))?;
+    .map_err(|e| PosError::Db(format!("gross_profit_sales query error: {}", e)))?;
 
-  let month_exchange_profit: f64 = conn
+  let exchange_profit: f64 = conn
     .query_row(
       r#"
       SELECT COALESCE(SUM((ei.unit_price - COALESCE(p.buy_price,0)) * ei.qty),0)
@@ -1338,14 +3962,14 @@ pub fn get_dashboard_summary(days: i64, months: i64) -> Result<DashboardSummary,
       JOIN returns r ON r.return_group_id = ei.exchange_group_id
       LEFT JOIN products p ON p.barcode = ei.product_barcode
       WHERE r.mode='EXCHANGE'
-        AND strftime('%Y-%m', r.created_at) = strftime('%Y-%m','now','localtime')
+        AND date(r.created_at) BETWEEN ?1 AND ?2
       "#,
-      [],
+      params![from, to],
       |row| row.get::<_, f64>(0),
     )
-    .map_err(|e| format!("month_exchange_profit query error: {}", e))?;
+    .map_err(|e| PosError::Db(format!("exchange_profit query error: {}", e)))?;
 
-  let month_refund_profit: f64 = conn
+  let refund_profit: f64 = conn
     .query_row(
       r#"
       SELECT COALESCE(SUM((ri.unit_price - COALESCE(p.buy_price,0)) * ri.qty),0)
@@ -1353,387 +3977,555 @@ pub fn get_dashboard_summary(days: i64, months: i64) -> Result<DashboardSummary,
       JOIN returns r ON r.return_group_id = ri.return_group_id
       LEFT JOIN products p ON p.barcode = ri.product_barcode
       WHERE r.mode='REFUND'
-        AND strftime('%Y-%m', r.created_at)=strftime('%Y-%m','now','localtime')
+        AND date(r.created_at) BETWEEN ?1 AND ?2
       "#,
-      [],
+      params![from, to],
       |row| row.get::<_, f64>(0),
     )
-    .map_err(|e| format!("month_refund_profit query error: {}", e))?;
+    .map_err(|e| PosError::Db(format!("refund_profit query error: {}", e)))?;
 
-  let month_gross_profit = month_gross_profit_sales + month_exchange_profit - month_refund_profit;
+  let gross_profit = gross_profit_sales + exchange_profit - refund_profit;
 
-  let month_expense = scalar_f64(
-    &conn,
-    "SELECT COALESCE(SUM(amount),0) FROM expenses WHERE strftime('%Y-%m',spent_at)=strftime('%Y-%m','now','localtime')",
-    &[],
+  let expense = scalar_f64(
+    conn,
+    "SELECT COALESCE(SUM(amount),0) FROM expenses WHERE date(spent_at) BETWEEN ?1 AND ?2",
+    &[&from, &to],
   );
 
-  let month_net_profit = month_gross_profit - month_expense;
-
   // ortalama sepet = net ciro / fiş sayısı
-  let month_receipts = scalar_i64(
-    &conn,
+  let receipts = scalar_i64(
+    conn,
     &format!(
-      "SELECT COALESCE(COUNT(DISTINCT sale_group_id),0) FROM sales WHERE {} AND strftime('%Y-%m',sold_at)=strftime('%Y-%m','now','localtime')",
+      "SELECT COALESCE(COUNT(DISTINCT sale_group_id),0) FROM sales WHERE {} AND date(sold_at) BETWEEN ?1 AND ?2",
       sales_for_revenue
     ),
-    &[],
+    &[&from, &to],
   );
 
-  let month_avg_basket = if month_receipts > 0 {
-    month_net_revenue / (month_receipts as f64)
-  } else {
-    0.0
-  };
+  let avg_basket = if receipts > 0 { net_revenue / receipts as f64 } else { 0.0 };
 
-  // DAILY SERIES 
-  let mut daily = Vec::new();
-  for i in (0..days).rev() {
-    let day: String = conn
-      .query_row(
-        "SELECT date('now','localtime', printf('-%d day', ?1))",
-        rusqlite::params![i],
-        |row| row.get(0),
-      )
-      .map_err(|e| e.to_string())?;
+  Ok(PeriodTotals {
+    net_qty,
+    net_revenue,
+    gross_profit,
+    expense,
+    net_profit: gross_profit - expense,
+    receipts,
+    avg_basket,
+  })
+}
 
-    // net adet: aktif satış + exchange verilen - refund
-    let sales_qty_active = scalar_i64(
-      &conn,
-      &format!(
-        "SELECT COALESCE(SUM(qty),0) FROM sales WHERE {} AND date(sold_at)=?1",
-        sales_active
-      ),
-      &[&day],
-    );
+impl Store {
+  pub fn get_dashboard_summary(&self, period: &Period, monthly: &Period) -> Result<DashboardSummary, PosError> {
+    let conn = self.read()?;
 
-    let exchange_qty = scalar_i64(
-      &conn,
-      r#"SELECT COALESCE(SUM(ei.qty),0)
-         FROM exchange_items ei
-         JOIN returns r ON r.return_group_id = ei.exchange_group_id
-         WHERE r.mode='EXCHANGE'
-           AND date(r.created_at)=?1"#,
-      &[&day],
-    );
+    // KPI kartları: her zaman bugün / bu ay
+    let today = chrono::Local::now().date_naive();
+    let today_s = today.format("%Y-%m-%d").to_string();
+    let month_start = today.format("%Y-%m-01").to_string();
 
-    let refund_qty = scalar_i64(
-      &conn,
-      r#"SELECT COALESCE(SUM(ri.qty),0)
-         FROM return_items ri
-         JOIN returns r ON r.return_group_id = ri.return_group_id
-         WHERE r.mode='REFUND'
-           AND date(r.created_at)=?1"#,
-      &[&day],
-    );
+    // grafik serilerinin üst sınırı (en yeni günler / aylar tutulur)
+    let settings = read_settings(&conn)?;
 
-    let net_qty = (sales_qty_active + exchange_qty - refund_qty).max(0);
+    let today_t = period_totals(&conn, &today_s, &today_s)?;
+    let month_t = period_totals(&conn, &month_start, &today_s)?;
 
-    // net ciro: revenue şartlı satış toplam + returns.diff
-    let sales_total = scalar_f64(
-      &conn,
-      &format!(
-        "SELECT COALESCE(SUM(total),0) FROM sales WHERE {} AND date(sold_at)=?1",
-        sales_for_revenue
-      ),
-      &[&day],
-    );
+    // Seçili dönemin toplamı (karşılaştırma bu değerler üzerinden)
+    let totals = period_totals(&conn, &period.first_day(), &period.last_day())?;
 
-    let return_diff = scalar_f64(
-      &conn,
-      "SELECT COALESCE(SUM(diff),0) FROM returns WHERE date(created_at)=?1",
-      &[&day],
-    );
+    // DAILY SERIES
+    let days = period.each_day();
+    let skip = days.len().saturating_sub(settings.dashboard_max_daily as usize);
+    let mut daily = Vec::new();
+    for day in days.into_iter().skip(skip) {
+      let t = period_totals(&conn, &day, &day)?;
 
-    let net_revenue = sales_total + return_diff;
+      if t.net_qty == 0 && t.net_revenue.abs() < 0.0001 && t.gross_profit.abs() < 0.0001 {
+        continue;
+      }
 
-    // brüt kâr: aktif satış kârı + exchange kârı - refund kârı
-    let gross_profit_sales = match conn.query_row(
-      &format!(
-        r#"
-        SELECT COALESCE(SUM((s.unit_price - COALESCE(p.buy_price,0)) * s.qty),0)
-        FROM sales s
-        LEFT JOIN products p ON p.barcode = s.product_barcode
-        WHERE {cond}
-          AND date(s.sold_at)=?1
-        "#,
-        cond = sales_active_s
-      ),
-      [&day],
-      |row| row.get::<_, f64>(0),
-    ) {
-      Ok(v) => v,
-      Err(_) => 0.0,
-    };
+      daily.push(DailyDashboardRow {
+        day,
+        net_qty: t.net_qty,
+        net_revenue: t.net_revenue,
+        gross_profit: t.gross_profit,
+        avg_basket: t.avg_basket,
+      });
+    }
 
-    let exchange_profit = match conn.query_row(
-      r#"
-      SELECT COALESCE(SUM((ei.unit_price - COALESCE(p.buy_price,0)) * ei.qty),0)
-      FROM exchange_items ei
-      JOIN returns r ON r.return_group_id = ei.exchange_group_id
-      LEFT JOIN products p ON p.barcode = ei.product_barcode
-      WHERE r.mode='EXCHANGE'
-        AND date(r.created_at)=?1
-      "#,
-      [&day],
-      |row| row.get::<_, f64>(0),
-    ) {
-      Ok(v) => v,
-      Err(_) => 0.0,
-    };
+    // MONTHLY SERIES (tam takvim ayları)
+    let months = monthly.each_month();
+    let skip = months.len().saturating_sub(settings.dashboard_max_months as usize);
+    let mut monthly_rows = Vec::new();
+    for period in months.into_iter().skip(skip) {
+      // 'YYYY-MM-31' metin olarak ayın tüm günlerinden büyük/eşit
+      let t = period_totals(&conn, &format!("{}-01", period), &format!("{}-31", period))?;
+
+      if t.net_qty == 0
+        && t.net_revenue.abs() < 0.0001
+        && t.gross_profit.abs() < 0.0001
+        && t.expense.abs() < 0.0001
+      {
+        continue;
+      }
 
-    let refund_profit = match conn.query_row(
-      r#"
-      SELECT COALESCE(SUM((ri.unit_price - COALESCE(p.buy_price,0)) * ri.qty),0)
-      FROM return_items ri
-      JOIN returns r ON r.return_group_id = ri.return_group_id
-      LEFT JOIN products p ON p.barcode = ri.product_barcode
-      WHERE r.mode='REFUND'
-        AND date(r.created_at)=?1
-      "#,
-      [&day],
-      |row| row.get::<_, f64>(0),
-    ) {
-      Ok(v) => v,
-      Err(_) => 0.0,
-    };
+      monthly_rows.push(MonthlyDashboardRow {
+        period,
+        net_qty: t.net_qty,
+        net_revenue: t.net_revenue,
+        gross_profit: t.gross_profit,
+        expense: t.expense,
+        net_profit: t.net_profit,
+        avg_basket: t.avg_basket,
+      });
+    }
 
-    let gross_profit = gross_profit_sales + exchange_profit - refund_profit;
+    Ok(DashboardSummary {
+      kpi: DashboardKpi {
+        today_qty: today_t.net_qty,
+        today_net_revenue: today_t.net_revenue,
+        month_gross_profit: month_t.gross_profit,
+        month_net_profit: month_t.net_profit,
+        month_avg_basket: month_t.avg_basket,
+        month_expense: month_t.expense,
+      },
+      totals,
+      daily,
+      monthly: monthly_rows,
+    })
+  }
 
-    // ortalama sepet: net ciro / fiş sayısı (revenue şartlı)
-    let receipts = scalar_i64(
-      &conn,
-      &format!(
-        "SELECT COALESCE(COUNT(DISTINCT sale_group_id),0) FROM sales WHERE {} AND date(sold_at)=?1",
-        sales_for_revenue
-      ),
-      &[&day],
+  pub fn delete_product(&self, barcode: &str) -> Result<i64, PosError> {
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
+
+    let hard = tx.execute(
+      "DELETE FROM products WHERE barcode = ?1",
+      rusqlite::params![barcode],
     );
 
-    let avg_basket = if receipts > 0 { net_revenue / receipts as f64 } else { 0.0 };
+    match hard {
+      Ok(n) => {
+        tx.commit()?;
+        Ok(n as i64) 
+      }
+      // satış / iade kaydı olan ürün silinemez; pasife alınır
+      Err(e) => {
+        if !crate::error::is_foreign_key_violation(&e) {
+          return Err(e.into());
+        }
 
-    if net_qty == 0 && net_revenue.abs() < 0.0001 && gross_profit.abs() < 0.0001 {
-      continue;
-    }
+        // sıfırlanan stok canlı pencerelere bildirilir (pasif ürün düşük stok uyarısı vermez)
+        let (magaza, depo): (i64, i64) = tx.query_row(
+          "SELECT COALESCE(magaza_stok,0), COALESCE(depo_stok,0) FROM products WHERE barcode = ?1",
+          rusqlite::params![barcode],
+          |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+
+        tx.execute(
+          r#"
+          UPDATE products
+          SET
+            is_active = 0,
+            magaza_stok = 0,
+            depo_stok = 0,
+            updated_at = datetime('now','localtime')
+          WHERE barcode = ?1
+          "#,
+          rusqlite::params![barcode],
+        )?;
+
+        let mut changes = Vec::new();
+        for (loc, old) in [("MAGAZA", magaza), ("DEPO", depo)] {
+          if old != 0 {
+            changes.push(stock_change(&tx, barcode, loc, -old)?);
+          }
+        }
+        let events = stock_events(&tx, &changes)?;
+        tx.commit()?;
+        drop(conn);
+        self.emit(events);
 
-    daily.push(DailyDashboardRow {
-      day,
-      net_qty,
-      net_revenue,
-      gross_profit,
-      avg_basket,
-    });
+        Ok(1)
+      }
+    }
   }
+}
 
-  // MONTHLY SERIES 
-  let mut monthly = Vec::new();
-  for i in (0..months).rev() {
-    let period: String = conn
-      .query_row(
-        "SELECT strftime('%Y-%m', date('now','localtime', printf('-%d month', ?1)))",
-        rusqlite::params![i],
-        |row| row.get(0),
-      )
-      .map_err(|e| e.to_string())?;
+// -------------------- BÜTÜNLÜK KONTROLÜ / ONARIM --------------------
+//
+// SQLite'ın kendi kontrolleri (integrity_check, foreign_key_check) + alan kontrolleri.
+// repair=true: yalnızca güvenle düzeltilebilenler tek transaction içinde düzeltilir;
+// dosya bozulması, FK ihlalleri ve negatif stok (sayım gerekir) sadece raporlanır.
+// Legacy `stock` kolonu v3'te kaldırıldı; göçten önce aynı kontrol çalışır ve düzeltilen
+// ürünler _meta'da saklanıp burada raporlanır. Toplam stok her zaman magaza + depo.
+
+const INTEGRITY_SAMPLE_LIMIT: usize = 20;
+
+// legacy `stock` ile lokasyon toplamı uyuşmayan ürünler (yalnız v3 öncesi şemada)
+const LEGACY_STOCK_MISMATCH: &str =
+  "COALESCE(magaza_stok,0) + COALESCE(depo_stok,0) <> COALESCE(stock,0)";
+const LEGACY_STOCK_LABEL: &str = "barcode || ' (stock ' || COALESCE(stock,0) || ', mağaza ' || \
+  COALESCE(magaza_stok,0) || ', depo ' || COALESCE(depo_stok,0) || ')'";
+// v3 göçünün düzelttiği ürünler, satır başına bir LEGACY_STOCK_LABEL
+const META_LEGACY_STOCK_REPORT: &str = "migration.0003.stock_mismatch";
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum IssueSeverity {
+  Error,
+  Warning,
+}
 
-    // net adet: aktif satış + exchange verilen - refund
-    let sales_qty_active = scalar_i64(
-      &conn,
-      &format!(
-        "SELECT COALESCE(SUM(qty),0) FROM sales WHERE {} AND strftime('%Y-%m',sold_at)=?1",
-        sales_active
-      ),
-      &[&period],
-    );
+#[derive(serde::Serialize)]
+pub struct IntegrityIssue {
+  pub check: String,
+  pub severity: IssueSeverity,
+  pub message: String,
+  pub count: i64,
+  // ilk birkaç kayıt (barkod / id), UI'da göstermek için
+  pub samples: Vec<String>,
+  pub repairable: bool,
+  pub repaired: i64,
+}
 
-    let exchange_qty = scalar_i64(
-      &conn,
-      r#"SELECT COALESCE(SUM(ei.qty),0)
-         FROM exchange_items ei
-         JOIN returns r ON r.return_group_id = ei.exchange_group_id
-         WHERE r.mode='EXCHANGE'
-           AND strftime('%Y-%m', r.created_at)=?1"#,
-      &[&period],
-    );
+#[derive(serde::Serialize)]
+pub struct IntegrityReport {
+  // onarımdan sonra kalan ERROR seviyesinde sorun yoksa true
+  pub ok: bool,
+  pub repair: bool,
+  pub checked_at: String,
+  pub issues: Vec<IntegrityIssue>,
+}
+
+struct DomainCheck {
+  check: &'static str,
+  severity: IssueSeverity,
+  message: &'static str,
+  // her satır bir sorun; tek kolon = örnek etiketi
+  select_sql: String,
+  repair_sql: Option<String>,
+}
+
+fn legacy_stock_mismatches(conn: &Connection) -> Result<Vec<String>, PosError> {
+  let mut stmt = conn
+    .prepare(&format!("SELECT {} FROM products WHERE {}", LEGACY_STOCK_LABEL, LEGACY_STOCK_MISMATCH))?;
+  let rows = stmt
+    .query_map([], |r| r.get::<_, String>(0))?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(rows)
+}
+
+/// `has_legacy_stock`: products'ta hâlâ `stock` kolonu var (göç edilmemiş / geri yüklenmiş eski DB)
+fn domain_checks(has_legacy_stock: bool) -> Vec<DomainCheck> {
+  let negative = "COALESCE(magaza_stok,0) < 0 OR COALESCE(depo_stok,0) < 0";
+  let orphan_returns =
+    "NOT EXISTS (SELECT 1 FROM returns r WHERE r.return_group_id = return_items.return_group_id)";
+  let orphan_exchanges =
+    "NOT EXISTS (SELECT 1 FROM returns r WHERE r.return_group_id = exchange_items.exchange_group_id)";
+  let stale_names = "
+    category IS NOT (SELECT name FROM categories WHERE id = products.category_id)
+    OR color IS NOT (SELECT name FROM colors     WHERE id = products.color_id)
+    OR size  IS NOT (SELECT name FROM sizes      WHERE id = products.size_id)";
+
+  let mut checks = vec![
+    // 0'a çekmek olmayan stok yaratır: sayımla düzeltilmeli
+    DomainCheck {
+      check: "negative_stock",
+      severity: IssueSeverity::Error,
+      message: "Negatif stoklu ürünler (sayım ile düzeltilmeli, otomatik onarılmaz)",
+      select_sql: format!("SELECT barcode FROM products WHERE {}", negative),
+      repair_sql: None,
+    },
+    DomainCheck {
+      check: "orphan_return_items",
+      severity: IssueSeverity::Error,
+      message: "İade kaydı olmayan iade satırları (onarım: satırlar silinir)",
+      select_sql: format!("SELECT CAST(id AS TEXT) FROM return_items WHERE {}", orphan_returns),
+      repair_sql: Some(format!("DELETE FROM return_items WHERE {}", orphan_returns)),
+    },
+    DomainCheck {
+      check: "orphan_exchange_items",
+      severity: IssueSeverity::Error,
+      message: "Değişim kaydı olmayan değişim satırları (onarım: satırlar silinir)",
+      select_sql: format!("SELECT CAST(id AS TEXT) FROM exchange_items WHERE {}", orphan_exchanges),
+      repair_sql: Some(format!("DELETE FROM exchange_items WHERE {}", orphan_exchanges)),
+    },
+    DomainCheck {
+      check: "stale_dictionary_names",
+      severity: IssueSeverity::Warning,
+      message: "Kategori/renk/beden adı sözlükle uyuşmayan ürünler (onarım: adlar id'den yeniden türetilir)",
+      select_sql: format!("SELECT barcode FROM products WHERE {}", stale_names),
+      repair_sql: Some(format!("UPDATE products SET {} WHERE {}", DERIVED_NAME_SET, stale_names)),
+    },
+    DomainCheck {
+      check: "sales_inactive_product",
+      severity: IssueSeverity::Warning,
+      message: "Pasif ürüne ait geçerli satış satırları (bilgi amaçlı, onarılmaz)",
+      select_sql: "
+        SELECT '#' || s.id || ' ' || s.product_barcode
+        FROM sales s
+        JOIN products p ON p.barcode = s.product_barcode
+        WHERE COALESCE(p.is_active,1) = 0
+          AND COALESCE(s.voided,0) = 0"
+        .to_string(),
+      repair_sql: None,
+    },
+  ];
+
+  if has_legacy_stock {
+    checks.push(DomainCheck {
+      check: "legacy_stock_mismatch",
+      severity: IssueSeverity::Error,
+      message: "Toplam stoğu (stock) mağaza + depo ile uyuşmayan ürünler (onarım: fark mağazaya yazılır)",
+      select_sql: format!("SELECT {} FROM products WHERE {}", LEGACY_STOCK_LABEL, LEGACY_STOCK_MISMATCH),
+      repair_sql: Some(format!(
+        "UPDATE products SET magaza_stok = COALESCE(stock,0) - COALESCE(depo_stok,0) WHERE {}",
+        LEGACY_STOCK_MISMATCH
+      )),
+    });
+  }
+  checks
+}
+
+fn pragma_issue(conn: &Connection, check: &str, message: &'static str, sql: &str) -> Result<IntegrityIssue, PosError> {
+  let mut stmt = conn.prepare(sql)?;
+  let rows: Vec<String> = if check == "integrity_check" {
+    stmt
+      .query_map([], |r| r.get::<_, String>(0))?
+      .collect::<Result<Vec<_>, _>>()?
+      .into_iter()
+      .filter(|m| m != "ok")
+      .collect()
+  } else {
+    // foreign_key_check: tablo, rowid, hedef tablo, fk no
+    stmt
+      .query_map([], |r| {
+        Ok(format!(
+          "{}#{} -> {}",
+          r.get::<_, String>(0)?,
+          r.get::<_, Option<i64>>(1)?.map(|v| v.to_string()).unwrap_or_default(),
+          r.get::<_, String>(2)?
+        ))
+      })?
+      .collect::<Result<Vec<_>, _>>()?
+  };
 
-    let refund_qty = scalar_i64(
-      &conn,
-      r#"SELECT COALESCE(SUM(ri.qty),0)
-         FROM return_items ri
-         JOIN returns r ON r.return_group_id = ri.return_group_id
-         WHERE r.mode='REFUND'
-           AND strftime('%Y-%m', r.created_at)=?1"#,
-      &[&period],
-    );
+  Ok(IntegrityIssue {
+    check: check.to_string(),
+    severity: IssueSeverity::Error,
+    message: i18n::text(message).to_string(),
+    count: rows.len() as i64,
+    samples: rows.into_iter().take(INTEGRITY_SAMPLE_LIMIT).collect(),
+    repairable: false,
+    repaired: 0,
+  })
+}
 
-    let net_qty = (sales_qty_active + exchange_qty - refund_qty).max(0);
+impl Store {
+  /// Tüm kontrolleri çalıştırır; `repair` ise onarılabilir sorunları tek transaction'da düzeltir.
+  /// Sorunsuz kontroller de raporda count = 0 ile yer alır.
+  pub fn check_integrity(&self, repair: bool) -> Result<IntegrityReport, PosError> {
+    let mut conn = self.write()?;
 
-    // net ciro: revenue şartlı satış toplam + returns.diff
-    let sales_total = scalar_f64(
-      &conn,
-      &format!(
-        "SELECT COALESCE(SUM(total),0) FROM sales WHERE {} AND strftime('%Y-%m',sold_at)=?1",
-        sales_for_revenue
-      ),
-      &[&period],
-    );
+    let mut issues = vec![
+      pragma_issue(&conn, "integrity_check", "SQLite dosya bütünlüğü", "PRAGMA integrity_check")?,
+      pragma_issue(&conn, "foreign_key_check", "Yabancı anahtar ihlalleri", "PRAGMA foreign_key_check")?,
+    ];
 
-    let return_diff = scalar_f64(
-      &conn,
-      "SELECT COALESCE(SUM(diff),0) FROM returns WHERE strftime('%Y-%m',created_at)=?1",
-      &[&period],
-    );
+    let checks = domain_checks(column_exists(&conn, "products", "stock")?);
+    for c in &checks {
+      let count: i64 = conn
+        .query_row(&format!("SELECT COUNT(*) FROM ({})", c.select_sql), [], |r| r.get(0))?;
+
+      let mut stmt = conn
+        .prepare(&format!("{} LIMIT {}", c.select_sql, INTEGRITY_SAMPLE_LIMIT))?;
+      let samples = stmt
+        .query_map([], |r| r.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+      issues.push(IntegrityIssue {
+        check: c.check.to_string(),
+        severity: c.severity,
+        message: i18n::text(c.message).to_string(),
+        count,
+        samples,
+        repairable: c.repair_sql.is_some(),
+        repaired: 0,
+      });
+    }
 
-    let net_revenue = sales_total + return_diff;
+    // v3 göçünden önceki kontrolün sonucu (bilgi amaçlı)
+    let reconciled: Vec<String> = meta_get(&conn, META_LEGACY_STOCK_REPORT)?
+      .unwrap_or_default()
+      .lines()
+      .map(str::to_string)
+      .collect();
+    issues.push(IntegrityIssue {
+      check: "legacy_stock_reconciled".to_string(),
+      severity: IssueSeverity::Warning,
+      message: i18n::text("Şema göçünde toplam stoktan (stock) düzeltilen ürünler (bilgi amaçlı)").to_string(),
+      count: reconciled.len() as i64,
+      samples: reconciled.into_iter().take(INTEGRITY_SAMPLE_LIMIT).collect(),
+      repairable: false,
+      repaired: 0,
+    });
 
-    // brüt kâr: aktif satış kârı + exchange kârı - refund kârı
-    let gross_profit_sales = match conn.query_row(
-      &format!(
-        r#"
-        SELECT COALESCE(SUM((s.unit_price - COALESCE(p.buy_price,0)) * s.qty),0)
-        FROM sales s
-        LEFT JOIN products p ON p.barcode = s.product_barcode
-        WHERE {cond}
-          AND strftime('%Y-%m',s.sold_at)=?1
-        "#,
-        cond = sales_active_s
-      ),
-      [&period],
-      |row| row.get::<_, f64>(0),
-    ) {
-      Ok(v) => v,
-      Err(_) => 0.0,
-    };
+    if repair && issues.iter().any(|i| i.repairable && i.count > 0) {
+      let tx = conn.transaction()?;
+      for c in &checks {
+        let Some(sql) = &c.repair_sql else { continue };
+        let Some(issue) = issues.iter_mut().find(|i| i.check == c.check) else { continue };
+        if issue.count == 0 {
+          continue;
+        }
+        issue.repaired = tx.execute(sql, [])? as i64;
+      }
+      tx.commit()?;
+    }
 
-    let exchange_profit = match conn.query_row(
-      r#"
-      SELECT COALESCE(SUM((ei.unit_price - COALESCE(p.buy_price,0)) * ei.qty),0)
-      FROM exchange_items ei
-      JOIN returns r ON r.return_group_id = ei.exchange_group_id
-      LEFT JOIN products p ON p.barcode = ei.product_barcode
-      WHERE r.mode='EXCHANGE'
-        AND strftime('%Y-%m', r.created_at)=?1
-      "#,
-      [&period],
-      |row| row.get::<_, f64>(0),
-    ) {
-      Ok(v) => v,
-      Err(_) => 0.0,
-    };
+    let ok = issues
+      .iter()
+      .all(|i| i.severity != IssueSeverity::Error || i.count <= i.repaired);
 
-    let refund_profit = match conn.query_row(
-      r#"
-      SELECT COALESCE(SUM((ri.unit_price - COALESCE(p.buy_price,0)) * ri.qty),0)
-      FROM return_items ri
-      JOIN returns r ON r.return_group_id = ri.return_group_id
-      LEFT JOIN products p ON p.barcode = ri.product_barcode
-      WHERE r.mode='REFUND'
-        AND strftime('%Y-%m', r.created_at)=?1
-      "#,
-      [&period],
-      |row| row.get::<_, f64>(0),
-    ) {
-      Ok(v) => v,
-      Err(_) => 0.0,
-    };
+    Ok(IntegrityReport {
+      ok,
+      repair,
+      checked_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+      issues,
+    })
+  }
+}
 
-    let gross_profit = gross_profit_sales + exchange_profit - refund_profit;
+// -------------------- ŞEMA GÖÇLERİ (versiyonlu) --------------------
+//
+// Her göç tek transaction içinde çalışır ve _meta'ya yazılır:
+//   schema_version        -> son uygulanan numara
+//   migration.0001        -> "<ad> @ <zaman>"
+// Yeni şema değişikliği = MIGRATIONS sonuna yeni numara. Eskiler değiştirilmez.
 
-    let expense = scalar_f64(
-      &conn,
-      "SELECT COALESCE(SUM(amount),0) FROM expenses WHERE strftime('%Y-%m',spent_at)=?1",
-      &[&period],
-    );
+const META_SCHEMA_VERSION: &str = "schema_version";
 
-    let net_profit = gross_profit - expense;
+struct Migration {
+  version: i64,
+  name: &'static str,
+  up: fn(&Connection) -> Result<(), PosError>,
+}
 
-    let receipts = scalar_i64(
-      &conn,
-      &format!(
-        "SELECT COALESCE(COUNT(DISTINCT sale_group_id),0) FROM sales WHERE {} AND strftime('%Y-%m',sold_at)=?1",
-        sales_for_revenue
-      ),
-      &[&period],
-    );
+const MIGRATIONS: &[Migration] = &[
+  Migration { version: 1, name: "baseline", up: migration_001_baseline },
+  Migration { version: 2, name: "products_fts", up: migration_002_products_fts },
+  Migration { version: 3, name: "dictionary_fks", up: migration_003_dictionary_fks },
+  Migration { version: 4, name: "backup_targets", up: migration_004_backup_targets },
+  Migration { version: 5, name: "settings", up: migration_005_settings },
+  Migration { version: 6, name: "event_outbox", up: migration_006_event_outbox },
+  Migration { version: 7, name: "low_stock_threshold", up: migration_007_low_stock_threshold },
+  Migration { version: 8, name: "sale_channel", up: migration_008_sale_channel },
+  Migration { version: 9, name: "fts_alias_barcodes", up: migration_009_fts_alias_barcodes },
+  Migration { version: 10, name: "typed_device_settings", up: migration_010_typed_device_settings },
+];
+
+/// Bu uygulamanın bildiği en yeni şema
+pub fn schema_version() -> i64 {
+  MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
 
-    let avg_basket = if receipts > 0 { net_revenue / receipts as f64 } else { 0.0 };
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, PosError> {
+  let n: i64 = conn
+    .query_row(
+      "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name=?1",
+      params![table],
+      |r| r.get(0),
+    )?;
+  Ok(n > 0)
+}
 
-    if net_qty == 0
-      && net_revenue.abs() < 0.0001
-      && gross_profit.abs() < 0.0001
-      && expense.abs() < 0.0001
-    {
-      continue;
-    }
+/// Kayıtlı şema versiyonu. _meta yoksa ya da versiyon yazılmamışsa 0
+/// (boş DB veya versiyonlamadan önceki eski kurulum).
+pub fn stored_schema_version(conn: &Connection) -> Result<i64, PosError> {
+  if !table_exists(conn, "_meta")? {
+    return Ok(0);
+  }
+  match meta_get(conn, META_SCHEMA_VERSION)? {
+    Some(v) => v
+      .trim()
+      .parse::<i64>()
+      .map_err(|_| PosError::Validation(tr!("Geçersiz şema versiyonu: {}", v))),
+    None => Ok(0),
+  }
+}
 
-    monthly.push(MonthlyDashboardRow {
-      period,
-      net_qty,
-      net_revenue,
-      gross_profit,
-      expense,
-      net_profit,
-      avg_basket,
-    });
+impl Store {
+  /// DB'ye özel erişim: süre boyunca read() bekler. `f` kendi bağlantısını alır;
+  /// içeride read() çağrılmamalı (kilitlenir).
+  pub(crate) fn with_exclusive_conn<T>(
+    &self,
+    f: impl FnOnce(&mut Connection) -> Result<T, PosError>,
+  ) -> Result<T, PosError> {
+    // sıra önemli: önce yazma kilidi, sonra kapı (yazan biri okuma bağlantısı alırken beklemesin)
+    let mut writer = self.lock_writer();
+    let _gate = self.inner.gate.write().unwrap_or_else(|e| e.into_inner());
+    f(&mut writer)
   }
+}
 
-  Ok(DashboardSummary {
-    kpi: DashboardKpi {
-      today_qty,
-      today_net_revenue,
-      month_gross_profit,
-      month_net_profit,
-      month_avg_basket,
-      month_expense,
-    },
-    daily,
-    monthly,
-  })
+/// Geri yüklenecek (daha eski şemalı olabilecek) DB'yi, canlı DB'ye kopyalanmadan önce
+/// geçici dosyada güncel şemaya getirir. Kaynak yedek dosyası değişmez; göç yedeği alınmaz.
+pub(crate) fn migrate_restored(conn: &Connection) -> Result<(), PosError> {
+  migrate(conn, |_from, _to| Ok(()))?;
+  seed_option_tables(conn)?;
+  seed_default_dictionaries(conn)
 }
 
-pub fn delete_product(barcode: &str) -> Result<i64, String> {
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
+/// Bekleyen göçleri sırayla uygular.
+/// `before_migrate(from, to)`: içinde veri olan bir DB göç edilecekse, ilk göçten önce
+/// bir kez çağrılır (otomatik yedek için). Hata dönerse hiçbir göç çalışmaz.
+fn migrate(
+  conn: &Connection,
+  before_migrate: impl FnOnce(i64, i64) -> Result<(), PosError>,
+) -> Result<(), PosError> {
+  let current = stored_schema_version(conn)?;
+  let target = schema_version();
+
+  if current > target {
+    return Err(PosError::Conflict(tr!(
+      "Veritabanı daha yeni bir sürümle oluşturulmuş (şema v{}); bu uygulama en fazla v{} destekliyor. Lütfen uygulamayı güncelleyin.",
+      current, target
+    )));
+  }
 
-  let hard = tx.execute(
-    "DELETE FROM products WHERE barcode = ?1",
-    rusqlite::params![barcode],
-  );
+  let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > current).collect();
+  if pending.is_empty() {
+    return Ok(());
+  }
 
-  match hard {
-    Ok(n) => {
-      tx.commit().map_err(|e| e.to_string())?;
-      Ok(n as i64) 
-    }
-    Err(e) => {
-      let msg = e.to_string();
-      if !msg.to_lowercase().contains("foreign key") {
-        return Err(msg);
-      }
+  // boş (yeni kurulum) DB'yi yedeklemeye gerek yok
+  if table_exists(conn, "products")? {
+    before_migrate(current, target)?;
+  }
 
-      tx.execute(
-        r#"
-        UPDATE products
-        SET
-          is_active = 0,
-          stock = 0,
-          magaza_stok = 0,
-          depo_stok = 0,
-          updated_at = datetime('now','localtime')
-        WHERE barcode = ?1
-        "#,
-        rusqlite::params![barcode],
-      )
-      .map_err(|e| e.to_string())?;
+  for m in pending {
+    let tx = conn.unchecked_transaction()?;
 
-      tx.commit().map_err(|e| e.to_string())?;
+    (m.up)(&tx).map_err(|e| PosError::Db(tr!("Şema göçü v{} ({}) başarısız: {}", m.version, m.name, e)))?;
 
-      Ok(1)
-    }
+    // _meta baseline ile oluşur; sonraki göçler de yazabilsin
+    meta_set(&tx, META_SCHEMA_VERSION, &m.version.to_string())?;
+    meta_set(
+      &tx,
+      &format!("migration.{:04}", m.version),
+      &format!("{} @ {}", m.name, chrono::Local::now().format("%Y-%m-%d %H:%M:%S")),
+    )?;
+
+    tx.commit()?;
   }
+
+  Ok(())
 }
 
-fn migrate(conn: &Connection) -> Result<(), String> {
+/// v1: versiyonlamadan önceki şemanın tamamı. Eski kurulumlarda da güvenle çalışır
+/// (IF NOT EXISTS + ensure_column).
+fn migration_001_baseline(conn: &Connection) -> Result<(), PosError> {
   conn
     .execute_batch(
       r#"
@@ -1879,90 +4671,498 @@ fn migrate(conn: &Connection) -> Result<(), String> {
         created_at TEXT DEFAULT (datetime('now','localtime'))
       );
 
-      CREATE TABLE IF NOT EXISTS colors (
-        id         INTEGER PRIMARY KEY AUTOINCREMENT,
-        name       TEXT NOT NULL UNIQUE,
-        is_active  INTEGER DEFAULT 1,
-        created_at TEXT DEFAULT (datetime('now','localtime'))
-      );
+      CREATE TABLE IF NOT EXISTS colors (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        name       TEXT NOT NULL UNIQUE,
+        is_active  INTEGER DEFAULT 1,
+        created_at TEXT DEFAULT (datetime('now','localtime'))
+      );
+
+      CREATE TABLE IF NOT EXISTS sizes (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        name       TEXT NOT NULL UNIQUE,
+        sort_order INTEGER DEFAULT 0,
+        is_active  INTEGER DEFAULT 1,
+        created_at TEXT DEFAULT (datetime('now','localtime'))
+      );
+
+      -- ek (alias) barkodlar: tedarikçi barkodu, eski etiket vb.
+      CREATE TABLE IF NOT EXISTS product_barcodes (
+        barcode         TEXT PRIMARY KEY,
+        product_barcode TEXT NOT NULL,
+        note            TEXT,
+        created_at      TEXT DEFAULT (datetime('now','localtime')),
+        FOREIGN KEY(product_barcode) REFERENCES products(barcode) ON DELETE CASCADE
+      );
+      CREATE INDEX IF NOT EXISTS idx_product_barcodes_product
+        ON product_barcodes(product_barcode);
+      "#,
+    )?;
+
+  ensure_column(conn, "products", "product_code", "TEXT")?;
+  ensure_column(conn, "products", "category", "TEXT")?;
+  ensure_column(conn, "products", "color", "TEXT")?;
+  ensure_column(conn, "products", "size", "TEXT")?;
+  ensure_column(conn, "products", "buy_price", "REAL DEFAULT 0")?;
+  ensure_column(conn, "products", "sell_price", "REAL NOT NULL DEFAULT 0")?;
+  ensure_column(conn, "products", "stock", "INTEGER DEFAULT 0")?;
+  ensure_column(conn, "products", "created_at", "TEXT DEFAULT (datetime('now','localtime'))")?;
+  ensure_column(conn, "products", "updated_at", "TEXT DEFAULT (datetime('now','localtime'))")?;
+  ensure_column(conn, "products", "is_active", "INTEGER DEFAULT 1")?;
+  ensure_column(conn, "products", "category_id", "INTEGER")?;
+  ensure_column(conn, "products", "color_id", "INTEGER")?;
+  ensure_column(conn, "products", "size_id", "INTEGER")?;
+
+  ensure_column(conn, "sales", "product_barcode", "TEXT")?;
+  ensure_column(conn, "sales", "qty", "INTEGER DEFAULT 0")?;
+  ensure_column(conn, "sales", "unit_price", "REAL DEFAULT 0")?;
+  ensure_column(conn, "sales", "total", "REAL DEFAULT 0")?;
+  ensure_column(conn, "sales", "sold_at", "TEXT DEFAULT (datetime('now','localtime'))")?;
+  ensure_column(conn, "sales", "payment_method", "TEXT NOT NULL DEFAULT 'CARD'")?;
+  ensure_column(conn, "sales", "note", "TEXT")?;
+
+  ensure_column(conn, "sales", "sale_group_id", "TEXT")?;
+  ensure_column(conn, "sales", "sold_from", "TEXT")?;
+  ensure_column(conn, "sales", "list_price", "REAL DEFAULT 0")?;
+  ensure_column(conn, "sales", "discount_amount", "REAL DEFAULT 0")?;
+  ensure_column(conn, "sales", "voided", "INTEGER DEFAULT 0")?;
+
+  ensure_column(conn, "products", "magaza_baslangic", "INTEGER DEFAULT 0")?;
+  ensure_column(conn, "products", "depo_baslangic", "INTEGER DEFAULT 0")?;
+  ensure_column(conn, "products", "magaza_stok", "INTEGER DEFAULT 0")?;
+  ensure_column(conn, "products", "depo_stok", "INTEGER DEFAULT 0")?;
+
+  ensure_column(conn, "expenses", "period", "TEXT")?;
+  ensure_column(conn, "returns", "diff_payment_method", "TEXT")?;
+  // dictionary tables (soft delete)
+  ensure_column(conn, "categories", "is_active", "INTEGER DEFAULT 1")?;
+  ensure_column(conn, "colors", "is_active", "INTEGER DEFAULT 1")?;
+  ensure_column(conn, "sizes", "is_active", "INTEGER DEFAULT 1")?;
+
+  // sizes: legacy order_no -> new sort_order (both can live)
+  ensure_column(conn, "sizes", "sort_order", "INTEGER DEFAULT 0")?;
+
+  ensure_returns_cascade_triggers(conn)?;
+
+  Ok(())
+}
+
+/// v2: ürün arama indeksi (FTS5) + senkron tetikleyicileri
+fn migration_002_products_fts(conn: &Connection) -> Result<(), PosError> {
+  ensure_products_fts(conn)
+}
+
+/// v3: kategori/renk/beden için tek doğru kaynak `*_id` kolonları.
+/// - Sözlükte olmayan eski metin değerleri sözlüğe eklenir, id'ler adlardan doldurulur.
+/// - products.category/color/size artık sadece okunur kopya: tetikleyiciler id'den türetir,
+///   sözlükte ad değişince ürünlere yayar.
+/// - Legacy `stock` kolonu kaldırılır; toplam stok = magaza_stok + depo_stok.
+fn migration_003_dictionary_fks(conn: &Connection) -> Result<(), PosError> {
+  // boş sözlüklü eski kurulum: önce options tablosundan tohumla
+  seed_option_tables(conn)?;
+
+  for (table, col) in [("categories", "category"), ("colors", "color"), ("sizes", "size")] {
+    conn
+      .execute(
+        &format!(
+          "INSERT OR IGNORE INTO {table}(name, is_active)
+           SELECT DISTINCT TRIM({col}), 1 FROM products
+           WHERE {col}_id IS NULL AND TRIM(COALESCE({col},'')) <> ''"
+        ),
+        [],
+      )?;
+
+    conn
+      .execute(
+        &format!(
+          "UPDATE products
+           SET {col}_id = (SELECT id FROM {table} WHERE name = TRIM(products.{col}))
+           WHERE ({col}_id IS NULL OR {col}_id = 0)
+             AND TRIM(COALESCE({col},'')) <> ''"
+        ),
+        [],
+      )?;
+
+    // 0 = eski "boş" işareti
+    conn
+      .execute(&format!("UPDATE products SET {col}_id = NULL WHERE {col}_id = 0"), [])?;
+  }
+
+  ensure_dictionary_name_triggers(conn)?;
+
+  // adları id'den yeniden türet (TRIM farkları, sözlükte sonradan yeniden adlandırılanlar)
+  conn
+    .execute_batch(&format!("UPDATE products SET {};", DERIVED_NAME_SET))?;
+
+  // magaza_stok/depo_stok sonradan ensure_column(DEFAULT 0) ile eklendi: o tarihten önceki
+  // ürünlerde stok yalnızca `stock`ta. Göç öncesi bütünlük kontrolü: uyuşmayanlar _meta'ya
+  // yazılır (check_integrity raporlar), fark mağazaya taşınır (depo korunur), sonra silinir.
+  let mismatched = legacy_stock_mismatches(conn)?;
+  if !mismatched.is_empty() {
+    meta_set(conn, META_LEGACY_STOCK_REPORT, &mismatched.join("\n"))?;
+    conn
+      .execute(
+        &format!(
+          "UPDATE products SET magaza_stok = COALESCE(stock,0) - COALESCE(depo_stok,0) WHERE {}",
+          LEGACY_STOCK_MISMATCH
+        ),
+        [],
+      )?;
+  }
+
+  conn
+    .execute_batch("ALTER TABLE products DROP COLUMN stock;")?;
+
+  Ok(())
+}
+
+// products.category/color/size: *_id kolonlarından türetilen ad kopyaları
+const DERIVED_NAME_SET: &str = "
+  category = (SELECT name FROM categories WHERE id = products.category_id),
+  color    = (SELECT name FROM colors     WHERE id = products.color_id),
+  size     = (SELECT name FROM sizes      WHERE id = products.size_id)";
+
+fn ensure_dictionary_name_triggers(conn: &Connection) -> Result<(), PosError> {
+  conn
+    .execute_batch(&format!(
+      r#"
+      DROP TRIGGER IF EXISTS trg_products_dict_names_insert;
+      DROP TRIGGER IF EXISTS trg_products_dict_names_update;
+      DROP TRIGGER IF EXISTS trg_categories_rename;
+      DROP TRIGGER IF EXISTS trg_colors_rename;
+      DROP TRIGGER IF EXISTS trg_sizes_rename;
+
+      CREATE TRIGGER trg_products_dict_names_insert
+      AFTER INSERT ON products
+      BEGIN
+        UPDATE products SET {set} WHERE barcode = NEW.barcode;
+      END;
+
+      CREATE TRIGGER trg_products_dict_names_update
+      AFTER UPDATE OF category_id, color_id, size_id, category, color, size ON products
+      BEGIN
+        UPDATE products SET {set} WHERE barcode = NEW.barcode;
+      END;
+
+      CREATE TRIGGER trg_categories_rename
+      AFTER UPDATE OF name ON categories
+      BEGIN
+        UPDATE products SET category = NEW.name WHERE category_id = NEW.id;
+      END;
+
+      CREATE TRIGGER trg_colors_rename
+      AFTER UPDATE OF name ON colors
+      BEGIN
+        UPDATE products SET color = NEW.name WHERE color_id = NEW.id;
+      END;
+
+      CREATE TRIGGER trg_sizes_rename
+      AFTER UPDATE OF name ON sizes
+      BEGIN
+        UPDATE products SET size = NEW.name WHERE size_id = NEW.id;
+      END;
+
+      -- FTS ekleme tetikleyicisi NEW yerine tablodaki satırı okur: ad tetikleyicisiyle
+      -- hangi sırada çalışırsa çalışsın indeks türetilmiş adları görür.
+      DROP TRIGGER IF EXISTS trg_products_fts_insert;
+      CREATE TRIGGER trg_products_fts_insert
+      AFTER INSERT ON products
+      BEGIN
+        DELETE FROM products_fts WHERE rowid = NEW.rowid;
+        INSERT INTO products_fts(rowid, barcode, name, product_code, category, color, size)
+        SELECT rowid, {fts_cols} FROM products WHERE rowid = NEW.rowid;
+      END;
+      "#,
+      set = DERIVED_NAME_SET,
+      fts_cols = fts_cols("")
+    ))
+    .map_err(PosError::from)
+}
+
+/// v4: yedeklerin ayrıca kopyalanacağı hedef klasörler (USB, NAS, ...)
+fn migration_004_backup_targets(conn: &Connection) -> Result<(), PosError> {
+  conn
+    .execute_batch(
+      r#"
+      CREATE TABLE IF NOT EXISTS backup_targets (
+        id              INTEGER PRIMARY KEY AUTOINCREMENT,
+        path            TEXT NOT NULL UNIQUE,
+        label           TEXT,
+        is_active       INTEGER NOT NULL DEFAULT 1,
+
+        -- son kopyalama denemesi
+        last_status     TEXT,            -- 'OK' | 'ERROR'
+        last_error      TEXT,
+        last_backup_at  TEXT,
+        last_file       TEXT,
+
+        created_at      TEXT DEFAULT (datetime('now','localtime'))
+      );
+      "#,
+    )
+    .map_err(PosError::from)
+}
+
+/// v5: mağaza ayarları (önceden koddaki sabitler; varsayılanlar aynı değerler)
+fn migration_005_settings(conn: &Connection) -> Result<(), PosError> {
+  conn
+    .execute_batch(
+      r#"
+      CREATE TABLE IF NOT EXISTS settings (
+        id                      INTEGER PRIMARY KEY CHECK (id = 1),
+        undo_window_minutes     INTEGER NOT NULL DEFAULT 30,
+        barcode_start           INTEGER NOT NULL DEFAULT 1000001,
+        product_code_limit      INTEGER NOT NULL DEFAULT 999,
+        default_location        TEXT    NOT NULL DEFAULT 'MAGAZA'
+                                CHECK (default_location IN ('MAGAZA','DEPO')),
+        default_payment_method  TEXT    NOT NULL DEFAULT 'CARD'
+                                CHECK (default_payment_method IN ('CARD','CASH','TRANSFER')),
+        dashboard_max_daily     INTEGER NOT NULL DEFAULT 366,
+        dashboard_max_months    INTEGER NOT NULL DEFAULT 36,
+        updated_at              TEXT
+      );
+      INSERT OR IGNORE INTO settings (id) VALUES (1);
+      "#,
+    )
+    .map_err(PosError::from)
+}
+
+/// v6: olay kuyruğu (outbox) ve webhook teslimatları
+fn migration_006_event_outbox(conn: &Connection) -> Result<(), PosError> {
+  conn
+    .execute_batch(
+      r#"
+      CREATE TABLE IF NOT EXISTS webhooks (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        url         TEXT NOT NULL UNIQUE,
+        label       TEXT,
+        is_active   INTEGER NOT NULL DEFAULT 1,
+        created_at  TEXT DEFAULT (datetime('now','localtime'))
+      );
+
+      CREATE TABLE IF NOT EXISTS event_outbox (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        event_type  TEXT NOT NULL,       -- 'sale.created' | 'return.created' | 'exchange.created' | 'transfer.created'
+        payload     TEXT NOT NULL,       -- JSON
+        created_at  TEXT NOT NULL DEFAULT (datetime('now','localtime'))
+      );
+
+      -- olay x aktif webhook; olayla aynı transaction'da yazılır
+      CREATE TABLE IF NOT EXISTS webhook_deliveries (
+        id               INTEGER PRIMARY KEY AUTOINCREMENT,
+        event_id         INTEGER NOT NULL REFERENCES event_outbox(id) ON DELETE CASCADE,
+        webhook_id       INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+        status           TEXT NOT NULL DEFAULT 'PENDING'
+                         CHECK (status IN ('PENDING','DELIVERED','FAILED')),
+        attempts         INTEGER NOT NULL DEFAULT 0,
+        next_attempt_at  TEXT NOT NULL DEFAULT (datetime('now','localtime')),
+        last_error       TEXT,
+        delivered_at     TEXT,
+        UNIQUE (event_id, webhook_id)
+      );
+
+      CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+      CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id);
+      "#,
+    )
+    .map_err(PosError::from)
+}
+
+/// v7: düşük stok olayı eşiği (Analiz ekranındaki varsayılanla aynı: 2)
+fn migration_007_low_stock_threshold(conn: &Connection) -> Result<(), PosError> {
+  ensure_column(conn, "settings", "low_stock_threshold", "INTEGER NOT NULL DEFAULT 2")
+}
+
+/// Satışın geldiği kanal (SaleChannel); önceki satışların hepsi kasadan
+fn migration_008_sale_channel(conn: &Connection) -> Result<(), PosError> {
+  ensure_column(conn, "sales", "channel", "TEXT NOT NULL DEFAULT 'POS'")
+}
+
+/// v9: ek (alias) barkodlar da aranır; terim sözlüğü (fts5vocab) yazım hatası aramasına. FTS5 tablosuna kolon eklenemediği için tablo
+/// aliases kolonuyla yeniden kurulur; ek barkod eklenince / silinince ürün satırı yenilenir.
+fn migration_009_fts_alias_barcodes(conn: &Connection) -> Result<(), PosError> {
+  let reindex = |rowid: &str| {
+    format!(
+      r#"
+        DELETE FROM products_fts WHERE rowid = {rowid};
+        INSERT INTO products_fts(rowid, barcode, name, product_code, category, color, size, aliases)
+        SELECT rowid, {cols}, {aliases} FROM products WHERE rowid = {rowid};
+      "#,
+      rowid = rowid,
+      cols = fts_cols(""),
+      aliases = FTS_ALIASES_SQL
+    )
+  };
+  let product_of = |side: &str| format!("(SELECT rowid FROM products WHERE barcode = {}.product_barcode)", side);
+
+  conn
+    .execute_batch(&format!(
+      r#"
+      DROP TRIGGER IF EXISTS trg_products_fts_insert;
+      DROP TRIGGER IF EXISTS trg_products_fts_update;
+      DROP TRIGGER IF EXISTS trg_products_fts_delete;
+      DROP TABLE IF EXISTS products_fts_vocab;
+      DROP TABLE IF EXISTS products_fts;
+
+      CREATE VIRTUAL TABLE products_fts USING fts5(
+        barcode, name, product_code, category, color, size, aliases,
+        tokenize = "unicode61 remove_diacritics 2"
+      );
+      -- yazım hatalı aramada yakın terimler buradan bulunur
+      CREATE VIRTUAL TABLE products_fts_vocab USING fts5vocab(products_fts, 'row');
+
+      CREATE TRIGGER trg_products_fts_insert
+      AFTER INSERT ON products
+      BEGIN {insert} END;
 
-      CREATE TABLE IF NOT EXISTS sizes (
-        id         INTEGER PRIMARY KEY AUTOINCREMENT,
-        name       TEXT NOT NULL UNIQUE,
-        sort_order INTEGER DEFAULT 0,
-        is_active  INTEGER DEFAULT 1,
-        created_at TEXT DEFAULT (datetime('now','localtime'))
-      );
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
+      CREATE TRIGGER trg_products_fts_update
+      AFTER UPDATE ON products
+      BEGIN
+        DELETE FROM products_fts WHERE rowid = OLD.rowid;
+        {update}
+      END;
 
-  ensure_column(conn, "products", "product_code", "TEXT")?;
-  ensure_column(conn, "products", "category", "TEXT")?;
-  ensure_column(conn, "products", "color", "TEXT")?;
-  ensure_column(conn, "products", "size", "TEXT")?;
-  ensure_column(conn, "products", "buy_price", "REAL DEFAULT 0")?;
-  ensure_column(conn, "products", "sell_price", "REAL NOT NULL DEFAULT 0")?;
-  ensure_column(conn, "products", "stock", "INTEGER DEFAULT 0")?;
-  ensure_column(conn, "products", "created_at", "TEXT DEFAULT (datetime('now','localtime'))")?;
-  ensure_column(conn, "products", "updated_at", "TEXT DEFAULT (datetime('now','localtime'))")?;
-  ensure_column(conn, "products", "is_active", "INTEGER DEFAULT 1")?;
-  ensure_column(conn, "products", "category_id", "INTEGER")?;
-  ensure_column(conn, "products", "color_id", "INTEGER")?;
-  ensure_column(conn, "products", "size_id", "INTEGER")?;
+      CREATE TRIGGER trg_products_fts_delete
+      AFTER DELETE ON products
+      BEGIN
+        DELETE FROM products_fts WHERE rowid = OLD.rowid;
+      END;
 
-  ensure_column(conn, "sales", "product_barcode", "TEXT")?;
-  ensure_column(conn, "sales", "qty", "INTEGER DEFAULT 0")?;
-  ensure_column(conn, "sales", "unit_price", "REAL DEFAULT 0")?;
-  ensure_column(conn, "sales", "total", "REAL DEFAULT 0")?;
-  ensure_column(conn, "sales", "sold_at", "TEXT DEFAULT (datetime('now','localtime'))")?;
-  ensure_column(conn, "sales", "payment_method", "TEXT NOT NULL DEFAULT 'CARD'")?;
-  ensure_column(conn, "sales", "note", "TEXT")?;
+      CREATE TRIGGER trg_product_barcodes_fts_insert
+      AFTER INSERT ON product_barcodes
+      BEGIN {alias_new} END;
 
-  ensure_column(conn, "sales", "sale_group_id", "TEXT")?;
-  ensure_column(conn, "sales", "sold_from", "TEXT")?;
-  ensure_column(conn, "sales", "list_price", "REAL DEFAULT 0")?;
-  ensure_column(conn, "sales", "discount_amount", "REAL DEFAULT 0")?;
-  ensure_column(conn, "sales", "voided", "INTEGER DEFAULT 0")?;
+      CREATE TRIGGER trg_product_barcodes_fts_delete
+      AFTER DELETE ON product_barcodes
+      BEGIN {alias_old} END;
 
-  ensure_column(conn, "products", "magaza_baslangic", "INTEGER DEFAULT 0")?;
-  ensure_column(conn, "products", "depo_baslangic", "INTEGER DEFAULT 0")?;
-  ensure_column(conn, "products", "magaza_stok", "INTEGER DEFAULT 0")?;
-  ensure_column(conn, "products", "depo_stok", "INTEGER DEFAULT 0")?;
+      CREATE TRIGGER trg_product_barcodes_fts_update
+      AFTER UPDATE ON product_barcodes
+      BEGIN {alias_old} {alias_new} END;
 
-  ensure_column(conn, "expenses", "period", "TEXT")?;
-  ensure_column(conn, "returns", "diff_payment_method", "TEXT")?;
-  // dictionary tables (soft delete)
-  ensure_column(conn, "categories", "is_active", "INTEGER DEFAULT 1")?;
-  ensure_column(conn, "colors", "is_active", "INTEGER DEFAULT 1")?;
-  ensure_column(conn, "sizes", "is_active", "INTEGER DEFAULT 1")?;
+      INSERT INTO products_fts(rowid, barcode, name, product_code, category, color, size, aliases)
+      SELECT rowid, {cols}, {aliases} FROM products;
+      "#,
+      insert = reindex("NEW.rowid"),
+      update = reindex("NEW.rowid"),
+      alias_new = reindex(&product_of("NEW")),
+      alias_old = reindex(&product_of("OLD")),
+      cols = fts_cols(""),
+      aliases = FTS_ALIASES_SQL
+    ))
+    .map_err(PosError::from)
+}
 
-  // sizes: legacy order_no -> new sort_order (both can live)
-  ensure_column(conn, "sizes", "sort_order", "INTEGER DEFAULT 0")?;
+/// v10: _meta'daki ayarlar tipli kolonlara taşınır. Barkod modu / ön eki ve dil mağazaya ait
+/// (settings); yedek politikası / parolası ve yerel API bu makineye ait (device_settings,
+/// geri yüklemede korunur). Okunamayan eski değer yerine varsayılan kalır.
+fn migration_010_typed_device_settings(conn: &Connection) -> Result<(), PosError> {
+  ensure_column(
+    conn,
+    "settings",
+    "barcode_mode",
+    "TEXT NOT NULL DEFAULT 'SEQUENTIAL' CHECK (barcode_mode IN ('SEQUENTIAL','EAN13'))",
+  )?;
+  ensure_column(conn, "settings", "ean13_prefix", "TEXT NOT NULL DEFAULT '200'")?;
+  ensure_column(
+    conn,
+    "settings",
+    "language",
+    "TEXT NOT NULL DEFAULT 'tr' CHECK (language IN ('tr','en'))",
+  )?;
 
-  ensure_returns_cascade_triggers(conn)?;
+  conn
+    .execute_batch(
+      r#"
+      CREATE TABLE IF NOT EXISTS device_settings (
+        id                       INTEGER PRIMARY KEY CHECK (id = 1),
+        backup_keep_daily        INTEGER NOT NULL DEFAULT 7,
+        backup_keep_weekly       INTEGER NOT NULL DEFAULT 4,
+        backup_keep_monthly      INTEGER NOT NULL DEFAULT 12,
+        backup_interval_minutes  INTEGER NOT NULL DEFAULT 60,
+        backup_passphrase        TEXT,
+        api_enabled              INTEGER NOT NULL DEFAULT 0,
+        api_lan                  INTEGER NOT NULL DEFAULT 0,
+        api_port                 INTEGER NOT NULL DEFAULT 8787,
+        api_token                TEXT,
+        updated_at               TEXT
+      );
+      INSERT OR IGNORE INTO device_settings (id) VALUES (1);
+      INSERT OR IGNORE INTO settings (id) VALUES (1);
+      "#,
+    )?;
 
+  let text = |key: &str| -> Result<Option<String>, PosError> {
+    Ok(meta_get(conn, key)?.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()))
+  };
+  let int = |key: &str| -> Result<Option<i64>, PosError> {
+    Ok(text(key)?.and_then(|v| v.parse::<i64>().ok()))
+  };
+  let flag = |key: &str| -> Result<Option<bool>, PosError> { Ok(text(key)?.map(|v| v == "1")) };
+
+  let mode = text("barcode_mode")?.filter(|m| m == "SEQUENTIAL" || m == "EAN13");
+  let lang = text("language")?.and_then(|v| i18n::Lang::parse(&v)).map(|l| l.code());
+  conn.execute(
+    "UPDATE settings SET
+       barcode_mode = COALESCE(?1, barcode_mode),
+       ean13_prefix = COALESCE(?2, ean13_prefix),
+       language     = COALESCE(?3, language)
+     WHERE id = 1",
+    params![mode, text("ean13_prefix")?, lang],
+  )?;
+
+  conn.execute(
+    "UPDATE device_settings SET
+       backup_keep_daily       = COALESCE(?1, backup_keep_daily),
+       backup_keep_weekly      = COALESCE(?2, backup_keep_weekly),
+       backup_keep_monthly     = COALESCE(?3, backup_keep_monthly),
+       backup_interval_minutes = COALESCE(?4, backup_interval_minutes),
+       backup_passphrase       = ?5,
+       api_enabled             = COALESCE(?6, api_enabled),
+       api_lan                 = COALESCE(?7, api_lan),
+       api_port                = COALESCE(?8, api_port),
+       api_token               = ?9
+     WHERE id = 1",
+    params![
+      int("backup.keep_daily")?,
+      int("backup.keep_weekly")?,
+      int("backup.keep_monthly")?,
+      int("backup.interval_minutes")?,
+      // parola olduğu gibi (kırpılmadan)
+      meta_get(conn, "backup.passphrase")?.filter(|p| !p.is_empty()),
+      flag("api.enabled")?,
+      flag("api.lan")?,
+      int("api.port")?,
+      text("api.token")?
+    ],
+  )?;
+
+  conn.execute(
+    "DELETE FROM _meta
+     WHERE key IN ('barcode_mode', 'ean13_prefix', 'language') OR key LIKE 'backup.%' OR key LIKE 'api.%'",
+    [],
+  )?;
   Ok(())
 }
 
-fn ensure_column(conn: &Connection, table: &str, col: &str, col_def: &str) -> Result<(), String> {
+fn column_exists(conn: &Connection, table: &str, col: &str) -> Result<bool, PosError> {
   let mut stmt = conn
-    .prepare(&format!("PRAGMA table_info({});", table))
-    .map_err(|e| e.to_string())?;
+    .prepare(&format!("PRAGMA table_info({});", table))?;
 
   let existing: Vec<String> = stmt
-    .query_map([], |row| row.get::<_, String>(1))
-    .map_err(|e| e.to_string())?
-    .collect::<Result<Vec<_>, _>>()
-    .map_err(|e| e.to_string())?;
+    .query_map([], |row| row.get::<_, String>(1))?
+    .collect::<Result<Vec<_>, _>>()?;
+
+  Ok(existing.iter().any(|c| c == col))
+}
 
-  if !existing.iter().any(|c| c == col) {
+fn ensure_column(conn: &Connection, table: &str, col: &str, col_def: &str) -> Result<(), PosError> {
+  if !column_exists(conn, table, col)? {
     conn
       .execute(
         &format!("ALTER TABLE {} ADD COLUMN {} {}", table, col, col_def),
         [],
-      )
-      .map_err(|e| e.to_string())?;
+      )?;
   }
 
   Ok(())
@@ -1977,7 +5177,7 @@ fn col_for_loc(loc: &str) -> &'static str {
 fn gen_group_id(prefix: &str) -> String {
   format!("{}{}", prefix, unique_ts_id())
 }
-fn seed_option_tables(conn: &Connection) -> Result<(), String> {
+fn seed_option_tables(conn: &Connection) -> Result<(), PosError> {
   // Bu fonksiyon sadece ilk migration/kurulum için: sözlük tabloları zaten doluysa
   // eski options/products değerleri yeni sözlüklere tekrar tekrar basılmasın.
   let cat_count: i64 = conn
@@ -2003,7 +5203,7 @@ fn seed_option_tables(conn: &Connection) -> Result<(), String> {
     WHERE COALESCE(is_active,1)=1 AND UPPER(kind)='CATEGORY'
     "#,
     [],
-  ).map_err(|e| e.to_string())?;
+  )?;
 
   // options -> colors
   conn.execute(
@@ -2013,7 +5213,7 @@ fn seed_option_tables(conn: &Connection) -> Result<(), String> {
     WHERE COALESCE(is_active,1)=1 AND UPPER(kind)='COLOR'
     "#,
     [],
-  ).map_err(|e| e.to_string())?;
+  )?;
 
   // options -> sizes (sort_order: options.sort)
   conn.execute(
@@ -2023,48 +5223,48 @@ fn seed_option_tables(conn: &Connection) -> Result<(), String> {
     WHERE COALESCE(is_active,1)=1 AND UPPER(kind)='SIZE'
     "#,
     [],
-  ).map_err(|e| e.to_string())?;
+  )?;
   conn.execute(r#"
   INSERT OR IGNORE INTO categories(name, is_active)
   SELECT DISTINCT TRIM(category), 1
   FROM products
   WHERE category IS NOT NULL AND TRIM(category) <> ''
-  "#, []).map_err(|e| e.to_string())?;
+  "#, [])?;
 
   conn.execute(r#"
   INSERT OR IGNORE INTO colors(name, is_active)
   SELECT DISTINCT TRIM(color), 1
   FROM products
   WHERE color IS NOT NULL AND TRIM(color) <> ''
-  "#, []).map_err(|e| e.to_string())?;
+  "#, [])?;
 
   conn.execute(r#"
   INSERT OR IGNORE INTO sizes(name, sort_order, is_active)
   SELECT DISTINCT TRIM(size), 0, 1
   FROM products
   WHERE size IS NOT NULL AND TRIM(size) <> ''
-  "#, []).map_err(|e| e.to_string())?;
+  "#, [])?;
 
   conn.execute(r#"
   UPDATE products
   SET category_id = (SELECT id FROM categories WHERE name = TRIM(products.category))
   WHERE (category_id IS NULL OR category_id = 0)
     AND category IS NOT NULL AND TRIM(category) <> ''
-  "#, []).map_err(|e| e.to_string())?;
+  "#, [])?;
 
   conn.execute(r#"
   UPDATE products
   SET color_id = (SELECT id FROM colors WHERE name = TRIM(products.color))
   WHERE (color_id IS NULL OR color_id = 0)
     AND color IS NOT NULL AND TRIM(color) <> ''
-  "#, []).map_err(|e| e.to_string())?;
+  "#, [])?;
 
   conn.execute(r#"
   UPDATE products
   SET size_id = (SELECT id FROM sizes WHERE name = TRIM(products.size))
   WHERE (size_id IS NULL OR size_id = 0)
     AND size IS NOT NULL AND TRIM(size) <> ''
-  "#, []).map_err(|e| e.to_string())?;
+  "#, [])?;
 
   // backfill legacy order_no if column exists (ignore errors)
   let _ = conn.execute(
@@ -2084,8 +5284,11 @@ pub struct CreateSaleItemPayload {
   pub barcode: String,
   pub qty: i64,
   pub list_price: f64,
+  #[serde(default)]
   pub discount_amount: f64,
   pub unit_price: f64,
+  // boş: sold_from_default, o da boşsa ayarlardaki varsayılan lokasyon
+  #[serde(default)]
   pub sold_from: String,
 }
 
@@ -2146,333 +5349,328 @@ pub struct CashReportRow {
   pub net_total: f64,         
 }
 
+impl ReportTotals for Vec<CashReportRow> {
+  fn totals(&self) -> Vec<(&'static str, f64)> {
+    let sum = |f: fn(&CashReportRow) -> f64| self.iter().map(f).sum::<f64>();
+    vec![
+      ("cash_sales", sum(|r| r.cash_sales)),
+      ("card_sales", sum(|r| r.card_sales)),
+      ("cash_refunds", sum(|r| r.cash_refunds)),
+      ("card_refunds", sum(|r| r.card_refunds)),
+      ("cash_net", sum(|r| r.cash_net)),
+      ("card_net", sum(|r| r.card_net)),
+      ("net_total", sum(|r| r.net_total)),
+    ]
+  }
+}
+
 fn pm_bucket(pm: &str) -> &'static str {
   let t = pm.trim().to_uppercase();
   if t == "CASH" || t == "NAKIT" || t == "NAKİT" { "CASH" } else { "CARD" }
 }
 
-// Günlük kasa raporu
-pub fn get_cash_report(days: i64) -> Result<Vec<CashReportRow>, String> {
-  let conn = get_conn()?;
-
-  use std::collections::BTreeMap;
-  let mut map: BTreeMap<String, CashReportRow> = BTreeMap::new();
-
-  // 1) SATIŞLAR 
-  let mut stmt_sales = conn
-  .prepare(
-    r#"
-    SELECT
-      date(sold_at) AS d,
-      COALESCE(payment_method,'CARD') AS pm,
-      SUM(total) AS sum_total
-    FROM sales
-    WHERE (
-      COALESCE(voided,0)=0
-      OR EXISTS (
-        SELECT 1
-        FROM return_items ri
-        JOIN returns r ON r.return_group_id = ri.return_group_id
-        WHERE r.mode = 'EXCHANGE'
-          AND ri.ref_sale_id = sales.id
-      )
-    )
-    AND date(sold_at) >= date('now','localtime', printf('-%d day', ?1))
-    GROUP BY d, pm
-    ORDER BY d ASC
-    "#,
-  )
-  .map_err(|e| e.to_string())?;
-
-  let sales_rows = stmt_sales
-    .query_map(params![days], |r| {
-      let d: String = r.get(0)?;
-      let pm: String = r.get(1)?;
-      let sum_total: f64 = r.get(2)?;
-      Ok((d, pm, sum_total))
-    })
-    .map_err(|e| e.to_string())?;
-
-  for row in sales_rows {
-    let (d, pm, sum_total) = row.map_err(|e| e.to_string())?;
-    let entry = map.entry(d.clone()).or_insert(CashReportRow {
-      day: d.clone(),
-      cash_sales: 0.0,
-      card_sales: 0.0,
-      cash_refunds: 0.0,
-      card_refunds: 0.0, 
-      cash_net: 0.0,
-      card_net: 0.0,
-      net_total: 0.0,
-    });
-
-    match pm_bucket(&pm) {
-      "CASH" => entry.cash_sales += sum_total,
-      _ => entry.card_sales += sum_total,
-    }
-  }
-
-  // 2) İADELER (REFUND) 
-  let mut stmt_refund = conn
-    .prepare(
-      r#"
-      SELECT
-        date(created_at) AS d,
-        SUM(COALESCE(returned_total,0)) AS sum_total
-      FROM returns
-      WHERE mode='REFUND'
-        AND date(created_at) >= date('now','localtime', printf('-%d day', ?1))
-      GROUP BY d
-      ORDER BY d ASC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
-
-  let refund_rows = stmt_refund
-    .query_map(params![days], |r| {
-      let d: String = r.get(0)?;
-      let sum_total: f64 = r.get(1)?;
-      Ok((d, sum_total))
-    })
-    .map_err(|e| e.to_string())?;
-
-  for row in refund_rows {
-    let (d, sum_total) = row.map_err(|e| e.to_string())?;
-    let entry = map.entry(d.clone()).or_insert(CashReportRow {
-      day: d.clone(),
-      cash_sales: 0.0,
-      card_sales: 0.0,
-      cash_refunds: 0.0,
-      card_refunds: 0.0,
-      cash_net: 0.0,
-      card_net: 0.0,
-      net_total: 0.0,
-    });
+impl Store {
+  // Günlük kasa raporu
+  pub fn get_cash_report(&self, period: &Period) -> Result<Vec<CashReportRow>, PosError> {
+    let conn = self.read()?;
+    let (from, to) = (period.first_day(), period.last_day());
 
-    entry.cash_refunds += sum_total; 
-  }
+    use std::collections::BTreeMap;
+    let mut map: BTreeMap<String, CashReportRow> = BTreeMap::new();
 
-  // 3) DEĞİŞİM FARKI 
-  let mut stmt_ex_pos = conn
+    // 1) SATIŞLAR 
+    let mut stmt_sales = conn
     .prepare(
       r#"
       SELECT
-        date(created_at) AS d,
-        COALESCE(diff_payment_method,'CARD') AS pm,
-        SUM(diff) AS sum_diff
-      FROM returns
-      WHERE mode='EXCHANGE'
-        AND diff > 0
-        AND date(created_at) >= date('now','localtime', printf('-%d day', ?1))
+        date(sold_at) AS d,
+        COALESCE(payment_method,'CARD') AS pm,
+        SUM(total) AS sum_total
+      FROM sales
+      WHERE (
+        COALESCE(voided,0)=0
+        OR EXISTS (
+          SELECT 1
+          FROM return_items ri
+          JOIN returns r ON r.return_group_id = ri.return_group_id
+          WHERE r.mode = 'EXCHANGE'
+            AND ri.ref_sale_id = sales.id
+        )
+      )
+      AND date(sold_at) BETWEEN ?1 AND ?2
       GROUP BY d, pm
       ORDER BY d ASC
       "#,
-    )
-    .map_err(|e| e.to_string())?;
-
-  let ex_pos_rows = stmt_ex_pos
-    .query_map(params![days], |r| {
-      let d: String = r.get(0)?;
-      let pm: String = r.get(1)?;
-      let sum_diff: f64 = r.get(2)?;
-      Ok((d, pm, sum_diff))
-    })
-    .map_err(|e| e.to_string())?;
-
-  for row in ex_pos_rows {
-    let (d, pm, sum_diff) = row.map_err(|e| e.to_string())?;
-    let entry = map.entry(d.clone()).or_insert(CashReportRow {
-      day: d.clone(),
-      cash_sales: 0.0,
-      card_sales: 0.0,
-      cash_refunds: 0.0,
-      card_refunds: 0.0,
-      cash_net: 0.0,
-      card_net: 0.0,
-      net_total: 0.0,
-    });
+    )?;
+
+    let sales_rows = stmt_sales
+      .query_map(params![from, to], |r| {
+        let d: String = r.get(0)?;
+        let pm: String = r.get(1)?;
+        let sum_total: f64 = r.get(2)?;
+        Ok((d, pm, sum_total))
+      })?;
+
+    for row in sales_rows {
+      let (d, pm, sum_total) = row?;
+      let entry = map.entry(d.clone()).or_insert(CashReportRow {
+        day: d.clone(),
+        cash_sales: 0.0,
+        card_sales: 0.0,
+        cash_refunds: 0.0,
+        card_refunds: 0.0, 
+        cash_net: 0.0,
+        card_net: 0.0,
+        net_total: 0.0,
+      });
+
+      match pm_bucket(&pm) {
+        "CASH" => entry.cash_sales += sum_total,
+        _ => entry.card_sales += sum_total,
+      }
+    }
 
-    match pm_bucket(&pm) {
-      "CASH" => entry.cash_sales += sum_diff, 
-      _ => entry.card_sales += sum_diff,
+    // 2) İADELER (REFUND) 
+    let mut stmt_refund = conn
+      .prepare(
+        r#"
+        SELECT
+          date(created_at) AS d,
+          SUM(COALESCE(returned_total,0)) AS sum_total
+        FROM returns
+        WHERE mode='REFUND'
+          AND date(created_at) BETWEEN ?1 AND ?2
+        GROUP BY d
+        ORDER BY d ASC
+        "#,
+      )?;
+
+    let refund_rows = stmt_refund
+      .query_map(params![from, to], |r| {
+        let d: String = r.get(0)?;
+        let sum_total: f64 = r.get(1)?;
+        Ok((d, sum_total))
+      })?;
+
+    for row in refund_rows {
+      let (d, sum_total) = row?;
+      let entry = map.entry(d.clone()).or_insert(CashReportRow {
+        day: d.clone(),
+        cash_sales: 0.0,
+        card_sales: 0.0,
+        cash_refunds: 0.0,
+        card_refunds: 0.0,
+        cash_net: 0.0,
+        card_net: 0.0,
+        net_total: 0.0,
+      });
+
+      entry.cash_refunds += sum_total; 
     }
-  }
 
-  // 4) DEĞİŞİM FARKI 
-  let mut stmt_ex_neg = conn
-    .prepare(
-      r#"
-      SELECT
-        date(created_at) AS d,
-        SUM(-diff) AS sum_out
-      FROM returns
-      WHERE mode='EXCHANGE'
-        AND diff < 0
-        AND date(created_at) >= date('now','localtime', printf('-%d day', ?1))
-      GROUP BY d
-      ORDER BY d ASC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
+    // 3) DEĞİŞİM FARKI 
+    let mut stmt_ex_pos = conn
+      .prepare(
+        r#"
+        SELECT
+          date(created_at) AS d,
+          COALESCE(diff_payment_method,'CARD') AS pm,
+          SUM(diff) AS sum_diff
+        FROM returns
+        WHERE mode='EXCHANGE'
+          AND diff > 0
+          AND date(created_at) BETWEEN ?1 AND ?2
+        GROUP BY d, pm
+        ORDER BY d ASC
+        "#,
+      )?;
+
+    let ex_pos_rows = stmt_ex_pos
+      .query_map(params![from, to], |r| {
+        let d: String = r.get(0)?;
+        let pm: String = r.get(1)?;
+        let sum_diff: f64 = r.get(2)?;
+        Ok((d, pm, sum_diff))
+      })?;
+
+    for row in ex_pos_rows {
+      let (d, pm, sum_diff) = row?;
+      let entry = map.entry(d.clone()).or_insert(CashReportRow {
+        day: d.clone(),
+        cash_sales: 0.0,
+        card_sales: 0.0,
+        cash_refunds: 0.0,
+        card_refunds: 0.0,
+        cash_net: 0.0,
+        card_net: 0.0,
+        net_total: 0.0,
+      });
+
+      match pm_bucket(&pm) {
+        "CASH" => entry.cash_sales += sum_diff, 
+        _ => entry.card_sales += sum_diff,
+      }
+    }
 
-  let ex_neg_rows = stmt_ex_neg
-    .query_map(params![days], |r| {
-      let d: String = r.get(0)?;
-      let sum_out: f64 = r.get(1)?;
-      Ok((d, sum_out))
-    })
-    .map_err(|e| e.to_string())?;
-
-  for row in ex_neg_rows {
-    let (d, sum_out) = row.map_err(|e| e.to_string())?;
-    let entry = map.entry(d.clone()).or_insert(CashReportRow {
-      day: d.clone(),
-      cash_sales: 0.0,
-      card_sales: 0.0,
-      cash_refunds: 0.0,
-      card_refunds: 0.0,
-      cash_net: 0.0,
-      card_net: 0.0,
-      net_total: 0.0,
-    });
+    // 4) DEĞİŞİM FARKI 
+    let mut stmt_ex_neg = conn
+      .prepare(
+        r#"
+        SELECT
+          date(created_at) AS d,
+          SUM(-diff) AS sum_out
+        FROM returns
+        WHERE mode='EXCHANGE'
+          AND diff < 0
+          AND date(created_at) BETWEEN ?1 AND ?2
+        GROUP BY d
+        ORDER BY d ASC
+        "#,
+      )?;
+
+    let ex_neg_rows = stmt_ex_neg
+      .query_map(params![from, to], |r| {
+        let d: String = r.get(0)?;
+        let sum_out: f64 = r.get(1)?;
+        Ok((d, sum_out))
+      })?;
+
+    for row in ex_neg_rows {
+      let (d, sum_out) = row?;
+      let entry = map.entry(d.clone()).or_insert(CashReportRow {
+        day: d.clone(),
+        cash_sales: 0.0,
+        card_sales: 0.0,
+        cash_refunds: 0.0,
+        card_refunds: 0.0,
+        cash_net: 0.0,
+        card_net: 0.0,
+        net_total: 0.0,
+      });
+
+      entry.cash_refunds += sum_out; 
+    }
 
-    entry.cash_refunds += sum_out; 
-  }
+    // 5) net hesapla 
+    for (_, v) in map.iter_mut() {
+      v.cash_net = v.cash_sales - v.cash_refunds;
+      v.card_net = v.card_sales - v.card_refunds;
+      v.net_total = v.cash_net + v.card_net;
+    }
 
-  // 5) net hesapla 
-  for (_, v) in map.iter_mut() {
-    v.cash_net = v.cash_sales - v.cash_refunds;
-    v.card_net = v.card_sales - v.card_refunds;
-    v.net_total = v.cash_net + v.card_net;
+    Ok(map.into_values().collect())
   }
-
-  Ok(map.into_values().collect())
 }
-/*
-fn like(s: &str) -> String {
-  format!("%{}%", s.trim())
-}*/
-pub fn list_sale_groups(days: i64, q: Option<String>) -> Result<Vec<SaleGroupRow>, String> {
-  let conn = get_conn()?;
-  let days = days.clamp(1, 365);
-
-  let q_like: Option<String> = q
-    .and_then(|s| {
-      let t = s.trim().to_string();
-      if t.is_empty() { None } else { Some(t) }
-    })
-    .map(|t| format!("%{}%", t));
-
-  let mut out: Vec<SaleGroupRow> = Vec::new();
-
-  // NORMAL SATIŞ FİŞLERİ
-  let mut stmt_sales = conn
-    .prepare(
-      r#"
+impl Store {
+  pub fn list_sale_groups(&self, query: &ListQuery) -> Result<Page<SaleGroupRow>, PosError> {
+    let conn = self.read()?;
+    let range = query.dates()?;
+    let q_like = query.q_like();
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(
+      &[("sold_at", "sold_at"), ("total", "total"), ("qty", "qty"), ("kind", "kind")],
+      true,
+    )?;
+
+    // Normal satış fişleri + değişim fişleri tek listede.
+    // ?1/?2 tarih aralığı (gün bazında, dahil), ?3 barkod / ürün adı araması
+    let groups_sql = r#"
       SELECT
-        s.sale_group_id,
-        MAX(s.sold_at) as sold_at,
-        SUM(s.qty) as qty,
-        SUM(s.total) as total,
-        MAX(COALESCE(s.payment_method,'CARD')) as payment_method
+        s.sale_group_id AS sale_group_id,
+        MAX(s.sold_at) AS sold_at,
+        SUM(s.qty) AS qty,
+        SUM(s.total) AS total,
+        MAX(COALESCE(s.payment_method,'CARD')) AS payment_method,
+        'SALE' AS kind
       FROM sales s
       WHERE COALESCE(s.voided,0)=0
         AND s.sale_group_id IS NOT NULL
-        AND s.sold_at >= datetime('now','localtime', printf('-%d day', ?1))
-        AND (?2 IS NULL OR EXISTS (
+        AND (?1 IS NULL OR date(s.sold_at) >= ?1)
+        AND (?2 IS NULL OR date(s.sold_at) <= ?2)
+        AND (?3 IS NULL OR EXISTS (
           SELECT 1
           FROM sales s2
           LEFT JOIN products p2 ON p2.barcode = s2.product_barcode
           WHERE s2.sale_group_id = s.sale_group_id
             AND (
-              TRIM(s2.product_barcode) LIKE TRIM(?2)
-              OR COALESCE(p2.name,'') LIKE ?2
+              TRIM(s2.product_barcode) LIKE TRIM(?3)
+              OR COALESCE(p2.name,'') LIKE ?3
             )
         ))
       GROUP BY s.sale_group_id
-      ORDER BY MAX(s.sold_at) DESC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
-
-  let rows = stmt_sales
-    .query_map(rusqlite::params![days, q_like], |r| {
-      Ok(SaleGroupRow {
-        sale_group_id: r.get(0)?,
-        sold_at: r.get(1)?,
-        qty: r.get(2)?,
-        total: r.get(3)?,
-        payment_method: r.get(4)?,
-        kind: "SALE".to_string(),
-      })
-    })
-    .map_err(|e| e.to_string())?;
 
-  for r in rows {
-    out.push(r.map_err(|e| e.to_string())?);
-  }
+      UNION ALL
 
-  // DEĞİŞİM FİŞLERİ
-  let mut stmt_exchange = conn
-    .prepare(
-      r#"
       SELECT
-        r.return_group_id,
-        r.created_at,
-        SUM(ei.qty) as qty,
-        SUM(ei.total) as total,
-        COALESCE(r.diff_payment_method,'CARD') as payment_method
+        r.return_group_id AS sale_group_id,
+        r.created_at AS sold_at,
+        SUM(ei.qty) AS qty,
+        SUM(ei.total) AS total,
+        COALESCE(r.diff_payment_method,'CARD') AS payment_method,
+        'EXCHANGE' AS kind
       FROM returns r
       JOIN exchange_items ei ON ei.exchange_group_id = r.return_group_id
       WHERE r.mode='EXCHANGE'
-        AND r.created_at >= datetime('now','localtime', printf('-%d day', ?1))
-        AND (?2 IS NULL OR EXISTS (
+        AND (?1 IS NULL OR date(r.created_at) >= ?1)
+        AND (?2 IS NULL OR date(r.created_at) <= ?2)
+        AND (?3 IS NULL OR EXISTS (
           SELECT 1
           FROM exchange_items ei2
           LEFT JOIN products p2 ON p2.barcode = ei2.product_barcode
           WHERE ei2.exchange_group_id = r.return_group_id
             AND (
-              TRIM(ei2.product_barcode) LIKE TRIM(?2)
-              OR COALESCE(p2.name,'') LIKE ?2
+              TRIM(ei2.product_barcode) LIKE TRIM(?3)
+              OR COALESCE(p2.name,'') LIKE ?3
             )
         ))
       GROUP BY r.return_group_id
-      ORDER BY r.created_at DESC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
-
-  let rows = stmt_exchange
-    .query_map(rusqlite::params![days, q_like], |r| {
-      Ok(SaleGroupRow {
-        sale_group_id: r.get(0)?,
-        sold_at: r.get(1)?,
-        qty: r.get(2)?,
-        total: r.get(3)?,
-        payment_method: r.get(4)?,
-        kind: "EXCHANGE".to_string(),
-      })
-    })
-    .map_err(|e| e.to_string())?;
-
-  for r in rows {
-    out.push(r.map_err(|e| e.to_string())?);
-  }
+    "#;
 
-  // Tarihe göre tek listede sırala
-  out.sort_by(|a, b| b.sold_at.cmp(&a.sold_at));
+    let total: i64 = conn
+      .query_row(
+        &format!("SELECT COUNT(*) FROM ({})", groups_sql),
+        params![range.from, range.to, q_like],
+        |r| r.get(0),
+      )?;
 
-  Ok(out)
-}
-pub fn list_sales_by_group(sale_group_id: &str) -> Result<Vec<SaleLineRow>, String> {
-  let conn = get_conn()?;
+    let mut stmt = conn
+      .prepare(&format!(
+        r#"
+        SELECT sale_group_id, sold_at, qty, total, payment_method, kind
+        FROM ({groups})
+        ORDER BY {order}, sold_at DESC, sale_group_id DESC
+        LIMIT ?4 OFFSET ?5
+        "#,
+        groups = groups_sql,
+        order = order
+      ))?;
+
+    let items = stmt
+      .query_map(params![range.from, range.to, q_like, limit, offset], |r| {
+        Ok(SaleGroupRow {
+          sale_group_id: r.get(0)?,
+          sold_at: r.get(1)?,
+          qty: r.get(2)?,
+          total: r.get(3)?,
+          payment_method: r.get(4)?,
+          kind: r.get(5)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
 
-  // Normal satış fişi 
-  if sale_group_id.starts_with('S') {
-    let mut st = conn
-      .prepare(
+    Ok(query.page_of(items, total))
+  }
+  /// Fiş satırları satır sırasıyla gelir; sayfalama ve q (barkod / ad) desteklenir.
+  pub fn list_sales_by_group(&self, sale_group_id: &str, query: &ListQuery) -> Result<Page<SaleLineRow>, PosError> {
+    let conn = self.read()?;
+    let q_like = query.q_like();
+    let (limit, offset) = query.limit_offset();
+
+    // S… normal satış fişi, E… değişim fişi; ikisi de aynı kolonlara eşlenir.
+    // ?1 fiş no, ?2 barkod / ürün adı araması
+    let (cols, source, order) = if sale_group_id.starts_with('S') {
+      (
         r#"
-        SELECT
           s.id,
           s.sale_group_id,
           s.product_barcode,
@@ -2500,49 +5698,19 @@ pub fn list_sales_by_group(sale_group_id: &str) -> Result<Vec<SaleLineRow>, Stri
             FROM return_items ri
             WHERE ri.ref_sale_id = s.id
           ) AS refund_kind
-        FROM sales s
-        LEFT JOIN products p ON p.barcode = s.product_barcode
-        WHERE s.sale_group_id = ?1
-          AND COALESCE(s.voided,0)=0
-        ORDER BY s.id ASC
         "#,
+        r#"
+          sales s
+          LEFT JOIN products p ON p.barcode = s.product_barcode
+          WHERE s.sale_group_id = ?1
+            AND COALESCE(s.voided,0)=0
+            AND (?2 IS NULL OR s.product_barcode LIKE ?2 OR COALESCE(p.name,'') LIKE ?2)
+        "#,
+        "s.id",
       )
-      .map_err(|e| e.to_string())?;
-
-    let rows = st
-      .query_map(rusqlite::params![sale_group_id], |r| {
-        Ok(SaleLineRow {
-          id: r.get(0)?,
-          sale_group_id: r.get(1)?,
-          product_barcode: r.get(2)?,
-          name: r.get(3)?,
-          qty: r.get(4)?,
-          list_price: r.get(5)?,
-          discount_amount: r.get(6)?,
-          unit_price: r.get(7)?,
-          total: r.get(8)?,
-          sold_at: r.get(9)?,
-          sold_from: r.get(10)?,
-          payment_method: r.get(11)?,
-          refunded_qty: r.get::<_, Option<i64>>(12)?.unwrap_or(0),
-          refund_kind: r.get::<_, Option<String>>(13)?,
-        })
-      })
-      .map_err(|e| e.to_string())?;
-
-    let mut out = Vec::new();
-    for x in rows {
-      out.push(x.map_err(|e| e.to_string())?);
-    }
-    return Ok(out);
-  }
-
-  // Değişim fişi 
-  if sale_group_id.starts_with('E') {
-    let mut st = conn
-      .prepare(
+    } else if sale_group_id.starts_with('E') {
+      (
         r#"
-        SELECT
           ei.id,
           ei.exchange_group_id AS sale_group_id,
           ei.product_barcode,
@@ -2554,18 +5722,35 @@ pub fn list_sales_by_group(sale_group_id: &str) -> Result<Vec<SaleLineRow>, Stri
           ei.total,
           r.created_at AS sold_at,
           COALESCE(ei.sold_from,'MAGAZA') AS sold_from,
-          COALESCE(r.diff_payment_method,'CARD') AS payment_method
-        FROM exchange_items ei
-        JOIN returns r ON r.return_group_id = ei.exchange_group_id
-        LEFT JOIN products p ON p.barcode = ei.product_barcode
-        WHERE ei.exchange_group_id = ?1
-        ORDER BY ei.id ASC
+          COALESCE(r.diff_payment_method,'CARD') AS payment_method,
+          0 AS refunded_qty,
+          'EXCHANGE' AS refund_kind
+        "#,
+        r#"
+          exchange_items ei
+          JOIN returns r ON r.return_group_id = ei.exchange_group_id
+          LEFT JOIN products p ON p.barcode = ei.product_barcode
+          WHERE ei.exchange_group_id = ?1
+            AND (?2 IS NULL OR ei.product_barcode LIKE ?2 OR COALESCE(p.name,'') LIKE ?2)
         "#,
+        "ei.id",
       )
-      .map_err(|e| e.to_string())?;
+    } else {
+      return Ok(query.page_of(Vec::new(), 0));
+    };
 
-    let rows = st
-      .query_map(rusqlite::params![sale_group_id], |r| {
+    let total: i64 = conn.query_row(
+      &format!("SELECT COUNT(*) FROM {}", source),
+      params![sale_group_id, q_like],
+      |r| r.get(0),
+    )?;
+
+    let mut st = conn.prepare(&format!(
+      "SELECT {} FROM {} ORDER BY {} ASC LIMIT ?3 OFFSET ?4",
+      cols, source, order
+    ))?;
+    let items = st
+      .query_map(params![sale_group_id, q_like, limit, offset], |r| {
         Ok(SaleLineRow {
           id: r.get(0)?,
           sale_group_id: r.get(1)?,
@@ -2579,227 +5764,269 @@ pub fn list_sales_by_group(sale_group_id: &str) -> Result<Vec<SaleLineRow>, Stri
           sold_at: r.get(9)?,
           sold_from: r.get(10)?,
           payment_method: r.get(11)?,
-          refunded_qty: 0,
-          refund_kind: Some("EXCHANGE".to_string()),
+          refunded_qty: r.get::<_, Option<i64>>(12)?.unwrap_or(0),
+          refund_kind: r.get::<_, Option<String>>(13)?,
         })
-      })
-      .map_err(|e| e.to_string())?;
-
-    let mut out = Vec::new();
-    for x in rows {
-      out.push(x.map_err(|e| e.to_string())?);
-    }
-    return Ok(out);
-  }
-
-  Ok(vec![])
-}
-//satışlar bitti
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
 
-pub fn create_sale(payload: CreateSalePayload) -> Result<CreateSaleResult, String> {
-  if payload.items.is_empty() {
-    return Err("Sepet boş".to_string());
+    Ok(query.page_of(items, total))
   }
+  //satışlar bitti
 
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-  let sale_group_id = format!("S{}", chrono_like_id());
-
-  let pm_raw = payload.payment_method.trim();
-  let pm_up = if pm_raw.is_empty() { "CARD" } else { pm_raw }.to_uppercase();
-
-  let pm = match pm_up.as_str() {
-    "CARD" | "KART" => "CARD",
-    "CASH" | "NAKIT" | "NAKİT" => "CASH",
-    "TRANSFER" | "HAVALE" | "EFT" => "TRANSFER",
-    _ => "CARD",
-  };
-
-  use std::collections::HashMap;
-
-  let mut need: HashMap<(String, String), i64> = HashMap::new();
-  for it in &payload.items {
-    let qty = if it.qty <= 0 { 1 } else { it.qty };
-    let sold_from = if it.sold_from.trim().is_empty() {
-      let d = payload.sold_from_default.trim();
-      if d.is_empty() { "MAGAZA".to_string() } else { payload.sold_from_default.clone() }
-    } else {
-      it.sold_from.clone()
-    };
-
-    *need.entry((it.barcode.clone(), sold_from)).or_insert(0) += qty;
+  /// Kasadan satış (Tauri komutu)
+  pub fn create_sale(&self, payload: CreateSalePayload) -> Result<CreateSaleResult, PosError> {
+    self.create_sale_via(payload, SaleChannel::Pos)
   }
 
-  for ((bc, sold_from), q) in need.iter() {
-    let col = col_for_loc(sold_from);
-    let sql = format!("SELECT COALESCE({}, 0) FROM products WHERE barcode = ?1", col);
-
-    let loc_stock: i64 = tx
-      .query_row(&sql, params![bc], |r| r.get(0))
-      .optional()
-      .map_err(|e| e.to_string())?
-      .ok_or_else(|| format!("Ürün bulunamadı: {}", bc))?;
-
-    if loc_stock < *q {
-      return Err(format!(
-        "Yetersiz stok: {} ({} stok: {}, istenen: {})",
-        bc, sold_from, loc_stock, q
-      ));
+  pub fn create_sale_via(&self, mut payload: CreateSalePayload, channel: SaleChannel) -> Result<CreateSaleResult, PosError> {
+    if payload.items.is_empty() {
+      return Err(PosError::Validation(tr!("Sepet boş")));
     }
-  }
-
-  let mut total: f64 = 0.0;
-  let mut lines: i64 = 0;
 
-  for it in payload.items {
-    let qty = if it.qty <= 0 { 1 } else { it.qty };
-    let list_price = if it.list_price.is_finite() { it.list_price } else { 0.0 };
-    let discount_amount = if it.discount_amount.is_finite() { it.discount_amount } else { 0.0 };
-    let unit_price = if it.unit_price.is_finite() { it.unit_price } else { 0.0 };
+    // arayüzden / HTTP API'den gelen değerler kırpılır
+    payload.sold_from_default = payload.sold_from_default.trim().to_string();
+    for it in payload.items.iter_mut() {
+      it.barcode = it.barcode.trim().to_string();
+      it.sold_from = it.sold_from.trim().to_string();
+    }
 
-    let sold_from = if it.sold_from.trim().is_empty() {
-      let d = payload.sold_from_default.trim();
-      if d.is_empty() { "MAGAZA".to_string() } else { payload.sold_from_default.clone() }
-    } else {
-      it.sold_from.clone()
-    };
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
 
-    let col = col_for_loc(&sold_from);
-    tx.execute(
-      &format!(
-        "UPDATE products SET {c} = COALESCE({c},0) - ?1, stock = COALESCE(stock,0) - ?1 WHERE barcode = ?2",
-        c = col
-      ),
-      params![qty, &it.barcode],
-    )
-    .map_err(|e| e.to_string())?;
+    // alias barkodlar ana barkoda çevrilir; satış kaydı ve raporlar ana barkodla
+    for it in payload.items.iter_mut() {
+      if let Some(bc) = resolve_barcode(&tx, &it.barcode)? {
+        it.barcode = bc;
+      }
+    }
 
-    let line_total = unit_price * qty as f64;
-    total += line_total;
-    lines += 1;
+    let sale_group_id = format!("S{}", chrono_like_id());
 
-    tx.execute(
-      r#"
-      INSERT INTO sales (
-        product_barcode, qty, unit_price, total, note,
-        sale_group_id, sold_from, list_price, discount_amount, voided,
-        payment_method, sold_at
-      ) VALUES (
-        ?1, ?2, ?3, ?4, NULL,
-        ?5, ?6, ?7, ?8, 0,
-        ?9, datetime('now','localtime')
-      )
-      "#,
-      params![
-        &it.barcode,
-        qty,
-        unit_price,
-        line_total,
-        &sale_group_id,
-        &sold_from,
-        list_price,
-        discount_amount,
-        pm
-      ],
-    )
-    .map_err(|e| e.to_string())?;
-  }
+    let settings = read_settings(&tx)?;
 
-  tx.commit().map_err(|e| e.to_string())?;
+    let pm = sale_payment_method(&payload.payment_method, &settings);
 
-  Ok(CreateSaleResult {
-    sale_group_id,
-    total,
-    lines,
-  })
-}
+    use std::collections::HashMap;
 
-// Satış geri alma penceresi: son 30 dakika.
-// Bu süre dışındaki satışlar geri alınamaz; eski kayıtlar korunur.
-const UNDO_WINDOW_MINUTES: i64 = 30;
+    let mut need: HashMap<(String, String), i64> = HashMap::new();
+    for it in &payload.items {
+      let qty = if it.qty <= 0 { 1 } else { it.qty };
+      let sold_from = if it.sold_from.trim().is_empty() {
+        let d = payload.sold_from_default.trim();
+        if d.is_empty() { settings.default_location.clone() } else { payload.sold_from_default.clone() }
+      } else {
+        it.sold_from.clone()
+      };
 
-pub fn undo_last_sale() -> Result<UndoLastSaleResult, String> {
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
+      *need.entry((it.barcode.clone(), sold_from)).or_insert(0) += qty;
+    }
 
-  // Son 30 dakika içindeki en son satış grubunu bul.
-  // sold_at >= now - 30min şartı: eski satışların kazara geri alınmasını önler.
-  let last: Option<(String, String)> = tx
-    .query_row(
-      "SELECT sale_group_id, sold_at
-       FROM sales
-       WHERE sale_group_id IS NOT NULL
-         AND COALESCE(voided,0) = 0
-         AND sold_at >= datetime('now','localtime', ?1)
-       ORDER BY id DESC
-       LIMIT 1",
-      params![format!("-{} minutes", UNDO_WINDOW_MINUTES)],
-      |r| Ok((r.get(0)?, r.get(1)?)),
-    )
-    .optional()
-    .map_err(|e| e.to_string())?;
+    // Satır döngülerindeki ifadeler bağlantı önbelleğinden (prepare_cached); sepet büyüdükçe
+    // her satırda yeniden derlenmez.
+    for ((bc, sold_from), q) in need.iter() {
+      let col = col_for_loc(sold_from);
+      let sql = format!("SELECT COALESCE({}, 0) FROM products WHERE barcode = ?1", col);
+
+      let loc_stock: i64 = tx
+        .prepare_cached(&sql)?
+        .query_row(params![bc], |r| r.get(0))
+        .optional()?
+        .ok_or_else(|| PosError::NotFound(tr!("Ürün bulunamadı: {}", bc)))?;
+
+      if loc_stock < *q {
+        return Err(PosError::InsufficientStock {
+          barcode: bc.clone(),
+          location: sold_from.clone(),
+          available: loc_stock,
+          requested: *q,
+        });
+      }
+    }
 
-  let (sale_group_id, sold_at) = last.ok_or_else(|| {
-    format!(
-      "Son {} dakika içinde geri alınabilecek satış bulunamadı.",
-      UNDO_WINDOW_MINUTES
-    )
-  })?;
+    let mut total: f64 = 0.0;
+    let mut lines: i64 = 0;
+    let mut event_lines = Vec::new();
+    let mut changes = Vec::new();
 
-  let mut restored_lines: i64 = 0;
-  {
-    let mut stmt = tx
-      .prepare(
-        r#"
-        SELECT product_barcode, qty, COALESCE(sold_from, 'MAGAZA') AS sold_from
-        FROM sales
-        WHERE sale_group_id = ?1 AND COALESCE(voided,0) = 0
-        "#,
-      )
-      .map_err(|e| e.to_string())?;
+    for it in payload.items {
+      let (qty, list_price, discount_amount, unit_price) = sale_line_values(&it);
 
-    let rows: Vec<(String, i64, String)> = stmt
-      .query_map(params![&sale_group_id], |r| {
-        Ok((
-          r.get::<_, String>(0)?,
-          r.get::<_, i64>(1)?,
-          r.get::<_, String>(2)?,
-        ))
-      })
-      .map_err(|e| e.to_string())?
-      .collect::<Result<_, _>>()
-      .map_err(|e| e.to_string())?;
+      let sold_from = if it.sold_from.trim().is_empty() {
+        let d = payload.sold_from_default.trim();
+        if d.is_empty() { settings.default_location.clone() } else { payload.sold_from_default.clone() }
+      } else {
+        it.sold_from.clone()
+      };
 
-    for (bc, qty, sold_from) in rows {
       let col = col_for_loc(&sold_from);
-      tx.execute(
-        &format!(
-          "UPDATE products SET {c} = COALESCE({c},0) + ?1, stock = COALESCE(stock,0) + ?1 WHERE barcode = ?2",
-          c = col
-        ),
-        params![qty, &bc],
-      )
-      .map_err(|e| e.to_string())?;
-      restored_lines += 1;
+      tx.prepare_cached(&format!(
+        "UPDATE products SET {c} = COALESCE({c},0) - ?1 WHERE barcode = ?2",
+        c = col
+      ))?
+      .execute(params![qty, &it.barcode])?;
+      changes.push(stock_change(&tx, &it.barcode, &sold_from, -qty)?);
+
+      let line_total = unit_price * qty as f64;
+      total += line_total;
+      lines += 1;
+      event_lines.push(serde_json::json!({
+        "barcode": &it.barcode,
+        "qty": qty,
+        "list_price": list_price,
+        "discount_amount": discount_amount,
+        "unit_price": unit_price,
+        "total": line_total,
+        "sold_from": &sold_from,
+      }));
+
+      tx.prepare_cached(
+        r#"
+        INSERT INTO sales (
+          product_barcode, qty, unit_price, total, note,
+          sale_group_id, sold_from, list_price, discount_amount, voided,
+          payment_method, channel, sold_at
+        ) VALUES (
+          ?1, ?2, ?3, ?4, NULL,
+          ?5, ?6, ?7, ?8, 0,
+          ?9, ?10, datetime('now','localtime')
+        )
+        "#,
+      )?
+      .execute(params![
+        &it.barcode,
+        qty,
+        unit_price,
+        line_total,
+        &sale_group_id,
+        &sold_from,
+        list_price,
+        discount_amount,
+        pm,
+        channel.as_str()
+      ])?;
     }
+
+    let data = serde_json::json!({
+      "sale_group_id": &sale_group_id,
+      "channel": channel,
+      "payment_method": pm,
+      "total": total,
+      "tendered": payload.tendered,
+      "change_due": change_due(pm, payload.tendered, total),
+      "lines": event_lines,
+      "stock_changes": &changes,
+    });
+    enqueue_event(&tx, "sale.created", data.clone())?;
+    let mut events = vec![StoreEvent::new(EVENT_SALE_CREATED, data)];
+    events.extend(stock_events(&tx, &changes)?);
+
+    tx.commit()?;
+    drop(conn);
+    self.emit(events);
+
+    Ok(CreateSaleResult {
+      sale_group_id,
+      total,
+      lines,
+    })
   }
 
-  // Soft delete: voided=1 olarak işaretle, kaydı silme.
-  // İade kayıtlarının ref_sale_id'si sarkıklık yaratmaz ve denetim izi korunur.
-  tx.execute(
-    "UPDATE sales SET voided = 1 WHERE sale_group_id = ?1 AND COALESCE(voided,0) = 0",
-    params![&sale_group_id],
-  )
-  .map_err(|e| e.to_string())?;
+  // Satış geri alma penceresi: ayarlardaki undo_window_minutes (varsayılan 30 dk).
+  // Bu süre dışındaki satışlar geri alınamaz; eski kayıtlar korunur.
+  pub fn undo_last_sale(&self) -> Result<UndoLastSaleResult, PosError> {
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
+    let window = read_settings(&tx)?.undo_window_minutes;
+
+    // Pencere içindeki en son kasa (POS) satış grubunu bul.
+    // sold_at >= now - pencere şartı: eski satışların kazara geri alınmasını önler.
+    // API'den gelen satışlar atlanır; kasiyer bir online siparişi geri alamaz.
+    let last: Option<(String, String)> = tx
+      .query_row(
+        "SELECT sale_group_id, sold_at
+         FROM sales
+         WHERE sale_group_id IS NOT NULL
+           AND COALESCE(voided,0) = 0
+           AND channel = 'POS'
+           AND sold_at >= datetime('now','localtime', ?1)
+         ORDER BY id DESC
+         LIMIT 1",
+        params![format!("-{} minutes", window)],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+      )
+      .optional()?;
 
-  tx.commit().map_err(|e| e.to_string())?;
+    let (sale_group_id, sold_at) = last.ok_or_else(|| {
+      PosError::NotFound(tr!(
+        "Son {} dakika içinde geri alınabilecek satış bulunamadı.",
+        window
+      ))
+    })?;
 
-  Ok(UndoLastSaleResult {
-    sale_group_id,
-    restored_lines,
-    sold_at,
-  })
+    let mut restored_lines: i64 = 0;
+    let mut changes = Vec::new();
+    {
+      let mut stmt = tx
+        .prepare(
+          r#"
+          SELECT product_barcode, qty, COALESCE(sold_from, 'MAGAZA') AS sold_from
+          FROM sales
+          WHERE sale_group_id = ?1 AND COALESCE(voided,0) = 0
+          "#,
+        )?;
+
+      let rows: Vec<(String, i64, String)> = stmt
+        .query_map(params![&sale_group_id], |r| {
+          Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, i64>(1)?,
+            r.get::<_, String>(2)?,
+          ))
+        })?
+        .collect::<Result<_, _>>()?;
+
+      for (bc, qty, sold_from) in rows {
+        let col = col_for_loc(&sold_from);
+        tx.execute(
+          &format!(
+            "UPDATE products SET {c} = COALESCE({c},0) + ?1 WHERE barcode = ?2",
+            c = col
+          ),
+          params![qty, &bc],
+        )?;
+        changes.push(stock_change(&tx, &bc, &sold_from, qty)?);
+        restored_lines += 1;
+      }
+    }
+
+    // Soft delete: voided=1 olarak işaretle, kaydı silme.
+    // İade kayıtlarının ref_sale_id'si sarkıklık yaratmaz ve denetim izi korunur.
+    tx.execute(
+      "UPDATE sales SET voided = 1 WHERE sale_group_id = ?1 AND COALESCE(voided,0) = 0",
+      params![&sale_group_id],
+    )?;
+    enqueue_event(
+      &tx,
+      "sale.undone",
+      serde_json::json!({
+        "sale_group_id": &sale_group_id,
+        "sold_at": &sold_at,
+        "stock_changes": &changes,
+      }),
+    )?;
+    let events = stock_events(&tx, &changes)?;
+
+    tx.commit()?;
+    drop(conn);
+    self.emit(events);
+
+    Ok(UndoLastSaleResult {
+      sale_group_id,
+      restored_lines,
+      sold_at,
+    })
+  }
 }
 
 fn chrono_like_id() -> String {
@@ -2837,6 +6064,7 @@ pub struct CreateTransferItemPayload {
 #[derive(serde::Deserialize)]
 pub struct CreateTransferPayload {
   pub items: Vec<CreateTransferItemPayload>,
+  #[serde(default)]
   pub note: Option<String>,
 }
 
@@ -2866,90 +6094,283 @@ pub struct Expense {
 #[derive(serde::Deserialize)]
 pub struct CreateSalePayload {
   pub items: Vec<CreateSaleItemPayload>,
+  #[serde(default)]
   pub sold_from_default: String,
-  pub payment_method: String, 
+  // boş / tanınmayan: ayarlardaki varsayılan ödeme yöntemi
+  #[serde(default)]
+  pub payment_method: String,
+  // nakitte müşteriden alınan tutar; yalnız sale_created olayında (para üstü) kullanılır
+  #[serde(default)]
+  pub tendered: Option<f64>,
 }
 
-pub fn list_expenses() -> Result<Vec<Expense>, String> {
-  let conn = get_conn()?;
+// -------------------- SEPET FİYATLAMA (müşteri ekranı) --------------------
+//
+// Satış ekranı sepet, ödeme yöntemi ya da alınan tutar değişince price_cart'ı çağırır.
+// Satırlar create_sale ile aynı kurallarla normalize edilir (adet, fiyat, alias barkod,
+// ödeme yöntemi); sonuç komuta döner ve cart_updated olarak yayılır. Stok düşülmez,
+// yetersiz stok kontrolü satışta yapılır.
+
+/// Satışın geldiği kanal; sales.channel ve sale_created / sale.created verisinde yazılır.
+/// Kasada geri alma yalnız POS satışlarına uygulanır (API'den gelen online sipariş geri alınmaz).
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SaleChannel {
+  Pos,
+  Api,
+}
 
-  let mut stmt = conn
-    .prepare(
-      r#"
-      SELECT
-        id,
-        spent_at,
-        period,
-        category,
-        amount,
-        note
-      FROM expenses
-      ORDER BY spent_at DESC, id DESC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
+impl SaleChannel {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      SaleChannel::Pos => "POS",
+      SaleChannel::Api => "API",
+    }
+  }
+}
 
-  let rows = stmt
-    .query_map([], |row| {
-      Ok(Expense {
-        id: row.get(0)?,
-        spent_at: row.get(1)?,
-        period: row.get(2)?,
-        category: row.get(3)?,
-        amount: row.get(4)?,
-        note: row.get(5)?,
-      })
-    })
-    .map_err(|e| e.to_string())?;
+#[derive(serde::Deserialize, Default)]
+pub struct PriceCartPayload {
+  #[serde(default)]
+  pub items: Vec<CreateSaleItemPayload>,
+  #[serde(default)]
+  pub payment_method: String,
+  #[serde(default)]
+  pub tendered: Option<f64>,
+}
+
+#[derive(serde::Serialize, Clone)]
+pub struct PricedCartLine {
+  pub barcode: String,
+  pub name: String,
+  pub color: Option<String>,
+  pub size: Option<String>,
+  pub qty: i64,
+  pub list_price: f64,
+  pub discount_amount: f64,
+  pub unit_price: f64,
+  pub total: f64,
+}
 
-  let mut out = Vec::new();
-  for r in rows {
-    out.push(r.map_err(|e| e.to_string())?);
+#[derive(serde::Serialize, Clone)]
+pub struct PricedCart {
+  pub lines: Vec<PricedCartLine>,
+  /// Toplam adet
+  pub item_count: i64,
+  /// İndirimsiz (liste fiyatı × adet)
+  pub subtotal: f64,
+  pub discount_total: f64,
+  pub total: f64,
+  pub payment_method: String,
+  pub tendered: Option<f64>,
+  /// Yalnız nakitte; negatifse müşterinin eksik ödediği tutar
+  pub change_due: Option<f64>,
+}
+
+/// create_sale ile price_cart'ın ortak satır kuralı: (adet, liste, indirim, birim fiyat)
+fn sale_line_values(it: &CreateSaleItemPayload) -> (i64, f64, f64, f64) {
+  let qty = if it.qty <= 0 { 1 } else { it.qty };
+  let list_price = if it.list_price.is_finite() { it.list_price } else { 0.0 };
+  let discount_amount = if it.discount_amount.is_finite() { it.discount_amount } else { 0.0 };
+  let unit_price = if it.unit_price.is_finite() { it.unit_price } else { 0.0 };
+  (qty, list_price, discount_amount, unit_price)
+}
+
+fn sale_payment_method<'a>(raw: &str, settings: &'a ShopSettings) -> &'a str {
+  match raw.trim().to_uppercase().as_str() {
+    "CARD" | "KART" => "CARD",
+    "CASH" | "NAKIT" | "NAKİT" => "CASH",
+    "TRANSFER" | "HAVALE" | "EFT" => "TRANSFER",
+    _ => settings.default_payment_method.as_str(),
   }
-  Ok(out)
 }
 
-pub fn add_expense(
-  spent_at: String,
-  period: Option<String>,
-  category: Option<String>,
-  amount: f64,
-  note: Option<String>,
-) -> Result<i64, String> {
-  if !amount.is_finite() || amount <= 0.0 {
-    return Err("Tutar 0'dan büyük sayı olmalı".to_string());
+fn change_due(payment_method: &str, tendered: Option<f64>, total: f64) -> Option<f64> {
+  match tendered {
+    Some(t) if payment_method == "CASH" && t.is_finite() => Some(((t - total) * 100.0).round() / 100.0),
+    _ => None,
   }
-  if spent_at.trim().is_empty() {
-    return Err("Tarih zorunlu".to_string());
+}
+
+impl Store {
+  pub fn price_cart(&self, payload: PriceCartPayload) -> Result<PricedCart, PosError> {
+    let conn = self.read()?;
+    let settings = read_settings(&conn)?;
+    let pm = sale_payment_method(&payload.payment_method, &settings).to_string();
+
+    let priced = (|| -> Result<Vec<PricedCartLine>, PosError> {
+      let mut lines = Vec::with_capacity(payload.items.len());
+      for it in &payload.items {
+        let code = it.barcode.trim();
+        let barcode = resolve_barcode(&conn, code)?.unwrap_or_else(|| code.to_string());
+        let (name, color, size) = conn
+          .query_row(
+            "SELECT name, color, size FROM products WHERE barcode = ?1",
+            params![&barcode],
+            |r| Ok((r.get::<_, String>(0)?, r.get(1)?, r.get(2)?)),
+          )
+          .optional()?
+          .ok_or_else(|| PosError::NotFound(tr!("Ürün bulunamadı: {}", code)))?;
+
+        let (qty, list_price, discount_amount, unit_price) = sale_line_values(it);
+        lines.push(PricedCartLine {
+          barcode,
+          name,
+          color,
+          size,
+          qty,
+          list_price,
+          discount_amount,
+          unit_price,
+          total: unit_price * qty as f64,
+        });
+      }
+      Ok(lines)
+    })();
+    drop(conn);
+
+    // Fiyatlanamayan sepet (ör. silinmiş ürün) müşteri ekranında eski haliyle kalmasın:
+    // boş sepet yayılır (ekran boşta görünümüne döner), hata satış ekranına döner.
+    let lines = match priced {
+      Ok(lines) => lines,
+      Err(e) => {
+        let empty = PricedCart {
+          lines: Vec::new(),
+          item_count: 0,
+          subtotal: 0.0,
+          discount_total: 0.0,
+          total: 0.0,
+          change_due: None,
+          tendered: None,
+          payment_method: pm,
+        };
+        self.emit(vec![StoreEvent::new(EVENT_CART_UPDATED, &empty)]);
+        return Err(e);
+      }
+    };
+
+    let subtotal: f64 = lines.iter().map(|l| l.list_price * l.qty as f64).sum();
+    let total: f64 = lines.iter().map(|l| l.total).sum();
+    let cart = PricedCart {
+      item_count: lines.iter().map(|l| l.qty).sum(),
+      subtotal,
+      discount_total: subtotal - total,
+      total,
+      change_due: change_due(&pm, payload.tendered, total),
+      tendered: payload.tendered,
+      payment_method: pm,
+      lines,
+    };
+    self.emit(vec![StoreEvent::new(EVENT_CART_UPDATED, &cart)]);
+    Ok(cart)
   }
+}
 
-  let conn = get_conn()?;
+impl Store {
+  pub fn list_expenses(&self, query: &ListQuery) -> Result<Page<Expense>, PosError> {
+    let conn = self.read()?;
+    let range = query.dates()?;
+    let q_like = query.q_like();
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(
+      &[
+        ("spent_at", "spent_at"),
+        ("amount", "amount"),
+        ("category", "COALESCE(category,'')"),
+      ],
+      true,
+    )?;
 
-  conn.execute(
-    r#"
-    INSERT INTO expenses (title, amount, spent_at, category, note, period)
-    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-    "#,
-    rusqlite::params![
-      "Gider",
-      amount,
-      spent_at.trim(),
-      category,
-      note,
-      period
-    ],
-  )
-  .map_err(|e| e.to_string())?;
+    // ?1/?2 tarih aralığı, ?3 kategori / not araması
+    let filter = r#"
+      (?1 IS NULL OR date(spent_at) >= ?1)
+      AND (?2 IS NULL OR date(spent_at) <= ?2)
+      AND (?3 IS NULL OR COALESCE(category,'') LIKE ?3 OR COALESCE(note,'') LIKE ?3)
+    "#;
 
-  Ok(conn.last_insert_rowid())
-}
+    let total: i64 = conn
+      .query_row(
+        &format!("SELECT COUNT(*) FROM expenses WHERE {}", filter),
+        params![range.from, range.to, q_like],
+        |r| r.get(0),
+      )?;
+
+    let mut stmt = conn
+      .prepare(&format!(
+        r#"
+        SELECT
+          id,
+          spent_at,
+          period,
+          category,
+          amount,
+          note
+        FROM expenses
+        WHERE {filter}
+        ORDER BY {order}, id DESC
+        LIMIT ?4 OFFSET ?5
+        "#,
+        filter = filter,
+        order = order
+      ))?;
+
+    let items = stmt
+      .query_map(params![range.from, range.to, q_like, limit, offset], |row| {
+        Ok(Expense {
+          id: row.get(0)?,
+          spent_at: row.get(1)?,
+          period: row.get(2)?,
+          category: row.get(3)?,
+          amount: row.get(4)?,
+          note: row.get(5)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(query.page_of(items, total))
+  }
+
+  pub fn add_expense(
+    &self,
+    spent_at: String,
+    period: Option<String>,
+    category: Option<String>,
+    amount: f64,
+    note: Option<String>,
+  ) -> Result<i64, PosError> {
+    if !amount.is_finite() || amount <= 0.0 {
+      return Err(PosError::Validation(tr!("Tutar 0'dan büyük sayı olmalı")));
+    }
+    if spent_at.trim().is_empty() {
+      return Err(PosError::Validation(tr!("Tarih zorunlu")));
+    }
 
-pub fn delete_expense(id: i64) -> Result<i64, String> {
-  let conn = get_conn()?;
-  let changed = conn
-    .execute("DELETE FROM expenses WHERE id = ?1", rusqlite::params![id])
-    .map_err(|e| e.to_string())?;
-  Ok(changed as i64)
+    let conn = self.write()?;
+
+    conn.execute(
+      r#"
+      INSERT INTO expenses (title, amount, spent_at, category, note, period)
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+      "#,
+      rusqlite::params![
+        "Gider",
+        amount,
+        spent_at.trim(),
+        category,
+        note,
+        period
+      ],
+    )?;
+
+    Ok(conn.last_insert_rowid())
+  }
+
+  pub fn delete_expense(&self, id: i64) -> Result<i64, PosError> {
+    let conn = self.write()?;
+    let changed = conn
+      .execute("DELETE FROM expenses WHERE id = ?1", rusqlite::params![id])?;
+    Ok(changed as i64)
+  }
 }
 
 
@@ -2985,557 +6406,691 @@ pub struct MonthlyDashboardRow {
   pub avg_basket: f64,
 }
 
+#[derive(serde::Serialize, Default, Clone)]
+pub struct PeriodTotals {
+  pub net_qty: i64,
+  pub net_revenue: f64,
+  pub gross_profit: f64,
+  pub expense: f64,
+  pub net_profit: f64,
+  pub receipts: i64,
+  pub avg_basket: f64,
+}
+
 #[derive(serde::Serialize)]
 pub struct DashboardSummary {
   pub kpi: DashboardKpi,
+  // seçili dönemin toplamı
+  pub totals: PeriodTotals,
   pub daily: Vec<DailyDashboardRow>,
   pub monthly: Vec<MonthlyDashboardRow>,
 }
 
-// -------------------- RETURN / EXCHANGE --------------------
-
-pub fn list_sales_by_barcode(barcode: &str, days: i64) -> Result<Vec<SaleLine>, String> {
-  let conn = get_conn()?;
-  let offset = format!("-{} days", days.max(0));
-
-  let mut stmt = conn
-    .prepare(
-      r#"
-      SELECT
-        s.sold_at,
-        s.qty,
-        s.unit_price,
-        s.total,
-        COALESCE(s.sold_from, 'MAGAZA') AS sold_from,
-        (
-          SELECT COALESCE(SUM(ri.qty), 0)
-          FROM return_items ri
-          WHERE ri.ref_sale_id = s.id
-        ) AS refunded_qty
-      FROM sales s
-      WHERE TRIM(s.product_barcode) = TRIM(?1)
-        AND COALESCE(s.voided, 0) = 0
-        AND s.sold_at >= datetime('now', ?2)
-      ORDER BY s.sold_at DESC
-      "#,
-    )
-    .map_err(|e| e.to_string())?;
-
-  let rows = stmt
-    .query_map(params![barcode, offset], |row| {
-      Ok(SaleLine {
-        sold_at: row.get(0)?,
-        qty: row.get(1)?,
-        unit_price: row.get(2)?,
-        total: row.get(3)?,
-        sold_from: row.get(4)?,
-        refunded_qty: row.get::<_, Option<i64>>(5)?.unwrap_or(0),
-      })
-    })
-    .map_err(|e| e.to_string())?;
-
-  let mut out = Vec::new();
-  for r in rows {
-    out.push(r.map_err(|e| e.to_string())?);
+impl ReportTotals for DashboardSummary {
+  fn totals(&self) -> Vec<(&'static str, f64)> {
+    let t = &self.totals;
+    vec![
+      ("net_qty", t.net_qty as f64),
+      ("net_revenue", t.net_revenue),
+      ("gross_profit", t.gross_profit),
+      ("expense", t.expense),
+      ("net_profit", t.net_profit),
+      ("receipts", t.receipts as f64),
+      ("avg_basket", t.avg_basket),
+    ]
   }
-  Ok(out)
 }
 
-pub fn create_return(payload: CreateReturnPayload) -> Result<CreateReturnResult, String> {
-  if payload.qty <= 0 {
-    return Err("İade adedi 1+ olmalı".to_string());
-  }
-
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
+// -------------------- RETURN / EXCHANGE --------------------
 
-  let ref_sale_id: Option<i64> = match payload.sold_at.as_deref() {
-    Some(sold_at) => tx
-      .query_row(
-        "SELECT id FROM sales WHERE TRIM(product_barcode) = TRIM(?1) AND sold_at = ?2 AND COALESCE(voided,0)=0 ORDER BY id DESC LIMIT 1",
-        params![&payload.barcode, sold_at],
-        |r| r.get(0),
-      )
-      .optional()
-      .map_err(|e| e.to_string())?,
-    None => None,
-  };
-  // Kısmi iade: referans satıştan kalan adet kontrolü
-  if let Some(ref_id) = ref_sale_id {
-    let sold_qty: i64 = tx
-      .query_row(
-        "SELECT qty FROM sales WHERE id = ?1 AND COALESCE(voided,0)=0",
-        params![ref_id],
-        |r| r.get(0),
-      )
-      .map_err(|e| e.to_string())?;
+impl Store {
+  pub fn list_sales_by_barcode(&self, barcode: &str, query: &ListQuery) -> Result<Page<SaleLine>, PosError> {
+    let conn = self.read()?;
+    let canonical = resolve_barcode(&conn, barcode)?;
+    let barcode = canonical.as_deref().unwrap_or(barcode);
+    let range = query.dates()?;
+    let (limit, offset) = query.limit_offset();
+    let order = query.order_by(
+      &[("sold_at", "s.sold_at"), ("qty", "s.qty"), ("total", "s.total")],
+      true,
+    )?;
+
+    let filter = r#"
+      TRIM(s.product_barcode) = TRIM(?1)
+      AND COALESCE(s.voided, 0) = 0
+      AND (?2 IS NULL OR date(s.sold_at) >= ?2)
+      AND (?3 IS NULL OR date(s.sold_at) <= ?3)
+    "#;
 
-    let already_refunded: i64 = tx
+    let total: i64 = conn
       .query_row(
-        "SELECT COALESCE(SUM(qty),0) FROM return_items WHERE ref_sale_id = ?1",
-        params![ref_id],
+        &format!("SELECT COUNT(*) FROM sales s WHERE {}", filter),
+        params![barcode, range.from, range.to],
         |r| r.get(0),
-      )
-      .map_err(|e| e.to_string())?;
-
-    let remaining = sold_qty - already_refunded;
-
-    if remaining <= 0 {
-      return Err("Bu satış satırı daha önce tamamen iade edilmiş".to_string());
-    }
-    if payload.qty > remaining {
-      return Err(format!(
-        "İade adedi satıştan fazla olamaz (satılan: {}, daha önce iade: {}, kalan: {})",
-        sold_qty, already_refunded, remaining
-      ));
-    }
-  }
-
-  let col = col_for_loc(&payload.return_to);
-  tx.execute(
-    &format!(
-      "UPDATE products SET {c} = COALESCE({c},0) + ?1, stock = COALESCE(stock,0) + ?1 WHERE barcode = ?2",
-      c = col
-    ),
-    params![payload.qty, &payload.barcode],
-  )
-  .map_err(|e| e.to_string())?;
-
-  let return_group_id = gen_group_id("R");
-  let returned_total = payload.unit_price * payload.qty as f64;
-
-  tx.execute(
-    "INSERT INTO returns (return_group_id, mode, returned_total, given_total, diff) VALUES (?1, 'REFUND', ?2, 0, ?3)",
-    params![&return_group_id, returned_total, -returned_total],
-  )
-  .map_err(|e| e.to_string())?;
-
-  tx.execute(
-    r#"
-    INSERT INTO return_items (
-      return_group_id, product_barcode, qty, unit_price, total,
-      return_to, ref_sale_id, ref_sold_at, ref_sold_from
-    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-    "#,
-    params![
-      &return_group_id,
-      &payload.barcode,
-      payload.qty,
-      payload.unit_price,
-      returned_total,
-      &payload.return_to,
-      ref_sale_id,
-      payload.sold_at,
-      payload.sold_from
-    ],
-  )
-  .map_err(|e| e.to_string())?;
+      )?;
 
-  tx.commit().map_err(|e| e.to_string())?;
-
-  Ok(CreateReturnResult {
-    return_group_id,
-    lines: 1,
-    returned_total,
-  })
-}
+    let mut stmt = conn
+      .prepare(&format!(
+        r#"
+        SELECT
+          s.sold_at,
+          s.qty,
+          s.unit_price,
+          s.total,
+          COALESCE(s.sold_from, 'MAGAZA') AS sold_from,
+          (
+            SELECT COALESCE(SUM(ri.qty), 0)
+            FROM return_items ri
+            WHERE ri.ref_sale_id = s.id
+          ) AS refunded_qty
+        FROM sales s
+        WHERE {filter}
+        ORDER BY {order}, s.id DESC
+        LIMIT ?4 OFFSET ?5
+        "#,
+        filter = filter,
+        order = order
+      ))?;
+
+    let items = stmt
+      .query_map(params![barcode, range.from, range.to, limit, offset], |row| {
+        Ok(SaleLine {
+          sold_at: row.get(0)?,
+          qty: row.get(1)?,
+          unit_price: row.get(2)?,
+          total: row.get(3)?,
+          sold_from: row.get(4)?,
+          refunded_qty: row.get::<_, Option<i64>>(5)?.unwrap_or(0),
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
 
-pub fn create_exchange(payload: CreateExchangePayload) -> Result<CreateExchangeResult, String> {
-  if payload.returned.qty <= 0 {
-    return Err("İade adedi 1+ olmalı".to_string());
-  }
-  if payload.given.is_empty() {
-    return Err("Değişimde verilecek sepet boş".to_string());
+    Ok(query.page_of(items, total))
   }
 
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
-
-
-  for g in &payload.given {
-    if g.qty <= 0 {
-      continue;
-    }
-
-    let col = col_for_loc(&g.sold_from);
-    let sql = format!("SELECT COALESCE({}, 0) FROM products WHERE barcode = ?1", col);
-
-    let loc_stock: i64 = tx
-      .query_row(&sql, params![&g.barcode], |r| r.get::<_, i64>(0))
-      .optional()
-      .map_err(|e| e.to_string())?
-      .ok_or_else(|| format!("Ürün bulunamadı: {}", g.barcode))?;
-
-    if loc_stock < g.qty {
-      return Err(format!(
-        "Yetersiz stok: {} ({} stok: {}, istenen: {})",
-        g.barcode,
-        g.sold_from,
-        loc_stock,
-        g.qty
-      ));
+  pub fn create_return(&self, mut payload: CreateReturnPayload) -> Result<CreateReturnResult, PosError> {
+    if payload.qty <= 0 {
+      return Err(PosError::Validation(tr!("İade adedi 1+ olmalı")));
     }
-  }
 
-  let ref_sale_id: Option<i64> = match payload.returned.sold_at.as_deref() {
-    Some(sold_at) => tx
-      .query_row(
-        "SELECT id FROM sales
-         WHERE TRIM(product_barcode) = TRIM(?1)
-           AND sold_at = ?2
-           AND COALESCE(voided,0)=0
-         ORDER BY id DESC
-         LIMIT 1",
-        params![&payload.returned.barcode, sold_at],
-        |r| r.get(0),
-      )
-      .optional()
-      .map_err(|e| e.to_string())?,
-    None => None,
-  };
-  // Kısmi değişim: referans satıştan kalan adet kontrolü
-  if let Some(ref_id) = ref_sale_id {
-    let sold_qty: i64 = tx
-      .query_row(
-        "SELECT qty FROM sales WHERE id = ?1 AND COALESCE(voided,0)=0",
-        params![ref_id],
-        |r| r.get(0),
-      )
-      .map_err(|e| e.to_string())?;
-
-    let already_refunded: i64 = tx
-      .query_row(
-        "SELECT COALESCE(SUM(qty),0) FROM return_items WHERE ref_sale_id = ?1",
-        params![ref_id],
-        |r| r.get(0),
-      )
-      .map_err(|e| e.to_string())?;
-
-    let remaining = sold_qty - already_refunded;
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
 
-    if remaining <= 0 {
-      return Err("Bu satış satırı daha önce tamamen iade/değişim yapılmış".to_string());
-    }
-    if payload.returned.qty > remaining {
-      return Err(format!(
-        "İade edilen adet satıştan fazla olamaz (satılan: {}, daha önce iade: {}, kalan: {})",
-        sold_qty, already_refunded, remaining
-      ));
+    if let Some(bc) = resolve_barcode(&tx, &payload.barcode)? {
+      payload.barcode = bc;
     }
-  }
-
-  let exchange_group_id = format!("E{}", chrono_like_id());
 
-  let return_col = col_for_loc(&payload.returned.return_to);
-  tx.execute(
-    &format!(
-      "UPDATE products
-       SET {c} = COALESCE({c},0) + ?1,
-           stock = COALESCE(stock,0) + ?1
-       WHERE barcode = ?2",
-      c = return_col
-    ),
-    params![payload.returned.qty, &payload.returned.barcode],
-  )
-  .map_err(|e| e.to_string())?;
+    let ref_sale_id: Option<i64> = match payload.sold_at.as_deref() {
+      Some(sold_at) => tx
+        .query_row(
+          "SELECT id FROM sales WHERE TRIM(product_barcode) = TRIM(?1) AND sold_at = ?2 AND COALESCE(voided,0)=0 ORDER BY id DESC LIMIT 1",
+          params![&payload.barcode, sold_at],
+          |r| r.get(0),
+        )
+        .optional()?,
+      None => None,
+    };
+    // Kısmi iade: referans satıştan kalan adet kontrolü
+    if let Some(ref_id) = ref_sale_id {
+      let sold_qty: i64 = tx
+        .query_row(
+          "SELECT qty FROM sales WHERE id = ?1 AND COALESCE(voided,0)=0",
+          params![ref_id],
+          |r| r.get(0),
+        )?;
 
-  let returned_total_calc: f64 = payload.returned.unit_price * payload.returned.qty as f64;
-  tx.execute(
-    r#"
-    INSERT INTO return_items (
-      return_group_id, product_barcode, qty, unit_price, total,
-      return_to, ref_sale_id, ref_sold_at, ref_sold_from
-    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-    "#,
-    params![
-      &exchange_group_id,
-      &payload.returned.barcode,
-      payload.returned.qty,
-      payload.returned.unit_price,
-      returned_total_calc,
-      &payload.returned.return_to,
-      ref_sale_id,
-      payload.returned.sold_at,
-      payload.returned.sold_from
-    ],
-  )
-  .map_err(|e| e.to_string())?;
+      let already_refunded: i64 = tx
+        .query_row(
+          "SELECT COALESCE(SUM(qty),0) FROM return_items WHERE ref_sale_id = ?1",
+          params![ref_id],
+          |r| r.get(0),
+        )?;
 
-  let mut given_total_calc: f64 = 0.0;
-  let mut lines: i64 = 0;
+      let remaining = sold_qty - already_refunded;
 
-  for it in &payload.given {
-    if it.qty <= 0 {
-      continue;
+      if remaining <= 0 {
+        return Err(PosError::Conflict(tr!("Bu satış satırı daha önce tamamen iade edilmiş")));
+      }
+      if payload.qty > remaining {
+        return Err(PosError::Validation(tr!(
+          "İade adedi satıştan fazla olamaz (satılan: {}, daha önce iade: {}, kalan: {})",
+          sold_qty, already_refunded, remaining
+        )));
+      }
     }
 
-    // stok düş: ilgili lokasyon + legacy stock
-    let sold_col = col_for_loc(&it.sold_from);
+    let col = col_for_loc(&payload.return_to);
     tx.execute(
       &format!(
-        "UPDATE products
-         SET {c} = COALESCE({c},0) - ?1,
-             stock = COALESCE(stock,0) - ?1
-         WHERE barcode = ?2",
-        c = sold_col
+        "UPDATE products SET {c} = COALESCE({c},0) + ?1 WHERE barcode = ?2",
+        c = col
       ),
-      params![it.qty, &it.barcode],
-    )
-    .map_err(|e| e.to_string())?;
+      params![payload.qty, &payload.barcode],
+    )?;
+    let change = stock_change(&tx, &payload.barcode, &payload.return_to, payload.qty)?;
 
-    let line_total = it.unit_price * it.qty as f64;
-    given_total_calc += line_total;
+    let return_group_id = gen_group_id("R");
+    let returned_total = payload.unit_price * payload.qty as f64;
+
+    tx.execute(
+      "INSERT INTO returns (return_group_id, mode, returned_total, given_total, diff) VALUES (?1, 'REFUND', ?2, 0, ?3)",
+      params![&return_group_id, returned_total, -returned_total],
+    )?;
 
     tx.execute(
       r#"
-      INSERT INTO exchange_items (
-        exchange_group_id, product_barcode, qty, unit_price, total, sold_from
-      ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+      INSERT INTO return_items (
+        return_group_id, product_barcode, qty, unit_price, total,
+        return_to, ref_sale_id, ref_sold_at, ref_sold_from
+      ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
       "#,
       params![
-        &exchange_group_id,
-        &it.barcode,
-        it.qty,
-        it.unit_price,
-        line_total,
-        &it.sold_from
+        &return_group_id,
+        &payload.barcode,
+        payload.qty,
+        payload.unit_price,
+        returned_total,
+        &payload.return_to,
+        ref_sale_id,
+        payload.sold_at,
+        payload.sold_from
       ],
-    )
-    .map_err(|e| e.to_string())?;
-
-    lines += 1;
+    )?;
+
+    enqueue_event(
+      &tx,
+      "return.created",
+      serde_json::json!({
+        "return_group_id": &return_group_id,
+        "barcode": &payload.barcode,
+        "qty": payload.qty,
+        "unit_price": payload.unit_price,
+        "returned_total": returned_total,
+        "return_to": &change.location,
+        "stock_changes": [&change],
+      }),
+    )?;
+    let events = stock_events(&tx, std::slice::from_ref(&change))?;
+
+    tx.commit()?;
+    drop(conn);
+    self.emit(events);
+
+    Ok(CreateReturnResult {
+      return_group_id,
+      lines: 1,
+      returned_total,
+    })
   }
 
-  let diff_calc: f64 = given_total_calc - returned_total_calc;
+  pub fn create_exchange(&self, mut payload: CreateExchangePayload) -> Result<CreateExchangeResult, PosError> {
+    if payload.returned.qty <= 0 {
+      return Err(PosError::Validation(tr!("İade adedi 1+ olmalı")));
+    }
+    if payload.given.is_empty() {
+      return Err(PosError::Validation(tr!("Değişimde verilecek sepet boş")));
+    }
 
-  let diff_pm_norm: Option<String> = if diff_calc > 0.0001 {
-    let pm_raw = payload
-      .summary
-      .diff_payment_method
-      .clone()
-      .unwrap_or_else(|| "CASH".to_string());
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
 
-    let pm_up = pm_raw.trim().to_uppercase();
-    let pm = match pm_up.as_str() {
-      "CARD" | "KART" => "CARD",
-      "CASH" | "NAKIT" | "NAKİT" => "CASH",
-      _ => {
-        return Err(
-          "diff_payment_method sadece CARD/KART veya CASH/NAKİT olabilir".to_string(),
-        )
+    if let Some(bc) = resolve_barcode(&tx, &payload.returned.barcode)? {
+      payload.returned.barcode = bc;
+    }
+    for g in payload.given.iter_mut() {
+      if let Some(bc) = resolve_barcode(&tx, &g.barcode)? {
+        g.barcode = bc;
       }
-    };
-
-    Some(pm.to_string())
-  } else {
-    None
-  };
-
-  tx.execute(
-    r#"
-    INSERT INTO returns
-      (return_group_id, mode, returned_total, given_total, diff, diff_payment_method, created_at)
-    VALUES
-      (?1, 'EXCHANGE', ?2, ?3, ?4, ?5, datetime('now','localtime'))
-    "#,
-    params![
-      &exchange_group_id,
-      returned_total_calc,
-      given_total_calc,
-      diff_calc,
-      diff_pm_norm
-    ],
-  )
-  .map_err(|e| e.to_string())?;
-
-  tx.commit().map_err(|e| e.to_string())?;
-
-  Ok(CreateExchangeResult {
-    exchange_group_id,
-    lines,
-    returned_total: returned_total_calc,
-    given_total: given_total_calc,
-    diff: diff_calc,
-  })
-}
-
-pub fn create_transfer(payload: CreateTransferPayload) -> Result<CreateTransferResult, String> {
-  if payload.items.is_empty() {
-    return Err("Sepet boş".to_string());
-  }
+    }
 
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for g in &payload.given {
+      if g.qty <= 0 {
+        continue;
+      }
 
-  let transfer_group_id = format!("T{}", chrono_like_id());
+      let col = col_for_loc(&g.sold_from);
+      let sql = format!("SELECT COALESCE({}, 0) FROM products WHERE barcode = ?1", col);
 
-  let note_norm: Option<String> = payload.note.as_ref().and_then(|s| {
-    let t = s.trim();
-    if t.is_empty() { None } else { Some(t.to_string()) }
-  });
+      let loc_stock: i64 = tx
+        .query_row(&sql, params![&g.barcode], |r| r.get::<_, i64>(0))
+        .optional()?
+        .ok_or_else(|| PosError::NotFound(tr!("Ürün bulunamadı: {}", g.barcode)))?;
 
-  use std::collections::HashMap;
-  let mut need: HashMap<(String, String), i64> = HashMap::new(); 
-  for it in &payload.items {
-    let q = if it.qty <= 0 { 1 } else { it.qty };
-    if it.from_loc == it.to_loc {
-      return Err("Nereden ve nereye aynı olamaz".to_string());
+      if loc_stock < g.qty {
+        return Err(PosError::InsufficientStock {
+          barcode: g.barcode.clone(),
+          location: g.sold_from.clone(),
+          available: loc_stock,
+          requested: g.qty,
+        });
+      }
     }
-    *need.entry((it.barcode.clone(), it.from_loc.clone())).or_insert(0) += q;
-  }
 
-  for ((bc, from_loc), q) in need.iter() {
-    let col = col_for_loc(from_loc);
-    let sql = format!("SELECT COALESCE({},0) FROM products WHERE barcode = ?1", col);
+    let ref_sale_id: Option<i64> = match payload.returned.sold_at.as_deref() {
+      Some(sold_at) => tx
+        .query_row(
+          "SELECT id FROM sales
+           WHERE TRIM(product_barcode) = TRIM(?1)
+             AND sold_at = ?2
+             AND COALESCE(voided,0)=0
+           ORDER BY id DESC
+           LIMIT 1",
+          params![&payload.returned.barcode, sold_at],
+          |r| r.get(0),
+        )
+        .optional()?,
+      None => None,
+    };
+    // Kısmi değişim: referans satıştan kalan adet kontrolü
+    if let Some(ref_id) = ref_sale_id {
+      let sold_qty: i64 = tx
+        .query_row(
+          "SELECT qty FROM sales WHERE id = ?1 AND COALESCE(voided,0)=0",
+          params![ref_id],
+          |r| r.get(0),
+        )?;
 
-    let stock: i64 = tx
-      .query_row(&sql, params![bc], |r| r.get(0))
-      .optional()
-      .map_err(|e| e.to_string())?
-      .ok_or_else(|| format!("Ürün bulunamadı: {}", bc))?;
+      let already_refunded: i64 = tx
+        .query_row(
+          "SELECT COALESCE(SUM(qty),0) FROM return_items WHERE ref_sale_id = ?1",
+          params![ref_id],
+          |r| r.get(0),
+        )?;
 
-    if stock < *q {
-      return Err(format!(
-        "Yetersiz stok: {} ({} stok: {}, istenen: {})",
-        bc, from_loc, stock, q
-      ));
-    }
-  }
+      let remaining = sold_qty - already_refunded;
 
-  let mut lines: i64 = 0;
-  for it in payload.items {
-    let qty = if it.qty <= 0 { 1 } else { it.qty };
-    if it.from_loc == it.to_loc {
-      return Err("Nereden ve nereye aynı olamaz".to_string());
+      if remaining <= 0 {
+        return Err(PosError::Conflict(tr!("Bu satış satırı daha önce tamamen iade/değişim yapılmış")));
+      }
+      if payload.returned.qty > remaining {
+        return Err(PosError::Validation(tr!(
+          "İade edilen adet satıştan fazla olamaz (satılan: {}, daha önce iade: {}, kalan: {})",
+          sold_qty, already_refunded, remaining
+        )));
+      }
     }
 
-    let from_col = col_for_loc(&it.from_loc);
-    let to_col = col_for_loc(&it.to_loc);
-
-    // 1) Kaynaktan düş
-    tx.execute(
-      &format!(
-        "UPDATE products SET {c} = COALESCE({c},0) - ?1 WHERE barcode = ?2",
-        c = from_col
-      ),
-      params![qty, &it.barcode],
-    )
-    .map_err(|e| e.to_string())?;
+    let exchange_group_id = format!("E{}", chrono_like_id());
 
-    // 2) Hedefe ekle
+    let return_col = col_for_loc(&payload.returned.return_to);
     tx.execute(
       &format!(
-        "UPDATE products SET {c} = COALESCE({c},0) + ?1 WHERE barcode = ?2",
-        c = to_col
+        "UPDATE products
+         SET {c} = COALESCE({c},0) + ?1
+         WHERE barcode = ?2",
+        c = return_col
       ),
-      params![qty, &it.barcode],
-    )
-    .map_err(|e| e.to_string())?;
+      params![payload.returned.qty, &payload.returned.barcode],
+    )?;
+    let mut changes = vec![stock_change(&tx, &payload.returned.barcode, &payload.returned.return_to, payload.returned.qty)?];
 
-    // kayıt ekle (undo + rapor için)
+    let returned_total_calc: f64 = payload.returned.unit_price * payload.returned.qty as f64;
     tx.execute(
       r#"
-      INSERT INTO transfers (
-        product_barcode, qty, from_loc, to_loc, transfer_group_id, note, transferred_at, voided
-      ) VALUES (
-        ?1, ?2, ?3, ?4, ?5, ?6, datetime('now','localtime'), 0
-      )
+      INSERT INTO return_items (
+        return_group_id, product_barcode, qty, unit_price, total,
+        return_to, ref_sale_id, ref_sold_at, ref_sold_from
+      ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
       "#,
       params![
-        &it.barcode,
-        qty,
-        it.from_loc.trim().to_uppercase(),
-        it.to_loc.trim().to_uppercase(),
-        &transfer_group_id,
-        note_norm,
+        &exchange_group_id,
+        &payload.returned.barcode,
+        payload.returned.qty,
+        payload.returned.unit_price,
+        returned_total_calc,
+        &payload.returned.return_to,
+        ref_sale_id,
+        payload.returned.sold_at,
+        payload.returned.sold_from
       ],
-    )
-    .map_err(|e| e.to_string())?;
-
-    lines += 1;
-  }
+    )?;
 
-  tx.commit().map_err(|e| e.to_string())?;
+    let mut given_total_calc: f64 = 0.0;
+    let mut lines: i64 = 0;
 
-  Ok(CreateTransferResult {
-    transfer_group_id,
-    lines,
-  })
-}
+    for it in &payload.given {
+      if it.qty <= 0 {
+        continue;
+      }
 
-pub fn undo_last_transfer() -> Result<UndoLastTransferResult, String> {
-  let mut conn = get_conn()?;
-  let tx = conn.transaction().map_err(|e| e.to_string())?;
+      // stok düş: ilgili lokasyon
+      let sold_col = col_for_loc(&it.sold_from);
+      tx.execute(
+        &format!(
+          "UPDATE products
+           SET {c} = COALESCE({c},0) - ?1
+           WHERE barcode = ?2",
+          c = sold_col
+        ),
+        params![it.qty, &it.barcode],
+      )?;
+      changes.push(stock_change(&tx, &it.barcode, &it.sold_from, -it.qty)?);
 
-  let last_group: Option<String> = tx
-    .query_row(
-      "SELECT transfer_group_id FROM transfers WHERE COALESCE(voided,0)=0 AND transfer_group_id IS NOT NULL ORDER BY id DESC LIMIT 1",
-      [],
-      |r| r.get(0),
-    )
-    .optional()
-    .map_err(|e| e.to_string())?;
+      let line_total = it.unit_price * it.qty as f64;
+      given_total_calc += line_total;
 
-  let transfer_group_id =
-    last_group.ok_or_else(|| "Geri alınacak transfer bulunamadı".to_string())?;
+      tx.execute(
+        r#"
+        INSERT INTO exchange_items (
+          exchange_group_id, product_barcode, qty, unit_price, total, sold_from
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#,
+        params![
+          &exchange_group_id,
+          &it.barcode,
+          it.qty,
+          it.unit_price,
+          line_total,
+          &it.sold_from
+        ],
+      )?;
+
+      lines += 1;
+    }
 
-  let mut restored_lines: i64 = 0;
+    let diff_calc: f64 = given_total_calc - returned_total_calc;
+
+    let diff_pm_norm: Option<String> = if diff_calc > 0.0001 {
+      let pm_raw = payload
+        .summary
+        .diff_payment_method
+        .clone()
+        .unwrap_or_else(|| "CASH".to_string());
+
+      let pm_up = pm_raw.trim().to_uppercase();
+      let pm = match pm_up.as_str() {
+        "CARD" | "KART" => "CARD",
+        "CASH" | "NAKIT" | "NAKİT" => "CASH",
+        _ => {
+          return Err(PosError::Validation(
+            tr!("diff_payment_method sadece CARD/KART veya CASH/NAKİT olabilir"),
+          ))
+        }
+      };
 
-  {
-    let mut stmt = tx
-      .prepare(
-        "SELECT product_barcode, qty, from_loc, to_loc
-         FROM transfers
-         WHERE transfer_group_id = ?1 AND COALESCE(voided,0)=0",
-      )
-      .map_err(|e| e.to_string())?;
+      Some(pm.to_string())
+    } else {
+      None
+    };
 
-    let rows = stmt
-      .query_map(params![&transfer_group_id], |r| {
-        Ok((
-          r.get::<_, String>(0)?,
-          r.get::<_, i64>(1)?,
-          r.get::<_, String>(2)?,
-          r.get::<_, String>(3)?,
-        ))
+    tx.execute(
+      r#"
+      INSERT INTO returns
+        (return_group_id, mode, returned_total, given_total, diff, diff_payment_method, created_at)
+      VALUES
+        (?1, 'EXCHANGE', ?2, ?3, ?4, ?5, datetime('now','localtime'))
+      "#,
+      params![
+        &exchange_group_id,
+        returned_total_calc,
+        given_total_calc,
+        diff_calc,
+        diff_pm_norm
+      ],
+    )?;
+
+    let given: Vec<serde_json::Value> = payload
+      .given
+      .iter()
+      .filter(|it| it.qty > 0)
+      .map(|it| {
+        serde_json::json!({
+          "barcode": &it.barcode,
+          "qty": it.qty,
+          "unit_price": it.unit_price,
+          "total": it.unit_price * it.qty as f64,
+          "sold_from": &it.sold_from,
+        })
       })
-      .map_err(|e| e.to_string())?;
+      .collect();
+    enqueue_event(
+      &tx,
+      "exchange.created",
+      serde_json::json!({
+        "exchange_group_id": &exchange_group_id,
+        "returned": {
+          "barcode": &payload.returned.barcode,
+          "qty": payload.returned.qty,
+          "unit_price": payload.returned.unit_price,
+          "total": returned_total_calc,
+          "return_to": &payload.returned.return_to,
+        },
+        "given": given,
+        "returned_total": returned_total_calc,
+        "given_total": given_total_calc,
+        "diff": diff_calc,
+        "diff_payment_method": &diff_pm_norm,
+        "stock_changes": &changes,
+      }),
+    )?;
+    let events = stock_events(&tx, &changes)?;
+
+    tx.commit()?;
+    drop(conn);
+    self.emit(events);
+
+    Ok(CreateExchangeResult {
+      exchange_group_id,
+      lines,
+      returned_total: returned_total_calc,
+      given_total: given_total_calc,
+      diff: diff_calc,
+    })
+  }
 
-    for r in rows {
-      let (bc, qty, from_loc, to_loc) = r.map_err(|e| e.to_string())?;
+  pub fn create_transfer(&self, mut payload: CreateTransferPayload) -> Result<CreateTransferResult, PosError> {
+    if payload.items.is_empty() {
+      return Err(PosError::Validation(tr!("Sepet boş")));
+    }
+    for it in payload.items.iter_mut() {
+      it.barcode = it.barcode.trim().to_string();
+      it.from_loc = it.from_loc.trim().to_string();
+      it.to_loc = it.to_loc.trim().to_string();
+    }
+
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
 
-      // geri al
-      let from_col = col_for_loc(&from_loc);
-      let to_col = col_for_loc(&to_loc);
+    let transfer_group_id = format!("T{}", chrono_like_id());
 
+    let note_norm: Option<String> = payload.note.as_ref().and_then(|s| {
+      let t = s.trim();
+      if t.is_empty() { None } else { Some(t.to_string()) }
+    });
+
+    use std::collections::HashMap;
+    let mut need: HashMap<(String, String), i64> = HashMap::new(); 
+    for it in &payload.items {
+      let q = if it.qty <= 0 { 1 } else { it.qty };
+      if it.from_loc == it.to_loc {
+        return Err(PosError::Validation(tr!("Nereden ve nereye aynı olamaz")));
+      }
+      *need.entry((it.barcode.clone(), it.from_loc.clone())).or_insert(0) += q;
+    }
+
+    for ((bc, from_loc), q) in need.iter() {
+      let col = col_for_loc(from_loc);
+      let sql = format!("SELECT COALESCE({},0) FROM products WHERE barcode = ?1", col);
+
+      let stock: i64 = tx
+        .query_row(&sql, params![bc], |r| r.get(0))
+        .optional()?
+        .ok_or_else(|| PosError::NotFound(tr!("Ürün bulunamadı: {}", bc)))?;
+
+      if stock < *q {
+        return Err(PosError::InsufficientStock {
+          barcode: bc.clone(),
+          location: from_loc.clone(),
+          available: stock,
+          requested: *q,
+        });
+      }
+    }
+
+    let mut lines: i64 = 0;
+    let mut event_lines = Vec::new();
+    let mut changes = Vec::new();
+    for it in payload.items {
+      let qty = if it.qty <= 0 { 1 } else { it.qty };
+      if it.from_loc == it.to_loc {
+        return Err(PosError::Validation(tr!("Nereden ve nereye aynı olamaz")));
+      }
+
+      let from_col = col_for_loc(&it.from_loc);
+      let to_col = col_for_loc(&it.to_loc);
+
+      // 1) Kaynaktan düş
       tx.execute(
-        &format!("UPDATE products SET {} = COALESCE({},0) - ?1 WHERE barcode = ?2", to_col, to_col),
-        params![qty, &bc],
-      )
-      .map_err(|e| e.to_string())?;
+        &format!(
+          "UPDATE products SET {c} = COALESCE({c},0) - ?1 WHERE barcode = ?2",
+          c = from_col
+        ),
+        params![qty, &it.barcode],
+      )?;
 
+      // 2) Hedefe ekle
       tx.execute(
-        &format!("UPDATE products SET {} = COALESCE({},0) + ?1 WHERE barcode = ?2", from_col, from_col),
-        params![qty, &bc],
-      )
-      .map_err(|e| e.to_string())?;
+        &format!(
+          "UPDATE products SET {c} = COALESCE({c},0) + ?1 WHERE barcode = ?2",
+          c = to_col
+        ),
+        params![qty, &it.barcode],
+      )?;
 
-      restored_lines += 1;
+      // kayıt ekle (undo + rapor için)
+      tx.execute(
+        r#"
+        INSERT INTO transfers (
+          product_barcode, qty, from_loc, to_loc, transfer_group_id, note, transferred_at, voided
+        ) VALUES (
+          ?1, ?2, ?3, ?4, ?5, ?6, datetime('now','localtime'), 0
+        )
+        "#,
+        params![
+          &it.barcode,
+          qty,
+          it.from_loc.trim().to_uppercase(),
+          it.to_loc.trim().to_uppercase(),
+          &transfer_group_id,
+          note_norm,
+        ],
+      )?;
+      changes.push(stock_change(&tx, &it.barcode, &it.from_loc, -qty)?);
+      changes.push(stock_change(&tx, &it.barcode, &it.to_loc, qty)?);
+
+      lines += 1;
+      event_lines.push(serde_json::json!({
+        "barcode": &it.barcode,
+        "qty": qty,
+        "from_loc": it.from_loc.trim().to_uppercase(),
+        "to_loc": it.to_loc.trim().to_uppercase(),
+      }));
     }
+
+    enqueue_event(
+      &tx,
+      "transfer.created",
+      serde_json::json!({
+        "transfer_group_id": &transfer_group_id,
+        "note": &note_norm,
+        "lines": event_lines,
+        "stock_changes": &changes,
+      }),
+    )?;
+    let events = stock_events(&tx, &changes)?;
+
+    tx.commit()?;
+    drop(conn);
+    self.emit(events);
+
+    Ok(CreateTransferResult {
+      transfer_group_id,
+      lines,
+    })
   }
 
-  tx.execute(
-    "UPDATE transfers SET voided = 1 WHERE transfer_group_id = ?1 AND COALESCE(voided,0)=0",
-    params![&transfer_group_id],
-  )
-  .map_err(|e| e.to_string())?;
+  pub fn undo_last_transfer(&self) -> Result<UndoLastTransferResult, PosError> {
+    let mut conn = self.write()?;
+    let tx = conn.transaction()?;
+
+    let last_group: Option<String> = tx
+      .query_row(
+        "SELECT transfer_group_id FROM transfers WHERE COALESCE(voided,0)=0 AND transfer_group_id IS NOT NULL ORDER BY id DESC LIMIT 1",
+        [],
+        |r| r.get(0),
+      )
+      .optional()?;
 
-  tx.commit().map_err(|e| e.to_string())?;
+    let transfer_group_id =
+      last_group.ok_or_else(|| PosError::NotFound(tr!("Geri alınacak transfer bulunamadı")))?;
 
-  Ok(UndoLastTransferResult {
-    transfer_group_id,
-    restored_lines,
-  })
+    let mut restored_lines: i64 = 0;
+    let mut changes = Vec::new();
+
+    {
+      let mut stmt = tx
+        .prepare(
+          "SELECT product_barcode, qty, from_loc, to_loc
+           FROM transfers
+           WHERE transfer_group_id = ?1 AND COALESCE(voided,0)=0",
+        )?;
+
+      let rows = stmt
+        .query_map(params![&transfer_group_id], |r| {
+          Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, i64>(1)?,
+            r.get::<_, String>(2)?,
+            r.get::<_, String>(3)?,
+          ))
+        })?;
+
+      for r in rows {
+        let (bc, qty, from_loc, to_loc) = r?;
+
+        // geri al
+        let from_col = col_for_loc(&from_loc);
+        let to_col = col_for_loc(&to_loc);
+
+        tx.execute(
+          &format!("UPDATE products SET {} = COALESCE({},0) - ?1 WHERE barcode = ?2", to_col, to_col),
+          params![qty, &bc],
+        )?;
+
+        tx.execute(
+          &format!("UPDATE products SET {} = COALESCE({},0) + ?1 WHERE barcode = ?2", from_col, from_col),
+          params![qty, &bc],
+        )?;
+        changes.push(stock_change(&tx, &bc, &to_loc, -qty)?);
+        changes.push(stock_change(&tx, &bc, &from_loc, qty)?);
+
+        restored_lines += 1;
+      }
+    }
+
+    tx.execute(
+      "UPDATE transfers SET voided = 1 WHERE transfer_group_id = ?1 AND COALESCE(voided,0)=0",
+      params![&transfer_group_id],
+    )?;
+    enqueue_event(
+      &tx,
+      "transfer.undone",
+      serde_json::json!({
+        "transfer_group_id": &transfer_group_id,
+        "stock_changes": &changes,
+      }),
+    )?;
+    let events = stock_events(&tx, &changes)?;
+
+    tx.commit()?;
+    drop(conn);
+    self.emit(events);
+
+    Ok(UndoLastTransferResult {
+      transfer_group_id,
+      restored_lines,
+    })
+  }
 }
 // ==================== ANALİZ FONKSİYONLARI ====================
 
@@ -3552,69 +7107,79 @@ pub struct VelocityRow {
   pub days_to_empty: Option<i64>,
 }
 
-/// Son `days` günde satışlara göre hız analizi.
-/// Sadece o dönemde en az 1 satış yapan aktif ürünler dahil edilir.
-pub fn get_velocity_report(days: i64) -> Result<Vec<VelocityRow>, String> {
-  let conn = get_conn()?;
-  let cutoff = format!("-{} days", days);
-  let days_f = days as f64;
-
-  let sql = "
-    SELECT
-      p.barcode,
-      p.name,
-      p.color,
-      p.size,
-      p.category,
-      COALESCE(SUM(s.qty), 0)                                              AS total_sold,
-      COALESCE(p.magaza_stok, 0) + COALESCE(p.depo_stok, 0)               AS current_stock
-    FROM products p
-    JOIN sales s ON s.product_barcode = p.barcode
-      AND COALESCE(s.voided, 0) = 0
-      AND s.sold_at >= datetime('now', ?1, 'localtime')
-    WHERE COALESCE(p.is_active, 1) = 1
-    GROUP BY p.barcode
-    ORDER BY total_sold DESC
-  ";
+impl ReportTotals for Vec<VelocityRow> {
+  fn totals(&self) -> Vec<(&'static str, f64)> {
+    vec![
+      ("products", self.len() as f64),
+      ("total_sold", self.iter().map(|r| r.total_sold as f64).sum()),
+      ("daily_avg", self.iter().map(|r| r.daily_avg).sum()),
+    ]
+  }
+}
 
-  let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-  let rows = stmt
-    .query_map(params![&cutoff], |r| {
-      Ok((
-        r.get::<_, String>(0)?,
-        r.get::<_, String>(1)?,
-        r.get::<_, Option<String>>(2)?,
-        r.get::<_, Option<String>>(3)?,
-        r.get::<_, Option<String>>(4)?,
-        r.get::<_, i64>(5)?,
-        r.get::<_, i64>(6)?,
-      ))
-    })
-    .map_err(|e| e.to_string())?;
-
-  let mut result = Vec::new();
-  for row in rows {
-    let (barcode, name, color, size, category, total_sold, current_stock) =
-      row.map_err(|e| e.to_string())?;
-    let daily_avg = total_sold as f64 / days_f;
-    let days_to_empty = if daily_avg > 0.0 && current_stock > 0 {
-      Some((current_stock as f64 / daily_avg).ceil() as i64)
-    } else {
-      None
-    };
-    result.push(VelocityRow {
-      barcode,
-      name,
-      color,
-      size,
-      category,
-      total_sold,
-      daily_avg,
-      current_stock,
-      days_to_empty,
-    });
+impl Store {
+  /// Dönem içindeki satışlara göre hız analizi (günlük ortalama = satılan / dönemdeki gün).
+  /// Sadece o dönemde en az 1 satış yapan aktif ürünler dahil edilir.
+  pub fn get_velocity_report(&self, period: &Period) -> Result<Vec<VelocityRow>, PosError> {
+    let conn = self.read()?;
+    let days_f = period.days() as f64;
+
+    let sql = "
+      SELECT
+        p.barcode,
+        p.name,
+        p.color,
+        p.size,
+        p.category,
+        COALESCE(SUM(s.qty), 0)                                              AS total_sold,
+        COALESCE(p.magaza_stok, 0) + COALESCE(p.depo_stok, 0)               AS current_stock
+      FROM products p
+      JOIN sales s ON s.product_barcode = p.barcode
+        AND COALESCE(s.voided, 0) = 0
+        AND date(s.sold_at) BETWEEN ?1 AND ?2
+      WHERE COALESCE(p.is_active, 1) = 1
+      GROUP BY p.barcode
+      ORDER BY total_sold DESC
+    ";
+
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+      .query_map(params![period.first_day(), period.last_day()], |r| {
+        Ok((
+          r.get::<_, String>(0)?,
+          r.get::<_, String>(1)?,
+          r.get::<_, Option<String>>(2)?,
+          r.get::<_, Option<String>>(3)?,
+          r.get::<_, Option<String>>(4)?,
+          r.get::<_, i64>(5)?,
+          r.get::<_, i64>(6)?,
+        ))
+      })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+      let (barcode, name, color, size, category, total_sold, current_stock) =
+        row?;
+      let daily_avg = total_sold as f64 / days_f;
+      let days_to_empty = if daily_avg > 0.0 && current_stock > 0 {
+        Some((current_stock as f64 / daily_avg).ceil() as i64)
+      } else {
+        None
+      };
+      result.push(VelocityRow {
+        barcode,
+        name,
+        color,
+        size,
+        category,
+        total_sold,
+        daily_avg,
+        current_stock,
+        days_to_empty,
+      });
+    }
+    Ok(result)
   }
-  Ok(result)
 }
 
 #[derive(serde::Serialize)]
@@ -3629,68 +7194,69 @@ pub struct DeadStockRow {
   pub last_sold_at: Option<String>,
 }
 
-/// Stoğu > 0 olan, `min_days` veya daha uzun süredir satış yapılmamış ürünler.
-/// `last_sold_at` NULL olanlarda created_at baz alınır.
-/// En uzun süredir satılmayan en üstte.
-pub fn get_dead_stock(min_days: i64) -> Result<Vec<DeadStockRow>, String> {
-  let conn = get_conn()?;
-
-  let sql = "
-    SELECT
-      p.barcode,
-      p.name,
-      p.color,
-      p.size,
-      p.category,
-      COALESCE(p.magaza_stok, 0) + COALESCE(p.depo_stok, 0)  AS stock,
-      MAX(s.sold_at)                                           AS last_sold_at,
-      CAST(
-        julianday('now', 'localtime') -
-        julianday(COALESCE(MAX(s.sold_at), p.created_at))
-        AS INTEGER
-      )                                                        AS days_since
-    FROM products p
-    LEFT JOIN sales s
-      ON s.product_barcode = p.barcode AND COALESCE(s.voided, 0) = 0
-    WHERE COALESCE(p.is_active, 1) = 1
-      AND (COALESCE(p.magaza_stok, 0) + COALESCE(p.depo_stok, 0)) > 0
-    GROUP BY p.barcode
-    HAVING days_since >= ?1
-    ORDER BY days_since DESC
-  ";
-
-  let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-  let rows = stmt
-    .query_map(params![min_days], |r| {
-      Ok((
-        r.get::<_, String>(0)?,
-        r.get::<_, String>(1)?,
-        r.get::<_, Option<String>>(2)?,
-        r.get::<_, Option<String>>(3)?,
-        r.get::<_, Option<String>>(4)?,
-        r.get::<_, i64>(5)?,
-        r.get::<_, Option<String>>(6)?,
-        r.get::<_, i64>(7)?,
-      ))
-    })
-    .map_err(|e| e.to_string())?;
-
-  let mut result = Vec::new();
-  for row in rows {
-    let (barcode, name, color, size, category, stock, last_sold_at, days_since_last_sale) =
-      row.map_err(|e| e.to_string())?;
-    result.push(DeadStockRow {
-      barcode,
-      name,
-      color,
-      size,
-      category,
-      stock,
-      days_since_last_sale,
-      last_sold_at,
-    });
+impl Store {
+  /// Stoğu > 0 olan, `min_days` veya daha uzun süredir satış yapılmamış ürünler.
+  /// `last_sold_at` NULL olanlarda created_at baz alınır.
+  /// En uzun süredir satılmayan en üstte.
+  pub fn get_dead_stock(&self, min_days: i64) -> Result<Vec<DeadStockRow>, PosError> {
+    let conn = self.read()?;
+
+    let sql = "
+      SELECT
+        p.barcode,
+        p.name,
+        p.color,
+        p.size,
+        p.category,
+        COALESCE(p.magaza_stok, 0) + COALESCE(p.depo_stok, 0)  AS stock,
+        MAX(s.sold_at)                                           AS last_sold_at,
+        CAST(
+          julianday('now', 'localtime') -
+          julianday(COALESCE(MAX(s.sold_at), p.created_at))
+          AS INTEGER
+        )                                                        AS days_since
+      FROM products p
+      LEFT JOIN sales s
+        ON s.product_barcode = p.barcode AND COALESCE(s.voided, 0) = 0
+      WHERE COALESCE(p.is_active, 1) = 1
+        AND (COALESCE(p.magaza_stok, 0) + COALESCE(p.depo_stok, 0)) > 0
+      GROUP BY p.barcode
+      HAVING days_since >= ?1
+      ORDER BY days_since DESC
+    ";
+
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+      .query_map(params![min_days], |r| {
+        Ok((
+          r.get::<_, String>(0)?,
+          r.get::<_, String>(1)?,
+          r.get::<_, Option<String>>(2)?,
+          r.get::<_, Option<String>>(3)?,
+          r.get::<_, Option<String>>(4)?,
+          r.get::<_, i64>(5)?,
+          r.get::<_, Option<String>>(6)?,
+          r.get::<_, i64>(7)?,
+        ))
+      })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+      let (barcode, name, color, size, category, stock, last_sold_at, days_since_last_sale) =
+        row?;
+      result.push(DeadStockRow {
+        barcode,
+        name,
+        color,
+        size,
+        category,
+        stock,
+        days_since_last_sale,
+        last_sold_at,
+      });
+    }
+    Ok(result)
   }
-  Ok(result)
 }
 
 #[derive(serde::Serialize)]
@@ -3704,84 +7270,104 @@ pub struct CategoryMarginRow {
   pub profit_share_pct: f64,
 }
 
-/// Son `days` günde kategori bazlı ciro, brüt kâr, marj %.
-/// Alış fiyatı 0/NULL olan ürünler sadece ciro tarafına girer, kâra dahil edilmez.
-pub fn get_category_margin(days: i64) -> Result<Vec<CategoryMarginRow>, String> {
-  let conn = get_conn()?;
-  let cutoff = format!("-{} days", days);
-
-  let sql = "
-    SELECT
-      COALESCE(p.category, 'Kategorisiz')             AS category,
-      SUM(s.qty)                                       AS total_qty,
-      SUM(s.qty * s.unit_price)                        AS revenue,
-      SUM(
-        CASE WHEN COALESCE(p.buy_price, 0) > 0
-          THEN s.qty * p.buy_price
-          ELSE 0
-        END
-      )                                                AS cost,
-      SUM(
-        CASE WHEN COALESCE(p.buy_price, 0) > 0
-          THEN s.qty * (s.unit_price - p.buy_price)
-          ELSE 0
-        END
-      )                                                AS gross_profit
-    FROM sales s
-    JOIN products p ON p.barcode = s.product_barcode
-    WHERE COALESCE(s.voided, 0) = 0
-      AND s.sold_at >= datetime('now', ?1, 'localtime')
-    GROUP BY category
-    ORDER BY gross_profit DESC
-  ";
-
-  let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-  let rows = stmt
-    .query_map(params![&cutoff], |r| {
-      Ok((
-        r.get::<_, String>(0)?,
-        r.get::<_, i64>(1)?,
-        r.get::<_, f64>(2)?,
-        r.get::<_, f64>(3)?,
-        r.get::<_, f64>(4)?,
-      ))
-    })
-    .map_err(|e| e.to_string())?;
-
-  let mut result: Vec<(String, i64, f64, f64, f64)> = Vec::new();
-  for row in rows {
-    result.push(row.map_err(|e| e.to_string())?);
+impl ReportTotals for Vec<CategoryMarginRow> {
+  fn totals(&self) -> Vec<(&'static str, f64)> {
+    let revenue: f64 = self.iter().map(|r| r.revenue).sum();
+    let gross_profit: f64 = self.iter().map(|r| r.gross_profit).sum();
+    let margin_pct = if revenue > 0.0 {
+      (gross_profit / revenue * 100.0 * 10.0).round() / 10.0
+    } else {
+      0.0
+    };
+    vec![
+      ("total_qty", self.iter().map(|r| r.total_qty as f64).sum()),
+      ("revenue", revenue),
+      ("cost", self.iter().map(|r| r.cost).sum()),
+      ("gross_profit", gross_profit),
+      ("margin_pct", margin_pct),
+    ]
   }
+}
 
-  // Toplam kâr: profit_share_pct hesabı için
-  let total_profit: f64 = result.iter().map(|r| r.4.max(0.0)).sum();
+impl Store {
+  /// Dönem içinde kategori bazlı ciro, brüt kâr, marj %.
+  /// Alış fiyatı 0/NULL olan ürünler sadece ciro tarafına girer, kâra dahil edilmez.
+  pub fn get_category_margin(&self, period: &Period) -> Result<Vec<CategoryMarginRow>, PosError> {
+    let conn = self.read()?;
 
-  Ok(
-    result
-      .into_iter()
-      .map(|(category, total_qty, revenue, cost, gross_profit)| {
-        let margin_pct = if revenue > 0.0 {
-          (gross_profit / revenue * 100.0 * 10.0).round() / 10.0
-        } else {
-          0.0
-        };
-        let profit_share_pct = if total_profit > 0.0 {
-          (gross_profit.max(0.0) / total_profit * 100.0 * 10.0).round() / 10.0
-        } else {
-          0.0
-        };
-        CategoryMarginRow {
-          category,
-          total_qty,
-          revenue,
-          cost,
-          gross_profit,
-          margin_pct,
-          profit_share_pct,
-        }
-      })
-      .collect(),
-  )
+    let sql = "
+      SELECT
+        COALESCE(c.name, ?3)                             AS category,
+        SUM(s.qty)                                       AS total_qty,
+        SUM(s.qty * s.unit_price)                        AS revenue,
+        SUM(
+          CASE WHEN COALESCE(p.buy_price, 0) > 0
+            THEN s.qty * p.buy_price
+            ELSE 0
+          END
+        )                                                AS cost,
+        SUM(
+          CASE WHEN COALESCE(p.buy_price, 0) > 0
+            THEN s.qty * (s.unit_price - p.buy_price)
+            ELSE 0
+          END
+        )                                                AS gross_profit
+      FROM sales s
+      JOIN products p ON p.barcode = s.product_barcode
+      LEFT JOIN categories c ON c.id = p.category_id
+      WHERE COALESCE(s.voided, 0) = 0
+        AND date(s.sold_at) BETWEEN ?1 AND ?2
+      GROUP BY p.category_id
+      ORDER BY gross_profit DESC
+    ";
+
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+      .query_map(params![period.first_day(), period.last_day(), tr!("Kategorisiz")], |r| {
+        Ok((
+          r.get::<_, String>(0)?,
+          r.get::<_, i64>(1)?,
+          r.get::<_, f64>(2)?,
+          r.get::<_, f64>(3)?,
+          r.get::<_, f64>(4)?,
+        ))
+      })?;
+
+    let mut result: Vec<(String, i64, f64, f64, f64)> = Vec::new();
+    for row in rows {
+      result.push(row?);
+    }
+
+    // Toplam kâr: profit_share_pct hesabı için
+    let total_profit: f64 = result.iter().map(|r| r.4.max(0.0)).sum();
+
+    Ok(
+      result
+        .into_iter()
+        .map(|(category, total_qty, revenue, cost, gross_profit)| {
+          let margin_pct = if revenue > 0.0 {
+            (gross_profit / revenue * 100.0 * 10.0).round() / 10.0
+          } else {
+            0.0
+          };
+          let profit_share_pct = if total_profit > 0.0 {
+            (gross_profit.max(0.0) / total_profit * 100.0 * 10.0).round() / 10.0
+          } else {
+            0.0
+          };
+          CategoryMarginRow {
+            category,
+            total_qty,
+            revenue,
+            cost,
+            gross_profit,
+            margin_pct,
+            profit_share_pct,
+          }
+        })
+        .collect(),
+    )
+  }
 }
 
 #[derive(serde::Serialize)]
@@ -3795,53 +7381,64 @@ pub struct BasketPairRow {
   pub together_count: i64,
 }
 
-/// Aynı fişte (sale_group_id) birlikte satılan ürün çiftleri.
-pub fn get_basket_pairs(limit: i64) -> Result<Vec<BasketPairRow>, String> {
-  let conn = get_conn()?;
-
-  let sql = "
-    SELECT
-      a.product_barcode                AS barcode_a,
-      b.product_barcode                AS barcode_b,
-      pa.name                          AS name_a,
-      pb.name                          AS name_b,
-      pa.color                         AS color_a,
-      pb.color                         AS color_b,
-      COUNT(*)                         AS together_count
-    FROM sales a
-    JOIN sales b
-      ON  a.sale_group_id = b.sale_group_id
-      AND a.product_barcode < b.product_barcode
-      AND COALESCE(b.voided, 0) = 0
-    JOIN products pa ON pa.barcode = a.product_barcode
-    JOIN products pb ON pb.barcode = b.product_barcode
-    WHERE a.sale_group_id IS NOT NULL
-      AND COALESCE(a.voided, 0) = 0
-    GROUP BY a.product_barcode, b.product_barcode
-    ORDER BY together_count DESC
-    LIMIT ?1
-  ";
-
-  let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-  let rows = stmt
-    .query_map(params![limit], |r| {
-      Ok(BasketPairRow {
-        barcode_a:      r.get(0)?,
-        barcode_b:      r.get(1)?,
-        name_a:         r.get(2)?,
-        name_b:         r.get(3)?,
-        color_a:        r.get(4)?,
-        color_b:        r.get(5)?,
-        together_count: r.get(6)?,
-      })
-    })
-    .map_err(|e| e.to_string())?;
+impl ReportTotals for Vec<BasketPairRow> {
+  fn totals(&self) -> Vec<(&'static str, f64)> {
+    vec![
+      ("pairs", self.len() as f64),
+      ("together_count", self.iter().map(|r| r.together_count as f64).sum()),
+    ]
+  }
+}
+
+impl Store {
+  /// Dönem içinde aynı fişte (sale_group_id) birlikte satılan ürün çiftleri.
+  pub fn get_basket_pairs(&self, period: &Period, limit: i64) -> Result<Vec<BasketPairRow>, PosError> {
+    let conn = self.read()?;
 
-  let mut result = Vec::new();
-  for row in rows {
-    result.push(row.map_err(|e| e.to_string())?);
+    let sql = "
+      SELECT
+        a.product_barcode                AS barcode_a,
+        b.product_barcode                AS barcode_b,
+        pa.name                          AS name_a,
+        pb.name                          AS name_b,
+        pa.color                         AS color_a,
+        pb.color                         AS color_b,
+        COUNT(*)                         AS together_count
+      FROM sales a
+      JOIN sales b
+        ON  a.sale_group_id = b.sale_group_id
+        AND a.product_barcode < b.product_barcode
+        AND COALESCE(b.voided, 0) = 0
+      JOIN products pa ON pa.barcode = a.product_barcode
+      JOIN products pb ON pb.barcode = b.product_barcode
+      WHERE a.sale_group_id IS NOT NULL
+        AND COALESCE(a.voided, 0) = 0
+        AND date(a.sold_at) BETWEEN ?2 AND ?3
+      GROUP BY a.product_barcode, b.product_barcode
+      ORDER BY together_count DESC
+      LIMIT ?1
+    ";
+
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+      .query_map(params![limit, period.first_day(), period.last_day()], |r| {
+        Ok(BasketPairRow {
+          barcode_a:      r.get(0)?,
+          barcode_b:      r.get(1)?,
+          name_a:         r.get(2)?,
+          name_b:         r.get(3)?,
+          color_a:        r.get(4)?,
+          color_b:        r.get(5)?,
+          together_count: r.get(6)?,
+        })
+      })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+      result.push(row?);
+    }
+    Ok(result)
   }
-  Ok(result)
 }
 
 #[derive(serde::Serialize)]
@@ -3856,50 +7453,51 @@ pub struct LowStockRow {
   pub total_stock: i64,
 }
 
-/// Toplam stoğu `max_stock` veya altında olan aktif ürünler, en azdan en çoğa sıralı.
-pub fn get_low_stock(max_stock: i64) -> Result<Vec<LowStockRow>, String> {
-  let conn = get_conn()?;
+impl Store {
+  /// Toplam stoğu `max_stock` veya altında olan aktif ürünler, en azdan en çoğa sıralı.
+  pub fn get_low_stock(&self, max_stock: i64) -> Result<Vec<LowStockRow>, PosError> {
+    let conn = self.read()?;
 
-  let sql = "
-    SELECT
-      barcode,
-      name,
-      color,
-      size,
-      category,
-      COALESCE(magaza_stok, 0)                                        AS magaza_stok,
-      COALESCE(depo_stok, 0)                                          AS depo_stok,
-      COALESCE(magaza_stok, 0) + COALESCE(depo_stok, 0)              AS total_stock
-    FROM products
-    WHERE COALESCE(is_active, 1) = 1
-      AND (COALESCE(magaza_stok, 0) + COALESCE(depo_stok, 0)) <= ?1
-    ORDER BY total_stock ASC, name ASC
-  ";
-
-  let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-  let rows = stmt
-    .query_map(params![max_stock], |r| {
-      Ok(LowStockRow {
-        barcode:      r.get(0)?,
-        name:         r.get(1)?,
-        color:        r.get(2)?,
-        size:         r.get(3)?,
-        category:     r.get(4)?,
-        magaza_stok:  r.get(5)?,
-        depo_stok:    r.get(6)?,
-        total_stock:  r.get(7)?,
-      })
-    })
-    .map_err(|e| e.to_string())?;
+    let sql = "
+      SELECT
+        barcode,
+        name,
+        color,
+        size,
+        category,
+        COALESCE(magaza_stok, 0)                                        AS magaza_stok,
+        COALESCE(depo_stok, 0)                                          AS depo_stok,
+        COALESCE(magaza_stok, 0) + COALESCE(depo_stok, 0)              AS total_stock
+      FROM products
+      WHERE COALESCE(is_active, 1) = 1
+        AND (COALESCE(magaza_stok, 0) + COALESCE(depo_stok, 0)) <= ?1
+      ORDER BY total_stock ASC, name ASC
+    ";
+
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+      .query_map(params![max_stock], |r| {
+        Ok(LowStockRow {
+          barcode:      r.get(0)?,
+          name:         r.get(1)?,
+          color:        r.get(2)?,
+          size:         r.get(3)?,
+          category:     r.get(4)?,
+          magaza_stok:  r.get(5)?,
+          depo_stok:    r.get(6)?,
+          total_stock:  r.get(7)?,
+        })
+      })?;
 
-  let mut result = Vec::new();
-  for row in rows {
-    result.push(row.map_err(|e| e.to_string())?);
+    let mut result = Vec::new();
+    for row in rows {
+      result.push(row?);
+    }
+    Ok(result)
   }
-  Ok(result)
 }
 
-fn ensure_returns_cascade_triggers(conn: &Connection) -> Result<(), String> {
+fn ensure_returns_cascade_triggers(conn: &Connection) -> Result<(), PosError> {
   conn.execute_batch(
     r#"
       -- drop possible legacy/duplicate names first
@@ -3920,11 +7518,11 @@ fn ensure_returns_cascade_triggers(conn: &Connection) -> Result<(), String> {
         DELETE FROM exchange_items WHERE exchange_group_id = OLD.return_group_id;
       END;
     "#
-  ).map_err(|e| e.to_string())?;
+  )?;
   Ok(())
 }
 
-fn seed_default_dictionaries(conn: &Connection) -> Result<(), String> {
+fn seed_default_dictionaries(conn: &Connection) -> Result<(), PosError> {
   // Eğer tablolar boşsa (veya çok az kayıt varsa) default değerleri bas.
   // NOT: INSERT OR IGNORE + UNIQUE(name) sayesinde tekrar tekrar çalışsa da sorun olmaz.
 
@@ -3949,8 +7547,7 @@ fn seed_default_dictionaries(conn: &Connection) -> Result<(), String> {
         .execute(
           "INSERT OR IGNORE INTO categories (name, is_active, created_at) VALUES (?1, 1, datetime('now','localtime'))",
           params![name],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     }
   }
 
@@ -3967,8 +7564,7 @@ fn seed_default_dictionaries(conn: &Connection) -> Result<(), String> {
         .execute(
           "INSERT OR IGNORE INTO colors (name, is_active, created_at) VALUES (?1, 1, datetime('now','localtime'))",
           params![name],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     }
   }
 
@@ -3985,8 +7581,7 @@ fn seed_default_dictionaries(conn: &Connection) -> Result<(), String> {
         .execute(
           "INSERT OR IGNORE INTO sizes (name, sort_order, is_active, created_at) VALUES (?1, ?2, 1, datetime('now','localtime'))",
           params![name, so],
-        )
-        .map_err(|e| e.to_string())?;
+        )?;
     }
   }
 
@@ -0,0 +1,127 @@
+// -------------------- HATA TİPİ --------------------
+//
+// Komutlar hatayı ön yüze { code, message, details } olarak döner:
+//   code    : sabit kod (NOT_FOUND, INSUFFICIENT_STOCK, ...); ön yüz davranışını buna göre seçer
+//   message : kullanıcıya gösterilecek metin (seçili dilde, bkz. i18n)
+//   details : yapılandırılmış ek bilgi (yoksa null)
+
+use serde::ser::SerializeStruct;
+use serde_json::json;
+
+use crate::i18n::tr;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PosError {
+  #[error("{0}")]
+  NotFound(String),
+
+  #[error("{}", tr!("Yetersiz stok: {} ({} stok: {}, istenen: {})", .barcode, .location, .available, .requested))]
+  InsufficientStock {
+    barcode: String,
+    location: String,
+    available: i64,
+    requested: i64,
+  },
+
+  // girdi / kural hatası; kullanıcı düzeltebilir
+  #[error("{0}")]
+  Validation(String),
+
+  // mevcut kayıtlarla çakışma (tekrar eden barkod, kullanımdaki kayıt, ...)
+  #[error("{0}")]
+  Conflict(String),
+
+  #[error("{}", tr!("Veritabanı hatası: {}", .0))]
+  Db(String),
+
+  #[error("{}", tr!("Dosya hatası: {}", .0))]
+  Io(String),
+}
+
+impl PosError {
+  pub fn code(&self) -> &'static str {
+    match self {
+      PosError::NotFound(_) => "NOT_FOUND",
+      PosError::InsufficientStock { .. } => "INSUFFICIENT_STOCK",
+      PosError::Validation(_) => "VALIDATION",
+      PosError::Conflict(_) => "CONFLICT",
+      PosError::Db(_) => "DB",
+      PosError::Io(_) => "IO",
+    }
+  }
+
+  fn details(&self) -> serde_json::Value {
+    match self {
+      PosError::InsufficientStock { barcode, location, available, requested } => json!({
+        "barcode": barcode,
+        "location": location,
+        "available": available,
+        "requested": requested,
+      }),
+      _ => serde_json::Value::Null,
+    }
+  }
+}
+
+impl serde::Serialize for PosError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut s = serializer.serialize_struct("PosError", 3)?;
+    s.serialize_field("code", self.code())?;
+    s.serialize_field("message", &self.to_string())?;
+    s.serialize_field("details", &self.details())?;
+    s.end()
+  }
+}
+
+impl From<rusqlite::Error> for PosError {
+  fn from(e: rusqlite::Error) -> Self {
+    PosError::Db(e.to_string())
+  }
+}
+
+impl From<r2d2::Error> for PosError {
+  fn from(e: r2d2::Error) -> Self {
+    PosError::Db(e.to_string())
+  }
+}
+
+impl From<std::io::Error> for PosError {
+  fn from(e: std::io::Error) -> Self {
+    PosError::Io(e.to_string())
+  }
+}
+
+impl From<rust_xlsxwriter::XlsxError> for PosError {
+  fn from(e: rust_xlsxwriter::XlsxError) -> Self {
+    PosError::Io(e.to_string())
+  }
+}
+
+fn extended_code(e: &rusqlite::Error) -> Option<std::os::raw::c_int> {
+  match e {
+    rusqlite::Error::SqliteFailure(f, _) => Some(f.extended_code),
+    _ => None,
+  }
+}
+
+/// FOREIGN KEY kısıtı ihlali (kayda başka tablolardan referans var)
+pub fn is_foreign_key_violation(e: &rusqlite::Error) -> bool {
+  match e {
+    rusqlite::Error::SqliteFailure(f, msg) => {
+      f.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_FOREIGNKEY
+        // tablo trigger'ı varken SQLite genişletilmiş kodu CONSTRAINT_TRIGGER döner;
+        // mesaj SQLite'ın sabit metni
+        || (f.code == rusqlite::ErrorCode::ConstraintViolation
+          && msg.as_deref() == Some("FOREIGN KEY constraint failed"))
+    }
+    _ => false,
+  }
+}
+
+/// UNIQUE / PRIMARY KEY kısıtı ihlali
+pub fn is_unique_violation(e: &rusqlite::Error) -> bool {
+  matches!(
+    extended_code(e),
+    Some(rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE | rusqlite::ffi::SQLITE_CONSTRAINT_PRIMARYKEY)
+  )
+}
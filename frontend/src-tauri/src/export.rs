@@ -0,0 +1,529 @@
+use std::fs;
+use std::path::Path;
+
+use rust_xlsxwriter::{Format, Workbook};
+
+use crate::db;
+use crate::error::PosError;
+
+// -------------------- EXPORT (CSV / XLSX) --------------------
+
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+  Csv,
+  Xlsx,
+}
+
+impl ExportFormat {
+  pub fn extension(self) -> &'static str {
+    match self {
+      ExportFormat::Csv => "csv",
+      ExportFormat::Xlsx => "xlsx",
+    }
+  }
+}
+
+/// Dışa aktarılabilecek raporlar/listeler.
+/// Parametreler ilgili db fonksiyonlarının parametreleriyle aynı.
+#[derive(serde::Deserialize)]
+#[serde(tag = "report", rename_all = "snake_case")]
+pub enum ExportReport {
+  VelocityReport {
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+  },
+  DeadStock { min_days: i64 },
+  CategoryMargin {
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+  },
+  BasketPairs {
+    limit: i64,
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+  },
+  LowStock { max_stock: i64 },
+  CashReport {
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+  },
+  Expenses {
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+  },
+  SaleGroups {
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+    #[serde(default)]
+    q: Option<String>,
+  },
+  /// Aktif ürünlerin tamamı; sütunlar PRODUCT_COLUMNS (CSV içe aktarma aynı başlıkları okur)
+  Products,
+}
+
+impl ExportReport {
+  fn title(&self) -> &'static str {
+    match self {
+      ExportReport::VelocityReport { .. } => "Satis_Hizi",
+      ExportReport::DeadStock { .. } => "Olu_Stok",
+      ExportReport::CategoryMargin { .. } => "Kategori_Marj",
+      ExportReport::BasketPairs { .. } => "Sepet_Ciftleri",
+      ExportReport::LowStock { .. } => "Dusuk_Stok",
+      ExportReport::CashReport { .. } => "Kasa_Raporu",
+      ExportReport::Expenses { .. } => "Giderler",
+      ExportReport::SaleGroups { .. } => "Satis_Fisleri",
+      ExportReport::Products => "Urunler",
+    }
+  }
+}
+
+pub enum Cell {
+  Text(String),
+  Int(i64),
+  /// Para / oran: 2 ondalık
+  Num(f64),
+  Empty,
+}
+
+impl From<Option<String>> for Cell {
+  fn from(v: Option<String>) -> Self {
+    match v {
+      Some(s) => Cell::Text(s),
+      None => Cell::Empty,
+    }
+  }
+}
+
+impl From<String> for Cell {
+  fn from(v: String) -> Self {
+    Cell::Text(v)
+  }
+}
+
+/// Ürün listesi sütunları (dışa aktarma + import::import_products_csv)
+pub const PRODUCT_COLUMNS: [&str; 10] = [
+  "Barkod", "Ürün Kodu", "Kategori", "Ürün", "Renk", "Beden",
+  "Alış Fiyatı", "Satış Fiyatı", "Mağaza", "Depo",
+];
+
+pub struct Table {
+  pub title: &'static str,
+  pub headers: Vec<&'static str>,
+  pub rows: Vec<Vec<Cell>>,
+}
+
+fn period(from: &Option<String>, to: &Option<String>) -> Result<db::Period, PosError> {
+  db::ReportRange {
+    from: from.clone(),
+    to: to.clone(),
+    compare: false,
+  }
+  .period()
+}
+
+pub fn build_table(store: &db::Store, report: &ExportReport) -> Result<Table, PosError> {
+  let title = report.title();
+
+  let (headers, rows): (Vec<&'static str>, Vec<Vec<Cell>>) = match report {
+    ExportReport::VelocityReport { from, to } => (
+      vec![
+        "Barkod", "Ürün", "Renk", "Beden", "Kategori",
+        "Satılan", "Günlük Ort.", "Mevcut Stok", "Tükenme (gün)",
+      ],
+      store.get_velocity_report(&period(from, to)?)?
+        .into_iter()
+        .map(|r| {
+          vec![
+            r.barcode.into(),
+            r.name.into(),
+            r.color.into(),
+            r.size.into(),
+            r.category.into(),
+            Cell::Int(r.total_sold),
+            Cell::Num(r.daily_avg),
+            Cell::Int(r.current_stock),
+            r.days_to_empty.map(Cell::Int).unwrap_or(Cell::Empty),
+          ]
+        })
+        .collect(),
+    ),
+
+    ExportReport::DeadStock { min_days } => (
+      vec![
+        "Barkod", "Ürün", "Renk", "Beden", "Kategori",
+        "Stok", "Satışsız Gün", "Son Satış",
+      ],
+      store.get_dead_stock(*min_days)?
+        .into_iter()
+        .map(|r| {
+          vec![
+            r.barcode.into(),
+            r.name.into(),
+            r.color.into(),
+            r.size.into(),
+            r.category.into(),
+            Cell::Int(r.stock),
+            Cell::Int(r.days_since_last_sale),
+            r.last_sold_at.into(),
+          ]
+        })
+        .collect(),
+    ),
+
+    ExportReport::CategoryMargin { from, to } => (
+      vec![
+        "Kategori", "Adet", "Ciro", "Maliyet", "Brüt Kâr", "Marj %", "Kâr Payı %",
+      ],
+      store.get_category_margin(&period(from, to)?)?
+        .into_iter()
+        .map(|r| {
+          vec![
+            r.category.into(),
+            Cell::Int(r.total_qty),
+            Cell::Num(r.revenue),
+            Cell::Num(r.cost),
+            Cell::Num(r.gross_profit),
+            Cell::Num(r.margin_pct),
+            Cell::Num(r.profit_share_pct),
+          ]
+        })
+        .collect(),
+    ),
+
+    ExportReport::BasketPairs { limit, from, to } => (
+      vec![
+        "Barkod A", "Ürün A", "Renk A", "Barkod B", "Ürün B", "Renk B", "Birlikte",
+      ],
+      store.get_basket_pairs(&period(from, to)?, *limit)?
+        .into_iter()
+        .map(|r| {
+          vec![
+            r.barcode_a.into(),
+            r.name_a.into(),
+            r.color_a.into(),
+            r.barcode_b.into(),
+            r.name_b.into(),
+            r.color_b.into(),
+            Cell::Int(r.together_count),
+          ]
+        })
+        .collect(),
+    ),
+
+    ExportReport::LowStock { max_stock } => (
+      vec![
+        "Barkod", "Ürün", "Renk", "Beden", "Kategori", "Mağaza", "Depo", "Toplam",
+      ],
+      store.get_low_stock(*max_stock)?
+        .into_iter()
+        .map(|r| {
+          vec![
+            r.barcode.into(),
+            r.name.into(),
+            r.color.into(),
+            r.size.into(),
+            r.category.into(),
+            Cell::Int(r.magaza_stok),
+            Cell::Int(r.depo_stok),
+            Cell::Int(r.total_stock),
+          ]
+        })
+        .collect(),
+    ),
+
+    ExportReport::CashReport { from, to } => (
+      vec![
+        "Gün", "Nakit Satış", "Kart Satış", "Nakit İade", "Kart İade",
+        "Nakit Net", "Kart Net", "Net Toplam",
+      ],
+      store.get_cash_report(&period(from, to)?)?
+        .into_iter()
+        .map(|r| {
+          vec![
+            r.day.into(),
+            Cell::Num(r.cash_sales),
+            Cell::Num(r.card_sales),
+            Cell::Num(r.cash_refunds),
+            Cell::Num(r.card_refunds),
+            Cell::Num(r.cash_net),
+            Cell::Num(r.card_net),
+            Cell::Num(r.net_total),
+          ]
+        })
+        .collect(),
+    ),
+
+    ExportReport::Expenses { from, to } => (
+      vec!["No", "Tarih", "Dönem", "Kategori", "Tutar", "Not"],
+      store.list_expenses(&db::ListQuery {
+        from: from.clone(),
+        to: to.clone(),
+        all: true,
+        ..Default::default()
+      })?
+      .items
+      .into_iter()
+      .map(|r| {
+        vec![
+          Cell::Int(r.id),
+          r.spent_at.into(),
+          r.period.into(),
+          r.category.into(),
+          Cell::Num(r.amount),
+          r.note.into(),
+        ]
+      })
+      .collect(),
+    ),
+
+    ExportReport::SaleGroups { from, to, q } => (
+      vec!["Fiş No", "Tarih", "Adet", "Tutar", "Ödeme", "Tür"],
+      store.list_sale_groups(&db::ListQuery {
+        from: from.clone(),
+        to: to.clone(),
+        q: q.clone(),
+        all: true,
+        ..Default::default()
+      })?
+      .items
+      .into_iter()
+      .map(|r| {
+        vec![
+          r.sale_group_id.into(),
+          r.sold_at.into(),
+          Cell::Int(r.qty),
+          Cell::Num(r.total),
+          r.payment_method.into(),
+          r.kind.into(),
+        ]
+      })
+      .collect(),
+    ),
+
+    ExportReport::Products => (
+      PRODUCT_COLUMNS.to_vec(),
+      store.list_products(&db::ListQuery::all())?
+        .items
+        .into_iter()
+        .map(|p| {
+          vec![
+            p.barcode.into(),
+            p.product_code.into(),
+            p.category.into(),
+            p.name.into(),
+            p.color.into(),
+            p.size.into(),
+            Cell::Num(p.buy_price),
+            Cell::Num(p.sell_price),
+            Cell::Int(p.magaza_stok),
+            Cell::Int(p.depo_stok),
+          ]
+        })
+        .collect(),
+    ),
+  };
+
+  Ok(Table { title, headers, rows })
+}
+
+/// Örn: "Kasa_Raporu_2026-01-31.csv"
+pub fn default_file_name(report: &ExportReport, format: ExportFormat) -> String {
+  let today = chrono::Local::now().format("%Y-%m-%d");
+  format!("{}_{}.{}", report.title(), today, format.extension())
+}
+
+pub fn write_table(path: &Path, table: &Table, format: ExportFormat) -> Result<(), PosError> {
+  match format {
+    ExportFormat::Csv => write_csv(path, table),
+    ExportFormat::Xlsx => write_xlsx(path, table),
+  }
+}
+
+// -------------------- CSV --------------------
+
+/// Türkçe ondalık biçimi: binlik ayırıcı '.', ondalık ayırıcı ','  (örn: -1.234,50)
+pub fn format_tr_decimal(v: f64) -> String {
+  let v = if v.is_finite() { v } else { 0.0 };
+  let fixed = format!("{:.2}", v.abs());
+  let (int_part, frac_part) = fixed.split_once('.').unwrap_or((fixed.as_str(), "00"));
+
+  let mut grouped = String::with_capacity(int_part.len() + int_part.len() / 3);
+  for (i, ch) in int_part.chars().enumerate() {
+    if i > 0 && (int_part.len() - i) % 3 == 0 {
+      grouped.push('.');
+    }
+    grouped.push(ch);
+  }
+
+  // -0,00 yazmasın
+  let sign = if v < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+  format!("{}{},{}", sign, grouped, frac_part)
+}
+
+fn csv_field(s: &str) -> String {
+  // Türkçe Excel ';' ayırıcı bekler (',' ondalık ayırıcı)
+  if s.contains(';') || s.contains('"') || s.contains('\n') || s.contains('\r') {
+    format!("\"{}\"", s.replace('"', "\"\""))
+  } else {
+    s.to_string()
+  }
+}
+
+fn csv_cell(c: &Cell) -> String {
+  match c {
+    Cell::Text(s) => csv_field(s),
+    Cell::Int(n) => n.to_string(),
+    Cell::Num(v) => format_tr_decimal(*v),
+    Cell::Empty => String::new(),
+  }
+}
+
+pub fn write_csv(path: &Path, table: &Table) -> Result<(), PosError> {
+  // UTF-8 BOM: Excel Türkçe karakterleri doğru açsın
+  let mut out = String::from("\u{FEFF}");
+
+  let header: Vec<String> = table.headers.iter().map(|h| csv_field(h)).collect();
+  out.push_str(&header.join(";"));
+  out.push_str("\r\n");
+
+  for row in &table.rows {
+    let line: Vec<String> = row.iter().map(csv_cell).collect();
+    out.push_str(&line.join(";"));
+    out.push_str("\r\n");
+  }
+
+  fs::write(path, out).map_err(|e| PosError::Io(format!("CSV yazılamadı: {}", e)))
+}
+
+// -------------------- JSON --------------------
+
+/// Satır başına bir nesne: { "<başlık>": değer, ... }. Sayılar sayı olarak kalır.
+pub fn table_json(table: &Table) -> serde_json::Value {
+  let rows = table
+    .rows
+    .iter()
+    .map(|row| {
+      let obj = table
+        .headers
+        .iter()
+        .zip(row)
+        .map(|(h, c)| {
+          let v = match c {
+            Cell::Text(s) => serde_json::Value::from(s.as_str()),
+            Cell::Int(n) => serde_json::Value::from(*n),
+            Cell::Num(v) => serde_json::Value::from(*v),
+            Cell::Empty => serde_json::Value::Null,
+          };
+          (h.to_string(), v)
+        })
+        .collect::<serde_json::Map<_, _>>();
+      serde_json::Value::Object(obj)
+    })
+    .collect();
+  serde_json::Value::Array(rows)
+}
+
+// -------------------- XLSX --------------------
+
+pub fn write_xlsx(path: &Path, table: &Table) -> Result<(), PosError> {
+  let mut workbook = Workbook::new();
+  let sheet = workbook.add_worksheet();
+
+  // Sayfa adı max 31 karakter
+  sheet
+    .set_name(table.title.chars().take(31).collect::<String>())?;
+
+  let bold = Format::new().set_bold();
+  let money = Format::new().set_num_format("#,##0.00");
+
+  for (col, h) in table.headers.iter().enumerate() {
+    sheet
+      .write_string_with_format(0, col as u16, *h, &bold)?;
+  }
+
+  for (i, row) in table.rows.iter().enumerate() {
+    let r = (i + 1) as u32;
+    for (col, c) in row.iter().enumerate() {
+      let col = col as u16;
+      match c {
+        Cell::Text(s) => {
+          sheet.write_string(r, col, s)?;
+        }
+        Cell::Int(n) => {
+          sheet.write_number(r, col, *n as f64)?;
+        }
+        Cell::Num(v) => {
+          sheet
+            .write_number_with_format(r, col, *v, &money)?;
+        }
+        Cell::Empty => {}
+      }
+    }
+  }
+
+  sheet.set_freeze_panes(1, 0)?;
+  sheet.autofit();
+
+  workbook
+    .save(path)
+    .map_err(|e| PosError::Io(format!("XLSX yazılamadı: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn tr_decimal_groups_thousands_and_rounds() {
+    assert_eq!(format_tr_decimal(0.0), "0,00");
+    assert_eq!(format_tr_decimal(999.5), "999,50");
+    assert_eq!(format_tr_decimal(1234.567), "1.234,57");
+    assert_eq!(format_tr_decimal(1_234_567.0), "1.234.567,00");
+    // yuvarlama binlik grubuna taşar
+    assert_eq!(format_tr_decimal(999.999), "1.000,00");
+    assert_eq!(format_tr_decimal(-1_234_567.5), "-1.234.567,50");
+    assert_eq!(format_tr_decimal(-12.3), "-12,30");
+    // -0,00 yazılmaz; sayı olmayan değer 0
+    assert_eq!(format_tr_decimal(-0.004), "0,00");
+    assert_eq!(format_tr_decimal(f64::NAN), "0,00");
+    assert_eq!(format_tr_decimal(f64::INFINITY), "0,00");
+  }
+
+  #[test]
+  fn csv_has_bom_semicolons_and_quoted_fields() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rapor.csv");
+    let table = Table {
+      title: "Test",
+      headers: vec!["Ürün", "Adet", "Tutar", "Not"],
+      rows: vec![
+        vec![Cell::Text("Gömlek; Siyah".into()), Cell::Int(3), Cell::Num(1500.5), Cell::Empty],
+        vec![Cell::Text("12\" Etek".into()), Cell::Int(-1), Cell::Num(-20.0), Cell::Text("iki\nsatır".into())],
+      ],
+    };
+    write_csv(&path, &table).unwrap();
+
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(&bytes[..3], &[0xEF, 0xBB, 0xBF]);
+    let text = String::from_utf8(bytes[3..].to_vec()).unwrap();
+    assert_eq!(
+      text,
+      "Ürün;Adet;Tutar;Not\r\n\
+       \"Gömlek; Siyah\";3;1.500,50;\r\n\
+       \"12\"\" Etek\";-1;-20,00;\"iki\nsatır\"\r\n"
+    );
+  }
+}
@@ -0,0 +1,287 @@
+// -------------------- YEREL HTTP/JSON API --------------------
+//
+// Aynı ağdaki araçlar (fiyat gösterme kiosku, e-ticaret senkron betiği) için gömülü sunucu.
+// SQLite dosyasına doğrudan erişmek yerine Tauri komutlarının kullandığı Store metodlarını
+// çağırır; doğrulama, kırpma ve stok kontrolü birebir aynı.
+//
+//   GET  /api/health
+//   GET  /api/products?q=&page=&page_size=&sort=&desc=&include_inactive=
+//   GET  /api/products/{barcode}
+//   GET  /api/stock/{barcode}
+//   GET  /api/stock/low?max_stock=2
+//   GET  /api/reports/{rapor}?from=&to=&q=&min_days=&max_stock=&limit=
+//   POST /api/sales       (create_sale ile aynı gövde; kanal API, kasada geri alınamaz)
+//   POST /api/transfers   (create_transfer ile aynı gövde)
+//
+// Her istek `Authorization: Bearer <token>` (ya da `X-Api-Token`) taşımalı.
+// Hata gövdesi Tauri komutlarıyla aynı: {code, message, details}.
+
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::db::{self, ApiSettings, Store};
+use crate::error::PosError;
+use crate::export::{self, ExportReport};
+use crate::i18n::tr;
+
+const WORKERS: usize = 4;
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+#[derive(serde::Serialize, Clone, Default)]
+pub struct ApiStatus {
+  pub running: bool,
+  /// Dinlenen adres (ör. 127.0.0.1:8787)
+  pub address: Option<String>,
+}
+
+struct Running {
+  server: Arc<Server>,
+  workers: Vec<JoinHandle<()>>,
+  address: String,
+}
+
+/// Masaüstünde Tauri state'inde tutulur; ayar değişince apply ile yeniden başlatılır.
+#[derive(Default)]
+pub struct ApiServer {
+  running: Mutex<Option<Running>>,
+}
+
+impl ApiServer {
+  pub fn status(&self) -> ApiStatus {
+    let running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+    ApiStatus {
+      running: running.is_some(),
+      address: running.as_ref().map(|r| r.address.clone()),
+    }
+  }
+
+  /// Çalışan sunucuyu durdurur; ayar açıksa yeni adres / anahtarla yeniden başlatır.
+  pub fn apply(&self, store: &Store, settings: &ApiSettings) -> Result<ApiStatus, PosError> {
+    let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(r) = running.take() {
+      shutdown(r);
+    }
+    if settings.enabled {
+      *running = Some(start(store, settings)?);
+    }
+    drop(running);
+    Ok(self.status())
+  }
+
+  pub fn stop(&self) {
+    let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(r) = running.take() {
+      shutdown(r);
+    }
+  }
+}
+
+fn start(store: &Store, settings: &ApiSettings) -> Result<Running, PosError> {
+  if settings.token.is_empty() {
+    return Err(PosError::Validation(tr!("API anahtarı boş")));
+  }
+  let host = if settings.lan { "0.0.0.0" } else { "127.0.0.1" };
+  let address = format!("{}:{}", host, settings.port);
+  let server = Server::http(&address)
+    .map(Arc::new)
+    .map_err(|e| PosError::Io(tr!("API sunucusu başlatılamadı ({}): {}", address, e)))?;
+
+  let token = Arc::new(settings.token.clone());
+  let workers = (0..WORKERS)
+    .map(|_| {
+      let server = Arc::clone(&server);
+      let store = store.clone();
+      let token = Arc::clone(&token);
+      std::thread::spawn(move || {
+        // unblock() çağrılınca recv hata döner ve thread biter
+        while let Ok(req) = server.recv() {
+          handle(&store, &token, req);
+        }
+      })
+    })
+    .collect();
+
+  Ok(Running { server, workers, address })
+}
+
+fn shutdown(r: Running) {
+  // her unblock tek bir bekleyen recv'i çözer
+  for _ in 0..r.workers.len() {
+    r.server.unblock();
+  }
+  for w in r.workers {
+    let _ = w.join();
+  }
+}
+
+// -------------------- İSTEK İŞLEME --------------------
+
+fn handle(store: &Store, token: &str, mut req: Request) {
+  let (status, body) = if !authorized(&req, token) {
+    (
+      401,
+      serde_json::json!({ "code": "UNAUTHORIZED", "message": tr!("Geçersiz API anahtarı"), "details": null }),
+    )
+  } else {
+    match route(store, &mut req) {
+      Ok(v) => (200, v),
+      Err(e) => (status_of(&e), serde_json::to_value(&e).unwrap_or_default()),
+    }
+  };
+
+  let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json; charset=utf-8"[..])
+    .expect("geçerli başlık");
+  let response = Response::from_string(body.to_string())
+    .with_status_code(status)
+    .with_header(content_type);
+  if let Err(e) = req.respond(response) {
+    eprintln!("[api] yanıt yazılamadı: {}", e);
+  }
+}
+
+fn status_of(e: &PosError) -> u16 {
+  match e {
+    PosError::NotFound(_) => 404,
+    PosError::Validation(_) => 400,
+    PosError::InsufficientStock { .. } | PosError::Conflict(_) => 409,
+    PosError::Db(_) | PosError::Io(_) => 500,
+  }
+}
+
+fn authorized(req: &Request, token: &str) -> bool {
+  req.headers().iter().any(|h| {
+    let value = h.value.as_str().trim();
+    let given = if h.field.equiv("Authorization") {
+      value.strip_prefix("Bearer ").map(str::trim)
+    } else if h.field.equiv("X-Api-Token") {
+      Some(value)
+    } else {
+      None
+    };
+    given.is_some_and(|g| constant_time_eq(g.as_bytes(), token.as_bytes()))
+  })
+}
+
+/// Anahtar karşılaştırması ilk farklı baytta erken dönmesin
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn route(store: &Store, req: &mut Request) -> Result<serde_json::Value, PosError> {
+  let url = req.url().to_string();
+  let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+  let segments: Vec<String> = path
+    .trim_matches('/')
+    .split('/')
+    .map(percent_decode)
+    .collect::<Result<_, _>>()?;
+  let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+  match (req.method(), segments.as_slice()) {
+    (Method::Get, ["api", "health"]) => Ok(serde_json::json!({ "ok": true, "version": env!("CARGO_PKG_VERSION") })),
+
+    (Method::Get, ["api", "products"]) => {
+      let q: db::ListQuery = parse_query(query)?;
+      json(store.list_products(&q)?)
+    }
+    (Method::Get, ["api", "products", barcode]) => json(find_product(store, barcode)?),
+
+    (Method::Get, ["api", "stock", "low"]) => {
+      let q: ReportQuery = parse_query(query)?;
+      json(store.get_low_stock(q.max_stock.unwrap_or(2))?)
+    }
+    (Method::Get, ["api", "stock", barcode]) => {
+      let p = find_product(store, barcode)?;
+      Ok(serde_json::json!({ "barcode": p.barcode, "magaza_stok": p.magaza_stok, "depo_stok": p.depo_stok }))
+    }
+
+    (Method::Get, ["api", "reports", name]) => {
+      let q: ReportQuery = parse_query(query)?;
+      let table = export::build_table(store, &report_of(name, q)?)?;
+      Ok(export::table_json(&table))
+    }
+
+    (Method::Post, ["api", "sales"]) => json(store.create_sale_via(read_body(req)?, db::SaleChannel::Api)?),
+    (Method::Post, ["api", "transfers"]) => json(store.create_transfer(read_body(req)?)?),
+
+    _ => Err(PosError::NotFound(tr!("Bilinmeyen uç nokta: {} {}", req.method(), path))),
+  }
+}
+
+fn find_product(store: &Store, barcode: &str) -> Result<db::Product, PosError> {
+  store
+    .find_product_by_barcode(barcode.trim())?
+    .ok_or_else(|| PosError::NotFound(tr!("Ürün bulunamadı: {}", barcode)))
+}
+
+fn json<T: serde::Serialize>(value: T) -> Result<serde_json::Value, PosError> {
+  serde_json::to_value(value).map_err(|e| PosError::Io(e.to_string()))
+}
+
+fn parse_query<T: serde::de::DeserializeOwned>(query: &str) -> Result<T, PosError> {
+  serde_urlencoded::from_str(query).map_err(|e| PosError::Validation(tr!("Geçersiz sorgu parametresi: {}", e)))
+}
+
+fn read_body<T: serde::de::DeserializeOwned>(req: &mut Request) -> Result<T, PosError> {
+  let mut body = String::new();
+  req
+    .as_reader()
+    .take(MAX_BODY_BYTES)
+    .read_to_string(&mut body)
+    .map_err(|e| PosError::Validation(tr!("İstek gövdesi okunamadı: {}", e)))?;
+  serde_json::from_str(&body).map_err(|e| PosError::Validation(tr!("Geçersiz JSON: {}", e)))
+}
+
+fn percent_decode(s: &str) -> Result<String, PosError> {
+  let bytes = s.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    if bytes[i] == b'%' {
+      let hex = s.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok());
+      let Some(b) = hex else {
+        return Err(PosError::Validation(tr!("Geçersiz URL: {}", s)));
+      };
+      out.push(b);
+      i += 3;
+    } else {
+      out.push(bytes[i]);
+      i += 1;
+    }
+  }
+  String::from_utf8(out).map_err(|_| PosError::Validation(tr!("Geçersiz URL: {}", s)))
+}
+
+// -------------------- RAPORLAR --------------------
+
+/// Rapor parametreleri; verilmeyenler CLI ile aynı varsayılanları alır
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct ReportQuery {
+  from: Option<String>,
+  to: Option<String>,
+  q: Option<String>,
+  min_days: Option<i64>,
+  max_stock: Option<i64>,
+  limit: Option<i64>,
+}
+
+/// "velocity_report" ya da "velocity-report"
+fn report_of(name: &str, q: ReportQuery) -> Result<ExportReport, PosError> {
+  let ReportQuery { from, to, q, min_days, max_stock, limit } = q;
+  Ok(match name.replace('-', "_").as_str() {
+    "velocity_report" => ExportReport::VelocityReport { from, to },
+    "dead_stock" => ExportReport::DeadStock { min_days: min_days.unwrap_or(90) },
+    "category_margin" => ExportReport::CategoryMargin { from, to },
+    "basket_pairs" => ExportReport::BasketPairs { limit: limit.unwrap_or(20), from, to },
+    "low_stock" => ExportReport::LowStock { max_stock: max_stock.unwrap_or(2) },
+    "cash_report" => ExportReport::CashReport { from, to },
+    "expenses" => ExportReport::Expenses { from, to },
+    "sale_groups" => ExportReport::SaleGroups { from, to, q },
+    "products" => ExportReport::Products,
+    _ => return Err(PosError::NotFound(tr!("Bilinmeyen rapor: {}", name))),
+  })
+}
@@ -0,0 +1,284 @@
+// -------------------- MESAJ KATALOĞU (TR / EN) --------------------
+//
+// Kaynak metinler Türkçe ve anahtar olarak metnin kendisi kullanılır:
+//   tr!("Sepet boş")                      -> "Sepet boş" | "Cart is empty"
+//   tr!("Ürün bulunamadı: {}", barcode)   -> yer tutucular sırayla doldurulur
+// İngilizce karşılığı olmayan metin Türkçe döner. Dil settings.language kolonunda saklanır;
+// açılışta ve geri yüklemeden sonra db tarafından yüklenir.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+  Tr,
+  En,
+}
+
+impl Lang {
+  pub fn code(self) -> &'static str {
+    match self {
+      Lang::Tr => "tr",
+      Lang::En => "en",
+    }
+  }
+
+  pub fn parse(s: &str) -> Option<Lang> {
+    match s.trim().to_lowercase().as_str() {
+      "tr" => Some(Lang::Tr),
+      "en" => Some(Lang::En),
+      _ => None,
+    }
+  }
+}
+
+pub const DEFAULT_LANG: Lang = Lang::Tr;
+
+static LANG: AtomicU8 = AtomicU8::new(0);
+
+pub fn lang() -> Lang {
+  match LANG.load(Ordering::Relaxed) {
+    1 => Lang::En,
+    _ => Lang::Tr,
+  }
+}
+
+pub fn set_lang(lang: Lang) {
+  LANG.store(if lang == Lang::En { 1 } else { 0 }, Ordering::Relaxed);
+}
+
+/// Aktif dilde metin (şablon olabilir; doldurmak için tr! kullan)
+pub fn text(key: &'static str) -> &'static str {
+  match lang() {
+    Lang::Tr => key,
+    Lang::En => en(key).unwrap_or(key),
+  }
+}
+
+/// "{}" yer tutucularını sırayla doldurur. Fazla yer tutucu olduğu gibi kalır.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+  let mut out = String::with_capacity(template.len() + 16);
+  let mut args = args.iter();
+  let mut rest = template;
+  while let Some(i) = rest.find("{}") {
+    out.push_str(&rest[..i]);
+    match args.next() {
+      Some(a) => out.push_str(&a.to_string()),
+      None => out.push_str("{}"),
+    }
+    rest = &rest[i + 2..];
+  }
+  out.push_str(rest);
+  out
+}
+
+macro_rules! tr {
+  ($key:literal) => {
+    $crate::i18n::text($key).to_string()
+  };
+  ($key:literal, $($arg:expr),+ $(,)?) => {
+    $crate::i18n::fill($crate::i18n::text($key), &[$(&$arg as &dyn std::fmt::Display),+])
+  };
+}
+pub(crate) use tr;
+
+// Anahtar = Türkçe metin (yer tutucular dahil birebir aynı olmalı)
+fn en(key: &str) -> Option<&'static str> {
+  Some(match key {
+    // genel / doğrulama
+    "{} zorunlu" => "{} is required",
+    "id geçersiz" => "Invalid id",
+    "Tarih zorunlu" => "Date is required",
+    "Barkod zorunlu" => "Barcode is required",
+    "product_code zorunlu" => "product_code is required",
+    "Dil tr veya en olmalı" => "Language must be tr or en",
+    "{} tarihi YYYY-AA-GG olmalı: {}" => "{} date must be YYYY-MM-DD: {}",
+    "Başlangıç tarihi bitişten sonra olamaz" => "Start date cannot be after end date",
+    "Geçersiz sıralama alanı: {}" => "Invalid sort field: {}",
+    "Lokasyon MAGAZA veya DEPO olmalı" => "Location must be MAGAZA or DEPO",
+    "Geçersiz şema versiyonu: {}" => "Invalid schema version: {}",
+    "Veritabanı daha yeni bir sürümle oluşturulmuş (şema v{}); bu uygulama en fazla v{} destekliyor. Lütfen uygulamayı güncelleyin." =>
+      "The database was created by a newer version (schema v{}); this app supports up to v{}. Please update the app.",
+    "Şema göçü v{} ({}) başarısız: {}" => "Schema migration v{} ({}) failed: {}",
+    "Veritabanı hatası: {}" => "Database error: {}",
+    "Dosya hatası: {}" => "File error: {}",
+    "Veri klasörü bulunamadı" => "Data folder not found",
+
+    // alan / sözlük adları
+    "Ürün adı" => "Product name",
+    "Ek barkod" => "Extra barcode",
+    "Hedef klasör" => "Target folder",
+    "Başlangıç" => "Start",
+    "Bitiş" => "End",
+    "Kategori" => "Category",
+    "Renk" => "Color",
+    "Beden" => "Size",
+    "Kategorisiz" => "Uncategorized",
+
+    // barkod / ürün
+    "Barkod modu SEQUENTIAL veya EAN13 olmalı" => "Barcode mode must be SEQUENTIAL or EAN13",
+    "EAN-13 ön eki 2 ile başlayan 2-5 haneli bir sayı olmalı (örn: 200)" =>
+      "EAN-13 prefix must be a 2-5 digit number starting with 2 (e.g. 200)",
+    "{} ön eki için EAN-13 numara aralığı doldu" => "EAN-13 number range is exhausted for prefix {}",
+    "{} için product_code limiti doldu ({})" => "product_code limit reached for {} ({})",
+    "Ürün bulunamadı: {}" => "Product not found: {}",
+    "Ürün ailesi bulunamadı: {}" => "Product family not found: {}",
+    "Bu barkod zaten kullanılıyor: {}" => "This barcode is already in use: {}",
+    "Bu barkod başka bir ürünün ek barkodu: {}" => "This barcode is an extra barcode of another product: {}",
+    "Satış fiyatı sayı olmalı" => "Sell price must be a number",
+    "Stok negatif olamaz" => "Stock cannot be negative",
+    "En az bir renk seçilmeli" => "Select at least one color",
+    "En az bir beden seçilmeli" => "Select at least one size",
+    "Tek seferde en fazla {} varyant oluşturulabilir" => "At most {} variants can be created at once",
+    "{} bulunamadı: {}" => "{} not found: {}",
+    "Kategori bulunamadı: {}" => "Category not found: {}",
+    "Renk bulunamadı: {}" => "Color not found: {}",
+    "Beden bulunamadı: {}" => "Size not found: {}",
+    "Kategori bulunamadı" => "Category not found",
+    "Renk bulunamadı" => "Color not found",
+    "Beden bulunamadı" => "Size not found",
+    "Kategori adı boş olamaz" => "Category name cannot be empty",
+    "Renk adı boş olamaz" => "Color name cannot be empty",
+    "Beden adı boş olamaz" => "Size name cannot be empty",
+    "Bu kategori ürünlerde kullanılıyor; pasife alınamaz" => "This category is used by products; it cannot be deactivated",
+    "Bu renk ürünlerde kullanılıyor; pasife alınamaz" => "This color is used by products; it cannot be deactivated",
+    "Bu beden ürünlerde kullanılıyor; pasife alınamaz" => "This size is used by products; it cannot be deactivated",
+    "Bu kategori ürünlerde kullanılıyor; silinemez / pasife alınamaz" =>
+      "This category is used by products; it cannot be deleted or deactivated",
+    "Bu renk ürünlerde kullanılıyor; silinemez / pasife alınamaz" =>
+      "This color is used by products; it cannot be deleted or deactivated",
+    "Bu beden ürünlerde kullanılıyor; silinemez / pasife alınamaz" =>
+      "This size is used by products; it cannot be deleted or deactivated",
+
+    // satış / iade / transfer / gider
+    "Yetersiz stok: {} ({} stok: {}, istenen: {})" => "Insufficient stock: {} ({} stock: {}, requested: {})",
+    "Sepet boş" => "Cart is empty",
+    "Son {} dakika içinde geri alınabilecek satış bulunamadı." => "No sale from the last {} minutes to undo.",
+    "İade adedi 1+ olmalı" => "Return quantity must be 1 or more",
+    "Bu satış satırı daha önce tamamen iade edilmiş" => "This sale line has already been fully returned",
+    "Bu satış satırı daha önce tamamen iade/değişim yapılmış" => "This sale line has already been fully returned/exchanged",
+    "İade adedi satıştan fazla olamaz (satılan: {}, daha önce iade: {}, kalan: {})" =>
+      "Return quantity cannot exceed the sale (sold: {}, already returned: {}, remaining: {})",
+    "İade edilen adet satıştan fazla olamaz (satılan: {}, daha önce iade: {}, kalan: {})" =>
+      "Returned quantity cannot exceed the sale (sold: {}, already returned: {}, remaining: {})",
+    "Değişimde verilecek sepet boş" => "The exchange cart is empty",
+    "diff_payment_method sadece CARD/KART veya CASH/NAKİT olabilir" => "diff_payment_method must be CARD/KART or CASH/NAKİT",
+    "Nereden ve nereye aynı olamaz" => "Source and destination cannot be the same",
+    "Geri alınacak transfer bulunamadı" => "No transfer to undo",
+    "Tutar 0'dan büyük sayı olmalı" => "Amount must be a number greater than 0",
+
+    // bütünlük kontrolü
+    "SQLite dosya bütünlüğü" => "SQLite file integrity",
+    "Yabancı anahtar ihlalleri" => "Foreign key violations",
+    "Negatif stoklu ürünler (sayım ile düzeltilmeli, otomatik onarılmaz)" =>
+      "Products with negative stock (fix with a stock count; not repaired automatically)",
+    "Toplam stoğu (stock) mağaza + depo ile uyuşmayan ürünler (onarım: fark mağazaya yazılır)" =>
+      "Products whose total stock (stock) differs from store + warehouse (repair: the difference goes to the store)",
+    "Şema göçünde toplam stoktan (stock) düzeltilen ürünler (bilgi amaçlı)" =>
+      "Products corrected from total stock (stock) during schema migration (informational)",
+    "İade kaydı olmayan iade satırları (onarım: satırlar silinir)" =>
+      "Return lines without a return record (repair: lines are deleted)",
+    "Değişim kaydı olmayan değişim satırları (onarım: satırlar silinir)" =>
+      "Exchange lines without an exchange record (repair: lines are deleted)",
+    "Kategori/renk/beden adı sözlükle uyuşmayan ürünler (onarım: adlar id'den yeniden türetilir)" =>
+      "Products whose category/color/size names do not match the dictionary (repair: names are re-derived from ids)",
+    "Pasif ürüne ait geçerli satış satırları (bilgi amaçlı, onarılmaz)" =>
+      "Valid sale lines of inactive products (informational, not repaired)",
+
+    // mağaza ayarları
+    "Geri alma süresi 1-1440 dakika olmalı" => "Undo window must be 1-1440 minutes",
+    "Barkod başlangıcı 1 ile 999999999999 arasında olmalı" => "Barcode start must be between 1 and 999999999999",
+    "Ürün kodu limiti 1-99999 arasında olmalı" => "Product code limit must be between 1 and 99999",
+    "Ödeme yöntemi CARD, CASH veya TRANSFER olmalı" => "Payment method must be CARD, CASH or TRANSFER",
+    "Günlük seri sınırı 1-3660 olmalı" => "Daily series limit must be 1-3660",
+    "Aylık seri sınırı 1-120 olmalı" => "Monthly series limit must be 1-120",
+    "Düşük stok eşiği 0-10000 olmalı" => "Low stock threshold must be 0-10000",
+
+    // yedek ayarları / hedefler
+    "Yedek parolası en az {} karakter olmalı" => "Backup passphrase must be at least {} characters",
+    "Günlük" => "Daily",
+    "Haftalık" => "Weekly",
+    "Aylık" => "Monthly",
+    "{} saklama sayısı 0-365 arasında olmalı" => "{} retention count must be between 0 and 365",
+    "En az bir otomatik yedek saklanmalı" => "At least one automatic backup must be kept",
+    "Yedek aralığı 0 (kapalı) veya 5-1440 dakika olmalı" => "Backup interval must be 0 (off) or 5-1440 minutes",
+    "Hedef klasör tam yol olmalı" => "Target folder must be an absolute path",
+    "Klasör bulunamadı: {}" => "Folder not found: {}",
+    "Bu klasör zaten ekli" => "This folder is already added",
+    "Yedek hedefi bulunamadı" => "Backup target not found",
+
+    // yedek / geri yükleme
+    "DB bulunamadı: {}" => "Database not found: {}",
+    "Yedek yazılamadı: {}" => "Backup could not be written: {}",
+    "Klasör erişilemiyor: {}" => "Folder is not accessible: {}",
+    "Geçersiz yedek dosyası" => "Invalid backup file",
+    "Kopyalanamadı: {}" => "Could not copy: {}",
+    "Yedek okunamadı: {}" => "Backup could not be read: {}",
+    "products tablosu yok" => "products table is missing",
+    "Eski yedek silinemedi: {}" => "Old backup could not be deleted: {}",
+    "backup_path boş olamaz" => "backup_path cannot be empty",
+    "Backup dosyası bulunamadı: {}" => "Backup file not found: {}",
+    "Güvenlik: Backup dosyası backups klasörünün dışında olamaz." => "Security: the backup file must be inside the backups folder.",
+    "Restore kopyalama hatası: {}" => "Restore copy error: {}",
+    "Yedek geçerli bir veritabanı değil: {}" => "Backup is not a valid database: {}",
+    "Yedek daha yeni bir sürümle alınmış (şema {} > {}). Önce uygulamayı güncelleyin." =>
+      "Backup was taken by a newer version (schema {} > {}). Update the app first.",
+    "Mevcut DB güvenlik yedeği alınamadı: {}" => "Safety backup of the current database failed: {}",
+    "Geri yükleme başarısız (güvenlik yedeği: {}): {}" => "Restore failed (safety backup: {}): {}",
+    "Yedek güncel şemaya getirilemedi: {}" => "The backup could not be upgraded to the current schema: {}",
+    "Güncellenen yedek doğrulanamadı: {}" => "The upgraded backup failed verification: {}",
+    "Yedekte {} satış yok ({} tarihinden beri)" => "{} sales are missing from the backup (since {})",
+    "Yedekte {} gider yok ({} tarihinden beri)" => "{} expenses are missing from the backup (since {})",
+    "Mevcut DB'de {} ürün fazla" => "The current database has {} more products",
+    "Yedekte {} ürün fazla" => "The backup has {} more products",
+    "Yedekte mevcut DB'de olmayan {} satış var" => "The backup has {} sales not in the current database",
+    "Yedek daha yeni bir sürümle alınmış (şema {} > {}); geri yüklenemez" =>
+      "Backup was taken by a newer version (schema {} > {}); it cannot be restored",
+    "Yedek eski şemada (v{}); geri yüklenirse v{}'e güncellenir" =>
+      "Backup uses an older schema (v{}); it will be upgraded to v{} on restore",
+    "Yedek mevcut DB ile aynı görünüyor" => "The backup looks identical to the current database",
+
+    // yedek arşivi
+    "Yedek arşivi değil" => "Not a backup archive",
+    "Desteklenmeyen arşiv sürümü: {}" => "Unsupported archive version: {}",
+    "Arşiv eksik (başlık)" => "Archive is truncated (header)",
+    "Arşiv başlığı bozuk (hex)" => "Archive header is corrupt (hex)",
+    "Arşiv başlığı bozuk (boyut)" => "Archive header is corrupt (size)",
+    "Arşiv başlığı bozuk (salt/nonce)" => "Archive header is corrupt (salt/nonce)",
+    "Arşiv başlığı okunamadı: {}" => "Archive header could not be read: {}",
+    "Anahtar türetilemedi: {}" => "Key derivation failed: {}",
+    "Şifreleme başarısız" => "Encryption failed",
+    "Yedek şifreli; parola gerekli" => "Backup is encrypted; passphrase required",
+    "Parola yanlış ya da arşiv bozuk" => "Wrong passphrase or corrupt archive",
+    "Arşiv açılamadı: {}" => "Archive could not be decompressed: {}",
+    "Sağlama (SHA-256) tutmuyor; yedek bozuk" => "Checksum (SHA-256) mismatch; backup is corrupt",
+
+    // ürün CSV içe aktarma
+    "Dosya okunamadı: {}" => "File could not be read: {}",
+    "CSV başlığı okunamadı: {}" => "CSV header could not be read: {}",
+    "CSV başlığında {} ve {} sütunları olmalı" => "CSV header must contain the {} and {} columns",
+    "Alış fiyatı sayı olmalı" => "Purchase price must be a number",
+    "Stok tam sayı olmalı: {}" => "Stock must be an integer: {}",
+
+    // yerel HTTP API
+    "API portu 1024-65535 arasında olmalı" => "API port must be between 1024 and 65535",
+    "API anahtarı boş" => "API token is empty",
+    "API sunucusu başlatılamadı ({}): {}" => "API server could not be started ({}): {}",
+    "Geçersiz API anahtarı" => "Invalid API token",
+    "Bilinmeyen uç nokta: {} {}" => "Unknown endpoint: {} {}",
+    "Bilinmeyen rapor: {}" => "Unknown report: {}",
+    "Geçersiz sorgu parametresi: {}" => "Invalid query parameter: {}",
+    "İstek gövdesi okunamadı: {}" => "Request body could not be read: {}",
+    "Geçersiz JSON: {}" => "Invalid JSON: {}",
+    "Geçersiz URL: {}" => "Invalid URL: {}",
+
+    // webhook / olay kuyruğu
+    "Adres" => "URL",
+    "Adres http:// veya https:// ile başlamalı" => "URL must start with http:// or https://",
+    "Bu adres zaten ekli" => "This URL is already added",
+    "Webhook bulunamadı" => "Webhook not found",
+    "Teslimat bulunamadı" => "Delivery not found",
+
+    _ => return None,
+  })
+}
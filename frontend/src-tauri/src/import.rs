@@ -0,0 +1,184 @@
+// -------------------- İÇE AKTARMA (CSV) --------------------
+//
+// Ürün CSV'si: dışa aktarılan "Urunler" tablosuyla aynı başlıklar (export::PRODUCT_COLUMNS).
+// Ayırıcı ';' (Türkçe Excel) ya da ','; ondalık "1.234,50" veya "1234.50" olabilir.
+//   - barkod boş / bilinmiyor -> yeni ürün (başlangıç stoğu Mağaza / Depo sütunlarından)
+//   - barkod kayıtlı          -> ürün bilgisi güncellenir (boş hücre mevcut değeri korur);
+//                                stok sütunları doluysa stok da yazılır
+// Hatalı satır diğerlerini durdurmaz; raporda satır numarasıyla döner.
+
+use std::path::Path;
+
+use crate::db::{self, Store};
+use crate::error::PosError;
+use crate::export::PRODUCT_COLUMNS;
+use crate::i18n::tr;
+
+#[derive(serde::Serialize, Default)]
+pub struct ImportRowError {
+  /// Dosyadaki satır (başlık = 1)
+  pub line: u64,
+  pub message: String,
+}
+
+#[derive(serde::Serialize, Default)]
+pub struct ImportReport {
+  pub created: i64,
+  pub updated: i64,
+  pub errors: Vec<ImportRowError>,
+}
+
+struct ProductRow {
+  barcode: Option<String>,
+  product_code: Option<String>,
+  category: Option<String>,
+  name: String,
+  color: Option<String>,
+  size: Option<String>,
+  buy_price: Option<f64>,
+  sell_price: f64,
+  magaza_stok: Option<i64>,
+  depo_stok: Option<i64>,
+}
+
+/// "1.234,50" / "1234.50" / "1234"
+fn parse_decimal(s: &str) -> Option<f64> {
+  let t = s.trim();
+  let normalized = if t.contains(',') { t.replace('.', "").replace(',', ".") } else { t.to_string() };
+  normalized.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn detect_delimiter(text: &str) -> u8 {
+  let first = text.lines().next().unwrap_or("");
+  if first.contains(';') { b';' } else { b',' }
+}
+
+pub fn import_products_csv(store: &Store, path: &Path) -> Result<ImportReport, PosError> {
+  let raw = std::fs::read_to_string(path)
+    .map_err(|e| PosError::Io(tr!("Dosya okunamadı: {}", e)))?;
+  let text = raw.trim_start_matches('\u{FEFF}');
+
+  let mut reader = csv::ReaderBuilder::new()
+    .delimiter(detect_delimiter(text))
+    .flexible(true)
+    .from_reader(text.as_bytes());
+
+  let headers = reader
+    .headers()
+    .map_err(|e| PosError::Validation(tr!("CSV başlığı okunamadı: {}", e)))?
+    .clone();
+  let col = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+  let idx: Vec<Option<usize>> = PRODUCT_COLUMNS.iter().map(|c| col(c)).collect();
+  // Ürün ve Satış Fiyatı olmadan satır anlamsız
+  if idx[3].is_none() || idx[7].is_none() {
+    return Err(PosError::Validation(tr!(
+      "CSV başlığında {} ve {} sütunları olmalı",
+      PRODUCT_COLUMNS[3],
+      PRODUCT_COLUMNS[7]
+    )));
+  }
+
+  let mut report = ImportReport::default();
+  for record in reader.records() {
+    let record = match record {
+      Ok(r) => r,
+      Err(e) => {
+        let line = e.position().map(|p| p.line()).unwrap_or(0);
+        report.errors.push(ImportRowError { line, message: e.to_string() });
+        continue;
+      }
+    };
+    let line = record.position().map(|p| p.line()).unwrap_or(0);
+    let field = |i: usize| -> Option<String> {
+      idx[i]
+        .and_then(|c| record.get(c))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+    };
+
+    let parsed = (|| -> Result<ProductRow, PosError> {
+      let sell_price = field(7)
+        .as_deref()
+        .and_then(parse_decimal)
+        .ok_or_else(|| PosError::Validation(tr!("Satış fiyatı sayı olmalı")))?;
+      let buy_price = match field(6) {
+        Some(v) => Some(parse_decimal(&v).ok_or_else(|| PosError::Validation(tr!("Alış fiyatı sayı olmalı")))?),
+        None => None,
+      };
+      let stock = |i: usize| -> Result<Option<i64>, PosError> {
+        match field(i) {
+          Some(v) => v
+            .parse::<i64>()
+            .map(Some)
+            .map_err(|_| PosError::Validation(tr!("Stok tam sayı olmalı: {}", v))),
+          None => Ok(None),
+        }
+      };
+      Ok(ProductRow {
+        barcode: field(0),
+        product_code: field(1),
+        category: field(2),
+        name: field(3).unwrap_or_default(),
+        color: field(4),
+        size: field(5),
+        buy_price,
+        sell_price,
+        magaza_stok: stock(8)?,
+        depo_stok: stock(9)?,
+      })
+    })();
+
+    match parsed.and_then(|row| apply_row(store, row)) {
+      Ok(true) => report.created += 1,
+      Ok(false) => report.updated += 1,
+      Err(e) => report.errors.push(ImportRowError { line, message: e.to_string() }),
+    }
+  }
+
+  Ok(report)
+}
+
+/// true = yeni ürün oluşturuldu
+fn apply_row(store: &Store, row: ProductRow) -> Result<bool, PosError> {
+  let existing = match row.barcode.as_deref() {
+    Some(bc) => store.find_product_by_barcode(bc)?,
+    None => None,
+  };
+
+  let Some(p) = existing else {
+    store.add_product(
+      row.barcode,
+      row.product_code,
+      row.category,
+      row.name,
+      row.color,
+      row.size,
+      row.buy_price,
+      row.sell_price,
+      row.magaza_stok,
+      row.depo_stok,
+    )?;
+    return Ok(true);
+  };
+
+  // Kayıtlı satırın üstüne birleştir: boş / eksik sütun mevcut değeri korur
+  store.update_product(db::UpdateProductPayload {
+    barcode: p.barcode.clone(),
+    product_code: row.product_code.or(p.product_code),
+    category: row.category.or(p.category),
+    name: if row.name.is_empty() { p.name } else { row.name },
+    color: row.color.or(p.color),
+    size: row.size.or(p.size),
+    buy_price: Some(row.buy_price.unwrap_or(p.buy_price)),
+    sell_price: row.sell_price,
+  })?;
+
+  if row.magaza_stok.is_some() || row.depo_stok.is_some() {
+    store.update_stock(db::UpdateStockPayload {
+      barcode: p.barcode,
+      magaza_stok: row.magaza_stok.unwrap_or(p.magaza_stok),
+      depo_stok: row.depo_stok.unwrap_or(p.depo_stok),
+    })?;
+  }
+  Ok(false)
+}
@@ -0,0 +1,634 @@
+use crate::barcode::{self, Symbology};
+use crate::db;
+use crate::error::PosError;
+use crate::export::format_tr_decimal;
+
+// -------------------- ETİKET (SVG / PDF sayfa, ZPL yazıcı) --------------------
+
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelFormat {
+  Svg,
+  Pdf,
+  Zpl,
+}
+
+impl LabelFormat {
+  pub fn extension(self) -> &'static str {
+    match self {
+      LabelFormat::Svg => "svg",
+      LabelFormat::Pdf => "pdf",
+      LabelFormat::Zpl => "zpl",
+    }
+  }
+}
+
+#[derive(serde::Deserialize)]
+pub struct LabelRequestItem {
+  pub barcode: String,
+  pub qty: i64,
+}
+
+/// Ölçüler mm. Varsayılan: A4, 3 × 8, 70 × 37 mm (yaygın etiket kağıdı).
+#[derive(serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct LabelLayout {
+  pub page_width_mm: f64,
+  pub page_height_mm: f64,
+  pub columns: u32,
+  pub rows: u32,
+  pub label_width_mm: f64,
+  pub label_height_mm: f64,
+  pub margin_left_mm: f64,
+  pub margin_top_mm: f64,
+  pub gap_x_mm: f64,
+  pub gap_y_mm: f64,
+  // yarım kullanılmış sayfalar için: ilk N pozisyonu boş bırak
+  pub start_position: u32,
+}
+
+impl Default for LabelLayout {
+  fn default() -> Self {
+    LabelLayout {
+      page_width_mm: 210.0,
+      page_height_mm: 297.0,
+      columns: 3,
+      rows: 8,
+      label_width_mm: 70.0,
+      label_height_mm: 37.0,
+      margin_left_mm: 0.0,
+      margin_top_mm: 0.5,
+      gap_x_mm: 0.0,
+      gap_y_mm: 0.0,
+      start_position: 0,
+    }
+  }
+}
+
+impl LabelLayout {
+  fn validate(&self) -> Result<(), PosError> {
+    if self.columns == 0 || self.rows == 0 {
+      return Err(PosError::Validation("Etiket sayfası en az 1 sütun ve 1 satır olmalı".to_string()));
+    }
+    if self.label_width_mm < 20.0 || self.label_height_mm < 15.0 {
+      return Err(PosError::Validation("Etiket boyutu çok küçük (min 20 × 15 mm)".to_string()));
+    }
+
+    let used_w = self.margin_left_mm
+      + self.columns as f64 * self.label_width_mm
+      + (self.columns - 1) as f64 * self.gap_x_mm;
+    let used_h = self.margin_top_mm
+      + self.rows as f64 * self.label_height_mm
+      + (self.rows - 1) as f64 * self.gap_y_mm;
+
+    if used_w > self.page_width_mm + 0.01 || used_h > self.page_height_mm + 0.01 {
+      return Err(PosError::Validation("Etiketler sayfaya sığmıyor; ölçüleri kontrol edin".to_string()));
+    }
+    Ok(())
+  }
+
+  fn per_page(&self) -> usize {
+    (self.columns * self.rows) as usize
+  }
+}
+
+pub struct LabelData {
+  pub barcode: String,
+  pub name: String,
+  pub variant: Option<String>,
+  pub price: f64,
+  pub qty: i64,
+}
+
+const MAX_LABELS: i64 = 5000;
+
+/// Barkodlara karşılık gelen ürün bilgilerini toplar (sıra korunur).
+pub fn collect_labels(store: &db::Store, items: Vec<LabelRequestItem>) -> Result<Vec<LabelData>, PosError> {
+  let mut out = Vec::new();
+  let mut total: i64 = 0;
+
+  for it in items {
+    let bc = it.barcode.trim().to_string();
+    if bc.is_empty() || it.qty <= 0 {
+      continue;
+    }
+
+    let p = store.find_product_by_barcode(&bc)?
+      .ok_or_else(|| PosError::NotFound(format!("Ürün bulunamadı: {}", bc)))?;
+
+    total += it.qty;
+    if total > MAX_LABELS {
+      return Err(PosError::Validation(format!("Tek seferde en fazla {} etiket basılabilir", MAX_LABELS)));
+    }
+
+    let variant = match (p.color.as_deref(), p.size.as_deref()) {
+      (Some(c), Some(s)) => Some(format!("{} / {}", c, s)),
+      (Some(c), None) => Some(c.to_string()),
+      (None, Some(s)) => Some(s.to_string()),
+      (None, None) => None,
+    };
+
+    out.push(LabelData {
+      barcode: p.barcode,
+      name: p.name,
+      variant,
+      price: p.sell_price,
+      qty: it.qty,
+    });
+  }
+
+  if out.is_empty() {
+    return Err(PosError::Validation("Basılacak etiket yok".to_string()));
+  }
+  Ok(out)
+}
+
+pub fn render(labels: &[LabelData], layout: &LabelLayout, format: LabelFormat) -> Result<Vec<u8>, PosError> {
+  match format {
+    LabelFormat::Svg => render_svg(labels, layout).map(String::into_bytes),
+    LabelFormat::Pdf => render_pdf(labels, layout),
+    LabelFormat::Zpl => render_zpl(labels, layout).map(String::into_bytes),
+  }
+}
+
+// -------------------- ORTAK ÇİZİM --------------------
+
+#[derive(Clone, Copy)]
+enum Anchor {
+  Start,
+  Middle,
+}
+
+enum Shape {
+  Bar { x: f64, y: f64, w: f64, h: f64 },
+  Text { x: f64, y: f64, size: f64, bold: bool, anchor: Anchor, text: String },
+}
+
+const PAD_MM: f64 = 2.0;
+const NAME_MM: f64 = 2.8;
+const VARIANT_MM: f64 = 2.4;
+const DIGITS_MM: f64 = 2.2;
+const PRICE_MM: f64 = 3.5;
+
+fn truncate_to_width(s: &str, width_mm: f64, size_mm: f64) -> String {
+  let max_chars = ((width_mm / (size_mm * 0.55)).floor() as usize).max(4);
+  if s.chars().count() <= max_chars {
+    s.to_string()
+  } else {
+    let mut t: String = s.chars().take(max_chars - 1).collect();
+    t.push('…');
+    t
+  }
+}
+
+fn price_text(v: f64) -> String {
+  format!("{} ₺", format_tr_decimal(v))
+}
+
+/// Tek etiketin şekilleri; (x0, y0) sol üst köşe, y aşağı doğru artar.
+fn label_shapes(label: &LabelData, x0: f64, y0: f64, w: f64, h: f64) -> Result<Vec<Shape>, PosError> {
+  let (symbology, modules) = barcode::encode_auto(&label.barcode)?;
+  let inner_w = w - 2.0 * PAD_MM;
+
+  let mut shapes = Vec::new();
+  let mut y = y0 + PAD_MM + NAME_MM;
+
+  shapes.push(Shape::Text {
+    x: x0 + PAD_MM,
+    y,
+    size: NAME_MM,
+    bold: true,
+    anchor: Anchor::Start,
+    text: truncate_to_width(&label.name, inner_w, NAME_MM),
+  });
+
+  if let Some(v) = &label.variant {
+    y += VARIANT_MM + 0.6;
+    shapes.push(Shape::Text {
+      x: x0 + PAD_MM,
+      y,
+      size: VARIANT_MM,
+      bold: false,
+      anchor: Anchor::Start,
+      text: truncate_to_width(v, inner_w, VARIANT_MM),
+    });
+  }
+
+  let bars_top = y + 1.2;
+  let price_baseline = y0 + h - PAD_MM;
+  let digits_baseline = price_baseline - PRICE_MM - 0.6;
+  let bars_bottom = digits_baseline - DIGITS_MM - 0.4;
+  let bars_h = bars_bottom - bars_top;
+  if bars_h < 4.0 {
+    return Err(PosError::Validation("Etiket yüksekliği barkod için yetersiz".to_string()));
+  }
+
+  // sessiz bölge: her iki yanda 10 modül
+  let total_modules = modules.len() as f64 + 20.0;
+  let module_w = (inner_w / total_modules).min(0.4);
+  let bars_w = modules.len() as f64 * module_w;
+  let bars_x = x0 + (w - bars_w) / 2.0;
+
+  // ardışık siyah modülleri tek dikdörtgende birleştir
+  let mut i = 0;
+  while i < modules.len() {
+    if modules[i] {
+      let start = i;
+      while i < modules.len() && modules[i] {
+        i += 1;
+      }
+      shapes.push(Shape::Bar {
+        x: bars_x + start as f64 * module_w,
+        y: bars_top,
+        w: (i - start) as f64 * module_w,
+        h: bars_h,
+      });
+    } else {
+      i += 1;
+    }
+  }
+
+  let digits = match symbology {
+    Symbology::Ean13 => format!("{} {} {}", &label.barcode[..1], &label.barcode[1..7], &label.barcode[7..]),
+    Symbology::Code128 => label.barcode.clone(),
+  };
+
+  shapes.push(Shape::Text {
+    x: x0 + w / 2.0,
+    y: digits_baseline,
+    size: DIGITS_MM,
+    bold: false,
+    anchor: Anchor::Middle,
+    text: digits,
+  });
+
+  shapes.push(Shape::Text {
+    x: x0 + w / 2.0,
+    y: price_baseline,
+    size: PRICE_MM,
+    bold: true,
+    anchor: Anchor::Middle,
+    text: price_text(label.price),
+  });
+
+  Ok(shapes)
+}
+
+/// Sayfa başına şekiller. Adetler açılır, start_position kadar pozisyon atlanır.
+fn paginate(labels: &[LabelData], layout: &LabelLayout) -> Result<Vec<Vec<Shape>>, PosError> {
+  layout.validate()?;
+
+  let per_page = layout.per_page();
+  let mut pages: Vec<Vec<Shape>> = Vec::new();
+  let mut pos = layout.start_position as usize % per_page;
+
+  for label in labels {
+    for _ in 0..label.qty {
+      if pages.is_empty() || pos == 0 {
+        pages.push(Vec::new());
+      }
+
+      let col = (pos % layout.columns as usize) as f64;
+      let row = (pos / layout.columns as usize) as f64;
+      let x = layout.margin_left_mm + col * (layout.label_width_mm + layout.gap_x_mm);
+      let y = layout.margin_top_mm + row * (layout.label_height_mm + layout.gap_y_mm);
+
+      let shapes = label_shapes(label, x, y, layout.label_width_mm, layout.label_height_mm)?;
+      if let Some(page) = pages.last_mut() {
+        page.extend(shapes);
+      }
+
+      pos = (pos + 1) % per_page;
+    }
+  }
+
+  Ok(pages)
+}
+
+// -------------------- SVG --------------------
+
+fn xml_escape(s: &str) -> String {
+  s.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Tüm sayfalar alt alta tek SVG içinde (her sayfa bir <g>).
+pub fn render_svg(labels: &[LabelData], layout: &LabelLayout) -> Result<String, PosError> {
+  let pages = paginate(labels, layout)?;
+  let pw = layout.page_width_mm;
+  let ph = layout.page_height_mm;
+  let total_h = ph * pages.len() as f64;
+
+  let mut out = String::new();
+  out.push_str(&format!(
+    "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{pw}mm\" height=\"{total_h}mm\" viewBox=\"0 0 {pw} {total_h}\" font-family=\"Arial, Helvetica, sans-serif\">\n"
+  ));
+
+  for (i, shapes) in pages.iter().enumerate() {
+    out.push_str(&format!(
+      "<g transform=\"translate(0 {:.3})\">\n<rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"#fff\"/>\n",
+      i as f64 * ph,
+      pw,
+      ph
+    ));
+    for s in shapes {
+      match s {
+        Shape::Bar { x, y, w, h } => out.push_str(&format!(
+          "<rect x=\"{:.3}\" y=\"{:.3}\" width=\"{:.3}\" height=\"{:.3}\" fill=\"#000\"/>\n",
+          x, y, w, h
+        )),
+        Shape::Text { x, y, size, bold, anchor, text } => {
+          let anchor = match anchor {
+            Anchor::Start => "start",
+            Anchor::Middle => "middle",
+          };
+          let weight = if *bold { " font-weight=\"bold\"" } else { "" };
+          out.push_str(&format!(
+            "<text x=\"{:.3}\" y=\"{:.3}\" font-size=\"{:.2}\" text-anchor=\"{}\"{}>{}</text>\n",
+            x,
+            y,
+            size,
+            anchor,
+            weight,
+            xml_escape(text)
+          ));
+        }
+      }
+    }
+    out.push_str("</g>\n");
+  }
+
+  out.push_str("</svg>\n");
+  Ok(out)
+}
+
+// -------------------- PDF --------------------
+//
+// Harici kütüphane olmadan minimal PDF: standart Helvetica fontları,
+// WinAnsiEncoding. Bu kodlamada olmayan ğ/ş/ı/İ harfleri ASCII karşılığına iner.
+
+const PT_PER_MM: f64 = 72.0 / 25.4;
+
+fn pdf_text_bytes(s: &str) -> Vec<u8> {
+  let mut out = Vec::with_capacity(s.len());
+  for ch in s.chars() {
+    let mapped = match ch {
+      'ğ' => 'g',
+      'Ğ' => 'G',
+      'ş' => 's',
+      'Ş' => 'S',
+      'ı' => 'i',
+      'İ' => 'I',
+      '…' => '.',
+      _ => ch,
+    };
+    match mapped as u32 {
+      0x20..=0x7E => out.push(mapped as u8),
+      // Latin-1 (ç, ö, ü, Ç, Ö, Ü ...) WinAnsi ile aynı
+      0xA0..=0xFF => out.push(mapped as u32 as u8),
+      _ => out.push(b'?'),
+    }
+  }
+  out
+}
+
+fn pdf_escape(bytes: &[u8]) -> String {
+  let mut out = String::with_capacity(bytes.len());
+  for &b in bytes {
+    match b {
+      b'(' | b')' | b'\\' => {
+        out.push('\\');
+        out.push(b as char);
+      }
+      0x20..=0x7E => out.push(b as char),
+      _ => out.push_str(&format!("\\{:03o}", b)),
+    }
+  }
+  out
+}
+
+// Helvetica için yaklaşık karakter genişliği (1000 birim üzerinden)
+fn helvetica_width(bytes: &[u8], bold: bool) -> f64 {
+  let units: f64 = bytes
+    .iter()
+    .map(|b| match b {
+      b'0'..=b'9' => 556.0,
+      b' ' | b'.' | b',' | b'/' => 278.0,
+      b'-' => 333.0,
+      b'A'..=b'Z' => 667.0,
+      b'a'..=b'z' => 520.0,
+      _ => 556.0,
+    })
+    .sum();
+  let factor = if bold { 1.06 } else { 1.0 };
+  units * factor / 1000.0
+}
+
+pub fn render_pdf(labels: &[LabelData], layout: &LabelLayout) -> Result<Vec<u8>, PosError> {
+  let pages = paginate(labels, layout)?;
+  let pw = layout.page_width_mm * PT_PER_MM;
+  let ph = layout.page_height_mm * PT_PER_MM;
+
+  // Sayfa içerik akışları
+  let mut contents: Vec<String> = Vec::with_capacity(pages.len());
+  for shapes in &pages {
+    let mut c = String::from("0 g\n");
+    for s in shapes {
+      match s {
+        Shape::Bar { x, y, w, h } => {
+          c.push_str(&format!(
+            "{:.3} {:.3} {:.3} {:.3} re f\n",
+            x * PT_PER_MM,
+            ph - (y + h) * PT_PER_MM,
+            w * PT_PER_MM,
+            h * PT_PER_MM
+          ));
+        }
+        Shape::Text { x, y, size, bold, anchor, text } => {
+          let text = text.replace('₺', "TL");
+          let bytes = pdf_text_bytes(&text);
+          let size_pt = size * PT_PER_MM;
+          let mut x_pt = x * PT_PER_MM;
+          if let Anchor::Middle = anchor {
+            x_pt -= helvetica_width(&bytes, *bold) * size_pt / 2.0;
+          }
+          let font = if *bold { "F2" } else { "F1" };
+          c.push_str(&format!(
+            "BT /{} {:.2} Tf {:.3} {:.3} Td ({}) Tj ET\n",
+            font,
+            size_pt,
+            x_pt,
+            ph - y * PT_PER_MM,
+            pdf_escape(&bytes)
+          ));
+        }
+      }
+    }
+    contents.push(c);
+  }
+
+  // Nesneler: 1 katalog, 2 sayfalar, 3-4 fontlar, sonra her sayfa için (sayfa, içerik)
+  let mut objects: Vec<String> = Vec::new();
+  let kids: Vec<String> = (0..pages.len())
+    .map(|i| format!("{} 0 R", 5 + i * 2))
+    .collect();
+
+  objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+  objects.push(format!(
+    "<< /Type /Pages /Kids [{}] /Count {} >>",
+    kids.join(" "),
+    pages.len()
+  ));
+  objects.push(
+    "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+  );
+  objects.push(
+    "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+      .to_string(),
+  );
+
+  for (i, c) in contents.iter().enumerate() {
+    objects.push(format!(
+      "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.3} {:.3}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+      pw,
+      ph,
+      6 + i * 2
+    ));
+    objects.push(format!("<< /Length {} >>\nstream\n{}endstream", c.len(), c));
+  }
+
+  let mut out: Vec<u8> = Vec::new();
+  out.extend_from_slice(b"%PDF-1.4\n");
+
+  let mut offsets: Vec<usize> = Vec::with_capacity(objects.len());
+  for (i, obj) in objects.iter().enumerate() {
+    offsets.push(out.len());
+    out.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, obj).as_bytes());
+  }
+
+  let xref_at = out.len();
+  let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+  for off in offsets {
+    xref.push_str(&format!("{:010} 00000 n \n", off));
+  }
+  out.extend_from_slice(xref.as_bytes());
+  out.extend_from_slice(
+    format!(
+      "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+      objects.len() + 1,
+      xref_at
+    )
+    .as_bytes(),
+  );
+
+  Ok(out)
+}
+
+// -------------------- ZPL (etiket yazıcı) --------------------
+
+const ZPL_DOTS_PER_MM: f64 = 8.0; // 203 dpi
+
+fn zpl_field(s: &str) -> String {
+  // ^ ve ~ ZPL komut karakterleri
+  s.replace(['^', '~'], " ")
+}
+
+/// Her ürün için tek format, adet ^PQ ile. Yazıcı UTF-8 (^CI28) ile çalışır.
+pub fn render_zpl(labels: &[LabelData], layout: &LabelLayout) -> Result<String, PosError> {
+  let dots = |mm: f64| (mm * ZPL_DOTS_PER_MM).round() as i64;
+  let w = dots(layout.label_width_mm);
+  let h = dots(layout.label_height_mm);
+  let pad = dots(PAD_MM);
+
+  if layout.label_width_mm < 20.0 || layout.label_height_mm < 15.0 {
+    return Err(PosError::Validation("Etiket boyutu çok küçük (min 20 × 15 mm)".to_string()));
+  }
+
+  let name_h = dots(NAME_MM);
+  let variant_h = dots(VARIANT_MM);
+  let price_h = dots(PRICE_MM);
+  let inner_w = w - 2 * pad;
+
+  let mut out = String::new();
+  for label in labels {
+    let mut y = pad;
+
+    out.push_str("^XA\n^CI28\n");
+    out.push_str(&format!("^PW{}\n^LL{}\n", w, h));
+
+    out.push_str(&format!(
+      "^FO{},{}^A0N,{},{}^FB{},1,0,L^FD{}^FS\n",
+      pad,
+      y,
+      name_h,
+      name_h,
+      inner_w,
+      zpl_field(&label.name)
+    ));
+    y += name_h + 4;
+
+    if let Some(v) = &label.variant {
+      out.push_str(&format!(
+        "^FO{},{}^A0N,{},{}^FB{},1,0,L^FD{}^FS\n",
+        pad,
+        y,
+        variant_h,
+        variant_h,
+        inner_w,
+        zpl_field(v)
+      ));
+      y += variant_h + 4;
+    }
+
+    // barkod + altındaki rakamlar (yazıcı çizer)
+    let price_y = h - pad - price_h;
+    let bar_h = price_y - y - dots(DIGITS_MM) - 12;
+    if bar_h < dots(4.0) {
+      return Err(PosError::Validation("Etiket yüksekliği barkod için yetersiz".to_string()));
+    }
+
+    let (symbology, modules) = barcode::encode_auto(&label.barcode)?;
+    let module_w = (inner_w / (modules.len() as i64 + 20)).clamp(1, 3);
+    let bar_x = ((w - module_w * modules.len() as i64) / 2).max(0);
+
+    out.push_str(&format!("^BY{}\n", module_w));
+    match symbology {
+      // ^BE kontrol hanesini kendisi hesaplar: ilk 12 hane
+      Symbology::Ean13 => out.push_str(&format!(
+        "^FO{},{}^BEN,{},Y,N^FD{}^FS\n",
+        bar_x,
+        y,
+        bar_h,
+        &label.barcode[..12]
+      )),
+      Symbology::Code128 => out.push_str(&format!(
+        "^FO{},{}^BCN,{},Y,N,N^FD{}^FS\n",
+        bar_x,
+        y,
+        bar_h,
+        zpl_field(&label.barcode)
+      )),
+    }
+
+    out.push_str(&format!(
+      "^FO{},{}^A0N,{},{}^FB{},1,0,C^FD{}^FS\n",
+      pad,
+      price_y,
+      price_h,
+      price_h,
+      inner_w,
+      zpl_field(&price_text(label.price))
+    ));
+
+    out.push_str(&format!("^PQ{}\n^XZ\n", label.qty));
+  }
+
+  Ok(out)
+}
+
+pub fn default_file_name(format: LabelFormat) -> String {
+  let today = chrono::Local::now().format("%Y-%m-%d");
+  format!("Etiketler_{}.{}", today, format.extension())
+}
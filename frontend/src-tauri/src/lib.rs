@@ -0,0 +1,14 @@
+// Masaüstü uygulaması (main.rs) ve komut satırı aracı (bin/ciel-pos-cli.rs) aynı katmanları kullanır.
+
+pub mod db;
+pub mod error;
+pub mod i18n;
+pub mod paths;
+pub mod archive;
+pub mod backup;
+pub mod export;
+pub mod import;
+pub mod barcode;
+pub mod labels;
+pub mod http_api;
+pub mod webhooks;
@@ -1,10 +1,14 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod db;
-mod backup;
+use app_lib::{backup, db, export, labels, webhooks};
+use app_lib::http_api::{ApiServer, ApiStatus};
+use app_lib::db::Store;
+use app_lib::error::PosError;
+use app_lib::paths::AppPaths;
 
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tauri_plugin_dialog;
+use tauri_plugin_dialog::DialogExt;
 
 // -------------------- INPUT NORMALIZATION --------------------
 
@@ -15,19 +19,19 @@ fn norm_opt(s: Option<String>) -> Option<String> {
   })
 }
 
-fn norm_req(field: &str, s: String) -> Result<String, String> {
+fn norm_req(field: &str, s: String) -> Result<String, PosError> {
   let t = s.trim().to_string();
   if t.is_empty() {
-    Err(format!("{} zorunlu (boş olamaz).", field))
+    Err(PosError::Validation(format!("{} zorunlu (boş olamaz).", field)))
   } else {
     Ok(t)
   }
 }
 
-fn norm_req_len(field: &str, s: String, max_len: usize) -> Result<String, String> {
+fn norm_req_len(field: &str, s: String, max_len: usize) -> Result<String, PosError> {
   let t = norm_req(field, s)?;
   if t.chars().count() > max_len {
-    Err(format!("{} çok uzun (max {}).", field, max_len))
+    Err(PosError::Validation(format!("{} çok uzun (max {}).", field, max_len)))
   } else {
     Ok(t)
   }
@@ -38,19 +42,6 @@ fn norm_opt_q(s: Option<String>) -> Option<String> {
   norm_opt(s)
 }
 
-fn normalize_payment_method(pm: Option<String>) -> String {
-  let t = pm
-    .as_deref()
-    .map(|s| s.trim().to_uppercase())
-    .unwrap_or_default();
-
-  match t.as_str() {
-    "CARD" | "CASH" | "TRANSFER" => t,
-    "" => "CARD".to_string(),
-    _ => "CARD".to_string(),
-  }
-}
-
 // -------------------- PRODUCTS --------------------
 
 #[derive(serde::Deserialize)]
@@ -63,41 +54,54 @@ struct AddProductPayload {
   size: Option<String>,
   buy_price: Option<f64>,
   sell_price: f64,
-  stock: Option<i64>,
   magaza_baslangic: Option<i64>,
   depo_baslangic: Option<i64>,
 }
 
 #[tauri::command]
-fn ping_db() -> Result<String, String> {
-  db::ping()
+fn ping_db(store: tauri::State<'_, Store>) -> Result<String, PosError> {
+  store.ping()
+}
+
+#[tauri::command]
+fn list_products(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<db::Product>, PosError> {
+  store.list_products(&query.unwrap_or_default())
 }
 
 #[tauri::command]
-fn list_products() -> Result<Vec<db::Product>, String> {
-  db::list_products()
+fn search_products(
+  store: tauri::State<'_, Store>,
+  query: Option<String>,
+  filters: Option<db::ProductSearchFilters>,
+  page: Option<db::PageRequest>,
+) -> Result<db::ProductSearchResult, PosError> {
+  store.search_products(
+    query.as_deref().unwrap_or(""),
+    filters.unwrap_or_default(),
+    page.unwrap_or_default(),
+  )
 }
 
 #[tauri::command]
-fn find_product(barcode: String) -> Result<Option<db::Product>, String> {
+fn find_product(store: tauri::State<'_, Store>, barcode: String) -> Result<Option<db::Product>, PosError> {
   let bc = barcode.trim().to_string();
   if bc.is_empty() {
-    return Err("barcode zorunlu (boş olamaz).".into());
+    return Err(PosError::Validation("barcode zorunlu (boş olamaz).".into()));
   }
-  db::find_product_by_barcode(&bc)
+  store.find_product_by_barcode(&bc)
 }
 
 #[tauri::command]
-fn delete_product(barcode: String) -> Result<i64, String> {
+fn delete_product(store: tauri::State<'_, Store>, barcode: String) -> Result<i64, PosError> {
   let bc = barcode.trim().to_string();
   if bc.is_empty() {
-    return Err("barcode zorunlu (boş olamaz).".into());
+    return Err(PosError::Validation("barcode zorunlu (boş olamaz).".into()));
   }
   println!("[delete_product] called with barcode={}", bc);
-  db::delete_product(&bc)
+  store.delete_product(&bc)
 }
 #[tauri::command]
-fn add_product(payload: AddProductPayload) -> Result<CreatedProductDto, String> {
+fn add_product(store: tauri::State<'_, Store>, payload: AddProductPayload) -> Result<CreatedProductDto, PosError> {
   // name zorunlu
   let name = norm_req_len("name", payload.name, 200)?;
 
@@ -109,15 +113,15 @@ fn add_product(payload: AddProductPayload) -> Result<CreatedProductDto, String>
   let size_opt = norm_opt(payload.size);
 
   if payload.sell_price < 0.0 {
-    return Err("sell_price negatif olamaz.".into());
+    return Err(PosError::Validation("sell_price negatif olamaz.".into()));
   }
   if let Some(bp) = payload.buy_price {
     if bp < 0.0 {
-      return Err("buy_price negatif olamaz.".into());
+      return Err(PosError::Validation("buy_price negatif olamaz.".into()));
     }
   }
 
-  let created = db::add_product(
+  let created = store.add_product(
     barcode_opt,
     product_code_opt,
     category_opt,
@@ -126,7 +130,6 @@ fn add_product(payload: AddProductPayload) -> Result<CreatedProductDto, String>
     size_opt,
     payload.buy_price,
     payload.sell_price,
-    payload.stock,
     payload.magaza_baslangic,
     payload.depo_baslangic,
   )?;
@@ -138,7 +141,7 @@ fn add_product(payload: AddProductPayload) -> Result<CreatedProductDto, String>
 }
 /*
 #[tauri::command]
-fn add_product(payload: AddProductPayload) -> Result<String, String> {
+fn add_product(payload: AddProductPayload) -> Result<String, PosError> {
   // name zorunlu
   let name = norm_req_len("name", payload.name, 200)?;
 
@@ -150,11 +153,11 @@ fn add_product(payload: AddProductPayload) -> Result<String, String> {
   let size_opt = norm_opt(payload.size);
 
   if payload.sell_price < 0.0 {
-    return Err("sell_price negatif olamaz.".into());
+    return Err(PosError::Validation("sell_price negatif olamaz.".into()));
   }
   if let Some(bp) = payload.buy_price {
     if bp < 0.0 {
-      return Err("buy_price negatif olamaz.".into());
+      return Err(PosError::Validation("buy_price negatif olamaz.".into()));
     }
   }
 
@@ -174,168 +177,184 @@ fn add_product(payload: AddProductPayload) -> Result<String, String> {
 }*/
 
 #[tauri::command]
-fn update_product(payload: db::UpdateProductPayload) -> Result<i64, String> {
-  db::update_product(payload)
+fn list_product_barcodes(
+  store: tauri::State<'_, Store>,
+  barcode: String,
+  query: Option<db::ListQuery>,
+) -> Result<db::Page<db::ProductBarcodeRow>, PosError> {
+  let bc = norm_req("barcode", barcode)?;
+  store.list_product_barcodes(&bc, &query.unwrap_or_default())
 }
 
 #[tauri::command]
-fn update_stock(payload: db::UpdateStockPayload) -> Result<i64, String> {
-  db::update_stock(payload)
+fn add_product_barcode(store: tauri::State<'_, Store>, barcode: String, alias: String, note: Option<String>) -> Result<i64, PosError> {
+  let bc = norm_req("barcode", barcode)?;
+  let alias = norm_req_len("alias", alias, 64)?;
+  store.add_product_barcode(&bc, &alias, norm_opt(note))
 }
 
 #[tauri::command]
-fn get_velocity_report(days: i64) -> Result<Vec<db::VelocityRow>, String> {
-  db::get_velocity_report(days)
+fn delete_product_barcode(store: tauri::State<'_, Store>, alias: String) -> Result<i64, PosError> {
+  let alias = norm_req("alias", alias)?;
+  store.delete_product_barcode(&alias)
 }
 
 #[tauri::command]
-fn get_dead_stock(min_days: i64) -> Result<Vec<db::DeadStockRow>, String> {
-  db::get_dead_stock(min_days)
+fn create_product_family(
+  store: tauri::State<'_, Store>,
+  payload: db::CreateProductFamilyPayload,
+) -> Result<db::CreateProductFamilyResult, PosError> {
+  let name = norm_req_len("name", payload.name, 200)?;
+
+  if payload.sell_price < 0.0 {
+    return Err(PosError::Validation("sell_price negatif olamaz.".into()));
+  }
+  if let Some(bp) = payload.buy_price {
+    if bp < 0.0 {
+      return Err(PosError::Validation("buy_price negatif olamaz.".into()));
+    }
+  }
+
+  store.create_product_family(db::CreateProductFamilyPayload {
+    name,
+    product_code: norm_opt(payload.product_code),
+    category: norm_opt(payload.category),
+    ..payload
+  })
 }
 
 #[tauri::command]
-fn get_category_margin(days: i64) -> Result<Vec<db::CategoryMarginRow>, String> {
-  db::get_category_margin(days)
+fn get_product_family(store: tauri::State<'_, Store>, product_code: String) -> Result<db::ProductFamilyGrid, PosError> {
+  let pc = norm_req("product_code", product_code)?;
+  store.get_product_family(&pc)
 }
 
 #[tauri::command]
-fn get_basket_pairs(limit: i64) -> Result<Vec<db::BasketPairRow>, String> {
-  db::get_basket_pairs(limit)
+fn update_product(store: tauri::State<'_, Store>, payload: db::UpdateProductPayload) -> Result<i64, PosError> {
+  store.update_product(payload)
 }
 
 #[tauri::command]
-fn get_low_stock(max_stock: i64) -> Result<Vec<db::LowStockRow>, String> {
-  db::get_low_stock(max_stock)
+fn update_stock(store: tauri::State<'_, Store>, payload: db::UpdateStockPayload) -> Result<i64, PosError> {
+  store.update_stock(payload)
 }
 
-#[derive(serde::Serialize)]
-pub struct CreatedProductDto {
-  pub barcode: String,
-  pub product_code: Option<String>,
+#[tauri::command]
+fn get_velocity_report(
+  store: tauri::State<'_, Store>,
+  range: Option<db::ReportRange>,
+) -> Result<db::ReportResult<Vec<db::VelocityRow>>, PosError> {
+  db::run_report(&range.unwrap_or_default(), |p| store.get_velocity_report(p))
 }
 
-// -------------------- SALES --------------------
-
-#[derive(serde::Deserialize)]
-struct CreateSaleItemPayload {
-  barcode: String,
-  qty: i64,
-  list_price: f64,
-  discount_amount: f64,
-  unit_price: f64,
-  sold_from: String,
+#[tauri::command]
+fn get_dead_stock(store: tauri::State<'_, Store>, min_days: i64) -> Result<Vec<db::DeadStockRow>, PosError> {
+  store.get_dead_stock(min_days)
 }
 
-#[derive(serde::Deserialize)]
-struct CreateSalePayload {
-  sold_from_default: String,
-  items: Vec<CreateSaleItemPayload>,
-  payment_method: Option<String>,
+#[tauri::command]
+fn get_category_margin(
+  store: tauri::State<'_, Store>,
+  range: Option<db::ReportRange>,
+) -> Result<db::ReportResult<Vec<db::CategoryMarginRow>>, PosError> {
+  db::run_report(&range.unwrap_or_default(), |p| store.get_category_margin(p))
 }
 
-#[derive(serde::Serialize)]
-struct CreateSaleResult {
-  sale_group_id: String,
-  total: f64,
-  lines: i64,
+#[tauri::command]
+fn get_basket_pairs(
+  store: tauri::State<'_, Store>,
+  limit: i64,
+  range: Option<db::ReportRange>,
+) -> Result<db::ReportResult<Vec<db::BasketPairRow>>, PosError> {
+  db::run_report(&range.unwrap_or_default(), |p| store.get_basket_pairs(p, limit))
 }
 
 #[tauri::command]
-fn create_sale(payload: CreateSalePayload) -> Result<CreateSaleResult, String> {
-  db::create_sale(db::CreateSalePayload {
-    sold_from_default: payload.sold_from_default.trim().to_string(),
-    payment_method: normalize_payment_method(payload.payment_method),
-    items: payload
-      .items
-      .into_iter()
-      .map(|i| db::CreateSaleItemPayload {
-        barcode: i.barcode.trim().to_string(),
-        qty: i.qty,
-        list_price: i.list_price,
-        discount_amount: i.discount_amount,
-        unit_price: i.unit_price,
-        sold_from: i.sold_from.trim().to_string(),
-      })
-      .collect(),
-  })
-  .map(|r| CreateSaleResult {
-    sale_group_id: r.sale_group_id,
-    total: r.total,
-    lines: r.lines,
-  })
+fn get_low_stock(store: tauri::State<'_, Store>, max_stock: i64) -> Result<Vec<db::LowStockRow>, PosError> {
+  store.get_low_stock(max_stock)
 }
 
 #[derive(serde::Serialize)]
-struct UndoLastSaleResult {
-  sale_group_id: String,
-  restored_lines: i64,
-  sold_at: String,
+pub struct CreatedProductDto {
+  pub barcode: String,
+  pub product_code: Option<String>,
 }
 
+// -------------------- SALES --------------------
+// Kırpma / ödeme yöntemi / varsayılan lokasyon db'de (HTTP API de aynı yolu kullanır)
+
 #[tauri::command]
-fn undo_last_sale() -> Result<UndoLastSaleResult, String> {
-  db::undo_last_sale().map(|r| UndoLastSaleResult {
-    sale_group_id: r.sale_group_id,
-    restored_lines: r.restored_lines,
-    sold_at: r.sold_at,
-  })
+fn create_sale(store: tauri::State<'_, Store>, payload: db::CreateSalePayload) -> Result<db::CreateSaleResult, PosError> {
+  store.create_sale(payload)
 }
 
-// -------------------- TRANSFER (Mağaza <-> Depo) --------------------
-
-#[derive(serde::Deserialize)]
-struct CreateTransferItemPayload {
-  barcode: String,
-  qty: i64,
-  from_loc: String,
-  to_loc: String,
+#[tauri::command]
+fn undo_last_sale(store: tauri::State<'_, Store>) -> Result<db::UndoLastSaleResult, PosError> {
+  store.undo_last_sale()
 }
 
-#[derive(serde::Deserialize)]
-struct CreateTransferPayload {
-  items: Vec<CreateTransferItemPayload>,
-  note: Option<String>,
+// Sepet her değiştiğinde; sonuç cart_updated olarak müşteri ekranına da gider
+#[tauri::command]
+fn price_cart(store: tauri::State<'_, Store>, payload: db::PriceCartPayload) -> Result<db::PricedCart, PosError> {
+  store.price_cart(payload)
 }
 
-#[derive(serde::Serialize)]
-struct CreateTransferResult {
-  transfer_group_id: String,
-  lines: i64,
+// -------------------- MÜŞTERİ EKRANI --------------------
+// İkinci pencere (/customer-display) yalnız cart_updated / sale_created olaylarını dinler.
+// Windows'ta senkron komuttan pencere açmak kilitlenir: async.
+
+const CUSTOMER_DISPLAY: &str = "customer-display";
+
+/// Pencereyi açar ya da öne getirir. İkinci bir monitör varsa orada tam ekran açılır
+/// ve true döner; tek monitörde normal pencere olarak açılır.
+#[tauri::command]
+async fn open_customer_display(app: tauri::AppHandle) -> Result<bool, PosError> {
+  let err = |e: tauri::Error| PosError::Io(e.to_string());
+
+  let primary = app.primary_monitor().map_err(err)?;
+  let secondary = app
+    .available_monitors()
+    .map_err(err)?
+    .into_iter()
+    .find(|m| primary.as_ref().map_or(true, |p| m.position() != p.position()));
+
+  let window = match app.get_webview_window(CUSTOMER_DISPLAY) {
+    Some(w) => w,
+    None => tauri::WebviewWindowBuilder::new(&app, CUSTOMER_DISPLAY, tauri::WebviewUrl::App(CUSTOMER_DISPLAY.into()))
+      .title("CIEL POS")
+      .inner_size(1024.0, 768.0)
+      .visible(false)
+      .build()
+      .map_err(err)?,
+  };
+
+  if let Some(m) = &secondary {
+    window.set_position(*m.position()).map_err(err)?;
+    window.set_fullscreen(true).map_err(err)?;
+  }
+  window.show().map_err(err)?;
+  window.set_focus().map_err(err)?;
+  Ok(secondary.is_some())
 }
 
-#[derive(serde::Serialize)]
-struct UndoLastTransferResult {
-  transfer_group_id: String,
-  restored_lines: i64,
+#[tauri::command]
+fn close_customer_display(app: tauri::AppHandle) -> Result<(), PosError> {
+  if let Some(w) = app.get_webview_window(CUSTOMER_DISPLAY) {
+    w.close().map_err(|e| PosError::Io(e.to_string()))?;
+  }
+  Ok(())
 }
 
+// -------------------- TRANSFER (Mağaza <-> Depo) --------------------
+
 #[tauri::command]
-fn create_transfer(payload: CreateTransferPayload) -> Result<CreateTransferResult, String> {
-  db::create_transfer(db::CreateTransferPayload {
-    items: payload
-      .items
-      .into_iter()
-      .map(|it| db::CreateTransferItemPayload {
-        barcode: it.barcode.trim().to_string(),
-        qty: it.qty,
-        from_loc: it.from_loc.trim().to_string(),
-        to_loc: it.to_loc.trim().to_string(),
-      })
-      .collect(),
-    note: norm_opt(payload.note),
-  })
-  .map(|r| CreateTransferResult {
-    transfer_group_id: r.transfer_group_id,
-    lines: r.lines,
-  })
+fn create_transfer(store: tauri::State<'_, Store>, payload: db::CreateTransferPayload) -> Result<db::CreateTransferResult, PosError> {
+  store.create_transfer(payload)
 }
 
 #[tauri::command]
-fn undo_last_transfer() -> Result<UndoLastTransferResult, String> {
-  db::undo_last_transfer().map(|r| UndoLastTransferResult {
-    transfer_group_id: r.transfer_group_id,
-    restored_lines: r.restored_lines,
-  })
+fn undo_last_transfer(store: tauri::State<'_, Store>) -> Result<db::UndoLastTransferResult, PosError> {
+  store.undo_last_transfer()
 }
 
 // -------------------- RETURN / EXCHANGE --------------------
@@ -343,7 +362,8 @@ fn undo_last_transfer() -> Result<UndoLastTransferResult, String> {
 #[derive(serde::Deserialize)]
 struct ListSalesByBarcodePayload {
   barcode: String,
-  days: i64,
+  #[serde(flatten)]
+  query: db::ListQuery,
 }
 
 #[derive(serde::Serialize)]
@@ -357,24 +377,21 @@ struct SaleLineDto {
 }
 
 #[tauri::command]
-fn list_sales_by_barcode(payload: ListSalesByBarcodePayload) -> Result<Vec<SaleLineDto>, String> {
+fn list_sales_by_barcode(store: tauri::State<'_, Store>, payload: ListSalesByBarcodePayload) -> Result<db::Page<SaleLineDto>, PosError> {
   let bc = payload.barcode.trim().to_string();
   if bc.is_empty() {
-    return Err("barcode zorunlu (boş olamaz).".into());
+    return Err(PosError::Validation("barcode zorunlu (boş olamaz).".into()));
   }
 
-  db::list_sales_by_barcode(&bc, payload.days).map(|rows| {
-    rows
-      .into_iter()
-      .map(|r| SaleLineDto {
-        sold_at: r.sold_at,
-        qty: r.qty,
-        unit_price: r.unit_price,
-        total: r.total,
-        sold_from: r.sold_from,
-        refunded_qty: r.refunded_qty,
-      })
-      .collect()
+  store.list_sales_by_barcode(&bc, &payload.query).map(|page| {
+    page.map(|r| SaleLineDto {
+      sold_at: r.sold_at,
+      qty: r.qty,
+      unit_price: r.unit_price,
+      total: r.total,
+      sold_from: r.sold_from,
+      refunded_qty: r.refunded_qty,
+    })
   })
 }
 
@@ -396,8 +413,8 @@ struct CreateReturnResult {
 }
 
 #[tauri::command]
-fn create_return(payload: CreateReturnPayload) -> Result<CreateReturnResult, String> {
-  db::create_return(db::CreateReturnPayload {
+fn create_return(store: tauri::State<'_, Store>, payload: CreateReturnPayload) -> Result<CreateReturnResult, PosError> {
+  store.create_return(db::CreateReturnPayload {
     barcode: payload.barcode.trim().to_string(),
     qty: payload.qty,
     return_to: payload.return_to.trim().to_string(),
@@ -452,8 +469,8 @@ struct CreateExchangeResult {
 }
 
 #[tauri::command]
-fn create_exchange(payload: CreateExchangePayload) -> Result<CreateExchangeResult, String> {
-  db::create_exchange(db::CreateExchangePayload {
+fn create_exchange(store: tauri::State<'_, Store>, payload: CreateExchangePayload) -> Result<CreateExchangeResult, PosError> {
+  store.create_exchange(db::CreateExchangePayload {
     returned: db::CreateExchangeReturnedPayload {
       barcode: payload.returned.barcode.trim().to_string(),
       qty: payload.returned.qty,
@@ -497,13 +514,13 @@ struct AddExpensePayload {
 }
 
 #[tauri::command]
-fn list_expenses() -> Result<Vec<db::Expense>, String> {
-  db::list_expenses()
+fn list_expenses(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<db::Expense>, PosError> {
+  store.list_expenses(&query.unwrap_or_default())
 }
 
 #[tauri::command]
-fn add_expense(payload: AddExpensePayload) -> Result<i64, String> {
-  db::add_expense(
+fn add_expense(store: tauri::State<'_, Store>, payload: AddExpensePayload) -> Result<i64, PosError> {
+  store.add_expense(
     payload.spent_at.trim().to_string(),
     norm_opt(payload.period),
     norm_opt(payload.category),
@@ -513,32 +530,58 @@ fn add_expense(payload: AddExpensePayload) -> Result<i64, String> {
 }
 
 #[tauri::command]
-fn delete_expense(id: i64) -> Result<i64, String> {
-  db::delete_expense(id)
+fn delete_expense(store: tauri::State<'_, Store>, id: i64) -> Result<i64, PosError> {
+  store.delete_expense(id)
 }
 
 // -------------------- SOLD PRODUCTS / GROUPS --------------------
 
 #[tauri::command]
-fn list_sale_groups(days: i64, q: Option<String>) -> Result<Vec<db::SaleGroupRow>, String> {
-  db::list_sale_groups(days, norm_opt_q(q))
+fn list_sale_groups(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<db::SaleGroupRow>, PosError> {
+  let mut query = query.unwrap_or_default();
+  query.q = norm_opt_q(query.q);
+  store.list_sale_groups(&query)
 }
 
 #[tauri::command]
-fn list_sales_by_group(sale_group_id: String) -> Result<Vec<db::SaleLineRow>, String> {
-  db::list_sales_by_group(&sale_group_id)
+fn list_sales_by_group(
+  store: tauri::State<'_, Store>,
+  sale_group_id: String,
+  query: Option<db::ListQuery>,
+) -> Result<db::Page<db::SaleLineRow>, PosError> {
+  store.list_sales_by_group(&sale_group_id, &query.unwrap_or_default())
 }
 
 // -------------------- DASHBOARD --------------------
 
 #[tauri::command]
-fn get_dashboard_summary(days: i64, months: i64) -> Result<db::DashboardSummary, String> {
-  db::get_dashboard_summary(days, months)
+fn get_dashboard_summary(
+  store: tauri::State<'_, Store>,
+  query: Option<db::DashboardQuery>,
+) -> Result<db::ReportResult<db::DashboardSummary>, PosError> {
+  let query = query.unwrap_or_default();
+
+  // aylık seri: ayrı aralık verilmediyse ana aralığın ayları
+  let monthly = if query.monthly_from.is_some() || query.monthly_to.is_some() {
+    db::ReportRange {
+      from: query.monthly_from.clone(),
+      to: query.monthly_to.clone(),
+      compare: false,
+    }
+    .period()?
+  } else {
+    query.range.period()?
+  };
+
+  db::run_report(&query.range, |p| store.get_dashboard_summary(p, &monthly))
 }
 
 #[tauri::command]
-fn get_cash_report(days: i64) -> Result<Vec<db::CashReportRow>, String> {
-  db::get_cash_report(days)
+fn get_cash_report(
+  store: tauri::State<'_, Store>,
+  range: Option<db::ReportRange>,
+) -> Result<db::ReportResult<Vec<db::CashReportRow>>, PosError> {
+  db::run_report(&range.unwrap_or_default(), |p| store.get_cash_report(p))
 }
 
 // -------------------- DICTIONARIES (Categories / Colors / Sizes) --------------------
@@ -553,201 +596,440 @@ struct DictItemDto {
 }
 
 #[tauri::command]
-fn list_categories(include_inactive: Option<bool>) -> Result<Vec<DictItemDto>, String> {
-  let inc = include_inactive.unwrap_or(false);
-  db::list_categories_full(inc).map(|rows| {
-    rows
-      .into_iter()
-      .map(|r| DictItemDto {
-        id: r.id,
-        name: r.name,
-        is_active: r.is_active,
-        created_at: r.created_at,
-        sort_order: None,
-      })
-      .collect()
+fn list_categories(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<DictItemDto>, PosError> {
+  store.list_categories_full(&query.unwrap_or_default()).map(|page| {
+    page.map(|r| DictItemDto {
+      id: r.id,
+      name: r.name,
+      is_active: r.is_active,
+      created_at: r.created_at,
+      sort_order: None,
+    })
   })
 }
 
 #[tauri::command]
-fn create_category(name: String) -> Result<i64, String> {
+fn create_category(store: tauri::State<'_, Store>, name: String) -> Result<i64, PosError> {
   let n = norm_req_len("name", name, 100)?;
-  db::create_category(n)
+  store.create_category(n)
 }
 
 #[tauri::command]
-fn update_category(id: i64, name: Option<String>, is_active: Option<i64>) -> Result<i64, String> {
+fn update_category(store: tauri::State<'_, Store>, id: i64, name: Option<String>, is_active: Option<i64>) -> Result<i64, PosError> {
   let n = match name {
     Some(v) => {
       let t = v.trim().to_string();
-      if t.is_empty() { return Err("name boş olamaz.".into()); }
+      if t.is_empty() { return Err(PosError::Validation("name boş olamaz.".into())); }
       Some(t)
     }
     None => None,
   };
-  db::update_category(id, n, is_active)
+  store.update_category(id, n, is_active)
 }
 
 #[tauri::command]
-fn delete_category(id: i64) -> Result<i64, String> {
-  db::delete_category(id)
+fn delete_category(store: tauri::State<'_, Store>, id: i64) -> Result<i64, PosError> {
+  store.delete_category(id)
 }
 
 #[tauri::command]
-fn list_colors(include_inactive: Option<bool>) -> Result<Vec<DictItemDto>, String> {
-  let inc = include_inactive.unwrap_or(false);
-  db::list_colors_full(inc).map(|rows| {
-    rows
-      .into_iter()
-      .map(|r| DictItemDto {
-        id: r.id,
-        name: r.name,
-        is_active: r.is_active,
-        created_at: r.created_at,
-        sort_order: None,
-      })
-      .collect()
+fn list_colors(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<DictItemDto>, PosError> {
+  store.list_colors_full(&query.unwrap_or_default()).map(|page| {
+    page.map(|r| DictItemDto {
+      id: r.id,
+      name: r.name,
+      is_active: r.is_active,
+      created_at: r.created_at,
+      sort_order: None,
+    })
   })
 }
 #[tauri::command]
-fn list_categories_full(include_inactive: bool) -> Result<Vec<db::CategoryRow>, String> {
-  db::list_categories_full(include_inactive)
+fn list_categories_full(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<db::CategoryRow>, PosError> {
+  store.list_categories_full(&query.unwrap_or_default())
 }
 
 #[tauri::command]
-fn list_colors_full(include_inactive: bool) -> Result<Vec<db::ColorRow>, String> {
-  db::list_colors_full(include_inactive)
+fn list_colors_full(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<db::ColorRow>, PosError> {
+  store.list_colors_full(&query.unwrap_or_default())
 }
 
 #[tauri::command]
-fn list_sizes_full(include_inactive: bool) -> Result<Vec<db::SizeRow>, String> {
-  db::list_sizes_full(include_inactive)
+fn list_sizes_full(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<db::SizeRow>, PosError> {
+  store.list_sizes_full(&query.unwrap_or_default())
 }
 
 #[tauri::command]
-fn create_color(name: String) -> Result<i64, String> {
+fn create_color(store: tauri::State<'_, Store>, name: String) -> Result<i64, PosError> {
   let n = norm_req_len("name", name, 100)?;
-  db::create_color(n)
+  store.create_color(n)
 }
 
 #[tauri::command]
-fn update_color(id: i64, name: Option<String>, is_active: Option<i64>) -> Result<i64, String> {
+fn update_color(store: tauri::State<'_, Store>, id: i64, name: Option<String>, is_active: Option<i64>) -> Result<i64, PosError> {
   let n = match name {
     Some(v) => {
       let t = v.trim().to_string();
-      if t.is_empty() { return Err("name boş olamaz.".into()); }
+      if t.is_empty() { return Err(PosError::Validation("name boş olamaz.".into())); }
       Some(t)
     }
     None => None,
   };
-  db::update_color(id, n, is_active)
+  store.update_color(id, n, is_active)
 }
 
 #[tauri::command]
-fn delete_color(id: i64) -> Result<i64, String> {
-  db::delete_color(id)
+fn delete_color(store: tauri::State<'_, Store>, id: i64) -> Result<i64, PosError> {
+  store.delete_color(id)
 }
 
 #[tauri::command]
-fn list_sizes(include_inactive: Option<bool>) -> Result<Vec<DictItemDto>, String> {
-  let inc = include_inactive.unwrap_or(false);
-  db::list_sizes_full(inc).map(|rows| {
-    rows
-      .into_iter()
-      .map(|r| DictItemDto {
-        id: r.id,
-        name: r.name,
-        is_active: r.is_active,
-        created_at: r.created_at,
-        sort_order: Some(r.sort_order),
-      })
-      .collect()
+fn list_sizes(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<DictItemDto>, PosError> {
+  store.list_sizes_full(&query.unwrap_or_default()).map(|page| {
+    page.map(|r| DictItemDto {
+      id: r.id,
+      name: r.name,
+      is_active: r.is_active,
+      created_at: r.created_at,
+      sort_order: Some(r.sort_order),
+    })
   })
 }
 
 #[tauri::command]
-fn create_size(name: String, sort_order: Option<i64>) -> Result<i64, String> {
+fn create_size(store: tauri::State<'_, Store>, name: String, sort_order: Option<i64>) -> Result<i64, PosError> {
   let n = norm_req_len("name", name, 100)?;
-  db::create_size(n, sort_order)
+  store.create_size(n, sort_order)
 }
 
 #[tauri::command]
 fn update_size(
+  store: tauri::State<'_, Store>,
   id: i64,
   name: Option<String>,
   sort_order: Option<i64>,
   is_active: Option<i64>,
-) -> Result<i64, String> {
+) -> Result<i64, PosError> {
   let n = match name {
     Some(v) => {
       let t = v.trim().to_string();
-      if t.is_empty() { return Err("name boş olamaz.".into()); }
+      if t.is_empty() { return Err(PosError::Validation("name boş olamaz.".into())); }
       Some(t)
     }
     None => None,
   };
-  db::update_size(id, n, sort_order, is_active)
+  store.update_size(id, n, sort_order, is_active)
+}
+
+#[tauri::command]
+fn delete_size(store: tauri::State<'_, Store>, id: i64) -> Result<i64, PosError> {
+  store.delete_size(id)
+}
+
+// -------------------- EXPORT (CSV / XLSX) --------------------
+
+#[derive(serde::Deserialize)]
+struct ExportReportPayload {
+  #[serde(flatten)]
+  report: export::ExportReport,
+  format: export::ExportFormat,
+}
+
+// Kaydetme diyaloğu bloklayıcı: async komut olarak çalışır, ana thread kilitlenmez.
+// Kullanıcı diyaloğu iptal ederse Ok(None) döner.
+#[tauri::command]
+async fn export_report(
+  store: tauri::State<'_, Store>,
+  app: tauri::AppHandle,
+  payload: ExportReportPayload,
+) -> Result<Option<String>, PosError> {
+  let table = export::build_table(&store, &payload.report)?;
+  let ext = payload.format.extension();
+
+  let picked = app
+    .dialog()
+    .file()
+    .set_file_name(export::default_file_name(&payload.report, payload.format))
+    .add_filter(ext.to_uppercase(), &[ext])
+    .blocking_save_file();
+
+  let Some(picked) = picked else {
+    return Ok(None);
+  };
+
+  let mut path = picked.into_path().map_err(|e| PosError::Io(e.to_string()))?;
+  if path.extension().is_none() {
+    path.set_extension(ext);
+  }
+
+  export::write_table(&path, &table, payload.format)?;
+  Ok(Some(path.to_string_lossy().to_string()))
+}
+
+// -------------------- MAĞAZA AYARLARI --------------------
+
+#[tauri::command]
+fn get_settings(store: tauri::State<'_, Store>) -> Result<db::ShopSettings, PosError> {
+  store.get_settings()
+}
+
+#[tauri::command]
+fn update_settings(store: tauri::State<'_, Store>, payload: db::ShopSettings) -> Result<db::ShopSettings, PosError> {
+  store.update_settings(payload)
+}
+
+// -------------------- DİL --------------------
+
+#[tauri::command]
+fn get_language() -> Result<String, PosError> {
+  db::get_language()
+}
+
+#[tauri::command]
+fn set_language(store: tauri::State<'_, Store>, lang: String) -> Result<String, PosError> {
+  store.set_language(lang)
 }
 
+// -------------------- YEREL API --------------------
+
+#[tauri::command]
+fn get_api_settings(store: tauri::State<'_, Store>) -> Result<db::ApiSettings, PosError> {
+  store.get_api_settings()
+}
+
+#[tauri::command]
+fn get_api_status(api: tauri::State<'_, ApiServer>) -> ApiStatus {
+  api.status()
+}
+
+/// Ayar kaydedilince sunucu yeni adres / portla yeniden başlar (kapalıysa durur)
+#[tauri::command]
+fn update_api_settings(
+  store: tauri::State<'_, Store>,
+  api: tauri::State<'_, ApiServer>,
+  payload: db::ApiSettings,
+) -> Result<ApiStatus, PosError> {
+  let settings = store.update_api_settings(payload)?;
+  api.apply(&store, &settings)
+}
+
+#[tauri::command]
+fn regenerate_api_token(
+  store: tauri::State<'_, Store>,
+  api: tauri::State<'_, ApiServer>,
+) -> Result<db::ApiSettings, PosError> {
+  let settings = store.regenerate_api_token()?;
+  api.apply(&store, &settings)?;
+  Ok(settings)
+}
+
+// -------------------- WEBHOOK (olay kuyruğu) --------------------
+
+#[tauri::command]
+fn list_webhooks(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<db::Webhook>, PosError> {
+  store.list_webhooks(&query.unwrap_or_default())
+}
+
+#[tauri::command]
+fn add_webhook(store: tauri::State<'_, Store>, url: String, label: Option<String>) -> Result<i64, PosError> {
+  store.add_webhook(url, label)
+}
+
+#[tauri::command]
+fn update_webhook(store: tauri::State<'_, Store>, id: i64, label: Option<String>, is_active: Option<i64>) -> Result<i64, PosError> {
+  store.update_webhook(id, label, is_active)
+}
+
+#[tauri::command]
+fn delete_webhook(store: tauri::State<'_, Store>, id: i64) -> Result<i64, PosError> {
+  store.delete_webhook(id)
+}
+
+#[tauri::command]
+fn list_webhook_deliveries(store: tauri::State<'_, Store>, limit: Option<i64>) -> Result<Vec<db::WebhookDeliveryRow>, PosError> {
+  store.list_webhook_deliveries(limit.unwrap_or(50))
+}
+
+/// Bekleyen / başarısız teslimatları hemen gönderir
+#[tauri::command]
+fn retry_webhook_deliveries(store: tauri::State<'_, Store>) -> Result<webhooks::DispatchSummary, PosError> {
+  store.retry_webhook_deliveries()?;
+  webhooks::dispatch_due(&store)
+}
+
+// -------------------- BARKOD / ETİKET --------------------
+
 #[tauri::command]
-fn delete_size(id: i64) -> Result<i64, String> {
-  db::delete_size(id)
+fn get_barcode_settings(store: tauri::State<'_, Store>) -> Result<db::BarcodeSettings, PosError> {
+  store.get_barcode_settings()
+}
+
+#[tauri::command]
+fn update_barcode_settings(store: tauri::State<'_, Store>, payload: db::BarcodeSettings) -> Result<db::BarcodeSettings, PosError> {
+  store.update_barcode_settings(payload)
+}
+
+#[derive(serde::Deserialize)]
+struct RenderLabelsPayload {
+  items: Vec<labels::LabelRequestItem>,
+  format: labels::LabelFormat,
+  layout: Option<labels::LabelLayout>,
+}
+
+// export_report ile aynı: kaydetme diyaloğu, iptal => Ok(None)
+#[tauri::command]
+async fn render_labels(
+  store: tauri::State<'_, Store>,
+  app: tauri::AppHandle,
+  payload: RenderLabelsPayload,
+) -> Result<Option<String>, PosError> {
+  let layout = payload.layout.unwrap_or_default();
+  let data = labels::collect_labels(&store, payload.items)?;
+  let bytes = labels::render(&data, &layout, payload.format)?;
+  let ext = payload.format.extension();
+
+  let picked = app
+    .dialog()
+    .file()
+    .set_file_name(labels::default_file_name(payload.format))
+    .add_filter(ext.to_uppercase(), &[ext])
+    .blocking_save_file();
+
+  let Some(picked) = picked else {
+    return Ok(None);
+  };
+
+  let mut path = picked.into_path().map_err(|e| PosError::Io(e.to_string()))?;
+  if path.extension().is_none() {
+    path.set_extension(ext);
+  }
+
+  std::fs::write(&path, bytes).map_err(|e| PosError::Io(format!("Etiket dosyası yazılamadı: {}", e)))?;
+  Ok(Some(path.to_string_lossy().to_string()))
 }
 
 // -------------------- BACKUP --------------------
 
 #[tauri::command]
-fn backup_now(app: tauri::AppHandle) -> Result<String, String> {
-  backup::backup_sqlite_db(&app)
+fn backup_now(store: tauri::State<'_, Store>, paths: tauri::State<'_, AppPaths>) -> Result<backup::BackupOutcome, PosError> {
+  backup::create_backup(&store, &paths, backup::BackupKind::Manual)
+}
+
+#[tauri::command]
+fn list_backup_targets(store: tauri::State<'_, Store>, query: Option<db::ListQuery>) -> Result<db::Page<db::BackupTarget>, PosError> {
+  store.list_backup_targets(&query.unwrap_or_default())
+}
+
+#[tauri::command]
+fn add_backup_target(store: tauri::State<'_, Store>, path: String, label: Option<String>) -> Result<i64, PosError> {
+  store.add_backup_target(path, label)
+}
+
+#[tauri::command]
+fn update_backup_target(store: tauri::State<'_, Store>, id: i64, label: Option<String>, is_active: Option<i64>) -> Result<i64, PosError> {
+  store.update_backup_target(id, label, is_active)
+}
+
+#[tauri::command]
+fn delete_backup_target(store: tauri::State<'_, Store>, id: i64) -> Result<i64, PosError> {
+  store.delete_backup_target(id)
+}
+
+#[tauri::command]
+fn list_backups(paths: tauri::State<'_, AppPaths>, query: Option<db::ListQuery>) -> Result<db::Page<backup::BackupInfo>, PosError> {
+  backup::list_backups(&paths, &query.unwrap_or_default())
+}
+
+#[tauri::command]
+fn get_backup_policy(store: tauri::State<'_, Store>) -> Result<db::BackupPolicy, PosError> {
+  store.get_backup_policy()
+}
+
+#[tauri::command]
+fn set_backup_passphrase(store: tauri::State<'_, Store>, passphrase: Option<String>) -> Result<db::BackupPolicy, PosError> {
+  store.set_backup_passphrase(passphrase)
+}
+
+#[tauri::command]
+fn update_backup_policy(
+  store: tauri::State<'_, Store>,
+  paths: tauri::State<'_, AppPaths>,
+  payload: db::BackupPolicy,
+) -> Result<db::BackupPolicy, PosError> {
+  let policy = store.update_backup_policy(payload)?;
+  // yeni politika hemen uygulansın
+  backup::prune_backups(&store, &paths, &policy)?;
+  Ok(policy)
 }
 
 #[tauri::command]
-fn get_backup_dir(app: tauri::AppHandle) -> Result<String, String> {
-  let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-  let backups_dir = app_dir.join("backups");
-  std::fs::create_dir_all(&backups_dir).map_err(|e| e.to_string())?;
-  Ok(backups_dir.to_string_lossy().to_string())
+fn get_backup_dir(paths: tauri::State<'_, AppPaths>) -> Result<String, PosError> {
+  Ok(paths.backup_dir()?.to_string_lossy().to_string())
 }
 
 #[tauri::command]
-fn open_backup_folder(app: tauri::AppHandle) -> Result<(), String> {
-  let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-  let backup_dir = app_dir.join("backups");
-  std::fs::create_dir_all(&backup_dir).map_err(|e| e.to_string())?;
+fn open_backup_folder(paths: tauri::State<'_, AppPaths>) -> Result<(), PosError> {
+  let backup_dir = paths.backup_dir()?;
 
   #[cfg(target_os = "macos")]
   {
     std::process::Command::new("open")
       .arg(&backup_dir)
-      .spawn()
-      .map_err(|e| e.to_string())?;
+      .spawn()?;
   }
 
   #[cfg(target_os = "windows")]
   {
     std::process::Command::new("explorer")
       .arg(&backup_dir)
-      .spawn()
-      .map_err(|e| e.to_string())?;
+      .spawn()?;
   }
 
   #[cfg(all(unix, not(target_os = "macos")))]
   {
     std::process::Command::new("xdg-open")
       .arg(&backup_dir)
-      .spawn()
-      .map_err(|e| e.to_string())?;
+      .spawn()?;
   }
 
   Ok(())
 }
 
+/// Bütünlük kontrolü. Onarım istenirse önce DB yedeklenir.
+#[tauri::command]
+fn check_integrity(store: tauri::State<'_, Store>, paths: tauri::State<'_, AppPaths>, repair: Option<bool>) -> Result<db::IntegrityReport, PosError> {
+  let repair = repair.unwrap_or(false);
+  if repair {
+    backup::backup_sqlite_db(&store, &paths, backup::BackupKind::BeforeRepair)?;
+  }
+  store.check_integrity(repair)
+}
+
 #[tauri::command]
 fn restore_from_backup(
-  app: tauri::AppHandle,
+  store: tauri::State<'_, Store>,
+  paths: tauri::State<'_, AppPaths>,
   payload: backup::RestoreFromBackupPayload,
-) -> Result<backup::RestoreFromBackupResult, String> {
-  backup::restore_from_backup(&app, payload)
+) -> Result<backup::RestoreFromBackupResult, PosError> {
+  backup::restore_from_backup(&store, &paths, payload)
+}
+
+#[tauri::command]
+fn verify_backup(
+  store: tauri::State<'_, Store>,
+  paths: tauri::State<'_, AppPaths>,
+  payload: backup::InspectBackupPayload,
+) -> Result<backup::BackupVerification, PosError> {
+  backup::verify_backup(&store, &paths, payload)
+}
+
+#[tauri::command]
+fn inspect_backup(
+  store: tauri::State<'_, Store>,
+  paths: tauri::State<'_, AppPaths>,
+  payload: backup::InspectBackupPayload,
+) -> Result<backup::BackupInspection, PosError> {
+  backup::inspect_backup(&store, &paths, payload)
 }
 
 fn main() {
@@ -755,20 +1037,48 @@ fn main() {
     .plugin(tauri_plugin_dialog::init())
     .setup(|app| {
       // DB init / migrations
-      db::init(&app.handle()).map_err(|e| {
+      // masaüstü veri klasörü (CLI aynı klasörü AppPaths::default_data_dir ile bulur)
+      let paths = AppPaths::new(app.path().app_data_dir()?);
+      let store = db::init(&paths).map_err(|e| {
         let err: Box<dyn std::error::Error> =
           Box::new(std::io::Error::new(std::io::ErrorKind::Other, e));
         tauri::Error::Setup(err.into())
       })?;
+      // db olayları tüm pencerelere (sale_created, stock_changed, low_stock_reached, cart_updated)
+      let handle = app.handle().clone();
+      store.set_event_listener(move |e| {
+        if let Err(err) = handle.emit(e.name, &e.payload) {
+          eprintln!("[event] {} yayınlanamadı: {}", e.name, err);
+        }
+      });
+
+      backup::start_backup_scheduler(store.clone(), paths.clone());
+      webhooks::start_webhook_dispatcher(store.clone());
+
+      // yerel API açıksa başlat; port doluysa uygulama yine açılır
+      let api = ApiServer::default();
+      if let Err(e) = store.get_api_settings().and_then(|s| api.apply(&store, &s)) {
+        eprintln!("[api] {}", e);
+      }
+      app.manage(api);
+      app.manage(store);
+      app.manage(paths);
       Ok(())
     })
-    // pencere kapanırken otomatik yedek
+    // ana pencere kapanırken otomatik yedek; müşteri ekranı da kapanır (uygulama açık kalmasın)
     .on_window_event(|window, event| {
+      if window.label() != "main" {
+        return;
+      }
       if let tauri::WindowEvent::CloseRequested { .. } = event {
-        let app = window.app_handle().clone();
+        if let Some(w) = window.get_webview_window(CUSTOMER_DISPLAY) {
+          let _ = w.close();
+        }
+        let store = window.state::<Store>().inner().clone();
+        let paths = window.state::<AppPaths>().inner().clone();
 
         let handle = std::thread::spawn(move || {
-          let _ = backup::backup_sqlite_db(&app);
+          let _ = backup::auto_backup(&store, &paths);
         });
         let _ = handle.join();
       }
@@ -782,10 +1092,21 @@ fn main() {
       update_stock,
       delete_product,
       find_product,
+      search_products,
+      list_product_barcodes,
+      add_product_barcode,
+      delete_product_barcode,
+      create_product_family,
+      get_product_family,
 
       // sales
       create_sale,
       undo_last_sale,
+      price_cart,
+
+      // müşteri ekranı
+      open_customer_display,
+      close_customer_display,
 
       // returns / exchange
       list_sales_by_barcode,
@@ -833,11 +1154,48 @@ fn main() {
       get_basket_pairs,
       get_low_stock,
 
+      // export
+      export_report,
+
+      // barkod / etiket
+      get_settings,
+      update_settings,
+      get_language,
+      set_language,
+      get_barcode_settings,
+      update_barcode_settings,
+      render_labels,
+
       // backup
       backup_now,
+      list_backups,
+      get_backup_policy,
+      update_backup_policy,
+      set_backup_passphrase,
+      list_backup_targets,
+      add_backup_target,
+      update_backup_target,
+      delete_backup_target,
       get_backup_dir,
       open_backup_folder,
       restore_from_backup,
+      inspect_backup,
+      verify_backup,
+      check_integrity,
+
+      // webhook
+      list_webhooks,
+      add_webhook,
+      update_webhook,
+      delete_webhook,
+      list_webhook_deliveries,
+      retry_webhook_deliveries,
+
+      // yerel api
+      get_api_settings,
+      get_api_status,
+      update_api_settings,
+      regenerate_api_token,
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
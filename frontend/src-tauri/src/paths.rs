@@ -0,0 +1,52 @@
+// -------------------- UYGULAMA YOLLARI --------------------
+//
+// DB ve yedekler tek bir veri klasöründe durur:
+//   <veri klasörü>/ciel_pos.sqlite
+//   <veri klasörü>/backups/
+// Masaüstü uygulaması Tauri'nin app_data_dir'ini kullanır; CLI aynı klasörü
+// (ya da --data-dir / CIEL_POS_DATA_DIR ile verileni) açar. db / backup AppHandle görmez;
+// app_lib Tauri'ye bağlı değildir (masaüstü yolu main.rs'te app_data_dir'den kurulur).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::PosError;
+use crate::i18n::tr;
+
+/// tauri.conf.json "identifier" ile aynı olmalı (app_data_dir bu adla oluşur)
+pub const APP_IDENTIFIER: &str = "com.ilaydakx.possystem";
+pub const DB_FILE_NAME: &str = "ciel_pos.sqlite";
+const BACKUP_DIR_NAME: &str = "backups";
+
+#[derive(Clone, Debug)]
+pub struct AppPaths {
+  data_dir: PathBuf,
+}
+
+impl AppPaths {
+  pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+    AppPaths { data_dir: data_dir.into() }
+  }
+
+  /// Tauri'nin app_data_dir'i ile aynı klasör: <platform veri klasörü>/<identifier>
+  pub fn default_data_dir() -> Result<PathBuf, PosError> {
+    dirs::data_dir()
+      .map(|d| d.join(APP_IDENTIFIER))
+      .ok_or_else(|| PosError::Io(tr!("Veri klasörü bulunamadı")))
+  }
+
+  pub fn data_dir(&self) -> &Path {
+    &self.data_dir
+  }
+
+  pub fn db_path(&self) -> PathBuf {
+    self.data_dir.join(DB_FILE_NAME)
+  }
+
+  /// Yedek klasörü (yoksa oluşturulur)
+  pub fn backup_dir(&self) -> Result<PathBuf, PosError> {
+    let dir = self.data_dir.join(BACKUP_DIR_NAME);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+  }
+}
@@ -0,0 +1,100 @@
+// -------------------- WEBHOOK GÖNDERİCİ --------------------
+//
+// event_outbox'a yazılan olayları (db.rs, OLAY KUYRUĞU) kayıtlı adreslere JSON olarak POST eder.
+// Gövde: {"id", "type", "created_at", "data"}; başlıklar X-Pos-Event (olay türü) ve
+// X-Pos-Delivery (teslimat no; alıcı tekrar gelen teslimatı bununla ayıklayabilir).
+// 2xx = teslim edildi; diğer her şey (bağlantı hatası, zaman aşımı, 4xx/5xx) yeniden denenir.
+// Bağlantı hatası / zaman aşımı veren adres o turda atlanır ve bekleyenleri ertelenir; ölü bir
+// adres diğer adreslerin teslimatlarını REQUEST_TIMEOUT × BATCH kadar bekletmez.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::db::Store;
+use crate::error::PosError;
+
+const DISPATCH_INTERVAL: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Tek turda gönderilecek en fazla teslimat (kalanlar sonraki turda)
+const BATCH: i64 = 50;
+/// Teslim edilmiş olaylar bu kadar gün saklanır
+const KEEP_EVENTS_DAYS: i64 = 30;
+
+#[derive(serde::Serialize, Default)]
+pub struct DispatchSummary {
+  pub delivered: i64,
+  /// Yeniden denenecek
+  pub retrying: i64,
+  /// WEBHOOK_MAX_ATTEMPTS doldu
+  pub failed: i64,
+  /// Adresi bu turda ulaşılamadığı için gönderilmedi (sonraki denemeye ertelendi)
+  pub deferred: i64,
+}
+
+/// Uygulama açıkken zamanı gelen teslimatları gönderir; eski olayları günde bir temizler.
+pub fn start_webhook_dispatcher(store: Store) {
+  std::thread::spawn(move || {
+    let mut last_prune: Option<Instant> = None;
+    loop {
+      std::thread::sleep(DISPATCH_INTERVAL);
+
+      if let Err(e) = dispatch_due(&store) {
+        eprintln!("[webhook] gönderim turu başarısız: {}", e);
+      }
+      let prune_due = match last_prune {
+        Some(t) => t.elapsed() > Duration::from_secs(24 * 3600),
+        None => true,
+      };
+      if prune_due {
+        last_prune = Some(Instant::now());
+        if let Err(e) = store.prune_event_outbox(KEEP_EVENTS_DAYS) {
+          eprintln!("[webhook] eski olaylar silinemedi: {}", e);
+        }
+      }
+    }
+  });
+}
+
+/// Zamanı gelmiş teslimatları bir kez gönderir (arka plan thread'i ve testler).
+pub fn dispatch_due(store: &Store) -> Result<DispatchSummary, PosError> {
+  let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+  let mut summary = DispatchSummary::default();
+  let mut unreachable: HashSet<i64> = HashSet::new();
+
+  for d in store.due_webhook_deliveries(BATCH)? {
+    if unreachable.contains(&d.webhook_id) {
+      summary.deferred += 1;
+      continue;
+    }
+    let sent = agent
+      .post(&d.url)
+      .set("Content-Type", "application/json")
+      .set("X-Pos-Event", &d.event_type)
+      .set("X-Pos-Delivery", &d.id.to_string())
+      .send_string(&d.body);
+
+    match sent {
+      Ok(_) => {
+        store.mark_webhook_delivered(d.id)?;
+        summary.delivered += 1;
+      }
+      Err(e) => {
+        // HTTP yanıtı veren adres ayakta (hata bu olaya özgü olabilir); diğerleri gönderilmeye devam eder
+        let (error, down) = match e {
+          ureq::Error::Status(code, _) => (format!("HTTP {}", code), false),
+          ureq::Error::Transport(t) => (t.to_string(), true),
+        };
+        if store.mark_webhook_failed(d.id, &error)? == "FAILED" {
+          summary.failed += 1;
+        } else {
+          summary.retrying += 1;
+        }
+        if down {
+          unreachable.insert(d.webhook_id);
+          store.defer_webhook_deliveries(d.id)?;
+        }
+      }
+    }
+  }
+  Ok(summary)
+}
@@ -0,0 +1,43 @@
+// Entegrasyon testlerinin ortak yardımcıları (store, webhooks, http_api). Her test ikilisi
+// yalnız kullandıklarını çağırır; kalanı o ikili için ölü kod sayılmasın.
+#![allow(dead_code)]
+
+use app_lib::db::{CreateSaleItemPayload, Store};
+
+/// Geçici dizinde göçleri uygulanmış boş DB; dizin düşünce silinir
+pub fn temp_store() -> (tempfile::TempDir, Store) {
+  let dir = tempfile::tempdir().unwrap();
+  let store = Store::open(dir.path().join("test.sqlite")).unwrap();
+  (dir, store)
+}
+
+/// ELBISE kategorisinde, alış fiyatı satışın yarısı; yeni barkodu döner
+pub fn add_product(store: &Store, name: &str, sell_price: f64, magaza: i64, depo: i64) -> String {
+  store
+    .add_product(
+      None,
+      None,
+      Some("ELBISE".into()),
+      name.into(),
+      None,
+      None,
+      Some(sell_price / 2.0),
+      sell_price,
+      Some(magaza),
+      Some(depo),
+    )
+    .unwrap()
+    .barcode
+}
+
+/// İndirimsiz satış satırı
+pub fn item(barcode: &str, qty: i64, price: f64, from: &str) -> CreateSaleItemPayload {
+  CreateSaleItemPayload {
+    barcode: barcode.into(),
+    qty,
+    list_price: price,
+    discount_amount: 0.0,
+    unit_price: price,
+    sold_from: from.into(),
+  }
+}
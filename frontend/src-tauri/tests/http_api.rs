@@ -0,0 +1,196 @@
+// Yerel HTTP/JSON API testleri: her test geçici bir DB üzerinde 127.0.0.1'de boş bir portta
+// sunucu açar ve gerçek HTTP istekleri gönderir (anahtar, yönlendirme, URL çözme, durum kodları).
+//
+//   cargo test --test http_api
+//
+// Mesaj dili süreç genelinde olduğu için hatalar metinle değil `code` alanıyla kontrol edilir.
+
+use app_lib::db::{self, ApiSettings, CreateSalePayload, Store};
+use app_lib::error::PosError;
+use app_lib::http_api::ApiServer;
+
+mod common;
+use common::{add_product, item, temp_store};
+
+/// Çalışan sunucu; düşünce durur
+struct Api {
+  server: ApiServer,
+  base: String,
+  token: String,
+}
+
+impl Drop for Api {
+  fn drop(&mut self) {
+    self.server.stop();
+  }
+}
+
+fn start_api(store: &Store) -> Api {
+  // boş port: işletim sistemine sor, bırak, sunucuya ver
+  let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+  let token = store.get_api_settings().unwrap().token;
+  let settings = ApiSettings { enabled: true, lan: false, port: port as i64, token: token.clone() };
+  let server = ApiServer::default();
+  let status = server.apply(store, &settings).unwrap();
+  assert!(status.running);
+  Api { server, base: format!("http://127.0.0.1:{}", port), token }
+}
+
+/// (durum kodu, JSON gövde); 4xx / 5xx da gövdesiyle döner
+fn send(req: ureq::Request, body: Option<&str>) -> (u16, serde_json::Value) {
+  let res = match body {
+    Some(b) => req.set("Content-Type", "application/json").send_string(b),
+    None => req.call(),
+  };
+  let resp = match res {
+    Ok(r) => r,
+    Err(ureq::Error::Status(_, r)) => r,
+    Err(e) => panic!("istek gönderilemedi: {}", e),
+  };
+  let status = resp.status();
+  (status, serde_json::from_str(&resp.into_string().unwrap()).unwrap())
+}
+
+impl Api {
+  fn get(&self, path: &str) -> (u16, serde_json::Value) {
+    send(ureq::get(&format!("{}{}", self.base, path)).set("Authorization", &format!("Bearer {}", self.token)), None)
+  }
+
+  fn post(&self, path: &str, body: &str) -> (u16, serde_json::Value) {
+    send(ureq::post(&format!("{}{}", self.base, path)).set("X-Api-Token", &self.token), Some(body))
+  }
+}
+
+fn sale_body(barcode: &str, qty: i64) -> String {
+  serde_json::json!({
+    "items": [{
+      "barcode": barcode,
+      "qty": qty,
+      "list_price": 100.0,
+      "discount_amount": 0.0,
+      "unit_price": 100.0,
+      "sold_from": "MAGAZA",
+    }],
+    "payment_method": "CARD",
+  })
+  .to_string()
+}
+
+// -------------------- ANAHTAR --------------------
+
+#[test]
+fn requests_without_valid_token_are_rejected() {
+  let (_dir, store) = temp_store();
+  let api = start_api(&store);
+
+  let (status, body) = send(ureq::get(&format!("{}/api/health", api.base)), None);
+  assert_eq!(status, 401);
+  assert_eq!(body["code"], "UNAUTHORIZED");
+
+  let wrong = ureq::get(&format!("{}/api/health", api.base)).set("Authorization", "Bearer yanlis");
+  assert_eq!(send(wrong, None).0, 401);
+
+  assert_eq!(api.get("/api/health").0, 200);
+}
+
+// -------------------- YÖNLENDİRME --------------------
+
+#[test]
+fn routes_products_stock_and_unknown_paths() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 2, 3);
+  let api = start_api(&store);
+
+  let (status, body) = api.get(&format!("/api/products/{}", a));
+  assert_eq!(status, 200);
+  assert_eq!(body["barcode"], a.as_str());
+
+  let (status, body) = api.get(&format!("/api/stock/{}", a));
+  assert_eq!(status, 200);
+  assert_eq!((body["magaza_stok"].as_i64(), body["depo_stok"].as_i64()), (Some(2), Some(3)));
+
+  // "low" barkod değil, düşük stok listesi
+  let (status, body) = api.get("/api/stock/low?max_stock=10");
+  assert_eq!(status, 200);
+  assert!(body.as_array().is_some_and(|rows| !rows.is_empty()));
+
+  let (status, body) = api.get("/api/bilinmeyen");
+  assert_eq!(status, 404);
+  assert_eq!(body["code"], "NOT_FOUND");
+  assert_eq!(api.get("/api/reports/bilinmeyen").0, 404);
+}
+
+#[test]
+fn path_segments_are_percent_decoded() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 1, 0);
+  let api = start_api(&store);
+
+  let encoded: String = a.bytes().map(|b| format!("%{:02X}", b)).collect();
+  let (status, body) = api.get(&format!("/api/products/{}", encoded));
+  assert_eq!(status, 200);
+  assert_eq!(body["barcode"], a.as_str());
+
+  let (status, body) = api.get("/api/products/%ZZ");
+  assert_eq!(status, 400);
+  assert_eq!(body["code"], "VALIDATION");
+}
+
+// -------------------- HATA KODLARI --------------------
+
+#[test]
+fn errors_map_to_status_codes() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 1, 0);
+  let api = start_api(&store);
+
+  let (status, body) = api.post("/api/sales", &sale_body(&a, 5));
+  assert_eq!(status, 409);
+  assert_eq!(body["code"], "INSUFFICIENT_STOCK");
+  assert_eq!(body["details"]["available"], 1);
+
+  let (status, body) = api.post("/api/sales", "{ bozuk");
+  assert_eq!(status, 400);
+  assert_eq!(body["code"], "VALIDATION");
+
+  assert_eq!(api.get("/api/products/YOK-123").0, 404);
+  assert_eq!(api.get("/api/products?sort=bilinmeyen").0, 400);
+}
+
+// -------------------- SATIŞ KANALI --------------------
+
+#[test]
+fn api_sales_are_not_undone_at_the_till() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 5, 0);
+  let api = start_api(&store);
+
+  // önce kasadan, sonra API'den; geri alma API satışını atlayıp kasa satışını bulur
+  let pos = store
+    .create_sale(CreateSalePayload {
+      items: vec![item(&a, 1, 100.0, "MAGAZA")],
+      sold_from_default: String::new(),
+      payment_method: "CASH".into(),
+      tendered: None,
+    })
+    .unwrap();
+  // müşteri ekranı sale_created'ı kanala göre süzer
+  let channels = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+  let sink = std::sync::Arc::clone(&channels);
+  store.set_event_listener(move |e| {
+    if e.name == db::EVENT_SALE_CREATED {
+      sink.lock().unwrap().push(e.payload["channel"].clone());
+    }
+  });
+  let (status, online) = api.post("/api/sales", &sale_body(&a, 2));
+  assert_eq!(status, 200);
+  assert_eq!(*channels.lock().unwrap(), vec![serde_json::json!("API")]);
+
+  let undo = store.undo_last_sale().unwrap();
+  assert_eq!(undo.sale_group_id, pos.sale_group_id);
+  assert_ne!(online["sale_group_id"], undo.sale_group_id.as_str());
+  assert_eq!(store.find_product_by_barcode(&a).unwrap().unwrap().magaza_stok, 3);
+
+  // yalnız API satışı kaldı: geri alınacak satış yok
+  assert!(matches!(store.undo_last_sale(), Err(PosError::NotFound(_))));
+}
@@ -0,0 +1,949 @@
+// Store entegrasyon testleri: her test kendi geçici DB'sinde (göçler + varsayılan kayıtlar dahil)
+// çalışır; Tauri gerekmez.
+//
+//   cargo test --test store
+//
+// Mesaj dili süreç genelinde olduğu için hatalar metinle değil türüyle kontrol edilir.
+
+use app_lib::db::{
+  self, CreateExchangeGivenItemPayload, CreateExchangePayload, CreateExchangeReturnedPayload,
+  CreateExchangeSummaryPayload, CreateReturnPayload, CreateSaleItemPayload, CreateSalePayload,
+  CreateSaleResult, CreateTransferItemPayload, CreateTransferPayload, ListQuery, Period, Store,
+};
+use app_lib::backup::{self, BackupKind, RestoreFromBackupPayload};
+use app_lib::error::PosError;
+use app_lib::paths::AppPaths;
+
+mod common;
+use common::{add_product, item, temp_store};
+
+/// (mağaza, depo)
+fn stock(store: &Store, barcode: &str) -> (i64, i64) {
+  let p = store.find_product_by_barcode(barcode).unwrap().unwrap();
+  (p.magaza_stok, p.depo_stok)
+}
+
+fn sell(store: &Store, items: Vec<CreateSaleItemPayload>, payment_method: &str) -> Result<CreateSaleResult, PosError> {
+  store.create_sale(CreateSalePayload {
+    items,
+    sold_from_default: "MAGAZA".into(),
+    payment_method: payment_method.into(),
+    tendered: None,
+  })
+}
+
+/// Satışın sold_at değeri (iade / değişimde referans satış için)
+fn last_sold_at(store: &Store, barcode: &str) -> String {
+  store
+    .list_sales_by_barcode(barcode, &ListQuery::default())
+    .unwrap()
+    .items
+    .remove(0)
+    .sold_at
+}
+
+/// Sonuç tipleri Debug değil; unwrap_err yerine
+fn expect_err<T>(res: Result<T, PosError>) -> PosError {
+  match res {
+    Ok(_) => panic!("hata bekleniyordu"),
+    Err(e) => e,
+  }
+}
+
+fn today() -> Period {
+  let d = chrono::Local::now().date_naive();
+  Period { from: d, to: d }
+}
+
+fn returned(barcode: &str, qty: i64, price: f64, sold_at: Option<String>) -> CreateReturnPayload {
+  CreateReturnPayload {
+    barcode: barcode.into(),
+    qty,
+    return_to: "MAGAZA".into(),
+    sold_at,
+    sold_from: Some("MAGAZA".into()),
+    unit_price: price,
+  }
+}
+
+// -------------------- AÇILIŞ --------------------
+
+#[test]
+fn open_applies_migrations_and_defaults() {
+  let (dir, store) = temp_store();
+  assert_eq!(store.ping().unwrap(), "OK (sqlite)");
+  assert_eq!(store.path(), Some(dir.path().join("test.sqlite").as_path()));
+
+  let conn = rusqlite::Connection::open(dir.path().join("test.sqlite")).unwrap();
+  assert_eq!(db::stored_schema_version(&conn).unwrap(), db::schema_version());
+
+  let settings = store.get_settings().unwrap();
+  assert_eq!(settings.default_location, "MAGAZA");
+  assert!(!store.list_categories_full(&ListQuery::default()).unwrap().items.is_empty());
+}
+
+#[test]
+fn reopening_keeps_data() {
+  let (dir, store) = temp_store();
+  let bc = add_product(&store, "Elbise", 100.0, 3, 0);
+  drop(store);
+
+  let store = Store::open(dir.path().join("test.sqlite")).unwrap();
+  assert_eq!(stock(&store, &bc), (3, 0));
+}
+
+#[test]
+fn legacy_stock_moves_into_locations_before_column_drop() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("legacy.sqlite");
+  {
+    // versiyonlamadan önceki kurulum: magaza/depo sonradan DEFAULT 0 ile eklenmiş
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn
+      .execute_batch(
+        "CREATE TABLE products (
+           barcode TEXT PRIMARY KEY, name TEXT NOT NULL, sell_price REAL NOT NULL DEFAULT 0,
+           stock INTEGER DEFAULT 0, magaza_stok INTEGER DEFAULT 0, depo_stok INTEGER DEFAULT 0,
+           created_at TEXT DEFAULT (datetime('now','localtime')),
+           updated_at TEXT DEFAULT (datetime('now','localtime'))
+         );
+         INSERT INTO products(barcode, name, sell_price, stock, magaza_stok, depo_stok) VALUES
+           ('ESKI', 'Eski', 10, 5, 0, 0),
+           ('UYUMLU', 'Uyumlu', 10, 7, 4, 3),
+           ('YARIM', 'Yarım', 10, 6, 0, 2);",
+      )
+      .unwrap();
+  }
+
+  let store = Store::open(&path).unwrap();
+  assert_eq!(stock(&store, "ESKI"), (5, 0));
+  assert_eq!(stock(&store, "UYUMLU"), (4, 3));
+  assert_eq!(stock(&store, "YARIM"), (4, 2));
+
+  // göç öncesi kontrolün sonucu raporda kalır
+  let report = store.check_integrity(false).unwrap();
+  let reconciled = report.issues.iter().find(|i| i.check == "legacy_stock_reconciled").unwrap();
+  assert_eq!(reconciled.count, 2);
+  assert!(reconciled.samples[0].starts_with("ESKI (stock 5"));
+  assert!(!report.issues.iter().any(|i| i.check == "legacy_stock_mismatch"));
+}
+
+/// Güncel DB'yi v9 kurulumuna geri çevirir: ayarlar `meta` değerleriyle _meta'da
+fn downgrade_to_v9(path: &std::path::Path, meta: &str) {
+  let conn = rusqlite::Connection::open(path).unwrap();
+  conn
+    .execute_batch(&format!(
+      "DROP TABLE device_settings;
+       ALTER TABLE settings DROP COLUMN barcode_mode;
+       ALTER TABLE settings DROP COLUMN ean13_prefix;
+       ALTER TABLE settings DROP COLUMN language;
+       INSERT INTO _meta(key, value) VALUES {};
+       UPDATE _meta SET value = '9' WHERE key = 'schema_version';",
+      meta
+    ))
+    .unwrap();
+}
+
+#[test]
+fn meta_settings_move_into_typed_tables() {
+  let (dir, store) = temp_store();
+  drop(store);
+  downgrade_to_v9(
+    &dir.path().join("test.sqlite"),
+    "('barcode_mode', 'EAN13'), ('ean13_prefix', '210'), ('language', 'en'),
+     ('backup.keep_daily', '3'), ('backup.interval_minutes', 'bozuk'),
+     ('backup.passphrase', ' uzun-bir-parola '),
+     ('api.enabled', '1'), ('api.port', '9090'), ('api.token', 'abc123')",
+  );
+
+  let store = Store::open(dir.path().join("test.sqlite")).unwrap();
+  let barcode = store.get_barcode_settings().unwrap();
+  assert_eq!((barcode.mode.as_str(), barcode.ean13_prefix.as_str()), ("EAN13", "210"));
+  assert_eq!(db::get_language().unwrap(), "en");
+  store.set_language("tr".into()).unwrap();
+
+  let policy = store.get_backup_policy().unwrap();
+  // okunamayan değer varsayılana döner
+  assert_eq!((policy.keep_daily, policy.keep_weekly, policy.interval_minutes), (3, 4, 60));
+  assert!(policy.encrypted);
+  let api = store.get_api_settings().unwrap();
+  assert_eq!((api.enabled, api.lan, api.port, api.token.as_str()), (true, false, 9090, "abc123"));
+
+  let conn = rusqlite::Connection::open(dir.path().join("test.sqlite")).unwrap();
+  let left: i64 = conn
+    .query_row("SELECT COUNT(*) FROM _meta WHERE key NOT LIKE 'migration.%' AND key <> 'schema_version'", [], |r| {
+      r.get(0)
+    })
+    .unwrap();
+  assert_eq!(left, 0);
+}
+
+#[test]
+fn negative_stock_is_reported_not_repaired() {
+  let (dir, store) = temp_store();
+  let bc = add_product(&store, "Elbise", 100.0, 1, 0);
+  rusqlite::Connection::open(dir.path().join("test.sqlite"))
+    .unwrap()
+    .execute("UPDATE products SET magaza_stok = -2 WHERE barcode = ?1", [&bc])
+    .unwrap();
+
+  let report = store.check_integrity(true).unwrap();
+  let negative = report.issues.iter().find(|i| i.check == "negative_stock").unwrap();
+  assert_eq!((negative.count, negative.repaired, negative.repairable), (1, 0, false));
+  assert!(!report.ok);
+  assert_eq!(stock(&store, &bc), (-2, 0));
+}
+
+#[test]
+fn in_memory_store_from_connection() {
+  let store = Store::from_connection(rusqlite::Connection::open_in_memory().unwrap()).unwrap();
+  assert_eq!(store.path(), None);
+
+  let bc = add_product(&store, "Elbise", 100.0, 2, 0);
+  sell(&store, vec![item(&bc, 1, 100.0, "MAGAZA")], "CARD").unwrap();
+  assert_eq!(stock(&store, &bc), (1, 0));
+
+  // yazıp ardından okuyan metod tek bağlantıda kilitlenmemeli
+  let policy = store.set_backup_passphrase(Some("uzun-bir-parola".into())).unwrap();
+  assert!(policy.encrypted);
+}
+
+// -------------------- SAYFALAMA --------------------
+
+#[test]
+fn lists_are_paged_in_sql_with_a_default_page_size() {
+  let (_dir, store) = temp_store();
+  for i in 0..(db::DEFAULT_PAGE_SIZE + 5) {
+    store.add_color(format!("RENK{:03}", i)).unwrap();
+  }
+  let q = |page: Option<i64>, page_size: Option<i64>| ListQuery {
+    page,
+    page_size,
+    q: Some("RENK".into()),
+    ..Default::default()
+  };
+
+  // page_size verilmezse varsayılan sayfa; toplam yine tamamı
+  let first = store.list_colors_full(&q(None, None)).unwrap();
+  assert_eq!(first.items.len() as i64, db::DEFAULT_PAGE_SIZE);
+  assert_eq!((first.total, first.page_size), (db::DEFAULT_PAGE_SIZE + 5, db::DEFAULT_PAGE_SIZE));
+
+  let last = store.list_colors_full(&q(Some(3), Some(50))).unwrap();
+  let names: Vec<&str> = last.items.iter().map(|c| c.name.as_str()).collect();
+  assert_eq!(names, vec!["RENK100", "RENK101", "RENK102", "RENK103", "RENK104"]);
+  assert_eq!(store.list_colors_full(&q(None, Some(10_000))).unwrap().page_size, db::MAX_PAGE_SIZE);
+
+  // dışa aktarma gibi iç kullanımlar tamamını alır
+  let all = store.list_colors_full(&ListQuery { q: Some("RENK".into()), ..ListQuery::all() }).unwrap();
+  assert_eq!(all.items.len() as i64, db::DEFAULT_PAGE_SIZE + 5);
+
+  // fiş satırları da SQL'de sayfalanır ve aranır
+  let a = add_product(&store, "Elbise", 100.0, 5, 0);
+  let b = add_product(&store, "Etek", 50.0, 5, 0);
+  let sale = sell(&store, vec![item(&a, 1, 100.0, "MAGAZA"), item(&b, 1, 50.0, "MAGAZA")], "CARD").unwrap();
+  let lines = store
+    .list_sales_by_group(&sale.sale_group_id, &ListQuery { page: Some(2), page_size: Some(1), ..Default::default() })
+    .unwrap();
+  assert_eq!((lines.total, lines.items[0].product_barcode.as_str()), (2, b.as_str()));
+  let etek = store
+    .list_sales_by_group(&sale.sale_group_id, &ListQuery { q: Some("etek".into()), ..Default::default() })
+    .unwrap();
+  assert_eq!(etek.total, 1);
+}
+
+// -------------------- YEDEK / GERİ YÜKLEME --------------------
+
+/// Veri klasöründe (ciel_pos.sqlite + backups/) açılan Store
+fn data_dir_store() -> (tempfile::TempDir, AppPaths, Store) {
+  let dir = tempfile::tempdir().unwrap();
+  let paths = AppPaths::new(dir.path());
+  let store = Store::open(paths.db_path()).unwrap();
+  (dir, paths, store)
+}
+
+/// Uygulama açılışı (db::init) sonrası: tek göç öncesi yedek ve güncel şema
+fn assert_migrated_with_backup(paths: &AppPaths) -> backup::BackupInfo {
+  let conn = rusqlite::Connection::open(paths.db_path()).unwrap();
+  assert_eq!(db::stored_schema_version(&conn).unwrap(), db::schema_version());
+
+  let mut backups = backup::list_backups(paths, &ListQuery::default()).unwrap().items;
+  assert_eq!(backups.len(), 1);
+  let saved = backups.remove(0);
+  assert_eq!(saved.kind, BackupKind::BeforeMigration);
+  assert!(std::path::Path::new(&saved.path).is_file());
+  saved
+}
+
+#[test]
+fn init_backs_up_an_unversioned_database_before_migrating() {
+  let dir = tempfile::tempdir().unwrap();
+  let paths = AppPaths::new(dir.path());
+  {
+    let conn = rusqlite::Connection::open(paths.db_path()).unwrap();
+    conn
+      .execute_batch(
+        "CREATE TABLE products (
+           barcode TEXT PRIMARY KEY, name TEXT NOT NULL, sell_price REAL NOT NULL DEFAULT 0,
+           stock INTEGER DEFAULT 0, magaza_stok INTEGER DEFAULT 0, depo_stok INTEGER DEFAULT 0,
+           created_at TEXT DEFAULT (datetime('now','localtime')),
+           updated_at TEXT DEFAULT (datetime('now','localtime'))
+         );
+         INSERT INTO products(barcode, name, sell_price, stock, magaza_stok, depo_stok)
+         VALUES ('ESKI', 'Eski', 10, 5, 5, 0);",
+      )
+      .unwrap();
+  }
+
+  let store = db::init(&paths).unwrap();
+  let saved = assert_migrated_with_backup(&paths);
+  assert!(!saved.encrypted);
+  assert_eq!(stock(&store, "ESKI"), (5, 0));
+}
+
+#[test]
+fn init_backs_up_a_v9_database_with_its_meta_passphrase() {
+  let dir = tempfile::tempdir().unwrap();
+  let paths = AppPaths::new(dir.path());
+  let bc = {
+    let store = Store::open(paths.db_path()).unwrap();
+    add_product(&store, "Elbise", 100.0, 2, 1)
+  };
+  downgrade_to_v9(&paths.db_path(), "('backup.passphrase', 'uzun-bir-parola')");
+
+  // göç öncesi yedek, v10'dan önce _meta'daki parolayla şifrelenir
+  let store = db::init(&paths).unwrap();
+  let saved = assert_migrated_with_backup(&paths);
+  assert!(saved.encrypted);
+  assert_eq!(stock(&store, &bc), (2, 1));
+  assert!(store.get_backup_policy().unwrap().encrypted);
+}
+
+fn restore(store: &Store, paths: &AppPaths, backup_path: &str) -> Result<backup::RestoreFromBackupResult, PosError> {
+  backup::restore_from_backup(store, paths, RestoreFromBackupPayload { backup_path: backup_path.into(), passphrase: None })
+}
+
+#[test]
+fn restore_replaces_live_db() {
+  let (_dir, paths, store) = data_dir_store();
+  let a = add_product(&store, "Elbise", 100.0, 3, 0);
+  let saved = backup::backup_sqlite_db(&store, &paths, BackupKind::Manual).unwrap();
+  let b = add_product(&store, "Etek", 50.0, 1, 0);
+
+  let res = restore(&store, &paths, &saved).unwrap();
+  assert!(!res.safety_backup_path.is_empty());
+  assert_eq!(stock(&store, &a), (3, 0));
+  assert!(store.find_product_by_barcode(&b).unwrap().is_none());
+}
+
+#[test]
+fn restore_keeps_device_settings() {
+  let (_dir, paths, store) = data_dir_store();
+  let saved = backup::backup_sqlite_db(&store, &paths, BackupKind::Manual).unwrap();
+
+  store.set_backup_passphrase(Some("uzun-bir-parola".into())).unwrap();
+  let target = tempfile::tempdir().unwrap();
+  store.add_backup_target(target.path().to_string_lossy().to_string(), None).unwrap();
+  let api = store.get_api_settings().unwrap();
+
+  // parolasız eski yedek: sonraki otomatik yedekler yine şifreli olmalı
+  restore(&store, &paths, &saved).unwrap();
+  assert!(store.get_backup_policy().unwrap().encrypted);
+  assert_eq!(store.list_backup_targets(&ListQuery::default()).unwrap().total, 1);
+  assert_eq!(store.get_api_settings().unwrap().token, api.token);
+}
+
+#[test]
+fn backups_are_listed_cheaply_and_verified_on_request() {
+  let (_dir, paths, store) = data_dir_store();
+  add_product(&store, "Elbise", 100.0, 1, 0);
+  let saved = backup::backup_sqlite_db(&store, &paths, BackupKind::Manual).unwrap();
+  let broken = paths.backup_dir().unwrap().join("ciel_pos_MANUAL_1.sqlite");
+  std::fs::write(&broken, b"bozuk").unwrap();
+
+  // listeleme dosyaları açmaz; bozuk yedek de listelenir
+  assert_eq!(backup::list_backups(&paths, &ListQuery::default()).unwrap().total, 2);
+  let second = ListQuery { page: Some(2), page_size: Some(1), ..Default::default() };
+  let page = backup::list_backups(&paths, &second).unwrap();
+  assert_eq!((page.total, page.items.len()), (2, 1));
+  let only = backup::list_backups(&paths, &ListQuery { q: Some("_1.".into()), ..Default::default() }).unwrap();
+  assert_eq!(only.items[0].file_name, "ciel_pos_MANUAL_1.sqlite");
+  assert_eq!(only.total, 1);
+
+  let verify = |path: &str| {
+    backup::verify_backup(&store, &paths, backup::InspectBackupPayload { backup_path: path.into(), passphrase: None })
+      .unwrap()
+  };
+  let ok = verify(&saved);
+  assert!(ok.valid && ok.error.is_none());
+  let bad = verify("ciel_pos_MANUAL_1.sqlite");
+  assert!(!bad.valid && bad.error.is_some());
+}
+
+#[test]
+fn archive_with_forged_size_is_rejected_before_allocating() {
+  let archive = app_lib::archive::write_archive(b"SQLite format 3", 0, None).unwrap();
+  assert_eq!(app_lib::archive::open_archive(&archive, None).unwrap(), b"SQLite format 3");
+
+  // başlık: 1 TB sıkıştırılmamış boyut, yük birkaç bayt
+  let header = br#"{"created_at":0,"size":1000000000000,"sha256":""}"#;
+  let mut forged = b"CPBAK\0\x01\x00".to_vec();
+  forged.extend_from_slice(&(header.len() as u32).to_le_bytes());
+  forged.extend_from_slice(header);
+  forged.extend_from_slice(&archive[archive.len() - 20..]);
+  assert!(matches!(app_lib::archive::open_archive(&forged, None), Err(PosError::Validation(_))));
+}
+
+#[test]
+fn restore_that_cannot_be_migrated_leaves_live_db_untouched() {
+  let (_dir, paths, store) = data_dir_store();
+  let a = add_product(&store, "Elbise", 100.0, 3, 0);
+
+  // eski şemalı yedek: dolu tabloya created_at (datetime varsayılanlı) eklenemez, v1 göçü başarısız
+  let legacy = paths.backup_dir().unwrap().join("ciel_pos_manual_1.sqlite");
+  rusqlite::Connection::open(&legacy)
+    .unwrap()
+    .execute_batch(
+      "CREATE TABLE products (barcode TEXT PRIMARY KEY, name TEXT NOT NULL, stock INTEGER);
+       INSERT INTO products VALUES ('ESKI', 'Eski', 1);",
+    )
+    .unwrap();
+
+  assert!(matches!(expect_err(restore(&store, &paths, legacy.to_str().unwrap())), PosError::Db(_)));
+  assert_eq!(stock(&store, &a), (3, 0));
+  let conn = rusqlite::Connection::open(paths.db_path()).unwrap();
+  assert_eq!(db::stored_schema_version(&conn).unwrap(), db::schema_version());
+}
+
+// -------------------- BARKOD --------------------
+
+#[test]
+fn generated_barcodes_skip_alias_codes() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 1, 0);
+  let next: i64 = a.parse::<i64>().unwrap() + 1;
+  store.add_product_barcode(&a, &next.to_string(), None).unwrap();
+  store.add_product_barcode(&a, &(next + 1).to_string(), None).unwrap();
+  assert_eq!(add_product(&store, "Etek", 50.0, 1, 0), (next + 2).to_string());
+
+  store
+    .update_barcode_settings(db::BarcodeSettings { mode: "EAN13".into(), ean13_prefix: "200".into() })
+    .unwrap();
+  let first = add_product(&store, "Gömlek", 80.0, 1, 0);
+  assert_eq!(first, app_lib::barcode::ean13_with_check_digit("200000000001").unwrap());
+  let taken = app_lib::barcode::ean13_with_check_digit("200000000002").unwrap();
+  store.add_product_barcode(&a, &taken, None).unwrap();
+  assert_eq!(
+    add_product(&store, "Ceket", 90.0, 1, 0),
+    app_lib::barcode::ean13_with_check_digit("200000000003").unwrap()
+  );
+}
+
+// -------------------- ARAMA --------------------
+
+fn search(store: &Store, q: &str) -> (Vec<String>, bool) {
+  let res = store
+    .search_products(q, db::ProductSearchFilters::default(), db::PageRequest::default())
+    .unwrap();
+  (res.items.into_iter().map(|p| p.barcode).collect(), res.fuzzy)
+}
+
+#[test]
+fn search_tolerates_typos_and_finds_alias_barcodes() {
+  let (_dir, store) = temp_store();
+  let shirt = add_product(&store, "Gömlek Siyah", 100.0, 1, 0);
+  let skirt = add_product(&store, "Etek", 50.0, 1, 0);
+
+  assert_eq!(search(&store, "gomlek"), (vec![shirt.clone()], false));
+  // yazım hatası: yakın terim ("gomlek") ve önek ("siy") birlikte
+  assert_eq!(search(&store, "gomlk siy"), (vec![shirt.clone()], true));
+  assert_eq!(search(&store, "xqzwv"), (Vec::new(), true));
+
+  store.add_product_barcode(&skirt, "8690001112223", None).unwrap();
+  assert_eq!(search(&store, "869000111").0, vec![skirt.clone()]);
+  store.delete_product_barcode("8690001112223").unwrap();
+  assert!(search(&store, "869000111").0.is_empty());
+}
+
+// -------------------- İÇE AKTARMA / STOK DÜZELTME --------------------
+
+#[test]
+fn import_update_keeps_blank_columns_and_sibling_prices() {
+  let (dir, store) = temp_store();
+  let family = |color: &str, price: f64| {
+    store
+      .add_product(
+        None,
+        Some("AB100".into()),
+        Some("ELBISE".into()),
+        "Elbise".into(),
+        Some(color.into()),
+        Some("M".into()),
+        Some(40.0),
+        price,
+        Some(1),
+        Some(0),
+      )
+      .unwrap()
+      .barcode
+  };
+  let a = family("SIYAH", 100.0);
+  let b = family("BEYAZ", 150.0);
+
+  // yalnız zorunlu sütunlar; fiyat aynı, ad değişiyor
+  let csv = dir.path().join("urunler.csv");
+  std::fs::write(&csv, format!("Barkod;Ürün;Satış Fiyatı\n{};Uzun Elbise;100\n", a)).unwrap();
+  let report = app_lib::import::import_products_csv(&store, &csv).unwrap();
+  assert_eq!((report.created, report.updated, report.errors.len()), (0, 1, 0));
+
+  let p = store.find_product_by_barcode(&a).unwrap().unwrap();
+  assert_eq!(p.name, "Uzun Elbise");
+  assert_eq!(p.category.as_deref(), Some("ELBISE"));
+  assert_eq!((p.color.as_deref(), p.size.as_deref()), (Some("SIYAH"), Some("M")));
+  assert_eq!(p.buy_price, 40.0);
+  assert_eq!(stock(&store, &a), (1, 0));
+  // fiyat değişmediği için aile kardeşinin farklı fiyatı ezilmez
+  assert_eq!(store.find_product_by_barcode(&b).unwrap().unwrap().sell_price, 150.0);
+}
+
+#[test]
+fn adjust_stock_is_relative_and_never_goes_negative() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 2, 3);
+
+  assert_eq!(store.adjust_stock(&a, -1, 4).unwrap(), (1, 7));
+  assert!(matches!(
+    store.adjust_stock(&a, 5, -8),
+    Err(PosError::InsufficientStock { available: 7, requested: 8, .. })
+  ));
+  // başarısız düzeltme hiçbir konumu değiştirmez
+  assert_eq!(stock(&store, &a), (1, 7));
+  assert!(matches!(store.adjust_stock("YOK-123", 1, 0), Err(PosError::NotFound(_))));
+}
+
+// -------------------- ÜRÜN AİLESİ --------------------
+
+#[test]
+fn family_matrix_takes_per_variant_stock_and_skips_existing() {
+  let (_dir, store) = temp_store();
+  for c in ["SIYAH", "BEYAZ"] {
+    store.add_color(c.into()).unwrap();
+  }
+  store.add_size("S".into(), Some(1)).unwrap();
+  store.add_size("M".into(), Some(2)).unwrap();
+
+  let payload = |colors: &[&str], stocks: Vec<db::FamilyVariantStock>| db::CreateProductFamilyPayload {
+    product_code: Some("AB-200".into()),
+    category: None,
+    name: "Elbise".into(),
+    buy_price: Some(40.0),
+    sell_price: 100.0,
+    colors: colors.iter().map(|c| c.to_string()).collect(),
+    sizes: vec!["S".into(), "M".into()],
+    magaza_baslangic: Some(1),
+    depo_baslangic: None,
+    stocks,
+  };
+  let res = store
+    .create_product_family(payload(
+      &["SIYAH"],
+      vec![db::FamilyVariantStock { color: "SIYAH".into(), size: "M".into(), magaza: 2, depo: 3 }],
+    ))
+    .unwrap();
+  assert_eq!((res.product_code.as_str(), res.created.len()), ("AB200", 2));
+
+  // aynı renk + beden tekrar oluşturulmaz
+  let res = store.create_product_family(payload(&["SIYAH", "BEYAZ"], Vec::new())).unwrap();
+  assert_eq!((res.created.len(), res.skipped.len()), (2, 2));
+
+  let grid = store.get_product_family("AB200").unwrap();
+  assert_eq!(grid.sizes, vec!["S", "M"]);
+  let siyah = grid.rows.iter().find(|r| r.color == "SIYAH").unwrap();
+  let cells: Vec<(i64, i64)> = siyah
+    .cells
+    .iter()
+    .map(|c| c.as_ref().map_or((0, 0), |c| (c.magaza_stok, c.depo_stok)))
+    .collect();
+  assert_eq!(cells, vec![(1, 0), (2, 3)]);
+  assert_eq!(grid.total_stock, 1 + 5 + 1 + 1);
+
+  let negative = vec![db::FamilyVariantStock { color: "BEYAZ".into(), size: "S".into(), magaza: -1, depo: 0 }];
+  assert!(matches!(store.create_product_family(payload(&["BEYAZ"], negative)), Err(PosError::Validation(_))));
+}
+
+// -------------------- SATIŞ --------------------
+
+#[test]
+fn sale_decrements_stock_per_location_and_undo_restores() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 3, 2);
+  let b = add_product(&store, "Etek", 50.0, 1, 4);
+
+  let res = sell(&store, vec![item(&a, 2, 100.0, "MAGAZA"), item(&b, 3, 50.0, "DEPO")], "CASH").unwrap();
+  assert_eq!(res.lines, 2);
+  assert_eq!(res.total, 350.0);
+  assert_eq!(stock(&store, &a), (1, 2));
+  assert_eq!(stock(&store, &b), (1, 1));
+
+  let undo = store.undo_last_sale().unwrap();
+  assert_eq!(undo.sale_group_id, res.sale_group_id);
+  assert_eq!(undo.restored_lines, 2);
+  assert_eq!(stock(&store, &a), (3, 2));
+  assert_eq!(stock(&store, &b), (1, 4));
+}
+
+#[test]
+fn sale_with_insufficient_stock_changes_nothing() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 5, 0);
+  let b = add_product(&store, "Etek", 50.0, 1, 0);
+
+  let err = expect_err(sell(&store, vec![item(&a, 1, 100.0, "MAGAZA"), item(&b, 2, 50.0, "MAGAZA")], "CARD"));
+  match err {
+    PosError::InsufficientStock { barcode, location, available, requested } => {
+      assert_eq!(barcode, b);
+      assert_eq!(location, "MAGAZA");
+      assert_eq!((available, requested), (1, 2));
+    }
+    other => panic!("beklenmeyen hata: {:?}", other.to_string()),
+  }
+  // ilk satır da yazılmamalı (tek transaction)
+  assert_eq!(stock(&store, &a), (5, 0));
+  assert!(store.list_sales_by_barcode(&a, &ListQuery::default()).unwrap().items.is_empty());
+}
+
+#[test]
+fn sale_rejects_empty_cart() {
+  let (_dir, store) = temp_store();
+  assert!(matches!(sell(&store, vec![], "CARD"), Err(PosError::Validation(_))));
+}
+
+// -------------------- İADE / DEĞİŞİM --------------------
+
+#[test]
+fn return_restocks_and_is_limited_by_sold_qty() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 3, 0);
+  sell(&store, vec![item(&a, 2, 100.0, "MAGAZA")], "CARD").unwrap();
+  let sold_at = last_sold_at(&store, &a);
+
+  let res = store.create_return(returned(&a, 1, 100.0, Some(sold_at.clone()))).unwrap();
+  assert_eq!(res.returned_total, 100.0);
+  assert_eq!(stock(&store, &a), (2, 0));
+
+  let line = store.list_sales_by_barcode(&a, &ListQuery::default()).unwrap().items.remove(0);
+  assert_eq!(line.refunded_qty, 1);
+
+  // satılan 2, iade edilen 1: en fazla 1 daha
+  let err = expect_err(store.create_return(returned(&a, 2, 100.0, Some(sold_at.clone()))));
+  assert!(matches!(err, PosError::Validation(_)));
+
+  store.create_return(returned(&a, 1, 100.0, Some(sold_at.clone()))).unwrap();
+  let err = expect_err(store.create_return(returned(&a, 1, 100.0, Some(sold_at))));
+  assert!(matches!(err, PosError::Conflict(_)));
+  assert_eq!(stock(&store, &a), (3, 0));
+}
+
+#[test]
+fn exchange_moves_both_products_and_computes_diff() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 2, 0);
+  let b = add_product(&store, "Ceket", 160.0, 0, 1);
+  sell(&store, vec![item(&a, 1, 100.0, "MAGAZA")], "CARD").unwrap();
+
+  let res = store
+    .create_exchange(CreateExchangePayload {
+      returned: CreateExchangeReturnedPayload {
+        barcode: a.clone(),
+        qty: 1,
+        return_to: "MAGAZA".into(),
+        sold_at: Some(last_sold_at(&store, &a)),
+        sold_from: Some("MAGAZA".into()),
+        unit_price: 100.0,
+      },
+      given: vec![CreateExchangeGivenItemPayload {
+        barcode: b.clone(),
+        qty: 1,
+        sold_from: "DEPO".into(),
+        unit_price: 160.0,
+      }],
+      summary: CreateExchangeSummaryPayload { diff_payment_method: Some("NAKİT".into()) },
+    })
+    .unwrap();
+
+  assert_eq!(res.returned_total, 100.0);
+  assert_eq!(res.given_total, 160.0);
+  assert_eq!(res.diff, 60.0);
+  assert_eq!(stock(&store, &a), (2, 0));
+  assert_eq!(stock(&store, &b), (0, 0));
+}
+
+#[test]
+fn exchange_with_insufficient_given_stock_changes_nothing() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 1, 0);
+  let b = add_product(&store, "Ceket", 160.0, 0, 0);
+
+  let err = expect_err(store.create_exchange(CreateExchangePayload {
+    returned: CreateExchangeReturnedPayload {
+      barcode: a.clone(),
+      qty: 1,
+      return_to: "MAGAZA".into(),
+      sold_at: None,
+      sold_from: None,
+      unit_price: 100.0,
+    },
+    given: vec![CreateExchangeGivenItemPayload {
+      barcode: b,
+      qty: 1,
+      sold_from: "MAGAZA".into(),
+      unit_price: 160.0,
+    }],
+    summary: CreateExchangeSummaryPayload { diff_payment_method: None },
+  }));
+
+  assert!(matches!(err, PosError::InsufficientStock { .. }));
+  assert_eq!(stock(&store, &a), (1, 0));
+}
+
+// -------------------- TRANSFER --------------------
+
+fn transfer(store: &Store, barcode: &str, qty: i64, from: &str, to: &str) -> Result<db::CreateTransferResult, PosError> {
+  store.create_transfer(CreateTransferPayload {
+    items: vec![CreateTransferItemPayload {
+      barcode: barcode.into(),
+      qty,
+      from_loc: from.into(),
+      to_loc: to.into(),
+    }],
+    note: None,
+  })
+}
+
+#[test]
+fn transfer_moves_stock_and_undo_restores() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 1, 5);
+
+  let res = transfer(&store, &a, 3, "DEPO", "MAGAZA").unwrap();
+  assert_eq!(res.lines, 1);
+  assert_eq!(stock(&store, &a), (4, 2));
+
+  let undo = store.undo_last_transfer().unwrap();
+  assert_eq!(undo.transfer_group_id, res.transfer_group_id);
+  assert_eq!(stock(&store, &a), (1, 5));
+}
+
+#[test]
+fn transfer_validates_locations_and_stock() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 1, 0);
+
+  assert!(matches!(transfer(&store, &a, 1, "MAGAZA", "MAGAZA"), Err(PosError::Validation(_))));
+  assert!(matches!(
+    transfer(&store, &a, 2, "MAGAZA", "DEPO"),
+    Err(PosError::InsufficientStock { available: 1, requested: 2, .. })
+  ));
+  assert!(matches!(transfer(&store, "YOK-123", 1, "MAGAZA", "DEPO"), Err(PosError::NotFound(_))));
+  assert_eq!(stock(&store, &a), (1, 0));
+}
+
+// -------------------- RAPORLAR --------------------
+
+#[test]
+fn reports_reflect_sales_and_returns() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 10, 0);
+  let b = add_product(&store, "Etek", 50.0, 1, 0);
+
+  sell(&store, vec![item(&a, 3, 100.0, "MAGAZA")], "CASH").unwrap();
+  sell(&store, vec![item(&a, 1, 100.0, "MAGAZA"), item(&b, 1, 50.0, "MAGAZA")], "CARD").unwrap();
+
+  let period = today();
+
+  let velocity = store.get_velocity_report(&period).unwrap();
+  let row = velocity.iter().find(|r| r.barcode == a).unwrap();
+  assert_eq!(row.total_sold, 4);
+  assert_eq!(row.current_stock, 6);
+
+  let cash = store.get_cash_report(&period).unwrap();
+  let day = cash.iter().find(|r| r.day == period.first_day()).unwrap();
+  assert_eq!(day.cash_sales, 300.0);
+  assert_eq!(day.card_sales, 150.0);
+  assert_eq!(day.net_total, 450.0);
+
+  let low = store.get_low_stock(0).unwrap();
+  assert!(low.iter().any(|r| r.barcode == b && r.total_stock == 0));
+  assert!(!low.iter().any(|r| r.barcode == a));
+
+  let margin = store.get_category_margin(&period).unwrap();
+  let elbise = margin.iter().find(|r| r.category == "ELBISE").unwrap();
+  assert_eq!(elbise.revenue, 450.0);
+
+  let groups = store.list_sale_groups(&ListQuery::default()).unwrap();
+  assert_eq!(groups.total, 2);
+}
+
+#[test]
+fn run_report_compares_with_previous_period() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 5, 0);
+  sell(&store, vec![item(&a, 2, 100.0, "MAGAZA")], "CARD").unwrap();
+
+  let range = db::ReportRange { compare: true, ..Default::default() };
+  let report = db::run_report(&range, |p| store.get_velocity_report(p)).unwrap();
+
+  let comparison = report.comparison.unwrap();
+  assert!(comparison.to < report.from);
+  assert!(comparison.data.iter().all(|r| r.total_sold == 0));
+}
+
+// -------------------- CANLI OLAYLAR --------------------
+
+/// Dinleyiciye gelen (olay adı, payload) listesi
+fn record_events(store: &Store) -> std::sync::Arc<std::sync::Mutex<Vec<(String, serde_json::Value)>>> {
+  let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+  let sink = events.clone();
+  store.set_event_listener(move |e| sink.lock().unwrap().push((e.name.to_string(), e.payload.clone())));
+  events
+}
+
+#[test]
+fn sale_emits_events_and_low_stock_once() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 4, 0);
+  let events = record_events(&store);
+
+  // 4 -> 2: varsayılan eşik (2) geçildi
+  let res = sell(&store, vec![item(&a, 2, 100.0, "MAGAZA")], "CASH").unwrap();
+  {
+    let got = events.lock().unwrap();
+    let names: Vec<&str> = got.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, vec![db::EVENT_SALE_CREATED, db::EVENT_STOCK_CHANGED, db::EVENT_LOW_STOCK_REACHED]);
+    assert_eq!(got[0].1["sale_group_id"], res.sale_group_id.as_str());
+    assert_eq!(got[0].1["channel"], "POS");
+    assert_eq!(got[1].1["location"], "MAGAZA");
+    assert_eq!(got[1].1["new_qty"], 2);
+    assert_eq!(got[2].1["total_stock"], 2);
+    assert_eq!(got[2].1["threshold"], 2);
+  }
+
+  // zaten eşiğin altında: yalnız stock_changed
+  events.lock().unwrap().clear();
+  sell(&store, vec![item(&a, 1, 100.0, "MAGAZA")], "CASH").unwrap();
+  let names: Vec<String> = events.lock().unwrap().iter().map(|(n, _)| n.clone()).collect();
+  assert_eq!(names, vec![db::EVENT_SALE_CREATED, db::EVENT_STOCK_CHANGED]);
+
+  // geri alma stoğu artırır
+  events.lock().unwrap().clear();
+  store.undo_last_sale().unwrap();
+  let got = events.lock().unwrap();
+  assert_eq!(got.len(), 1);
+  assert_eq!((got[0].1["delta"].as_i64(), got[0].1["new_qty"].as_i64()), (Some(1), Some(2)));
+}
+
+#[test]
+fn failed_writes_emit_nothing_and_stock_edits_emit_per_location() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 1, 5);
+  let events = record_events(&store);
+
+  expect_err(sell(&store, vec![item(&a, 3, 100.0, "MAGAZA")], "CASH"));
+  assert!(events.lock().unwrap().is_empty());
+
+  store
+    .update_stock(db::UpdateStockPayload { barcode: a.clone(), magaza_stok: 1, depo_stok: 8 })
+    .unwrap();
+  let got = events.lock().unwrap();
+  assert_eq!(got.len(), 1);
+  assert_eq!(got[0].0, db::EVENT_STOCK_CHANGED);
+  assert_eq!(got[0].1["location"], "DEPO");
+  assert_eq!(got[0].1["new_qty"], 8);
+}
+
+#[test]
+fn variant_merge_and_soft_delete_emit_stock_changes() {
+  let (_dir, store) = temp_store();
+  let variant = |magaza: i64, depo: i64| {
+    store
+      .add_product(None, Some("ELB900".into()), None, "Elbise".into(), None, None, None, 100.0, Some(magaza), Some(depo))
+      .unwrap()
+      .barcode
+  };
+  let a = variant(3, 1);
+  sell(&store, vec![item(&a, 1, 100.0, "MAGAZA")], "CASH").unwrap();
+  let events = record_events(&store);
+
+  // aynı aile + renk + beden: stok artar
+  assert_eq!(variant(0, 4), a);
+  {
+    let got = events.lock().unwrap();
+    assert_eq!(got.len(), 1);
+    assert_eq!(got[0].0, db::EVENT_STOCK_CHANGED);
+    assert_eq!((got[0].1["location"].as_str(), got[0].1["delta"].as_i64()), (Some("DEPO"), Some(4)));
+    assert_eq!(got[0].1["new_qty"], 5);
+  }
+  events.lock().unwrap().clear();
+
+  // satışı olan ürün pasife alınır, stoğu sıfırlanır; pasif ürün düşük stok uyarısı vermez
+  assert_eq!(store.delete_product(&a).unwrap(), 1);
+  let got = events.lock().unwrap();
+  let changes: Vec<(&str, &str, i64, i64)> = got
+    .iter()
+    .map(|(name, p)| {
+      (name.as_str(), p["location"].as_str().unwrap(), p["delta"].as_i64().unwrap(), p["new_qty"].as_i64().unwrap())
+    })
+    .collect();
+  assert_eq!(
+    changes,
+    vec![(db::EVENT_STOCK_CHANGED, "MAGAZA", -2, 0), (db::EVENT_STOCK_CHANGED, "DEPO", -5, 0)]
+  );
+}
+
+#[test]
+fn price_cart_emits_cart_without_touching_stock() {
+  let (_dir, store) = temp_store();
+  let a = add_product(&store, "Elbise", 100.0, 1, 0);
+  let b = add_product(&store, "Etek", 50.0, 0, 0);
+  let events = record_events(&store);
+
+  let mut discounted = item(&b, 0, 50.0, "");
+  discounted.discount_amount = 10.0;
+  discounted.unit_price = 40.0;
+  let cart = store
+    .price_cart(db::PriceCartPayload {
+      // stok yetmese de fiyatlanır; kontrol satışta
+      items: vec![item(&format!(" {} ", a), 3, 100.0, "MAGAZA"), discounted],
+      payment_method: "nakit".into(),
+      tendered: Some(400.0),
+    })
+    .unwrap();
+
+  assert_eq!(cart.payment_method, "CASH");
+  assert_eq!((cart.item_count, cart.lines[1].qty), (4, 1));
+  assert_eq!(cart.lines[0].barcode, a);
+  assert_eq!(cart.lines[1].name, "Etek");
+  assert_eq!((cart.subtotal, cart.discount_total, cart.total), (350.0, 10.0, 340.0));
+  assert_eq!(cart.change_due, Some(60.0));
+  assert_eq!(stock(&store, &a), (1, 0));
+
+  let got = events.lock().unwrap();
+  assert_eq!(got.len(), 1);
+  assert_eq!(got[0].0, db::EVENT_CART_UPDATED);
+  assert_eq!(got[0].1["total"], 340.0);
+  drop(got);
+
+  // kartta para üstü yok; bilinmeyen barkod hata verir, müşteri ekranına boş sepet gider
+  let card = store
+    .price_cart(db::PriceCartPayload { items: vec![], payment_method: "CARD".into(), tendered: Some(10.0) })
+    .unwrap();
+  assert_eq!((card.total, card.change_due), (0.0, None));
+  events.lock().unwrap().clear();
+  assert!(matches!(
+    expect_err(store.price_cart(db::PriceCartPayload { items: vec![item("YOK", 1, 1.0, "")], ..Default::default() })),
+    PosError::NotFound(_)
+  ));
+  let got = events.lock().unwrap();
+  assert_eq!(got.len(), 1);
+  assert_eq!(got[0].0, db::EVENT_CART_UPDATED);
+  assert_eq!(got[0].1["lines"], serde_json::json!([]));
+}
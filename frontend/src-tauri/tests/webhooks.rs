@@ -0,0 +1,293 @@
+// Olay kuyruğu (outbox) + webhook gönderici testleri. Alıcı olarak yerel bir tiny_http
+// sunucusu kullanılır; verilen durum kodlarını sırayla döner ve gelen istekleri kaydeder.
+//
+//   cargo test --test webhooks
+
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use app_lib::db::{
+  CreateReturnPayload, CreateSalePayload, CreateTransferItemPayload, CreateTransferPayload, ListQuery,
+  UpdateStockPayload,
+};
+use app_lib::webhooks;
+
+mod common;
+use common::{add_product, item, temp_store};
+
+fn sale(barcode: &str, qty: i64) -> CreateSalePayload {
+  CreateSalePayload {
+    items: vec![item(barcode, qty, 100.0, "MAGAZA")],
+    sold_from_default: String::new(),
+    payment_method: "CASH".into(),
+    tendered: Some(250.0),
+  }
+}
+
+struct Received {
+  event: String,
+  delivery: String,
+  body: serde_json::Value,
+}
+
+/// Yerel alıcı: `statuses` sırayla döner, bitince kapanır
+struct Receiver {
+  url: String,
+  rx: mpsc::Receiver<Received>,
+  handle: JoinHandle<()>,
+}
+
+fn receiver(statuses: Vec<u16>) -> Receiver {
+  let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+  let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+  let (tx, rx) = mpsc::channel();
+  let handle = std::thread::spawn(move || {
+    for status in statuses {
+      let mut req = server.recv().unwrap();
+      let header = |name: &'static str| {
+        req
+          .headers()
+          .iter()
+          .find(|h| h.field.equiv(name))
+          .map(|h| h.value.to_string())
+          .unwrap_or_default()
+      };
+      let (event, delivery) = (header("X-Pos-Event"), header("X-Pos-Delivery"));
+      let mut body = String::new();
+      req.as_reader().read_to_string(&mut body).unwrap();
+      tx.send(Received { event, delivery, body: serde_json::from_str(&body).unwrap() }).unwrap();
+      req.respond(tiny_http::Response::empty(status)).unwrap();
+    }
+  });
+  Receiver { url, rx, handle }
+}
+
+#[test]
+fn sale_event_is_posted_with_stock_changes() {
+  let (_dir, store) = temp_store();
+  let bc = add_product(&store, "Elbise", 100.0, 5, 0);
+  let hook = receiver(vec![200]);
+  store.add_webhook(hook.url.clone(), Some("e-ticaret".into())).unwrap();
+
+  let res = store.create_sale(sale(&bc, 2)).unwrap();
+  let summary = webhooks::dispatch_due(&store).unwrap();
+  assert_eq!((summary.delivered, summary.retrying, summary.failed), (1, 0, 0));
+
+  let got = hook.rx.recv().unwrap();
+  hook.handle.join().unwrap();
+  assert_eq!(got.event, "sale.created");
+  assert_eq!(got.body["type"], "sale.created");
+  let data = &got.body["data"];
+  assert_eq!(data["sale_group_id"], res.sale_group_id.as_str());
+  assert_eq!(data["payment_method"], "CASH");
+  assert_eq!(data["change_due"], 50.0);
+  assert_eq!(data["lines"][0]["qty"], 2);
+  assert_eq!(data["stock_changes"][0]["location"], "MAGAZA");
+  assert_eq!(data["stock_changes"][0]["delta"], -2);
+  assert_eq!(data["stock_changes"][0]["new_qty"], 3);
+
+  let deliveries = store.list_webhook_deliveries(10).unwrap();
+  assert_eq!(deliveries.len(), 1);
+  assert_eq!(deliveries[0].status, "DELIVERED");
+  assert_eq!(deliveries[0].id.to_string(), got.delivery);
+  // ikinci tur bir şey göndermez
+  assert_eq!(webhooks::dispatch_due(&store).unwrap().delivered, 0);
+}
+
+#[test]
+fn failed_delivery_backs_off_and_is_retried() {
+  let (_dir, store) = temp_store();
+  let bc = add_product(&store, "Elbise", 100.0, 5, 5);
+  let hook = receiver(vec![500, 200]);
+  store.add_webhook(hook.url.clone(), None).unwrap();
+
+  store
+    .create_transfer(CreateTransferPayload {
+      items: vec![CreateTransferItemPayload {
+        barcode: bc.clone(),
+        qty: 2,
+        from_loc: "DEPO".into(),
+        to_loc: "MAGAZA".into(),
+      }],
+      note: None,
+    })
+    .unwrap();
+
+  let first = webhooks::dispatch_due(&store).unwrap();
+  assert_eq!((first.delivered, first.retrying), (0, 1));
+  let d = &store.list_webhook_deliveries(10).unwrap()[0];
+  assert_eq!((d.status.as_str(), d.attempts), ("PENDING", 1));
+  assert_eq!(d.last_error.as_deref(), Some("HTTP 500"));
+
+  // bekleme süresi dolmadan tekrar denenmez
+  let second = webhooks::dispatch_due(&store).unwrap();
+  assert_eq!((second.delivered, second.retrying), (0, 0));
+
+  assert_eq!(store.retry_webhook_deliveries().unwrap(), 1);
+  assert_eq!(webhooks::dispatch_due(&store).unwrap().delivered, 1);
+
+  let events: Vec<Received> = hook.rx.iter().collect();
+  hook.handle.join().unwrap();
+  assert_eq!(events.len(), 2);
+  assert_eq!(events[0].body["id"], events[1].body["id"]);
+  assert_eq!(events[1].event, "transfer.created");
+  // kaynak ve hedef lokasyonun yeni stoğu
+  let changes = &events[1].body["data"]["stock_changes"];
+  assert_eq!(changes[0]["location"], "DEPO");
+  assert_eq!(changes[0]["new_qty"], 3);
+  assert_eq!(changes[1]["location"], "MAGAZA");
+  assert_eq!(changes[1]["new_qty"], 7);
+
+  let d = &store.list_webhook_deliveries(10).unwrap()[0];
+  assert_eq!((d.status.as_str(), d.attempts), ("DELIVERED", 2));
+}
+
+#[test]
+fn rolled_back_sale_writes_no_event() {
+  let (_dir, store) = temp_store();
+  let bc = add_product(&store, "Elbise", 100.0, 1, 0);
+  store.add_webhook("http://127.0.0.1:9/hook".into(), None).unwrap();
+
+  assert!(store.create_sale(sale(&bc, 3)).is_err());
+  assert!(store.list_webhook_deliveries(10).unwrap().is_empty());
+}
+
+#[test]
+fn inactive_and_unreachable_webhooks() {
+  let (_dir, store) = temp_store();
+  let bc = add_product(&store, "Elbise", 100.0, 5, 0);
+  let off = store.add_webhook("http://127.0.0.1:9/off".into(), None).unwrap();
+  store.update_webhook(off, None, Some(0)).unwrap();
+  // 9 (discard) portunda dinleyen yok: bağlantı hatası yeniden denenir
+  store.add_webhook("http://127.0.0.1:9/down".into(), None).unwrap();
+
+  let res = store.create_sale(sale(&bc, 1)).unwrap();
+  store
+    .create_return(CreateReturnPayload {
+      barcode: bc.clone(),
+      qty: 1,
+      unit_price: 100.0,
+      return_to: "MAGAZA".into(),
+      sold_at: None,
+      sold_from: None,
+    })
+    .unwrap();
+  assert!(!res.sale_group_id.is_empty());
+
+  // yalnız aktif adrese teslimat açılır
+  let deliveries = store.list_webhook_deliveries(10).unwrap();
+  assert_eq!(deliveries.len(), 2);
+  assert!(deliveries.iter().all(|d| d.url.ends_with("/down")));
+  assert_eq!(deliveries[0].event_type, "return.created");
+
+  // ilk bağlantı hatasından sonra aynı adresin kalan teslimatı bu turda denenmez
+  let summary = webhooks::dispatch_due(&store).unwrap();
+  assert_eq!((summary.delivered, summary.retrying, summary.deferred), (0, 1, 1));
+  assert_eq!(webhooks::dispatch_due(&store).unwrap().retrying, 0);
+
+  let hooks = store.list_webhooks(&ListQuery::default()).unwrap().items;
+  assert_eq!(hooks.iter().map(|w| w.pending).collect::<Vec<_>>(), vec![0, 2]);
+
+  // silinen adresin teslimatları da gider
+  store.delete_webhook(hooks[1].id).unwrap();
+  assert!(store.list_webhook_deliveries(10).unwrap().is_empty());
+}
+
+#[test]
+fn unreachable_webhook_does_not_hold_up_others() {
+  let (_dir, store) = temp_store();
+  let bc = add_product(&store, "Elbise", 100.0, 5, 0);
+  store.add_webhook("http://127.0.0.1:9/down".into(), None).unwrap();
+  let hook = receiver(vec![200, 200, 200]);
+  store.add_webhook(hook.url.clone(), None).unwrap();
+
+  for _ in 0..3 {
+    store.create_sale(sale(&bc, 1)).unwrap();
+  }
+  let summary = webhooks::dispatch_due(&store).unwrap();
+  assert_eq!((summary.delivered, summary.retrying, summary.deferred), (3, 1, 2));
+  assert_eq!(hook.rx.iter().count(), 3);
+  hook.handle.join().unwrap();
+
+  // ulaşılamayan adresin ertelenen teslimatları deneme sayılmaz
+  let down: Vec<i64> = store
+    .list_webhook_deliveries(10)
+    .unwrap()
+    .iter()
+    .filter(|d| d.url.ends_with("/down"))
+    .map(|d| d.attempts)
+    .collect();
+  assert_eq!(down.iter().sum::<i64>(), 1);
+}
+
+#[test]
+fn undo_and_stock_edits_are_posted() {
+  let (_dir, store) = temp_store();
+  let bc = add_product(&store, "Elbise", 100.0, 5, 5);
+  let hook = receiver(vec![200; 6]);
+  store.add_webhook(hook.url.clone(), None).unwrap();
+
+  let res = store.create_sale(sale(&bc, 2)).unwrap();
+  store.undo_last_sale().unwrap();
+  store
+    .create_transfer(CreateTransferPayload {
+      items: vec![CreateTransferItemPayload { barcode: bc.clone(), qty: 1, from_loc: "DEPO".into(), to_loc: "MAGAZA".into() }],
+      note: None,
+    })
+    .unwrap();
+  store.undo_last_transfer().unwrap();
+  store.update_stock(UpdateStockPayload { barcode: bc.clone(), magaza_stok: 4, depo_stok: 5 }).unwrap();
+  store.adjust_stock(&bc, 0, -2).unwrap();
+  // değişmeyen stok olay yazmaz
+  store.update_stock(UpdateStockPayload { barcode: bc.clone(), magaza_stok: 4, depo_stok: 3 }).unwrap();
+
+  assert_eq!(webhooks::dispatch_due(&store).unwrap().delivered, 6);
+  let events: Vec<Received> = hook.rx.iter().collect();
+  hook.handle.join().unwrap();
+  let types: Vec<&str> = events.iter().map(|e| e.event.as_str()).collect();
+  assert_eq!(
+    types,
+    vec!["sale.created", "sale.undone", "transfer.created", "transfer.undone", "stock.updated", "stock.updated"]
+  );
+  assert_eq!(events[1].body["data"]["sale_group_id"], res.sale_group_id.as_str());
+  assert_eq!(events[1].body["data"]["stock_changes"][0]["delta"], 2);
+  assert_eq!(events[3].body["data"]["stock_changes"][1]["new_qty"], 5);
+  let adjusted = &events[5].body["data"];
+  assert_eq!(adjusted["barcode"], bc.as_str());
+  assert_eq!(adjusted["stock_changes"][0]["location"], "DEPO");
+  assert_eq!(adjusted["stock_changes"][0]["new_qty"], 3);
+}
+
+#[test]
+fn merged_variant_stock_is_posted() {
+  let (_dir, store) = temp_store();
+  let variant = |magaza: i64, depo: i64| {
+    store
+      .add_product(None, Some("ELB900".into()), None, "Elbise".into(), None, None, None, 100.0, Some(magaza), Some(depo))
+      .unwrap()
+      .barcode
+  };
+  let bc = variant(3, 0);
+  let hook = receiver(vec![200]);
+  store.add_webhook(hook.url.clone(), None).unwrap();
+
+  // aynı aile + renk + beden: yeni ürün açılmaz, stok artar (CSV içe aktarma da bu yoldan)
+  assert_eq!(variant(2, 0), bc);
+  assert_eq!(webhooks::dispatch_due(&store).unwrap().delivered, 1);
+  let got = hook.rx.recv().unwrap();
+  hook.handle.join().unwrap();
+  assert_eq!(got.event, "stock.updated");
+  let changes = &got.body["data"]["stock_changes"];
+  assert_eq!(changes.as_array().unwrap().len(), 1);
+  assert_eq!((changes[0]["location"].as_str(), changes[0]["delta"].as_i64()), (Some("MAGAZA"), Some(2)));
+  assert_eq!(changes[0]["new_qty"], 5);
+}
+
+#[test]
+fn webhook_url_is_validated() {
+  let (_dir, store) = temp_store();
+  assert!(store.add_webhook("ftp://x".into(), None).is_err());
+  assert!(store.add_webhook("  ".into(), None).is_err());
+  store.add_webhook(" https://example.com/hook ".into(), None).unwrap();
+  assert!(store.add_webhook("https://example.com/hook".into(), None).is_err());
+}
@@ -15,10 +15,14 @@ import Settings from "../pages/Settings";
 import BarcodePrint from "../pages/BarcodePrint";
 import ProductEdit from "../pages/ProductEdit";
 import { Analytics } from "../pages/Analytics";
+import { CustomerDisplay } from "../pages/CustomerDisplay";
 
 export const router = createBrowserRouter([
   { path: "/unlock", element: <Unlock /> },
 
+  // Müşteri ekranı penceresi (menü / App kabuğu olmadan)
+  { path: "/customer-display", element: <CustomerDisplay /> },
+
   {
     path: "/",
     element: <App />,
@@ -0,0 +1,64 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { C, btnSecondary } from "../lib/ds";
+import { errorMessage } from "../lib/errors";
+
+// export_report komutunun rapor seçimi (export::ExportReport)
+export type ExportReport =
+  | { report: "velocity_report"; from?: string | null; to?: string | null }
+  | { report: "dead_stock"; min_days: number }
+  | { report: "category_margin"; from?: string | null; to?: string | null }
+  | { report: "basket_pairs"; limit: number; from?: string | null; to?: string | null }
+  | { report: "low_stock"; max_stock: number }
+  | { report: "cash_report"; from?: string | null; to?: string | null }
+  | { report: "expenses"; from?: string | null; to?: string | null }
+  | { report: "sale_groups"; from?: string | null; to?: string | null; q?: string | null }
+  | { report: "products" };
+
+type ExportFormat = "csv" | "xlsx";
+
+// Ekranda görünen raporu CSV / Excel olarak kaydeder; kaydetme diyaloğunu backend açar.
+export function ExportButtons({ report, disabled }: { report: ExportReport; disabled?: boolean }) {
+  const [busy, setBusy] = useState(false);
+  const [msg, setMsg] = useState<{ ok: boolean; text: string } | null>(null);
+
+  const run = async (format: ExportFormat) => {
+    setBusy(true);
+    setMsg(null);
+    try {
+      const path = await invoke<string | null>("export_report", { payload: { ...report, format } });
+      // null: kullanıcı diyaloğu iptal etti
+      if (path) setMsg({ ok: true, text: `✅ Kaydedildi: ${path}` });
+    } catch (e) {
+      setMsg({ ok: false, text: `❌ Dışa aktarılamadı: ${errorMessage(e)}` });
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
+      {msg && (
+        <span
+          title={msg.text}
+          style={{
+            fontSize: 12,
+            color: msg.ok ? C.green : C.rose,
+            maxWidth: 320,
+            overflow: "hidden",
+            textOverflow: "ellipsis",
+            whiteSpace: "nowrap",
+          }}
+        >
+          {msg.text}
+        </span>
+      )}
+      <button type="button" onClick={() => run("csv")} disabled={busy || disabled} style={btnSecondary}>
+        CSV
+      </button>
+      <button type="button" onClick={() => run("xlsx")} disabled={busy || disabled} style={btnSecondary}>
+        Excel
+      </button>
+    </div>
+  );
+}
@@ -0,0 +1,146 @@
+import { useEffect, useState } from "react";
+import type { CSSProperties } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { C, R, btnSecondary, errBox } from "../lib/ds";
+import { errorMessage } from "../lib/errors";
+
+// get_product_family yanıtı (db::ProductFamilyGrid)
+type FamilyGridCell = {
+  barcode: string;
+  magaza_stok: number;
+  depo_stok: number;
+  stock: number;
+};
+
+type ProductFamilyGrid = {
+  product_code: string;
+  name: string;
+  category?: string | null;
+  sizes: string[];
+  rows: { color: string; cells: (FamilyGridCell | null)[]; total_stock: number }[];
+  size_totals: number[];
+  total_stock: number;
+};
+
+// Aile (product_code) için renk × beden stok tablosu; hücrede mağaza / depo kırılımı title'da
+export function FamilyGrid({ productCode, onClose }: { productCode: string; onClose: () => void }) {
+  const [grid, setGrid] = useState<ProductFamilyGrid | null>(null);
+  const [err, setErr] = useState("");
+
+  useEffect(() => {
+    setGrid(null);
+    setErr("");
+    invoke<ProductFamilyGrid>("get_product_family", { productCode })
+      .then(setGrid)
+      .catch((e) => setErr(errorMessage(e)));
+  }, [productCode]);
+
+  return (
+    <div style={overlay} onClick={onClose}>
+      <div style={modal} onClick={(e) => e.stopPropagation()}>
+        <div style={{ display: "flex", alignItems: "center", gap: 12, marginBottom: 16 }}>
+          <div style={{ minWidth: 0 }}>
+            <div style={{ fontSize: 16, fontWeight: 700, color: C.ink }}>
+              {grid ? grid.name : productCode}
+            </div>
+            <div style={{ fontSize: 12, color: C.ink3, marginTop: 2 }}>
+              {productCode}
+              {grid?.category ? ` · ${grid.category}` : ""}
+            </div>
+          </div>
+          <button type="button" onClick={onClose} style={{ ...btnSecondary, marginLeft: "auto" }}>
+            Kapat
+          </button>
+        </div>
+
+        {err && <div style={errBox}>{err}</div>}
+        {!grid && !err && <div style={{ fontSize: 13, color: C.ink4 }}>Yükleniyor…</div>}
+
+        {grid && (
+          <div style={{ overflowX: "auto" }}>
+            <table style={{ borderCollapse: "collapse", fontSize: 13 }}>
+              <thead>
+                <tr>
+                  <th style={{ ...cellTh, textAlign: "left" }}>Renk / Beden</th>
+                  {grid.sizes.map((s) => (
+                    <th key={s} style={cellTh}>{s || "—"}</th>
+                  ))}
+                  <th style={cellTh}>Toplam</th>
+                </tr>
+              </thead>
+              <tbody>
+                {grid.rows.map((r) => (
+                  <tr key={r.color}>
+                    <td style={{ ...cellTd, textAlign: "left", fontWeight: 600 }}>{r.color || "—"}</td>
+                    {r.cells.map((c, i) =>
+                      c ? (
+                        <td
+                          key={i}
+                          title={`${c.barcode} · Mağaza ${c.magaza_stok} · Depo ${c.depo_stok}`}
+                          style={{ ...cellTd, color: c.stock > 0 ? C.ink : C.rose, fontWeight: 600 }}
+                        >
+                          {c.stock}
+                        </td>
+                      ) : (
+                        <td key={i} style={{ ...cellTd, color: C.ink4 }}>·</td>
+                      )
+                    )}
+                    <td style={{ ...cellTd, fontWeight: 700 }}>{r.total_stock}</td>
+                  </tr>
+                ))}
+                <tr>
+                  <td style={{ ...cellTd, textAlign: "left", fontWeight: 700 }}>Toplam</td>
+                  {grid.size_totals.map((t, i) => (
+                    <td key={i} style={{ ...cellTd, fontWeight: 700 }}>{t}</td>
+                  ))}
+                  <td style={{ ...cellTd, fontWeight: 800 }}>{grid.total_stock}</td>
+                </tr>
+              </tbody>
+            </table>
+            <div style={{ marginTop: 8, fontSize: 12, color: C.ink3 }}>
+              Hücre üzerine gelince barkod ve mağaza / depo kırılımı görünür. · = bu renk / beden yok.
+            </div>
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
+
+const overlay: CSSProperties = {
+  position: "fixed",
+  inset: 0,
+  background: "rgba(0,0,0,.35)",
+  display: "grid",
+  placeItems: "center",
+  zIndex: 9999,
+  padding: 16,
+};
+
+const modal: CSSProperties = {
+  width: "min(760px, 100%)",
+  maxHeight: "90vh",
+  overflow: "auto",
+  background: C.canvas,
+  borderRadius: R.xl,
+  border: `1px solid ${C.border}`,
+  boxShadow: "0 12px 40px rgba(0,0,0,.18)",
+  padding: 20,
+  boxSizing: "border-box",
+};
+
+const cellTh: CSSProperties = {
+  padding: "6px 12px",
+  textAlign: "center",
+  borderBottom: `2px solid ${C.border}`,
+  color: C.ink3,
+  fontWeight: 600,
+  whiteSpace: "nowrap",
+};
+
+const cellTd: CSSProperties = {
+  padding: "6px 12px",
+  textAlign: "center",
+  borderBottom: `1px solid ${C.border}`,
+  whiteSpace: "nowrap",
+};
@@ -0,0 +1,82 @@
+import { useEffect, useRef } from "react";
+import { listen } from "@tauri-apps/api/event";
+
+// Backend'in commit sonrası yaydığı olaylar (db.rs, CANLI OLAYLAR)
+export type StockChangedEvent = {
+  barcode: string;
+  location: "MAGAZA" | "DEPO";
+  delta: number;
+  new_qty: number;
+};
+
+export type LowStockReachedEvent = {
+  barcode: string;
+  name: string;
+  magaza_stok: number;
+  depo_stok: number;
+  total_stock: number;
+  threshold: number;
+};
+
+export type SaleCreatedEvent = {
+  sale_group_id: string;
+  /** Satışın geldiği kanal: kasa (Tauri komutu) ya da yerel HTTP API */
+  channel: "POS" | "API";
+  payment_method: string;
+  total: number;
+  /** Nakitte alınan tutar / para üstü (satış ekranı girdiyse) */
+  tendered: number | null;
+  change_due: number | null;
+  lines: { barcode: string; qty: number; unit_price: number; total: number; sold_from: string }[];
+  stock_changes: StockChangedEvent[];
+};
+
+export type PricedCartLine = {
+  barcode: string;
+  name: string;
+  color: string | null;
+  size: string | null;
+  qty: number;
+  list_price: number;
+  discount_amount: number;
+  unit_price: number;
+  total: number;
+};
+
+// price_cart sonucu; satış ekranındaki açık sepet
+export type CartUpdatedEvent = {
+  lines: PricedCartLine[];
+  item_count: number;
+  subtotal: number;
+  discount_total: number;
+  total: number;
+  payment_method: string;
+  tendered: number | null;
+  change_due: number | null;
+};
+
+type BackendEvents = {
+  sale_created: SaleCreatedEvent;
+  stock_changed: StockChangedEvent;
+  low_stock_reached: LowStockReachedEvent;
+  cart_updated: CartUpdatedEvent;
+};
+
+/** Olay geldikçe handler çağrılır; handler her render'da değişebilir, abonelik bir kez kurulur. */
+export function useBackendEvent<K extends keyof BackendEvents>(name: K, handler: (payload: BackendEvents[K]) => void) {
+  const ref = useRef(handler);
+  ref.current = handler;
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+    listen<BackendEvents[K]>(name, (e) => ref.current(e.payload)).then((fn) => {
+      if (cancelled) fn();
+      else unlisten = fn;
+    });
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [name]);
+}
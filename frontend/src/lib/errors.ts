@@ -0,0 +1,26 @@
+// Komut hataları (src-tauri/src/error.rs, PosError): { code, message, details }
+export type PosErrorCode =
+  | "NOT_FOUND"
+  | "INSUFFICIENT_STOCK"
+  | "VALIDATION"
+  | "CONFLICT"
+  | "DB"
+  | "IO";
+
+export type PosError = {
+  code: PosErrorCode;
+  message: string;
+  // INSUFFICIENT_STOCK: { barcode, location, available, requested }
+  details: Record<string, unknown> | null;
+};
+
+export function isPosError(e: unknown): e is PosError {
+  return typeof e === "object" && e !== null && "code" in e && "message" in e;
+}
+
+// catch bloklarında kullanıcıya gösterilecek metin
+export function errorMessage(e: unknown): string {
+  if (isPosError(e)) return e.message;
+  if (e instanceof Error) return e.message;
+  return String(e);
+}
@@ -0,0 +1,71 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// list_* komutlarının ortak sayfa yanıtı (db::Page)
+export type Page<T> = {
+  items: T[];
+  total: number;
+  page: number;
+  page_size: number;
+};
+
+// list_* komutlarının ortak parametreleri (db::ListQuery). Hepsi opsiyonel.
+export type ListQuery = {
+  page?: number;
+  page_size?: number;
+  sort?: string;
+  desc?: boolean;
+  q?: string | null;
+  from?: string | null; // YYYY-MM-DD
+  to?: string | null; // YYYY-MM-DD
+  include_inactive?: boolean;
+};
+
+// Bugünden N gün önce (yerel saat), YYYY-MM-DD
+export function daysAgo(days: number): string {
+  const d = new Date();
+  d.setDate(d.getDate() - Math.max(0, days));
+  const mm = String(d.getMonth() + 1).padStart(2, "0");
+  const dd = String(d.getDate()).padStart(2, "0");
+  return `${d.getFullYear()}-${mm}-${dd}`;
+}
+
+// N ay önceki ayın ilk günü, YYYY-MM-DD (0 = bu ay)
+export function monthsAgoStart(months: number): string {
+  const d = new Date();
+  d.setDate(1);
+  d.setMonth(d.getMonth() - Math.max(0, months));
+  const mm = String(d.getMonth() + 1).padStart(2, "0");
+  return `${d.getFullYear()}-${mm}-01`;
+}
+
+// "Tümü" seçimi için alt sınır
+export const ALL_TIME_FROM = "2000-01-01";
+
+// Rapor komutlarının ortak yanıtı (db::ReportResult)
+export type MetricDelta = {
+  metric: string;
+  current: number;
+  previous: number;
+  change: number;
+  change_pct: number | null;
+};
+
+export type ReportResult<T> = {
+  from: string;
+  to: string;
+  data: T;
+  comparison: { from: string; to: string; data: T; deltas: MetricDelta[] } | null;
+};
+
+// list_* komutları varsayılan olarak tek sayfa (100 kayıt) döner. Tamamı gereken
+// ekranlar (ürün listesi, açılır listeler, ayarlar) sayfaları sırayla çeker.
+export const MAX_PAGE_SIZE = 500;
+
+export async function listAll<T>(cmd: string, args: Record<string, unknown> = {}, query: ListQuery = {}): Promise<T[]> {
+  const out: T[] = [];
+  for (let page = 1; ; page++) {
+    const res = await invoke<Page<T>>(cmd, { ...args, query: { ...query, page, page_size: MAX_PAGE_SIZE } });
+    out.push(...res.items);
+    if (res.items.length === 0 || out.length >= res.total) return out;
+  }
+}
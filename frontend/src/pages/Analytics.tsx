@@ -1,5 +1,8 @@
 import React, { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { ALL_TIME_FROM, daysAgo, type ReportResult } from "../lib/page";
+import { errorMessage } from "../lib/errors";
+import { ExportButtons, type ExportReport } from "../components/ExportButtons";
 
 type VelocityRow = {
   barcode: string;
@@ -129,7 +132,9 @@ export function Analytics() {
     try {
       if (t === "velocity") {
         const [rows, zero] = await Promise.all([
-          invoke<VelocityRow[]>("get_velocity_report", { days: velocityDays }),
+          invoke<ReportResult<VelocityRow[]>>("get_velocity_report", {
+            range: { from: daysAgo(velocityDays - 1) },
+          }).then((r) => r.data),
           invoke<DeadStockRow[]>("get_dead_stock", { minDays: velocityDays }),
         ]);
         setVelocity(rows ?? []);
@@ -139,17 +144,22 @@ export function Analytics() {
         const rows = await invoke<DeadStockRow[]>("get_dead_stock", { minDays: 21 });
         setDead(rows ?? []);
       } else if (t === "margin") {
-        const rows = await invoke<CategoryMarginRow[]>("get_category_margin", { days: marginDays });
+        const { data: rows } = await invoke<ReportResult<CategoryMarginRow[]>>("get_category_margin", {
+          range: { from: daysAgo(marginDays - 1) },
+        });
         setMargin(rows ?? []);
       } else if (t === "basket") {
-        const rows = await invoke<BasketPairRow[]>("get_basket_pairs", { limit: 30 });
+        const { data: rows } = await invoke<ReportResult<BasketPairRow[]>>("get_basket_pairs", {
+          limit: 30,
+          range: { from: ALL_TIME_FROM },
+        });
         setBasket(rows ?? []);
       } else if (t === "lowstock") {
         const rows = await invoke<LowStockRow[]>("get_low_stock", { maxStock: lowStockThreshold });
         setLowStock(rows ?? []);
       }
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -160,6 +170,18 @@ export function Analytics() {
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, [tab, velocityDays, marginDays, lowStockThreshold]);
 
+  // Dışa aktarma, ekrandaki sekmenin parametreleriyle aynı raporu üretir
+  const exportReport: ExportReport =
+    tab === "velocity"
+      ? { report: "velocity_report", from: daysAgo(velocityDays - 1) }
+      : tab === "dead"
+        ? { report: "dead_stock", min_days: 21 }
+        : tab === "margin"
+          ? { report: "category_margin", from: daysAgo(marginDays - 1) }
+          : tab === "basket"
+            ? { report: "basket_pairs", limit: 30, from: ALL_TIME_FROM }
+            : { report: "low_stock", max_stock: lowStockThreshold };
+
   const tabs: { key: Tab; label: string }[] = [
     { key: "velocity", label: "Hız Analizi" },
     { key: "dead", label: "Ölü Stok" },
@@ -170,10 +192,15 @@ export function Analytics() {
 
   return (
     <div style={{ padding: 18, fontFamily: "system-ui", background: "#fbf6f3", minHeight: "100%" }}>
-      <div style={{ marginBottom: 16 }}>
-        <h1 style={{ margin: 0, fontSize: 26, fontWeight: 900, color: "#111827" }}>Analiz</h1>
-        <div style={{ fontSize: 13, opacity: 0.6, marginTop: 4 }}>
-          Mevcut veriler üzerinden iş kararı almak için
+      <div style={{ display: "flex", alignItems: "center", gap: 12, marginBottom: 16 }}>
+        <div>
+          <h1 style={{ margin: 0, fontSize: 26, fontWeight: 900, color: "#111827" }}>Analiz</h1>
+          <div style={{ fontSize: 13, opacity: 0.6, marginTop: 4 }}>
+            Mevcut veriler üzerinden iş kararı almak için
+          </div>
+        </div>
+        <div style={{ marginLeft: "auto" }}>
+          <ExportButtons report={exportReport} disabled={loading} />
         </div>
       </div>
 
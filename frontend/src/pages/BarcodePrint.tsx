@@ -1,7 +1,8 @@
 import { useEffect, useMemo, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
 import BarcodeLabelSheet from "../components/BarcodeLabelSheet";
 import type { LabelItem } from "../components/BarcodeLabelSheet";
+import { listAll } from "../lib/page";
+import { errorMessage } from "../lib/errors";
 
 
 type Product = {
@@ -138,7 +139,7 @@ export default function BarcodePrint() {
     pdf.save(`etiket_${yyyyMmDdLocal(new Date())}.pdf`);
     showToast("✅ PDF indirildi");
   } catch (e) {
-    showToast(`❌ PDF indirilemedi: ${String(e)}`);
+    showToast(`❌ PDF indirilemedi: ${errorMessage(e)}`);
   } finally {
     setBusyPdf(false);
   }
@@ -149,8 +150,7 @@ export default function BarcodePrint() {
       setErr("");
       setLoading(true);
 
-      const rows = await invoke<Product[]>("list_products");
-      const list = rows || [];
+      const list = await listAll<Product>("list_products");
       setProducts(list);
 
       // qty default = stok kadar (stok 0 ise 0)
@@ -160,7 +160,7 @@ export default function BarcodePrint() {
       }
       setQtyByBarcode(q);
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setLoading(false);
     }
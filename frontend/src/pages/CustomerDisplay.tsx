@@ -0,0 +1,230 @@
+import React, { useEffect, useRef, useState } from "react";
+import {
+  useBackendEvent,
+  type CartUpdatedEvent,
+  type SaleCreatedEvent,
+} from "../hooks/useBackendEvent";
+
+// Müşteriye dönük ikinci pencere (main.rs: open_customer_display).
+// Kasiyer penceresiyle doğrudan konuşmaz; yalnız backend olaylarını dinler:
+//   cart_updated -> açık sepet (Sales sayfası price_cart çağırdıkça)
+//   sale_created -> teşekkür ekranı (toplam / para üstü), sonra boşta ekranı;
+//                   yalnız kasa satışları (channel POS), yerel API'den gelen online satışlar değil
+
+// Boşta ekranında dönen kampanya metinleri (satır başına bir tane, Ayarlar'dan)
+export const LS_DISPLAY_PROMOS = "cielpos_customer_display_promos";
+
+const THANKS_MS = 8000;
+const PROMO_ROTATE_MS = 6000;
+
+function fmtMoney(v: number) {
+  return new Intl.NumberFormat("tr-TR", {
+    style: "currency",
+    currency: "TRY",
+    maximumFractionDigits: 2,
+  }).format(v ?? 0);
+}
+
+function readPromos(): string[] {
+  return (localStorage.getItem(LS_DISPLAY_PROMOS) ?? "")
+    .split("\n")
+    .map((s) => s.trim())
+    .filter(Boolean);
+}
+
+export function CustomerDisplay() {
+  const [cart, setCart] = useState<CartUpdatedEvent | null>(null);
+  const [thanks, setThanks] = useState<SaleCreatedEvent | null>(null);
+  const thanksTimer = useRef<number | undefined>(undefined);
+
+  const [promos, setPromos] = useState<string[]>(readPromos);
+  const [promoIdx, setPromoIdx] = useState(0);
+
+  useBackendEvent("cart_updated", (c) => {
+    // satış sonrası gelen boş sepet teşekkür ekranını kapatmasın
+    if (!c.lines.length) {
+      setCart(null);
+      return;
+    }
+    window.clearTimeout(thanksTimer.current);
+    setThanks(null);
+    setCart(c);
+  });
+
+  useBackendEvent("sale_created", (s) => {
+    if (s.channel !== "POS") return;
+    setCart(null);
+    setThanks(s);
+    window.clearTimeout(thanksTimer.current);
+    thanksTimer.current = window.setTimeout(() => setThanks(null), THANKS_MS);
+  });
+
+  useEffect(() => () => window.clearTimeout(thanksTimer.current), []);
+
+  // Ayarlar'da kaydedilince (aynı origin, başka pencere) güncellenir
+  useEffect(() => {
+    const onStorage = (e: StorageEvent) => {
+      if (e.key === LS_DISPLAY_PROMOS) setPromos(readPromos());
+    };
+    window.addEventListener("storage", onStorage);
+    return () => window.removeEventListener("storage", onStorage);
+  }, []);
+
+  useEffect(() => {
+    if (promos.length < 2) return;
+    const t = window.setInterval(() => setPromoIdx((i) => i + 1), PROMO_ROTATE_MS);
+    return () => window.clearInterval(t);
+  }, [promos.length]);
+
+  if (thanks) {
+    return (
+      <div style={D.center}>
+        <div style={D.bigIcon}>🛍️</div>
+        <div style={D.title}>Teşekkür ederiz!</div>
+        <div style={D.thanksTotal}>{fmtMoney(thanks.total)}</div>
+        {thanks.change_due !== null && thanks.change_due > 0 && (
+          <div style={D.thanksChange}>Para üstü: {fmtMoney(thanks.change_due)}</div>
+        )}
+        <div style={D.muted}>Yine bekleriz.</div>
+      </div>
+    );
+  }
+
+  if (!cart) {
+    const promo = promos.length ? promos[promoIdx % promos.length] : "";
+    return (
+      <div style={D.center}>
+        <div style={D.title}>Hoş geldiniz</div>
+        {promo ? <div style={D.promo}>{promo}</div> : <div style={D.muted}>CIEL</div>}
+      </div>
+    );
+  }
+
+  return (
+    <div style={D.page}>
+      <div style={D.lines}>
+        {cart.lines.map((l, i) => (
+          <div key={`${l.barcode}-${i}`} style={D.line}>
+            <div style={{ minWidth: 0 }}>
+              <div style={D.lineName}>{l.name}</div>
+              <div style={D.lineMeta}>
+                {[l.color, l.size].filter(Boolean).join(" · ")}
+                {l.qty > 1 ? ` ${l.qty} × ${fmtMoney(l.unit_price)}` : ""}
+              </div>
+            </div>
+            <div style={{ textAlign: "right" }}>
+              {l.unit_price !== l.list_price && <div style={D.strike}>{fmtMoney(l.qty * l.list_price)}</div>}
+              <div style={D.lineTotal}>{fmtMoney(l.total)}</div>
+            </div>
+          </div>
+        ))}
+      </div>
+
+      <div style={D.summary}>
+        {cart.discount_total > 0 && (
+          <>
+            <div style={D.sumRow}>
+              <span>Ara toplam</span>
+              <span>{fmtMoney(cart.subtotal)}</span>
+            </div>
+            <div style={{ ...D.sumRow, color: "#059669" }}>
+              <span>İndirim</span>
+              <span>−{fmtMoney(cart.discount_total)}</span>
+            </div>
+          </>
+        )}
+        <div style={D.totalRow}>
+          <span>Toplam ({cart.item_count} adet)</span>
+          <span>{fmtMoney(cart.total)}</span>
+        </div>
+        {cart.tendered !== null && cart.change_due !== null && (
+          <>
+            <div style={D.sumRow}>
+              <span>Alınan</span>
+              <span>{fmtMoney(cart.tendered)}</span>
+            </div>
+            <div style={{ ...D.sumRow, fontWeight: 800, color: cart.change_due >= 0 ? "#111827" : "#b91c1c" }}>
+              <span>{cart.change_due >= 0 ? "Para üstü" : "Kalan"}</span>
+              <span>{fmtMoney(Math.abs(cart.change_due))}</span>
+            </div>
+          </>
+        )}
+      </div>
+    </div>
+  );
+}
+
+// ── Styles ─────────────────────────────────────────────────────────────────
+
+const D: Record<string, React.CSSProperties> = {
+  page: {
+    height: "100vh",
+    display: "flex",
+    flexDirection: "column",
+    padding: 32,
+    boxSizing: "border-box",
+    fontFamily: "system-ui, -apple-system, sans-serif",
+    background: "#FAF9F7",
+    color: "#111827",
+  },
+  center: {
+    height: "100vh",
+    display: "flex",
+    flexDirection: "column",
+    alignItems: "center",
+    justifyContent: "center",
+    gap: 18,
+    padding: 32,
+    boxSizing: "border-box",
+    textAlign: "center",
+    fontFamily: "system-ui, -apple-system, sans-serif",
+    background: "#FAF9F7",
+    color: "#111827",
+  },
+  bigIcon: { fontSize: 72 },
+  title: { fontSize: 48, fontWeight: 900 },
+  muted: { fontSize: 22, color: "#9ca3af", fontWeight: 700 },
+  promo: { fontSize: 34, fontWeight: 800, maxWidth: 900, lineHeight: 1.3 },
+  thanksTotal: { fontSize: 56, fontWeight: 900 },
+  thanksChange: { fontSize: 32, fontWeight: 800, color: "#065f46" },
+
+  lines: {
+    flex: 1,
+    overflowY: "auto",
+    display: "flex",
+    flexDirection: "column",
+    gap: 10,
+  },
+  line: {
+    display: "flex",
+    justifyContent: "space-between",
+    gap: 16,
+    alignItems: "center",
+    background: "#fff",
+    border: "1px solid #EAE8E5",
+    borderRadius: 14,
+    padding: "14px 18px",
+  },
+  lineName: { fontSize: 24, fontWeight: 800 },
+  lineMeta: { fontSize: 17, color: "#6b7280", marginTop: 4 },
+  strike: { fontSize: 16, color: "#9ca3af", textDecoration: "line-through" },
+  lineTotal: { fontSize: 24, fontWeight: 900 },
+
+  summary: {
+    marginTop: 20,
+    background: "#fff",
+    border: "1px solid #EAE8E5",
+    borderRadius: 16,
+    padding: "18px 22px",
+    display: "flex",
+    flexDirection: "column",
+    gap: 8,
+  },
+  sumRow: { display: "flex", justifyContent: "space-between", fontSize: 22, color: "#374151" },
+  totalRow: {
+    display: "flex",
+    justifyContent: "space-between",
+    fontSize: 40,
+    fontWeight: 900,
+  },
+};
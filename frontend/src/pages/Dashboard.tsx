@@ -1,4 +1,4 @@
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import ExportModal from "./ExportModal";
 import {
@@ -13,6 +13,9 @@ import {
   Legend,
   BarChart,
 } from "recharts";
+import { ALL_TIME_FROM, daysAgo, monthsAgoStart, type ReportResult } from "../lib/page";
+import { errorMessage } from "../lib/errors";
+import { useBackendEvent } from "../hooks/useBackendEvent";
 
 type RangeDays = 7 | 14 | 30;
 
@@ -73,24 +76,26 @@ export function Dashboard() {
     window.setTimeout(() => setToast(null), 2200);
   };
 
-  const load = async () => {
+  // silent: canlı güncellemede yükleniyor göstergesi yanıp sönmesin
+  const load = async (silent = false) => {
     try {
       setErr("");
-      setLoading(true);
+      if (!silent) setLoading(true);
 
       // Günlük kasa 
-      const daysArg = cashDays === 9999 ? 3650 : cashDays; 
-      const cash = await invoke<CashReportRow[]>("get_cash_report", { days: daysArg });
-      setCashRows(cash);
+      const cashFrom = cashDays === 9999 ? ALL_TIME_FROM : daysAgo(cashDays);
+      const cash = await invoke<ReportResult<CashReportRow[]>>("get_cash_report", {
+        range: { from: cashFrom },
+      });
+      setCashRows(cash.data);
 
 
-      const res = await invoke<DashboardSummary>("get_dashboard_summary", {
-        days: rangeDays,
-        months: 12,
+      const res = await invoke<ReportResult<DashboardSummary>>("get_dashboard_summary", {
+        query: { from: daysAgo(rangeDays - 1), monthly_from: monthsAgoStart(11) },
       });
-      setData(res);
+      setData(res.data);
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
       setData(null);
     } finally {
       setLoading(false);
@@ -101,6 +106,18 @@ export function Dashboard() {
     load();
   }, [rangeDays, cashDays]);
 
+  // satış / stok olayında yeniden yükle (art arda gelen olaylar tek yüklemede birleşir)
+  const reloadTimer = useRef<number | undefined>(undefined);
+  const scheduleReload = () => {
+    window.clearTimeout(reloadTimer.current);
+    reloadTimer.current = window.setTimeout(() => load(true), 400);
+  };
+  useBackendEvent("sale_created", scheduleReload);
+  useBackendEvent("stock_changed", scheduleReload);
+  useBackendEvent("low_stock_reached", (e) =>
+    showToast(`⚠️ Stok azaldı: ${e.name} (${e.total_stock} adet)`, "err")
+  );
+
 
   const dailyRows = (data?.daily ?? []).filter((r) => {
     // satış/iade etkisi olan günler
@@ -232,7 +249,7 @@ export function Dashboard() {
           <div style={title}>Dashboard</div>
         </div>
 
-        <button style={btnSoft} onClick={load} disabled={loading}>
+        <button style={btnSoft} onClick={() => load()} disabled={loading}>
           {loading ? "Yükleniyor…" : "Yenile"}
         </button>
       </div>
@@ -1,6 +1,9 @@
 import { useEffect, useMemo, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { C, page, card, cardPadded, input, select as dsSelect, btnPrimary, btnSecondary, btnDanger, th as dsTh, td as dsTd, fieldLabel, errBox } from "../lib/ds";
+import { listAll } from "../lib/page";
+import { errorMessage } from "../lib/errors";
+import { ExportButtons } from "../components/ExportButtons";
 
 type Expense = {
   id: number;
@@ -37,10 +40,9 @@ export function Expenses() {
     try {
       setErr("");
       setLoading(true);
-      const data = await invoke<Expense[]>("list_expenses");
-      setRows(data);
+      setRows(await listAll<Expense>("list_expenses"));
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -122,7 +124,7 @@ export function Expenses() {
       setAmount("");
       setNote("");
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     }
   };
 
@@ -134,7 +136,7 @@ export function Expenses() {
       await invoke<number>("delete_expense", { id });
       setRows((prev) => prev.filter((x) => x.id !== id));
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     }
   };
 
@@ -143,9 +145,12 @@ export function Expenses() {
       {/* Header */}
       <div style={{ display: "flex", alignItems: "center", gap: 12, marginBottom: 20 }}>
         <h2 style={{ fontSize: 20, fontWeight: 700, color: C.ink }}>Giderler</h2>
-        <button onClick={load} disabled={loading} style={{ ...btnSecondary, marginLeft: "auto" }}>
-          Yenile
-        </button>
+        <div style={{ marginLeft: "auto", display: "flex", gap: 8 }}>
+          <ExportButtons report={{ report: "expenses", ...periodRange(periodFilter) }} disabled={loading} />
+          <button onClick={load} disabled={loading} style={btnSecondary}>
+            Yenile
+          </button>
+        </div>
       </div>
 
       {err && <div style={{ ...errBox, marginBottom: 16 }}>{err}</div>}
@@ -317,6 +322,14 @@ function derivePeriod(spentAt: string): string {
   return "";
 }
 
+// Seçili dönemin (YYYY-MM) ilk ve son günü; "ALL" ise tüm giderler
+function periodRange(period: string): { from: string | null; to: string | null } {
+  if (period === "ALL") return { from: null, to: null };
+  const [y, m] = period.split("-").map(Number);
+  const last = new Date(y, m, 0).getDate();
+  return { from: `${period}-01`, to: `${period}-${String(last).padStart(2, "0")}` };
+}
+
 function fmtMoney(v: number) {
   return new Intl.NumberFormat("tr-TR", {
     style: "currency",
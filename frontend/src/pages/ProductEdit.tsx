@@ -1,6 +1,8 @@
 import React, { useEffect, useMemo, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useNavigate, useParams } from "react-router-dom";
+import { listAll } from "../lib/page";
+import { errorMessage } from "../lib/errors";
 
 type Product = {
   barcode: string;
@@ -43,9 +45,9 @@ async function safeList(
   cmd: "list_categories" | "list_colors" | "list_sizes",
 ): Promise<string[]> {
   try {
-    const rows = await invoke<DictItemDto[]>(cmd, { include_inactive: true });
+    const rows = await listAll<DictItemDto>(cmd, {}, { include_inactive: true });
     // only active items
-    const names = (rows || [])
+    const names = rows
       .filter((r) => Number(r.is_active) === 1)
       .map((r) => String(r.name).trim())
       .filter(Boolean);
@@ -128,7 +130,7 @@ export default function ProductEdit() {
       setMagazaStok(String(p.magaza_stok ?? 0));
       setDepoStok(String(p.depo_stok ?? 0));
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -172,7 +174,7 @@ export default function ProductEdit() {
 
       nav("/products");
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setSaving(false);
     }
@@ -198,7 +200,7 @@ export default function ProductEdit() {
       setStockNote("✅ Stok güncellendi");
       setTimeout(() => setStockNote(""), 2500);
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setSaving(false);
     }
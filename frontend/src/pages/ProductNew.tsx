@@ -1,6 +1,8 @@
 import React, { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useNavigate, useSearchParams } from "react-router-dom";
+import { listAll } from "../lib/page";
+import { errorMessage } from "../lib/errors";
 
 type VariantLine = {
   size: string;
@@ -11,6 +13,12 @@ type CreatedProductDto = {
   barcode: string;
   product_code?: string | null;
 };
+type FamilyVariantDto = { barcode: string; color: string; size: string };
+type CreateFamilyResultDto = {
+  product_code: string;
+  created: FamilyVariantDto[];
+  skipped: FamilyVariantDto[];
+};
 
 function toName(x: any): string {
   if (x == null) return "";
@@ -26,7 +34,7 @@ async function safeList(
   cmd: "list_categories" | "list_colors" | "list_sizes",
 ): Promise<string[]> {
   try {
-    const rows = await invoke<any[]>(cmd, { include_inactive: true });
+    const rows = await listAll<any>(cmd, {}, { include_inactive: true });
 
     // aktif filtreyi de güvenli yap
     const names = (rows || [])
@@ -263,7 +271,7 @@ export function ProductNew() {
     const spNum = Number(sellPrice);
     const hasSellPrice = !!sellPrice.trim() && !Number.isNaN(spNum);
     if (!hasName || !hasSellPrice) return false;
-    let hasAny = false;
+    if (matrixColors.length === 0 || matrixSizes.length === 0) return false;
     for (const c of matrixColors) {
       for (const s of matrixSizes) {
         const cell = getMatrixCell(c, s);
@@ -271,10 +279,9 @@ export function ProductNew() {
         const ds = cell.depo.trim() === "" ? 0 : Number(cell.depo);
         if (!Number.isFinite(ms) || !Number.isInteger(ms) || ms < 0) return false;
         if (!Number.isFinite(ds) || !Number.isInteger(ds) || ds < 0) return false;
-        if (ms + ds > 0) hasAny = true;
       }
     }
-    return hasAny;
+    return true;
   })();
 
   const handleSave = async () => {
@@ -460,7 +467,7 @@ export function ProductNew() {
       setTimeout(() => nav("/products"), 300);
       return;
     } catch (e) {
-      setResult(`❌ ${String(e)}`);
+      setResult(`❌ ${errorMessage(e)}`);
     }
   };
 
@@ -478,59 +485,51 @@ export function ProductNew() {
       const bp = buyPrice.trim() === "" ? null : Number(buyPrice.trim());
       if (bp !== null && Number.isNaN(bp)) { setResult("❌ Alış fiyatı sayı olmalı"); return; }
 
-      const toCreate: Array<{ color: string; size: string; ms: number; ds: number }> = [];
+      if (matrixColors.length === 0 || matrixSizes.length === 0) {
+        setResult("❌ En az bir renk ve bir beden seçmelisin"); return;
+      }
+
+      const stocks: Array<{ color: string; size: string; magaza: number; depo: number }> = [];
       for (const c of matrixColors) {
         for (const s of matrixSizes) {
           const cell = getMatrixCell(c, s);
           const ms = cell.magaza.trim() === "" ? 0 : Number(cell.magaza);
           const ds = cell.depo.trim() === "" ? 0 : Number(cell.depo);
-          if (ms + ds <= 0) continue;
           if (!Number.isFinite(ms) || !Number.isInteger(ms) || ms < 0) {
             setResult(`❌ ${c} / ${s}: Mağaza stok geçersiz`); return;
           }
           if (!Number.isFinite(ds) || !Number.isInteger(ds) || ds < 0) {
             setResult(`❌ ${c} / ${s}: Depo stok geçersiz`); return;
           }
-          toCreate.push({ color: c, size: s, ms, ds });
+          stocks.push({ color: c, size: s, magaza: ms, depo: ds });
         }
       }
 
-      if (toCreate.length === 0) { setResult("❌ En az bir hücreye stok girmelisin"); return; }
-
       setResult("Ekleniyor...");
 
-      let familyCode = productCode.trim()
-        ? productCode.trim().toUpperCase().replaceAll("-", "")
+      // Tüm varyantlar tek transaction'da oluşur: biri hata verirse hiçbiri eklenmez
+      const res = await invoke<CreateFamilyResultDto>("create_product_family", {
+        payload: {
+          product_code: productCode.trim() ? productCode.trim() : null,
+          category: category.trim() ? category.trim() : null,
+          name: name.trim(),
+          buy_price: bp,
+          sell_price: spNum,
+          colors: matrixColors,
+          sizes: matrixSizes,
+          magaza_baslangic: 0,
+          depo_baslangic: 0,
+          stocks,
+        },
+      });
+
+      const skipped = res.skipped.length > 0
+        ? `\n${res.skipped.length} varyant zaten vardı: ${res.skipped.map((v) => `${v.color} / ${v.size}`).join(", ")}`
         : "";
-
-      let createdCount = 0;
-      for (const item of toCreate) {
-        const res = await invoke<CreatedProductDto>("add_product", {
-          payload: {
-            barcode: null,
-            product_code: familyCode ? familyCode : null,
-            category: category.trim() ? category.trim() : null,
-            name: name.trim(),
-            color: item.color,
-            size: item.size,
-            buy_price: bp,
-            sell_price: spNum,
-            stock: item.ms + item.ds,
-            magaza_baslangic: item.ms,
-            depo_baslangic: item.ds,
-          },
-        });
-        createdCount += 1;
-        if (!familyCode) {
-          const pc = (res?.product_code ?? "").toString().trim();
-          if (pc) familyCode = pc.toUpperCase().replaceAll("-", "");
-        }
-      }
-
-      setResult(`✅ ${createdCount} ürün eklendi`);
-      setTimeout(() => nav("/products"), 300);
+      setResult(`✅ ${res.product_code}: ${res.created.length} ürün eklendi${skipped}`);
+      if (res.skipped.length === 0) setTimeout(() => nav("/products"), 300);
     } catch (e) {
-      setResult(`❌ ${String(e)}`);
+      setResult(`❌ ${errorMessage(e)}`);
     }
   };
 
@@ -911,7 +910,7 @@ export function ProductNew() {
                     </tbody>
                   </table>
                   <div style={{ marginTop: 6, fontSize: 12, opacity: 0.6 }}>
-                    M = Mağaza stok &nbsp;·&nbsp; D = Depo stok &nbsp;·&nbsp; Boş bırakılan hücreler oluşturulmaz.
+                    M = Mağaza stok &nbsp;·&nbsp; D = Depo stok &nbsp;·&nbsp; Boş bırakılan hücreler 0 stokla oluşturulur.
                   </div>
                 </div>
               )}
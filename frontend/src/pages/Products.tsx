@@ -2,6 +2,9 @@ import React, { useEffect, useMemo, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { Link } from "react-router-dom";
 import { confirm } from "@tauri-apps/plugin-dialog";
+import { listAll } from "../lib/page";
+import { errorMessage } from "../lib/errors";
+import { FamilyGrid } from "../components/FamilyGrid";
 
 type Product = {
   barcode: string;
@@ -76,14 +79,15 @@ export function Products() {
   const [showAll, setShowAll]   = useState(false);
   const [expanded, setExpanded] = useState<Set<string>>(new Set());
   const [activeCat, setActiveCat] = useState<string>("__all__");
+  const [gridCode, setGridCode] = useState<string | null>(null); // renk × beden tablosu açık aile
 
   const load = async () => {
     try {
       setErr("");
       setLoading(true);
-      setProducts(await invoke<Product[]>("list_products"));
+      setProducts(await listAll<Product>("list_products"));
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -133,7 +137,7 @@ export function Products() {
       await invoke<number>("delete_product", { barcode: barcode.trim() });
       setProducts((prev) => prev.filter((p) => p.barcode !== barcode));
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     }
   };
 
@@ -276,6 +280,17 @@ export function Products() {
                     </span>
                   </div>
 
+                  {/* Renk × beden stok tablosu */}
+                  {!isSolo && (
+                    <button
+                      type="button"
+                      onClick={(e) => { e.stopPropagation(); setGridCode(family.key); }}
+                      style={P.inlineBtn}
+                    >
+                      Tablo
+                    </button>
+                  )}
+
                   {/* Varyant ekle (family level) */}
                   {!isSolo && (
                     <Link
@@ -375,6 +390,8 @@ export function Products() {
           })}
         </div>
       )}
+
+      {gridCode && <FamilyGrid productCode={gridCode} onClose={() => setGridCode(null)} />}
     </div>
   );
 }
@@ -1,6 +1,8 @@
 import React, { useEffect, useMemo, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { confirm, message } from "@tauri-apps/plugin-dialog";
+import type { Page } from "../lib/page";
+import { errorMessage } from "../lib/errors";
 
 type Loc = "MAGAZA" | "DEPO";
 
@@ -157,11 +159,11 @@ export function ReturnExchange() {
       if (!p) { setErr("Ürün bulunamadı."); return; }
       setProduct(p);
       try {
-        const rows = await invoke<SaleLine[]>("list_sales_by_barcode", { payload: { barcode: bc.trim(), days: 9999 } });
-        setHistory([...rows].sort((a, b) => a.sold_at < b.sold_at ? 1 : -1));
+        const page = await invoke<Page<SaleLine>>("list_sales_by_barcode", { payload: { barcode: bc.trim() } });
+        setHistory([...page.items].sort((a, b) => a.sold_at < b.sold_at ? 1 : -1));
       } catch { setHistory([]); }
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -187,7 +189,7 @@ export function ReturnExchange() {
         return [...prev, { barcode: p.barcode, name: p.name, qty: 1, sold_from: "MAGAZA", unit_price: p.sell_price }];
       });
       setGiveBarcode("");
-    } catch (e) { setErr(String(e)); }
+    } catch (e) { setErr(errorMessage(e)); }
   };
 
   const completeRefund = async () => {
@@ -203,7 +205,7 @@ export function ReturnExchange() {
       }});
       await message("İade tamamlandı.", { title: "İade / Değişim" });
       await fetchProductAndHistory(product.barcode);
-    } catch (e) { setErr(String(e)); } finally { setLoading(false); }
+    } catch (e) { setErr(errorMessage(e)); } finally { setLoading(false); }
   };
 
   const completeExchange = async () => {
@@ -230,7 +232,7 @@ export function ReturnExchange() {
       await message("Değişim tamamlandı.", { title: "İade / Değişim" });
       setCart([]); setGiveBarcode("");
       await fetchProductAndHistory(product.barcode);
-    } catch (e) { setErr(String(e)); } finally { setLoading(false); }
+    } catch (e) { setErr(errorMessage(e)); } finally { setLoading(false); }
   };
 
   useEffect(() => { focusBarcode(); }, []);
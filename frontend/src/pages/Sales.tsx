@@ -2,6 +2,8 @@ import React, { useEffect, useMemo, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { confirm, message } from "@tauri-apps/plugin-dialog";
 import { useWindowWidth } from "../hooks/useWindowWidth";
+import { listAll } from "../lib/page";
+import { errorMessage } from "../lib/errors";
 
 let _allProductsCache: Product[] | null = null;
 // Arama listesi bu süreden eski ise yeniden yüklenir (milisaniye).
@@ -51,6 +53,15 @@ type CreateSaleResult = {
   lines: number;
 };
 
+// create_sale / price_cart satırları
+function saleItems(cart: CartLine[]) {
+  return cart.map((l) => ({
+    barcode: l.barcode, qty: l.qty, list_price: l.list_price,
+    discount_amount: l.discount_enabled ? l.list_price - l.unit_price : 0,
+    unit_price: l.unit_price, sold_from: l.sold_from,
+  }));
+}
+
 function fmtMoney(v: number) {
   return new Intl.NumberFormat("tr-TR", {
     style: "currency",
@@ -69,7 +80,10 @@ export function Sales() {
   const inputRef = useRef<HTMLInputElement | null>(null);
 
   const [paymentMethod, setPaymentMethod] = useState<PaymentMethod>("CARD");
+  const [undoWindow, setUndoWindow] = useState(30);
   const [cart, setCart] = useState<CartLine[]>([]);
+  // Nakitte müşteriden alınan tutar (para üstü için)
+  const [tendered, setTendered] = useState("");
 
   const [allProducts, setAllProducts] = useState<Product[]>(_allProductsCache ?? []);
   const [searchOpen, setSearchOpen] = useState(false);
@@ -79,10 +93,18 @@ export function Sales() {
 
   useEffect(() => {
     setTimeout(() => inputRef.current?.focus(), 50);
+    invoke<{ undo_window_minutes: number; default_payment_method: string }>("get_settings")
+      .then((st) => {
+        setUndoWindow(st.undo_window_minutes);
+        if (st.default_payment_method === "CARD" || st.default_payment_method === "CASH") {
+          setPaymentMethod(st.default_payment_method);
+        }
+      })
+      .catch(() => {});
     const stale = !_allProductsCache || Date.now() - _allProductsCacheAt > PRODUCT_CACHE_TTL_MS;
     if (stale) {
-      invoke<Product[]>("list_products").then((rows) => {
-        _allProductsCache = rows ?? [];
+      listAll<Product>("list_products").then((items) => {
+        _allProductsCache = items;
         _allProductsCacheAt = Date.now();
         setAllProducts(_allProductsCache);
       }).catch(() => {});
@@ -121,8 +143,31 @@ export function Sales() {
     [cart]
   );
 
+  const tenderedAmount = useMemo(() => {
+    if (paymentMethod !== "CASH" || !tendered.trim()) return null;
+    const n = Number(tendered.replace(",", "."));
+    return Number.isFinite(n) ? n : null;
+  }, [paymentMethod, tendered]);
+
+  // Müşteri ekranı: sepet backend'de fiyatlanır ve cart_updated olayıyla yayılır.
+  // Fiyatlanamazsa (ör. sepetteki ürün silindi) backend ekranı boşaltır; sebep burada gösterilir.
+  useEffect(() => {
+    const t = setTimeout(() => {
+      invoke("price_cart", {
+        payload: { items: saleItems(cart), payment_method: paymentMethod, tendered: tenderedAmount },
+      }).catch((e) => setErr(`❌ Müşteri ekranı güncellenemedi: ${errorMessage(e)}`));
+    }, 150);
+    return () => clearTimeout(t);
+  }, [cart, paymentMethod, tenderedAmount]);
+
+  // sayfadan çıkınca müşteri ekranı boşalır
+  useEffect(() => () => {
+    invoke("price_cart", { payload: { items: [] } }).catch(() => {});
+  }, []);
+
   const resetSale = () => {
     setCart([]);
+    setTendered("");
     setBarcode("");
     setErr("");
     setSearchOpen(false);
@@ -130,8 +175,8 @@ export function Sales() {
     setPaymentMethod("CARD");
     _allProductsCache = null;
     _allProductsCacheAt = 0;
-    invoke<Product[]>("list_products").then((rows) => {
-      _allProductsCache = rows ?? [];
+    listAll<Product>("list_products").then((items) => {
+      _allProductsCache = items;
       _allProductsCacheAt = Date.now();
       setAllProducts(_allProductsCache);
     }).catch(() => {});
@@ -185,7 +230,7 @@ export function Sales() {
       setBarcode("");
       setTimeout(() => inputRef.current?.focus(), 10);
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setBusy(false);
     }
@@ -271,22 +316,20 @@ export function Sales() {
       setErr("");
       setBusy(true);
       const payload = {
-        sold_from_default: "MAGAZA",
+        sold_from_default: "", // boş: ayarlardaki varsayılan lokasyon
         payment_method: paymentMethod,
-        items: cart.map((l) => ({
-          barcode: l.barcode, qty: l.qty, list_price: l.list_price,
-          discount_amount: l.discount_enabled ? l.list_price - l.unit_price : 0,
-          unit_price: l.unit_price, sold_from: l.sold_from,
-        })),
+        tendered: tenderedAmount,
+        items: saleItems(cart),
       };
       const res = await invoke<CreateSaleResult>("create_sale", { payload });
       await message(
-        `Fiş No: ${res.sale_group_id}\nÖdeme: ${paymentMethod === "CARD" ? "Kart" : "Nakit"}\nToplam: ${fmtMoney(res.total)}`,
+        `Fiş No: ${res.sale_group_id}\nÖdeme: ${paymentMethod === "CARD" ? "Kart" : "Nakit"}\nToplam: ${fmtMoney(res.total)}` +
+          (tenderedAmount !== null && tenderedAmount >= res.total ? `\nPara üstü: ${fmtMoney(tenderedAmount - res.total)}` : ""),
         { title: "Satış Kaydedildi", kind: "info" }
       );
       resetSale();
     } catch (e) {
-      const msg = String(e);
+      const msg = errorMessage(e);
       setErr(msg);
       alert("❌ create_sale HATA:\n" + msg);
     } finally {
@@ -295,9 +338,9 @@ export function Sales() {
   };
 
   const undoLastSale = async () => {
-    // Backend yalnızca son 30 dakika içindeki satışları geri alır.
+    // Backend yalnızca ayarlardaki süre (undo_window_minutes) içindeki satışları geri alır.
     const ok = await confirm(
-      "Son satışı geri almak istiyor musun?\n\nNot: Yalnızca son 30 dakika içindeki satışlar geri alınabilir. Stok iade edilir, kayıt silinmez.",
+      `Son satışı geri almak istiyor musun?\n\nNot: Yalnızca son ${undoWindow} dakika içindeki satışlar geri alınabilir. Stok iade edilir, kayıt silinmez.`,
       { title: "Son Satışı Geri Al", kind: "warning" }
     );
     if (!ok) return;
@@ -311,7 +354,7 @@ export function Sales() {
         { title: "Geri Alındı", kind: "info" }
       );
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setBusy(false);
     }
@@ -579,6 +622,29 @@ export function Sales() {
                 </div>
               </div>
 
+              {/* Cash tendered / change */}
+              {paymentMethod === "CASH" && (
+                <div style={{ marginTop: 14 }}>
+                  <div style={P.payLabel}>Alınan</div>
+                  <input
+                    inputMode="decimal"
+                    value={tendered}
+                    disabled={busy}
+                    onChange={(e) => setTendered(e.target.value)}
+                    placeholder="0,00"
+                    style={P.tenderedInput}
+                  />
+                  {tenderedAmount !== null && (
+                    <div style={P.changeRow}>
+                      <span>{tenderedAmount >= total ? "Para üstü" : "Eksik"}</span>
+                      <b style={{ color: tenderedAmount >= total ? "#065f46" : "#b91c1c" }}>
+                        {fmtMoney(Math.abs(tenderedAmount - total))}
+                      </b>
+                    </div>
+                  )}
+                </div>
+              )}
+
               {/* CTA */}
               <button
                 type="button"
@@ -959,6 +1025,24 @@ const P: Record<string, React.CSSProperties> = {
     fontWeight: 700,
     cursor: "pointer",
   },
+  tenderedInput: {
+    width: "100%",
+    boxSizing: "border-box",
+    marginTop: 8,
+    padding: "11px 12px",
+    borderRadius: 12,
+    border: "1px solid #e5e7eb",
+    fontSize: 16,
+    fontWeight: 800,
+    outline: "none",
+  },
+  changeRow: {
+    display: "flex",
+    justifyContent: "space-between",
+    marginTop: 8,
+    fontSize: 14,
+    color: "#374151",
+  },
   ctaBtn: {
     marginTop: 14,
     width: "100%",
@@ -1,6 +1,9 @@
 import { useCallback, useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-dialog";
+import { listAll, type Page } from "../lib/page";
+import { errorMessage } from "../lib/errors";
+import { LS_DISPLAY_PROMOS } from "./CustomerDisplay";
 
 
 const LS_LAST_BACKUP_PATH = "cielpos_last_backup_path";
@@ -44,11 +47,149 @@ function dirOf(p: string): string {
 }
 type RestoreFromBackupPayload = {
   backup_path: string;
+  passphrase?: string | null;
 };
 type RestoreFromBackupResult = {
   restored_db_path: string;
   used_backup_path: string;
   safety_backup_path: string;
+  backup_schema_version: number;
+  schema_version: number;
+};
+
+type DbSummary = {
+  schema_version: number;
+  product_count: number;
+  sale_count: number;
+  last_sale_at: string | null;
+  sales_total: number;
+  expense_count: number;
+  expenses_total: number;
+};
+type BackupInspection = {
+  path: string;
+  file_name: string;
+  kind: BackupKind | null;
+  compressed: boolean;
+  encrypted: boolean;
+  backup: DbSummary;
+  current: DbSummary;
+  diff: { summary: string[] };
+  restorable: boolean;
+};
+
+type BackupKind = "manual" | "auto" | "before-restore" | "before-migration" | "before-repair";
+type BackupInfo = {
+  file_name: string;
+  path: string;
+  kind: BackupKind;
+  size_bytes: number;
+  compressed: boolean;
+  encrypted: boolean;
+  timestamp: number;
+  created_at: string;
+};
+// verify_backup: yedek listede açılmaz, satırdaki "Doğrula" ile kontrol edilir
+type BackupVerification = {
+  file_name: string;
+  valid: boolean;
+  error: string | null;
+};
+type ShopSettings = {
+  undo_window_minutes: number;
+  barcode_start: number;
+  product_code_limit: number;
+  default_location: "MAGAZA" | "DEPO";
+  default_payment_method: "CARD" | "CASH" | "TRANSFER";
+  dashboard_max_daily: number;
+  dashboard_max_months: number;
+  low_stock_threshold: number;
+};
+
+type ApiSettings = {
+  enabled: boolean;
+  lan: boolean;
+  port: number;
+  token: string;
+};
+
+type ApiStatus = { running: boolean; address: string | null };
+
+type BackupPolicy = {
+  keep_daily: number;
+  keep_weekly: number;
+  keep_monthly: number;
+  interval_minutes: number;
+  encrypted: boolean;
+};
+
+type MirrorResult = {
+  target_id: number;
+  target_path: string;
+  ok: boolean;
+  file?: string | null;
+  error?: string | null;
+};
+type BackupOutcome = { path: string; mirrors: MirrorResult[] };
+type BackupTarget = {
+  id: number;
+  path: string;
+  label?: string | null;
+  is_active: number;
+  last_status?: "OK" | "ERROR" | null;
+  last_error?: string | null;
+  last_backup_at?: string | null;
+  last_file?: string | null;
+};
+
+type Webhook = {
+  id: number;
+  url: string;
+  label?: string | null;
+  is_active: number;
+  pending: number;
+  failed: number;
+  last_delivered_at?: string | null;
+};
+type WebhookDelivery = {
+  id: number;
+  event_type: string;
+  url: string;
+  status: "PENDING" | "DELIVERED" | "FAILED";
+  attempts: number;
+  next_attempt_at: string;
+  last_error?: string | null;
+  created_at: string;
+};
+
+const BACKUP_KIND_LABEL: Record<BackupKind, string> = {
+  manual: "Manuel",
+  auto: "Otomatik",
+  "before-restore": "Geri yükleme öncesi",
+  "before-migration": "Güncelleme öncesi",
+  "before-repair": "Onarım öncesi",
+};
+
+function formatBytes(n: number): string {
+  if (n < 1024) return `${n} B`;
+  if (n < 1024 * 1024) return `${(n / 1024).toFixed(1)} KB`;
+  return `${(n / 1024 / 1024).toFixed(1)} MB`;
+}
+
+type IntegrityIssue = {
+  check: string;
+  severity: "ERROR" | "WARNING";
+  message: string;
+  count: number;
+  samples: string[];
+  repairable: boolean;
+  repaired: number;
+};
+type IntegrityReport = {
+  ok: boolean;
+  repair: boolean;
+  checked_at: string;
+  issues: IntegrityIssue[];
 };
 
 const styles = {
@@ -163,6 +304,32 @@ export default function Settings() {
   const [backupFile, setBackupFile] = useState<string>("");
   const [restoring, setRestoring] = useState(false);
   const [restoreMsg, setRestoreMsg] = useState("");
+  const [backups, setBackups] = useState<BackupInfo[]>([]);
+  const [verified, setVerified] = useState<Record<string, BackupVerification | "busy">>({});
+  const [policy, setPolicy] = useState<BackupPolicy | null>(null);
+  const [policyMsg, setPolicyMsg] = useState("");
+  const [shop, setShop] = useState<ShopSettings | null>(null);
+  const [shopMsg, setShopMsg] = useState("");
+  const [api, setApi] = useState<ApiSettings | null>(null);
+  const [apiStatus, setApiStatus] = useState<ApiStatus | null>(null);
+  const [apiMsg, setApiMsg] = useState("");
+  const [newPassphrase, setNewPassphrase] = useState("");
+  const [targets, setTargets] = useState<BackupTarget[]>([]);
+  const [targetErr, setTargetErr] = useState("");
+  const [hooks, setHooks] = useState<Webhook[]>([]);
+  const [deliveries, setDeliveries] = useState<WebhookDelivery[]>([]);
+  const [newHookUrl, setNewHookUrl] = useState("");
+  const [hookMsg, setHookMsg] = useState("");
+  const [promos, setPromos] = useState(() => localStorage.getItem(LS_DISPLAY_PROMOS) ?? "");
+  const [displayMsg, setDisplayMsg] = useState("");
+  const [restorePassphrase, setRestorePassphrase] = useState("");
+  const [inspection, setInspection] = useState<BackupInspection | null>(null);
+  const [inspecting, setInspecting] = useState(false);
+  const [integrity, setIntegrity] = useState<IntegrityReport | null>(null);
+  const [integrityBusy, setIntegrityBusy] = useState(false);
+  const [integrityErr, setIntegrityErr] = useState("");
+  const [language, setLanguage] = useState<"tr" | "en">("tr");
+  const [languageErr, setLanguageErr] = useState("");
 
   // --- Sözlükler (Kategori / Renk / Beden)
   const [cats, setCats] = useState<string[]>([]);
@@ -187,6 +354,15 @@ export default function Settings() {
   const [pwNew2, setPwNew2] = useState("");
   const [pwMsg, setPwMsg] = useState<{ text: string; ok: boolean } | null>(null);
 
+  const changeLanguage = async (lang: "tr" | "en") => {
+    try {
+      setLanguageErr("");
+      setLanguage(await invoke<"tr" | "en">("set_language", { lang }));
+    } catch (e) {
+      setLanguageErr(errorMessage(e));
+    }
+  };
+
   const doChangePassword = () => {
     const current = localStorage.getItem(LS_PASSWORD) ?? DEFAULT_PASSWORD;
     if (pwCurrent !== current) {
@@ -215,25 +391,221 @@ export default function Settings() {
         setErr("");
         const p = await invoke<string>("get_backup_dir");
         setBackupDir(p);
+        setPolicy(await invoke<BackupPolicy>("get_backup_policy"));
+        setShop(await invoke<ShopSettings>("get_settings"));
+        setApi(await invoke<ApiSettings>("get_api_settings"));
+        setApiStatus(await invoke<ApiStatus>("get_api_status"));
       } catch (e) {
-        setErr(String(e));
+        setErr(errorMessage(e));
       }
     })();
+    invoke<"tr" | "en">("get_language")
+      .then(setLanguage)
+      .catch((e) => setLanguageErr(errorMessage(e)));
+    loadBackups();
+    loadHooks();
   }, []);
 
+  async function loadBackups() {
+    try {
+      // yedekler en yeni başta, ilk sayfa yeter; hedefler hepsi
+      setBackups((await invoke<Page<BackupInfo>>("list_backups")).items);
+      setTargets(await listAll<BackupTarget>("list_backup_targets"));
+    } catch (e) {
+      setErr(errorMessage(e));
+    }
+  }
+
+  async function verifyBackup(b: BackupInfo) {
+    setVerified((v) => ({ ...v, [b.file_name]: "busy" }));
+    try {
+      const payload = { backup_path: b.path, passphrase: restorePassphrase.trim() || null };
+      const r = await invoke<BackupVerification>("verify_backup", { payload });
+      setVerified((v) => ({ ...v, [b.file_name]: r }));
+    } catch (e) {
+      setVerified((v) => ({ ...v, [b.file_name]: { file_name: b.file_name, valid: false, error: errorMessage(e) } }));
+    }
+  }
+
+  async function addTarget() {
+    setTargetErr("");
+    const dir = await open({ title: "Yedek hedef klasörü", directory: true, multiple: false });
+    if (typeof dir !== "string") return;
+    try {
+      await invoke<number>("add_backup_target", { path: dir, label: null });
+      await loadBackups();
+    } catch (e) {
+      setTargetErr(errorMessage(e));
+    }
+  }
+
+  async function loadHooks() {
+    try {
+      setHooks(await listAll<Webhook>("list_webhooks"));
+      setDeliveries(await invoke<WebhookDelivery[]>("list_webhook_deliveries", { limit: 20 }));
+    } catch (e) {
+      setHookMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function addHook() {
+    setHookMsg("");
+    try {
+      await invoke<number>("add_webhook", { url: newHookUrl, label: null });
+      setNewHookUrl("");
+      await loadHooks();
+    } catch (e) {
+      setHookMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function toggleHook(h: Webhook) {
+    setHookMsg("");
+    try {
+      await invoke<number>("update_webhook", { id: h.id, label: null, is_active: h.is_active ? 0 : 1 });
+      await loadHooks();
+    } catch (e) {
+      setHookMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function removeHook(h: Webhook) {
+    if (!confirm(`${h.url}\nadresi kaldırılsın mı? (bekleyen gönderimler de silinir)`)) return;
+    setHookMsg("");
+    try {
+      await invoke<number>("delete_webhook", { id: h.id });
+      await loadHooks();
+    } catch (e) {
+      setHookMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function openDisplay() {
+    setDisplayMsg("");
+    try {
+      const secondary = await invoke<boolean>("open_customer_display");
+      setDisplayMsg(secondary ? "✅ İkinci ekranda açıldı" : "✅ Açıldı (ikinci ekran bulunamadı; pencereyi taşıyabilirsin)");
+    } catch (e) {
+      setDisplayMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function closeDisplay() {
+    setDisplayMsg("");
+    try {
+      await invoke("close_customer_display");
+    } catch (e) {
+      setDisplayMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  function savePromos() {
+    localStorage.setItem(LS_DISPLAY_PROMOS, promos);
+    setDisplayMsg("✅ Kampanya metinleri kaydedildi");
+  }
+
+  async function retryHooks() {
+    setHookMsg("");
+    try {
+      const r = await invoke<{ delivered: number; retrying: number; failed: number; deferred: number }>("retry_webhook_deliveries");
+      setHookMsg(`✅ Gönderildi: ${r.delivered} · yeniden denenecek: ${r.retrying + r.failed + r.deferred}`);
+      await loadHooks();
+    } catch (e) {
+      setHookMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function toggleTarget(t: BackupTarget) {
+    setTargetErr("");
+    try {
+      await invoke<number>("update_backup_target", { id: t.id, label: null, is_active: t.is_active ? 0 : 1 });
+      await loadBackups();
+    } catch (e) {
+      setTargetErr(errorMessage(e));
+    }
+  }
+
+  async function removeTarget(t: BackupTarget) {
+    if (!confirm(`${t.path}\nhedefi kaldırılsın mı? (klasördeki yedekler silinmez)`)) return;
+    setTargetErr("");
+    try {
+      await invoke<number>("delete_backup_target", { id: t.id });
+      await loadBackups();
+    } catch (e) {
+      setTargetErr(errorMessage(e));
+    }
+  }
+
+  async function savePassphrase(clear: boolean) {
+    setPolicyMsg("");
+    try {
+      setPolicy(await invoke<BackupPolicy>("set_backup_passphrase", { passphrase: clear ? null : newPassphrase }));
+      setNewPassphrase("");
+      setPolicyMsg(clear ? "✅ Şifreleme kapatıldı" : "✅ Yeni yedekler şifrelenecek");
+    } catch (e) {
+      setPolicyMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function saveShop() {
+    if (!shop) return;
+    setShopMsg("");
+    try {
+      setShop(await invoke<ShopSettings>("update_settings", { payload: shop }));
+      setShopMsg("✅ Kaydedildi");
+    } catch (e) {
+      setShopMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function saveApi() {
+    if (!api) return;
+    setApiMsg("");
+    try {
+      setApiStatus(await invoke<ApiStatus>("update_api_settings", { payload: api }));
+      setApiMsg("✅ Kaydedildi");
+    } catch (e) {
+      setApiMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function regenerateApiToken() {
+    if (!confirm("Yeni anahtar üretilsin mi? Eski anahtarı kullanan araçlar erişemez.")) return;
+    setApiMsg("");
+    try {
+      setApi(await invoke<ApiSettings>("regenerate_api_token"));
+      setApiStatus(await invoke<ApiStatus>("get_api_status"));
+      setApiMsg("✅ Yeni anahtar üretildi");
+    } catch (e) {
+      setApiMsg("❌ " + errorMessage(e));
+    }
+  }
+
+  async function savePolicy() {
+    if (!policy) return;
+    setPolicyMsg("");
+    try {
+      setPolicy(await invoke<BackupPolicy>("update_backup_policy", { payload: policy }));
+      setPolicyMsg("✅ Kaydedildi");
+      await loadBackups();
+    } catch (e) {
+      setPolicyMsg("❌ " + errorMessage(e));
+    }
+  }
+
   const loadDictionaries = useCallback(async () => {
     try {
       setDictErr("");
       const [c1, c2, c3] = await Promise.all([
-        invoke<DictItemDto[]>("list_categories", { include_inactive: true }),
-        invoke<DictItemDto[]>("list_colors", { include_inactive: true }),
-        invoke<DictItemDto[]>("list_sizes", { include_inactive: true }),
+        listAll<DictItemDto>("list_categories", {}, { include_inactive: true }),
+        listAll<DictItemDto>("list_colors", {}, { include_inactive: true }),
+        listAll<DictItemDto>("list_sizes", {}, { include_inactive: true }),
       ]);
       setCats(toActiveNames(c1));
       setColors(toActiveNames(c2));
       setSizes(toActiveNames(c3));
     } catch (e) {
-      setDictErr(String(e));
+      setDictErr(errorMessage(e));
     }
   }, []);
 
@@ -250,7 +622,7 @@ export default function Settings() {
       setNewCat("");
       await loadDictionaries();
     } catch (e) {
-      alert("❌ Kategori eklenemedi: " + String(e));
+      alert("❌ Kategori eklenemedi: " + errorMessage(e));
     } finally {
       setDictBusy(false);
     }
@@ -265,7 +637,7 @@ export default function Settings() {
       setNewColor("");
       await loadDictionaries();
     } catch (e) {
-      alert("❌ Renk eklenemedi: " + String(e));
+      alert("❌ Renk eklenemedi: " + errorMessage(e));
     } finally {
       setDictBusy(false);
     }
@@ -280,7 +652,7 @@ export default function Settings() {
       setNewSize("");
       await loadDictionaries();
     } catch (e) {
-      alert("❌ Beden eklenemedi: " + String(e));
+      alert("❌ Beden eklenemedi: " + errorMessage(e));
     } finally {
       setDictBusy(false);
     }
@@ -289,7 +661,7 @@ export default function Settings() {
   const handleBackup = async () => {
     try {
       setBusy(true);
-      const path = await invoke<string>("backup_now");
+      const { path, mirrors } = await invoke<BackupOutcome>("backup_now");
 
       const at = nowLocalLabel();
       localStorage.setItem(LS_LAST_BACKUP_PATH, path);
@@ -298,9 +670,11 @@ export default function Settings() {
       setLastPath(path);
       setLastAt(at);
 
-      alert("✅ Yedek alındı:\n" + path);
+      const mirrorLines = mirrors.map((m) => (m.ok ? "✅ " : "❌ ") + m.target_path + (m.error ? " — " + m.error : ""));
+      alert("✅ Yedek alındı:\n" + path + (mirrorLines.length ? "\n\nEk hedefler:\n" + mirrorLines.join("\n") : ""));
+      await loadBackups();
     } catch (e) {
-      alert("❌ Yedek alınamadı: " + String(e));
+      alert("❌ Yedek alınamadı: " + errorMessage(e));
     } finally {
       setBusy(false);
     }
@@ -309,7 +683,7 @@ export default function Settings() {
     try {
       await invoke("open_backup_folder");
     } catch (e) {
-      alert("❌ Backup klasörü açılamadı: " + String(e));
+      alert("❌ Backup klasörü açılamadı: " + errorMessage(e));
     }
   };
 
@@ -326,17 +700,17 @@ export default function Settings() {
     setEditLoading(true);
     try {
       if (kind === "CATEGORY") {
-        const rows = await invoke<DictItemDto[]>("list_categories", { include_inactive: true });
+        const rows = await listAll<DictItemDto>("list_categories", {}, { include_inactive: true });
         setEditRows(rows);
       } else if (kind === "COLOR") {
-        const rows = await invoke<DictItemDto[]>("list_colors", { include_inactive: true });
+        const rows = await listAll<DictItemDto>("list_colors", {}, { include_inactive: true });
         setEditRows(rows);
       } else {
-        const rows = await invoke<DictItemDto[]>("list_sizes", { include_inactive: true });
+        const rows = await listAll<DictItemDto>("list_sizes", {}, { include_inactive: true });
         setEditRows(rows);
       }
     } catch (e) {
-      setEditErr(String(e));
+      setEditErr(errorMessage(e));
     } finally {
       setEditLoading(false);
     }
@@ -356,7 +730,7 @@ export default function Settings() {
 
       await refreshAfterEdit();
     } catch (e) {
-      setEditErr(String(e));
+      setEditErr(errorMessage(e));
     }
   };
 
@@ -372,7 +746,7 @@ export default function Settings() {
 
       await refreshAfterEdit();
     } catch (e) {
-      setEditErr(String(e));
+      setEditErr(errorMessage(e));
     }
   };
 
@@ -385,7 +759,7 @@ export default function Settings() {
 
       await refreshAfterEdit();
     } catch (e) {
-      setEditErr(String(e));
+      setEditErr(errorMessage(e));
     }
   };
 
@@ -395,14 +769,33 @@ export default function Settings() {
     setRestoreMsg("");
     const preferredDir = dirOf(lastPath) || backupDir || "";
     const f = await open({
-      title: "Yedek seç (.sqlite)",
+      title: "Yedek seç (.cpbak / .sqlite)",
       multiple: false,
       defaultPath: preferredDir || undefined,
-      filters: [{ name: "SQLite", extensions: ["sqlite", "db"] }],
+      filters: [{ name: "Yedek", extensions: ["cpbak", "sqlite", "db"] }],
     });
 
     if (typeof f === "string") {
       setBackupFile(f);
+      setInspection(null);
+    }
+  }
+
+  async function doInspect() {
+    if (!canRestore) return;
+    setInspecting(true);
+    setRestoreMsg("");
+    try {
+      const payload: RestoreFromBackupPayload = {
+        backup_path: backupFile,
+        passphrase: restorePassphrase.trim() || null,
+      };
+      setInspection(await invoke<BackupInspection>("inspect_backup", { payload }));
+    } catch (e) {
+      setInspection(null);
+      setRestoreMsg("❌ Hata: " + errorMessage(e));
+    } finally {
+      setInspecting(false);
     }
   }
 
@@ -414,9 +807,12 @@ export default function Settings() {
     try {
       const payload: RestoreFromBackupPayload = {
         backup_path: backupFile,
+        passphrase: restorePassphrase.trim() || null,
       };
 
       const res = await invoke<RestoreFromBackupResult>("restore_from_backup", { payload });
+      setInspection(null);
+      await loadBackups();
 
       setRestoreMsg(
         "✅ Geri yüklendi.\n" +
@@ -428,16 +824,33 @@ export default function Settings() {
           "\n" +
           "Güvenlik yedeği: " +
           res.safety_backup_path +
-          "\n\n" +
-          "⚠️ Not: Uygulamayı kapatıp açman gerekebilir."
+          (res.backup_schema_version < res.schema_version
+            ? "\nŞema güncellendi: v" + res.backup_schema_version + " → v" + res.schema_version
+            : "")
       );
     } catch (e) {
-      setRestoreMsg("❌ Hata: " + String(e));
+      setRestoreMsg("❌ Hata: " + errorMessage(e));
     } finally {
       setRestoring(false);
     }
   }
 
+  async function runIntegrity(repair: boolean) {
+    if (repair && !confirm("Onarılabilir sorunlar düzeltilecek (öncesinde yedek alınır). Devam edilsin mi?")) return;
+    setIntegrityBusy(true);
+    setIntegrityErr("");
+    try {
+      setIntegrity(await invoke<IntegrityReport>("check_integrity", { repair }));
+    } catch (e) {
+      setIntegrityErr(errorMessage(e));
+    } finally {
+      setIntegrityBusy(false);
+    }
+  }
+
+  const integrityFound = (integrity?.issues || []).filter((i) => i.count > 0);
+  const canRepair = !integrity?.repair && integrityFound.some((i) => i.repairable && i.repaired < i.count);
+
   return (
     <div style={styles.page}>
       <div style={styles.headerRow}>
@@ -445,11 +858,163 @@ export default function Settings() {
         <div style={styles.brand}>CIEL POS</div>
       </div>
 
+      {/* Mağaza Ayarları */}
+<div style={{ marginTop: 14, ...styles.card }}>
+  <h4 style={styles.cardTitle}>🏪 Mağaza Ayarları</h4>
+  <div style={styles.subText}>
+    Satış geri alma süresi, barkod / ürün kodu serisi, varsayılan lokasyon ve ödeme yöntemi, dashboard grafik sınırları,
+    düşük stok uyarısı eşiği (toplam stok bu değere inince bildirim).
+  </div>
+
+  {shop && (
+    <div style={{ marginTop: 10, ...styles.row }}>
+      {([
+        ["undo_window_minutes", "Geri alma (dk)"],
+        ["barcode_start", "Barkod başlangıcı"],
+        ["product_code_limit", "Ürün kodu limiti"],
+        ["dashboard_max_daily", "Günlük seri (gün)"],
+        ["dashboard_max_months", "Aylık seri (ay)"],
+        ["low_stock_threshold", "Düşük stok uyarısı (adet)"],
+      ] as [keyof ShopSettings, string][]).map(([k, label]) => (
+        <label key={k} style={{ fontSize: 12, display: "grid", gap: 4 }}>
+          {label}
+          <input
+            type="number"
+            min={1}
+            value={shop[k]}
+            onChange={(e) => setShop({ ...shop, [k]: Number(e.target.value) || 0 })}
+            style={{ ...styles.input, width: 130 }}
+          />
+        </label>
+      ))}
+      <label style={{ fontSize: 12, display: "grid", gap: 4 }}>
+        Varsayılan lokasyon
+        <select
+          value={shop.default_location}
+          onChange={(e) => setShop({ ...shop, default_location: e.target.value as ShopSettings["default_location"] })}
+          style={{ ...styles.input, width: 130 }}
+        >
+          <option value="MAGAZA">Mağaza</option>
+          <option value="DEPO">Depo</option>
+        </select>
+      </label>
+      <label style={{ fontSize: 12, display: "grid", gap: 4 }}>
+        Varsayılan ödeme
+        <select
+          value={shop.default_payment_method}
+          onChange={(e) =>
+            setShop({ ...shop, default_payment_method: e.target.value as ShopSettings["default_payment_method"] })
+          }
+          style={{ ...styles.input, width: 130 }}
+        >
+          <option value="CARD">Kart</option>
+          <option value="CASH">Nakit</option>
+          <option value="TRANSFER">Havale / EFT</option>
+        </select>
+      </label>
+      <button onClick={saveShop} style={styles.btn}>Kaydet</button>
+    </div>
+  )}
+  {shopMsg && <div style={{ marginTop: 8, fontSize: 13 }}>{shopMsg}</div>}
+</div>
+
+      {/* Müşteri Ekranı */}
+<div style={{ marginTop: 14, ...styles.card }}>
+  <h4 style={styles.cardTitle}>🖥️ Müşteri Ekranı</h4>
+  <div style={styles.subText}>
+    Müşteriye dönük monitörde sepeti, indirimleri, toplamı ve para üstünü gösterir; satış sonrası teşekkür,
+    boştayken aşağıdaki kampanya metinlerini (satır başına bir tane) döndürür.
+  </div>
+  <div style={{ marginTop: 10, ...styles.row }}>
+    <button onClick={openDisplay} style={styles.btn}>Müşteri Ekranını Aç</button>
+    <button onClick={closeDisplay} style={styles.btn}>Kapat</button>
+  </div>
+  <textarea
+    value={promos}
+    onChange={(e) => setPromos(e.target.value)}
+    placeholder={"Yeni sezon ürünlerinde %20 indirim\n2 alana 3. ürün hediye"}
+    rows={3}
+    style={{ ...styles.input, marginTop: 10, width: "100%", boxSizing: "border-box", fontFamily: "inherit" }}
+  />
+  <div style={{ marginTop: 8, ...styles.row }}>
+    <button onClick={savePromos} style={styles.btn}>Kaydet</button>
+  </div>
+  {displayMsg && <div style={{ marginTop: 8, fontSize: 13 }}>{displayMsg}</div>}
+</div>
+
+      {/* Yerel API */}
+<div style={{ marginTop: 14, ...styles.card }}>
+  <h4 style={styles.cardTitle}>🔌 Yerel API</h4>
+  <div style={styles.subText}>
+    Aynı ağdaki araçlar (fiyat kiosku, e-ticaret senkronu) ürün, stok ve raporları okuyabilir; satış ve transfer
+    kaydedebilir. Her istek <code>Authorization: Bearer &lt;anahtar&gt;</code> başlığı taşımalı. API'den gelen
+    satışlar kasada "son satışı geri al" ile geri alınamaz.
+  </div>
+
+  {api && (
+    <div style={{ marginTop: 10, ...styles.row }}>
+      <label style={{ fontSize: 12, display: "flex", gap: 6, alignItems: "center" }}>
+        <input type="checkbox" checked={api.enabled} onChange={(e) => setApi({ ...api, enabled: e.target.checked })} />
+        Açık
+      </label>
+      <label style={{ fontSize: 12, display: "flex", gap: 6, alignItems: "center" }}>
+        <input type="checkbox" checked={api.lan} onChange={(e) => setApi({ ...api, lan: e.target.checked })} />
+        Yerel ağdan erişilsin (kapalıysa yalnız bu bilgisayar)
+      </label>
+      <label style={{ fontSize: 12, display: "grid", gap: 4 }}>
+        Port
+        <input
+          type="number"
+          min={1024}
+          max={65535}
+          value={api.port}
+          onChange={(e) => setApi({ ...api, port: Number(e.target.value) || 0 })}
+          style={{ ...styles.input, width: 110 }}
+        />
+      </label>
+      <button onClick={saveApi} style={styles.btn}>Kaydet</button>
+    </div>
+  )}
+  {api?.lan && (
+    <div style={{ marginTop: 10, fontSize: 13, color: "#92400e", background: "#fffbeb", border: "1px solid #fde68a", borderRadius: 10, padding: "8px 10px" }}>
+      ⚠️ Yerel ağ modunda bağlantı şifresiz HTTP'dir: anahtar ve satış verisi ağdaki herkes tarafından
+      okunabilir. Yalnız güvendiğin, parolalı bir ağda aç; misafir / ortak Wi-Fi'da kapalı tut.
+    </div>
+  )}
+  {api && (
+    <div style={{ marginTop: 10, ...styles.row }}>
+      <input readOnly value={api.token} style={{ ...styles.input, width: 420, fontFamily: "monospace" }} />
+      <button onClick={regenerateApiToken} style={styles.btn}>Yeni anahtar</button>
+    </div>
+  )}
+  {apiStatus && (
+    <div style={{ marginTop: 8, fontSize: 13 }}>
+      {apiStatus.running ? `🟢 Çalışıyor: http://${apiStatus.address}/api/health` : "⚪ Kapalı"}
+    </div>
+  )}
+  {apiMsg && <div style={{ marginTop: 8, fontSize: 13 }}>{apiMsg}</div>}
+</div>
+
+      {/* Dil */}
+<div style={{ marginTop: 14, ...styles.card }}>
+  <h4 style={styles.cardTitle}>🌐 Dil / Language</h4>
+  <div style={styles.subText}>
+    Sunucu tarafı mesajları (hata, yedek ve bütünlük raporu) bu dilde gelir. Backend messages (errors, backups, integrity report) use this language.
+  </div>
+  <div style={{ marginTop: 10, ...styles.row }}>
+    <select value={language} onChange={(e) => changeLanguage(e.target.value as "tr" | "en")} style={{ ...styles.input, width: 180 }}>
+      <option value="tr">Türkçe</option>
+      <option value="en">English</option>
+    </select>
+  </div>
+  {languageErr && <div style={{ marginTop: 10, fontSize: 13, color: "#b91c1c" }}>❌ {languageErr}</div>}
+</div>
+
       {/* Yedekleme */}
 <div style={{ marginTop: 14, ...styles.card }}>
   <h4 style={styles.cardTitle}>💾 Yedekleme</h4>
   <div style={styles.subText}>
-    • Uygulama kapanırken otomatik yedek alır. <br />
+    • Uygulama kapanırken ve açıkken belirli aralıklarla otomatik yedek alır. <br />
     • Ekstra güvenlik için gün sonunda manuel yedek alabilirsin.
   </div>
 
@@ -489,6 +1054,174 @@ export default function Settings() {
   </div>
 </div>
 
+{/* Yedek Listesi + Saklama */}
+<div style={{ marginTop: 14, ...styles.card }}>
+  <h4 style={styles.cardTitle}>🗂️ Yedekler</h4>
+  <div style={styles.subText}>
+    Otomatik yedeklerden her gün / hafta / ay için en yenisi saklanır, gerisi silinir. Manuel ve güvenlik yedeklerine dokunulmaz.
+  </div>
+
+  {policy && (
+    <div style={{ marginTop: 10, ...styles.row }}>
+      {([
+        ["keep_daily", "Gün"],
+        ["keep_weekly", "Hafta"],
+        ["keep_monthly", "Ay"],
+        ["interval_minutes", "Aralık (dk, 0 = kapalı)"],
+      ] as [keyof BackupPolicy, string][]).map(([k, label]) => (
+        <label key={k} style={{ fontSize: 12, display: "grid", gap: 4 }}>
+          {label}
+          <input
+            type="number"
+            min={0}
+            value={policy[k]}
+            onChange={(e) => setPolicy({ ...policy, [k]: Number(e.target.value) || 0 })}
+            style={{ ...styles.input, width: 110 }}
+          />
+        </label>
+      ))}
+      <button onClick={savePolicy} style={styles.btn}>Kaydet</button>
+    </div>
+  )}
+
+  {policy && (
+    <div style={{ marginTop: 10, ...styles.row }}>
+      <span style={{ fontSize: 13 }}>{policy.encrypted ? "🔐 Yedekler şifreli" : "🔓 Yedekler şifresiz"}</span>
+      <input
+        type="password"
+        placeholder="Yedek parolası (en az 8 karakter)"
+        value={newPassphrase}
+        onChange={(e) => setNewPassphrase(e.target.value)}
+        style={{ ...styles.input, width: 240 }}
+      />
+      <button onClick={() => savePassphrase(false)} disabled={!newPassphrase} style={styles.btn}>
+        Parolayı Kaydet
+      </button>
+      {policy.encrypted && (
+        <button onClick={() => savePassphrase(true)} style={styles.btn}>Şifrelemeyi Kapat</button>
+      )}
+    </div>
+  )}
+  {policyMsg && <div style={{ marginTop: 8, fontSize: 13 }}>{policyMsg}</div>}
+
+  <div style={styles.divider} />
+
+  <div style={{ display: "grid", gap: 6, maxHeight: 260, overflowY: "auto", fontSize: 13 }}>
+    {backups.map((b) => {
+      const v = verified[b.file_name];
+      return (
+      <div key={b.file_name} style={{ display: "flex", gap: 10, alignItems: "center" }}>
+        {v === undefined ? (
+          <button onClick={() => verifyBackup(b)} style={{ ...styles.btn, padding: "2px 8px", fontSize: 12 }}>
+            Doğrula
+          </button>
+        ) : (
+          <span title={v === "busy" ? "Doğrulanıyor…" : v.error || "Yedek sağlam"}>
+            {v === "busy" ? "⏳" : v.valid ? "✅" : "⚠️"}
+          </span>
+        )}
+        <span title={b.encrypted ? "Şifreli" : b.compressed ? "Sıkıştırılmış" : "Eski düz kopya"}>
+          {b.encrypted ? "🔐" : b.compressed ? "🗜️" : "📄"}
+        </span>
+        <span style={{ fontWeight: 700, minWidth: 150 }}>{b.created_at}</span>
+        <span style={{ minWidth: 140 }}>{BACKUP_KIND_LABEL[b.kind]}</span>
+        <span style={{ minWidth: 80, opacity: 0.75 }}>{formatBytes(b.size_bytes)}</span>
+        <span
+          style={{ fontSize: 12, opacity: 0.6, wordBreak: "break-all", cursor: "pointer" }}
+          title="Geri yükleme için seç"
+          onClick={() => setBackupFile(b.path)}
+        >
+          {b.file_name}
+        </span>
+      </div>
+      );
+    })}
+    {!backups.length && <div style={{ opacity: 0.7 }}>Henüz yedek yok.</div>}
+  </div>
+</div>
+
+{/* Ek Yedek Hedefleri */}
+<div style={{ marginTop: 14, ...styles.card }}>
+  <h4 style={styles.cardTitle}>🔁 Ek Yedek Hedefleri</h4>
+  <div style={styles.subText}>
+    Her yedek ayrıca bu klasörlere de kopyalanır (USB bellek, ağ paylaşımı vb.).
+  </div>
+
+  <div style={{ marginTop: 10, display: "grid", gap: 8, fontSize: 13 }}>
+    {targets.map((t) => (
+      <div key={t.id} style={{ display: "flex", gap: 10, alignItems: "center", flexWrap: "wrap" }}>
+        <span>{!t.is_active ? "⏸️" : t.last_status === "ERROR" ? "❌" : t.last_status === "OK" ? "✅" : "•"}</span>
+        <span style={{ fontWeight: 700, wordBreak: "break-all" }}>{t.label ? `${t.label} — ` : ""}{t.path}</span>
+        <span style={{ fontSize: 12, opacity: 0.7 }}>
+          {t.last_backup_at ? `son: ${t.last_backup_at}` : "henüz kopyalanmadı"}
+          {t.last_error ? ` · ${t.last_error}` : ""}
+        </span>
+        <button onClick={() => toggleTarget(t)} style={styles.btnSmall}>{t.is_active ? "Durdur" : "Etkinleştir"}</button>
+        <button onClick={() => removeTarget(t)} style={styles.btnSmall}>Kaldır</button>
+      </div>
+    ))}
+    {!targets.length && <div style={{ opacity: 0.7 }}>Ek hedef yok.</div>}
+  </div>
+
+  <div style={{ marginTop: 10, ...styles.row }}>
+    <button onClick={addTarget} style={styles.btn}>+ Klasör Ekle</button>
+    {targetErr && <div style={{ fontSize: 13, color: "crimson" }}>❌ {targetErr}</div>}
+  </div>
+</div>
+
+{/* Webhook */}
+<div style={{ marginTop: 14, ...styles.card }}>
+  <h4 style={styles.cardTitle}>📡 Webhook</h4>
+  <div style={styles.subText}>
+    Satış, iade, değişim ve transferler kaydedildiğinde, geri alındığında ve stok elle düzeltildiğinde bu adreslere
+    JSON olarak gönderilir (<code>sale.created</code>, <code>sale.undone</code>, <code>return.created</code>,{" "}
+    <code>exchange.created</code>, <code>transfer.created</code>, <code>transfer.undone</code>, <code>stock.updated</code>).
+    Ulaşılamayan adres artan aralıklarla yeniden denenir; diğer adreslere gönderimi bekletmez.
+  </div>
+
+  <div style={{ marginTop: 10, display: "grid", gap: 8, fontSize: 13 }}>
+    {hooks.map((h) => (
+      <div key={h.id} style={{ display: "flex", gap: 10, alignItems: "center", flexWrap: "wrap" }}>
+        <span>{!h.is_active ? "⏸️" : h.failed ? "❌" : h.pending ? "⏳" : "✅"}</span>
+        <span style={{ fontWeight: 700, wordBreak: "break-all" }}>{h.label ? `${h.label} — ` : ""}{h.url}</span>
+        <span style={{ fontSize: 12, opacity: 0.7 }}>
+          {h.last_delivered_at ? `son: ${h.last_delivered_at}` : "henüz gönderilmedi"}
+          {h.pending ? ` · bekleyen: ${h.pending}` : ""}
+          {h.failed ? ` · başarısız: ${h.failed}` : ""}
+        </span>
+        <button onClick={() => toggleHook(h)} style={styles.btnSmall}>{h.is_active ? "Durdur" : "Etkinleştir"}</button>
+        <button onClick={() => removeHook(h)} style={styles.btnSmall}>Kaldır</button>
+      </div>
+    ))}
+    {!hooks.length && <div style={{ opacity: 0.7 }}>Kayıtlı adres yok.</div>}
+  </div>
+
+  <div style={{ marginTop: 10, ...styles.row }}>
+    <input
+      value={newHookUrl}
+      onChange={(e) => setNewHookUrl(e.target.value)}
+      placeholder="https://ornek.com/pos-webhook"
+      style={{ ...styles.input, width: 320 }}
+    />
+    <button onClick={addHook} style={styles.btn}>+ Adres Ekle</button>
+    <button onClick={retryHooks} style={styles.btn}>Şimdi Yeniden Dene</button>
+  </div>
+  {hookMsg && <div style={{ marginTop: 8, fontSize: 13 }}>{hookMsg}</div>}
+
+  {deliveries.length > 0 && (
+    <div style={{ marginTop: 10, display: "grid", gap: 4, fontSize: 12 }}>
+      {deliveries.map((d) => (
+        <div key={d.id} style={{ opacity: d.status === "DELIVERED" ? 0.7 : 1 }}>
+          {d.status === "DELIVERED" ? "✅" : d.status === "FAILED" ? "❌" : "⏳"} {d.created_at} · {d.event_type} →{" "}
+          <span style={{ wordBreak: "break-all" }}>{d.url}</span>
+          {d.status !== "DELIVERED" && ` · deneme: ${d.attempts} · sonraki: ${d.next_attempt_at}`}
+          {d.last_error ? ` · ${d.last_error}` : ""}
+        </div>
+      ))}
+    </div>
+  )}
+</div>
+
 {/* Yedek Konumu */}
 <div style={{ marginTop: 14, ...styles.card }}>
   <h4 style={styles.cardTitle}>📍 Yedek Konumu</h4>
@@ -692,18 +1425,108 @@ export default function Settings() {
           <div style={{ fontSize: 12, opacity: 0.85, wordBreak: "break-all" }}>{backupFile ? backupFile : "Henüz seçilmedi"}</div>
         </div>
 
+        <div style={{ marginTop: 12, ...styles.row }}>
+          <input
+            type="password"
+            placeholder="Parola (şifreli yedekler için, boşsa kayıtlı parola)"
+            value={restorePassphrase}
+            onChange={(e) => setRestorePassphrase(e.target.value)}
+            style={{ ...styles.input, width: 360 }}
+          />
+        </div>
+
         <div style={{ marginTop: 12, fontSize: 13, opacity: 0.75 }}>
-          Seçilen yedek dosyasındaki tüm veriler mevcut veritabanının üzerine yazılır.
+          Seçilen yedek dosyasındaki tüm veriler mevcut veritabanının üzerine yazılır. Sağlaması tutmayan yedek geri yüklenmez.
         </div>
 
+        {inspection && (
+          <div style={{ marginTop: 12, fontSize: 13 }}>
+            <table style={{ borderCollapse: "collapse" }}>
+              <thead>
+                <tr>
+                  <th style={{ textAlign: "left", paddingRight: 16 }}></th>
+                  <th style={{ textAlign: "right", paddingRight: 16 }}>Yedek</th>
+                  <th style={{ textAlign: "right" }}>Mevcut</th>
+                </tr>
+              </thead>
+              <tbody>
+                {(
+                  [
+                    ["Şema", "v" + inspection.backup.schema_version, "v" + inspection.current.schema_version],
+                    ["Ürün", inspection.backup.product_count, inspection.current.product_count],
+                    ["Satış", inspection.backup.sale_count, inspection.current.sale_count],
+                    ["Son satış", inspection.backup.last_sale_at ?? "-", inspection.current.last_sale_at ?? "-"],
+                    ["Satış toplamı", inspection.backup.sales_total.toFixed(2), inspection.current.sales_total.toFixed(2)],
+                    ["Gider toplamı", inspection.backup.expenses_total.toFixed(2), inspection.current.expenses_total.toFixed(2)],
+                  ] as [string, string | number, string | number][]
+                ).map(([label, b, c]) => (
+                  <tr key={label}>
+                    <td style={{ paddingRight: 16, opacity: 0.75 }}>{label}</td>
+                    <td style={{ textAlign: "right", paddingRight: 16 }}>{b}</td>
+                    <td style={{ textAlign: "right" }}>{c}</td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+            <ul style={{ margin: "8px 0 0", paddingLeft: 18 }}>
+              {inspection.diff.summary.map((line) => (
+                <li key={line}>{line}</li>
+              ))}
+            </ul>
+          </div>
+        )}
+
         <div style={{ marginTop: 12, ...styles.row }}>
-          <button onClick={doRestore} disabled={!canRestore || restoring} style={styles.btnPrimary(canRestore && !restoring)}>
+          <button onClick={doInspect} disabled={!canRestore || inspecting} style={styles.btn}>
+            {inspecting ? "İnceleniyor..." : "Önizle"}
+          </button>
+          <button onClick={doRestore} disabled={!canRestore || restoring || inspection?.restorable === false} style={styles.btnPrimary(canRestore && !restoring && inspection?.restorable !== false)}>
             {restoring ? "Geri yükleniyor..." : "Geri Yükle"}
           </button>
           {restoreMsg && <div style={{ fontSize: 13 }}>{restoreMsg}</div>}
         </div>
       </div>
 
+      {/* Bütünlük Kontrolü */}
+      <div style={{ marginTop: 14, ...styles.card }}>
+        <h4 style={styles.cardTitle}>🩺 Veritabanı Kontrolü</h4>
+        <div style={{ fontSize: 13, opacity: 0.75 }}>
+          Dosya bütünlüğü, yabancı anahtarlar, negatif stok ve sahipsiz iade / değişim satırlarını kontrol eder.
+        </div>
+
+        <div style={{ marginTop: 12, ...styles.row }}>
+          <button onClick={() => runIntegrity(false)} disabled={integrityBusy} style={styles.btn}>
+            {integrityBusy ? "Kontrol ediliyor..." : "Kontrol Et"}
+          </button>
+          {canRepair && (
+            <button onClick={() => runIntegrity(true)} disabled={integrityBusy} style={styles.btnPrimary(!integrityBusy)}>
+              Onar
+            </button>
+          )}
+        </div>
+
+        {integrityErr && <div style={{ marginTop: 10, fontSize: 13, color: "#b91c1c" }}>❌ {integrityErr}</div>}
+
+        {integrity && (
+          <div style={{ marginTop: 12, fontSize: 13, display: "grid", gap: 6 }}>
+            <div style={{ fontWeight: 800 }}>
+              {integrity.ok ? "✅ Sorun bulunamadı" : "⚠️ Sorun bulundu"} · {integrity.checked_at}
+            </div>
+            {integrityFound.map((i) => (
+              <div key={i.check}>
+                <span style={{ fontWeight: 700, color: i.severity === "ERROR" ? "#b91c1c" : "#92400e" }}>
+                  {i.message}: {i.count}
+                </span>
+                {i.repaired > 0 && <span> · onarıldı: {i.repaired}</span>}
+                {i.samples.length > 0 && (
+                  <div style={{ fontSize: 12, opacity: 0.7, wordBreak: "break-all" }}>{i.samples.join(", ")}</div>
+                )}
+              </div>
+            ))}
+          </div>
+        )}
+      </div>
+
       {/* Şifre Değiştir */}
       <div style={{ ...styles.card, marginTop: 14 }}>
         <h4 style={styles.cardTitle}>🔒 Şifre Değiştir</h4>
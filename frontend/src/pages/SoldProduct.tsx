@@ -1,6 +1,8 @@
 import React, { useEffect, useMemo, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
 import { C, R, page as dsPage, card, btnPrimary, btnSecondary, input, select as dsSelect, th as dsTh, td as dsTd, badgeRose, badgeAmber } from "../lib/ds";
+import { daysAgo, listAll } from "../lib/page";
+import { errorMessage } from "../lib/errors";
+import { ExportButtons } from "../components/ExportButtons";
 
 type SaleGroupRow = {
   sale_group_id: string;
@@ -59,10 +61,10 @@ export function SoldProducts() {
 
   const fetchLines = async (sale_group_id: string) => {
     try {
-      return await invoke<SaleLineRow[]>("list_sales_by_group", { saleGroupId: sale_group_id });
+      return await listAll<SaleLineRow>("list_sales_by_group", { saleGroupId: sale_group_id });
     } catch (e1) {
       try {
-        return await invoke<SaleLineRow[]>("list_sales_by_group", { sale_group_id });
+        return await listAll<SaleLineRow>("list_sales_by_group", { sale_group_id });
       } catch (e2) {
         // surface the first error (usually the useful one)
         throw e1;
@@ -98,7 +100,7 @@ export function SoldProducts() {
             const ls = await fetchLines(g.sale_group_id);
             return { id: g.sale_group_id, ls };
           } catch (e) {
-            setErr(String(e));
+            setErr(errorMessage(e));
             return { id: g.sale_group_id, ls: [] as SaleLineRow[] };
           }
         })
@@ -133,11 +135,11 @@ export function SoldProducts() {
     try {
       setErr("");
       setLoading(true);
-      const res = await invoke<SaleGroupRow[]>("list_sale_groups", { days, q: q.trim() || null });
+      const res = await listAll<SaleGroupRow>("list_sale_groups", {}, { from: daysAgo(days), q: q.trim() || null });
       setGroups(res);
       await preloadSummaries(res);
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -149,7 +151,7 @@ export function SoldProducts() {
       const res = await fetchLines(sale_group_id);
       setLines((prev) => ({ ...prev, [sale_group_id]: res }));
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     }
   };
 
@@ -167,7 +169,8 @@ export function SoldProducts() {
     <div style={dsPage}>
       <div style={{ display: "flex", alignItems: "center", gap: 12, marginBottom: 20 }}>
         <h2 style={{ fontSize: 20, fontWeight: 700, color: C.ink }}>Satılan Ürünler</h2>
-        <div style={{ marginLeft: "auto" }}>
+        <div style={{ marginLeft: "auto", display: "flex", gap: 8 }}>
+          <ExportButtons report={{ report: "sale_groups", from: daysAgo(days), q: q.trim() || null }} disabled={loading} />
           <button onClick={loadGroups} disabled={loading} style={btnSecondary}>
             Yenile
           </button>
@@ -1,6 +1,8 @@
 import React, { useEffect, useMemo, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
 import { useWindowWidth } from "../hooks/useWindowWidth";
+import { useBackendEvent } from "../hooks/useBackendEvent";
+import { listAll } from "../lib/page";
+import { errorMessage } from "../lib/errors";
 
 type Product = {
   barcode: string;
@@ -66,9 +68,9 @@ export function StockControl() {
     try {
       setErr("");
       setLoading(true);
-      setRows(await invoke<Product[]>("list_products"));
+      setRows(await listAll<Product>("list_products"));
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -76,6 +78,17 @@ export function StockControl() {
 
   useEffect(() => { load(); }, []);
 
+  // satış / transfer / iade başka pencereden de olsa satır yerinde güncellenir
+  useBackendEvent("stock_changed", (e) =>
+    setRows((prev) =>
+      prev.map((p) =>
+        p.barcode === e.barcode
+          ? { ...p, [e.location === "DEPO" ? "depo_stok" : "magaza_stok"]: e.new_qty }
+          : p
+      )
+    )
+  );
+
   const computed = useMemo(() => {
     const t = q.trim().toLowerCase();
 
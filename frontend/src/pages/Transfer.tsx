@@ -1,6 +1,7 @@
 import React, { useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { confirm } from "@tauri-apps/plugin-dialog";
+import { errorMessage } from "../lib/errors";
 
 type Product = {
   barcode: string;
@@ -62,7 +63,7 @@ export function Transfer() {
       setBarcode("");
       setTimeout(() => inputRef.current?.focus(), 10);
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setBusy(false);
     }
@@ -107,7 +108,7 @@ export function Transfer() {
       );
       reset();
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setBusy(false);
     }
@@ -126,7 +127,7 @@ export function Transfer() {
         { title: "Geri Alındı", kind: "info" }
       );
     } catch (e) {
-      setErr(String(e));
+      setErr(errorMessage(e));
     } finally {
       setBusy(false);
     }